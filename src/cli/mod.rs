@@ -185,7 +185,7 @@ pub enum AccountCmd {
         #[arg(short, long)]
         token: Option<String>,
     },
-    /// Check account status
+    /// Check account status and storage quota
     Status {
         /// Account ID or Name
         #[arg(short, long)]
@@ -241,6 +241,10 @@ pub enum TaskCmd {
 
         #[arg(short, long)]
         encrypt: bool,
+
+        /// Compress uploaded content (zstd or gzip)
+        #[arg(long)]
+        compress: Option<String>,
     },
     /// List all tasks
     List,
@@ -1,8 +1,9 @@
 use crate::config::{AccountConfig, ConfigManager, ProviderType, RateLimitConfig, RetryPolicy};
 use crate::providers::StorageProvider;
-use crate::services::account_service::verify_account_connection;
+use crate::services::account_service::{query_account_quota, verify_account_connection};
 use crate::services::provider_factory::create_provider;
 use crate::utils::account::find_account_id;
+use crate::utils::format_bytes;
 use dialoguer::{Input, Password, Select};
 use prettytable::{Table, row};
 use std::collections::HashMap;
@@ -17,7 +18,17 @@ pub async fn cmd_add_account(
 
     // 解析提供商类型
     let provider_str = if provider_str.is_empty() {
-        let providers = vec!["AliYunDrive", "WebDAV", "115", "Quark"];
+        let providers = vec![
+            "AliYunDrive",
+            "WebDAV",
+            "115",
+            "Quark",
+            "S3",
+            "SFTP",
+            "Local",
+            "Memory",
+            "Crypt",
+        ];
         let selection = Select::new()
             .with_prompt("请选择存储提供商")
             .items(&providers)
@@ -33,6 +44,12 @@ pub async fn cmd_add_account(
         "115" | "115网盘" => ProviderType::OneOneFive,
         "quark" | "夸克网盘" => ProviderType::Quark,
         "webdav" => ProviderType::WebDAV,
+        "local" | "本地" => ProviderType::Local,
+        "s3" | "minio" => ProviderType::S3,
+        "sftp" | "ssh" => ProviderType::SFTP,
+        "smb" | "cifs" | "nas" => ProviderType::SMB,
+        "memory" | "内存" => ProviderType::Memory,
+        "crypt" | "加密" => ProviderType::Crypt,
         _ => {
             return Err(format!("不支持的提供商: {}", provider_str).into());
         }
@@ -110,9 +127,163 @@ pub async fn cmd_add_account(
 
             credentials.insert("cookie".to_string(), cookie);
         }
-        _ => {
-            println!("ℹ️  该提供商需要手动配置");
-            println!("请在配置文件中手动添加凭证信息");
+        ProviderType::Local => {
+            println!("📝 添加本地目录账户");
+
+            let root = Input::<String>::new()
+                .with_prompt("本地根目录 (例如: /home/user/backup)")
+                .interact_text()?;
+
+            credentials.insert("root".to_string(), root);
+        }
+        ProviderType::S3 => {
+            println!("📝 添加 S3 兼容存储账户");
+
+            let endpoint = Input::<String>::new()
+                .with_prompt("服务地址 (例如: https://s3.amazonaws.com 或 http://127.0.0.1:9000)")
+                .interact_text()?;
+
+            let bucket = Input::<String>::new()
+                .with_prompt("存储桶名称")
+                .interact_text()?;
+
+            let region = Input::<String>::new()
+                .with_prompt("区域")
+                .default("us-east-1".to_string())
+                .interact_text()?;
+
+            let access_key = Input::<String>::new()
+                .with_prompt("Access Key")
+                .interact_text()?;
+
+            let secret_key = Password::new().with_prompt("Secret Key").interact()?;
+
+            credentials.insert("endpoint".to_string(), endpoint);
+            credentials.insert("bucket".to_string(), bucket);
+            credentials.insert("region".to_string(), region);
+            credentials.insert("access_key".to_string(), access_key);
+            credentials.insert("secret_key".to_string(), secret_key);
+        }
+        ProviderType::SFTP => {
+            println!("📝 添加 SFTP 账户");
+
+            let host = Input::<String>::new()
+                .with_prompt("主机地址")
+                .interact_text()?;
+
+            let port = Input::<u16>::new()
+                .with_prompt("端口")
+                .default(22)
+                .interact_text()?;
+
+            let username = Input::<String>::new()
+                .with_prompt("用户名")
+                .interact_text()?;
+
+            let auth_methods = vec!["私钥", "密码"];
+            let auth = Select::new()
+                .with_prompt("认证方式")
+                .items(&auth_methods)
+                .default(0)
+                .interact()?;
+            if auth == 0 {
+                let private_key = Input::<String>::new()
+                    .with_prompt("私钥文件路径 (例如: /home/user/.ssh/id_ed25519)")
+                    .interact_text()?;
+                let passphrase = Password::new()
+                    .with_prompt("私钥口令 (没有则直接回车)")
+                    .allow_empty_password(true)
+                    .interact()?;
+                credentials.insert("private_key".to_string(), private_key);
+                if !passphrase.is_empty() {
+                    credentials.insert("passphrase".to_string(), passphrase);
+                }
+            } else {
+                let password = Password::new().with_prompt("密码").interact()?;
+                credentials.insert("password".to_string(), password);
+            }
+
+            let root = Input::<String>::new()
+                .with_prompt("远程根目录 (默认为用户主目录)")
+                .default(".".to_string())
+                .interact_text()?;
+
+            credentials.insert("host".to_string(), host);
+            credentials.insert("port".to_string(), port.to_string());
+            // 未填写时按 ~/.ssh/known_hosts 校验主机公钥
+            let host_fingerprint = Input::<String>::new()
+                .with_prompt("主机公钥指纹 SHA256:... (留空则使用 ~/.ssh/known_hosts)")
+                .allow_empty(true)
+                .interact_text()?;
+
+            credentials.insert("username".to_string(), username);
+            credentials.insert("root".to_string(), root);
+            if !host_fingerprint.trim().is_empty() {
+                credentials.insert("host_fingerprint".to_string(), host_fingerprint);
+            }
+        }
+        ProviderType::SMB => {
+            println!("📝 添加 SMB 共享账户");
+
+            let server = Input::<String>::new()
+                .with_prompt("服务器地址 (例如: nas.local 或 192.168.1.2)")
+                .interact_text()?;
+
+            let share = Input::<String>::new()
+                .with_prompt("共享名称")
+                .interact_text()?;
+
+            let username = Input::<String>::new()
+                .with_prompt("用户名")
+                .interact_text()?;
+
+            let password = Password::new()
+                .with_prompt("密码")
+                .allow_empty_password(true)
+                .interact()?;
+
+            let domain = Input::<String>::new()
+                .with_prompt("域/工作组 (可留空)")
+                .allow_empty(true)
+                .interact_text()?;
+
+            credentials.insert("server".to_string(), server);
+            credentials.insert("share".to_string(), share);
+            credentials.insert("username".to_string(), username);
+            credentials.insert("password".to_string(), password);
+            if !domain.is_empty() {
+                credentials.insert("domain".to_string(), domain);
+            }
+        }
+        ProviderType::Memory => {
+            println!("📝 添加内存存储账户（数据仅保存在进程内，退出后丢失，适合演示）");
+        }
+        ProviderType::Crypt => {
+            println!("📝 添加加密账户（包装已有账户，内容与文件名加密后再上传）");
+
+            let remote = Input::<String>::new()
+                .with_prompt("被加密的账户 (ID 或名称)")
+                .interact_text()?;
+            let remote_id = find_account_id(config_manager, &remote)
+                .ok_or_else(|| format!("未找到账户: {}", remote))?;
+
+            let key_id = Input::<String>::new()
+                .with_prompt("密钥 ID (由 gen-key 生成)")
+                .interact_text()?;
+            let key_password = Password::new().with_prompt("密钥主密码").interact()?;
+
+            let encrypt_filenames = dialoguer::Confirm::new()
+                .with_prompt("是否加密文件名?")
+                .default(true)
+                .interact()?;
+
+            credentials.insert("remote".to_string(), remote_id);
+            credentials.insert("key_id".to_string(), key_id);
+            credentials.insert("key_password".to_string(), key_password);
+            credentials.insert(
+                "encrypt_filenames".to_string(),
+                encrypt_filenames.to_string(),
+            );
         }
     }
 
@@ -155,12 +326,13 @@ pub async fn cmd_add_account(
             max_delay_ms: 10000,
             backoff_factor: 2.0,
         },
+        resource_limits: None,
     };
 
     // 验证账户连接
     println!("🔗 正在验证账户连接...");
 
-    match verify_account_connection(&account).await {
+    match verify_account_connection(&account, config_manager).await {
         Ok(_) => {
             println!("✅ 账户验证成功!");
 
@@ -311,7 +483,7 @@ pub async fn cmd_update_account(
     if changed {
         if new_token.is_some() {
             println!("🔗 正在验证新凭证...");
-            verify_account_connection(&account).await?;
+            verify_account_connection(&account, config_manager).await?;
             println!("✅ 验证成功!");
         }
 
@@ -339,9 +511,10 @@ pub async fn cmd_account_status(
     println!("🔍 正在检查账户状态: {} ({})", account.name, id);
     println!("   类型: {:?}", account.provider);
 
-    match verify_account_connection(&account).await {
+    match verify_account_connection(&account, config_manager).await {
         Ok(_) => {
             println!("✅ 状态: 正常 (连接成功)");
+            print_account_quota(&account, config_manager).await;
         }
         Err(e) => {
             println!("❌ 状态: 异常 (连接失败)");
@@ -352,6 +525,25 @@ pub async fn cmd_account_status(
     Ok(())
 }
 
+/// 显示存储空间用量，提供商不支持配额查询时不显示
+async fn print_account_quota(account: &AccountConfig, config_manager: &ConfigManager) {
+    match query_account_quota(account, config_manager).await {
+        Ok(Some(quota)) => {
+            if let Some(used) = quota.used_bytes {
+                println!("   已用空间: {}", format_bytes(used));
+            }
+            if let Some(available) = quota.available_bytes {
+                println!("   可用空间: {}", format_bytes(available));
+            }
+            if let Some(total) = quota.total_bytes() {
+                println!("   总容量: {}", format_bytes(total));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => println!("   ⚠️ 无法获取存储空间: {}", e),
+    }
+}
+
 pub async fn cmd_browse_account(
     config_manager: &ConfigManager,
     id_or_name: &str,
@@ -365,7 +557,7 @@ pub async fn cmd_browse_account(
     let account = config_manager.get_account(&id).ok_or("Account not found")?;
 
     println!("正在连接账户 {}...", account.name);
-    let provider = create_provider(&account).await?;
+    let provider = create_provider(&account, config_manager).await?;
 
     // Convert Box<dyn StorageProvider> to Arc<dyn StorageProvider>
     let provider: std::sync::Arc<dyn StorageProvider> = std::sync::Arc::from(provider);
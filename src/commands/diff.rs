@@ -1,3 +1,4 @@
+use crate::commands::key::unlock_task_key;
 use crate::config::ConfigManager;
 use crate::services::provider_factory::create_provider;
 use crate::sync::engine::SyncEngine;
@@ -23,13 +24,14 @@ pub async fn cmd_diff_task(
     println!("   目标: {}:{}", &task.target_account, &task.target_path);
 
     let mut engine = SyncEngine::new().await?;
+    unlock_task_key(&mut engine, &task)?;
 
     // 注册源提供商
     let source_account = config_manager
         .get_account(&task.source_account)
         .ok_or_else(|| format!("源账户不存在: {}", task.source_account))?;
 
-    let source_provider = create_provider(&source_account).await?;
+    let source_provider = create_provider(&source_account, config_manager).await?;
     engine.register_provider(task.source_account.clone(), source_provider);
 
     // 注册目标提供商
@@ -37,7 +39,7 @@ pub async fn cmd_diff_task(
         .get_account(&task.target_account)
         .ok_or_else(|| format!("目标账户不存在: {}", task.target_account))?;
 
-    let target_provider = create_provider(&target_account).await?;
+    let target_provider = create_provider(&target_account, config_manager).await?;
     engine.register_provider(task.target_account.clone(), target_provider);
 
     // 创建一个不定长的 spinner 进度条，因为 diff 计算时间未知
@@ -1,9 +1,8 @@
-use crate::models::key::KeyFile;
+use crate::config::SyncTask;
+use crate::encryption::key_store::{KeyFile, KeyStore};
+use crate::sync::engine::SyncEngine;
 use crate::utils::crypto::generate_recovery_code;
-use aes_gcm::KeyInit;
-use aes_gcm::aead::Aead;
 use rand::{Rng, rng};
-use std::fs;
 
 pub fn cmd_generate_key(
     key_name: &str,
@@ -27,17 +26,6 @@ pub fn cmd_generate_key(
     let mut key_bytes = vec![0u8; key_size];
     rng().fill(&mut key_bytes[..]);
 
-    // 创建密钥存储目录
-    let keys_dir = dirs::data_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("disksync")
-        .join("keys");
-
-    fs::create_dir_all(&keys_dir)?;
-
-    // 保存密钥文件
-    let key_file = keys_dir.join(format!("{}.key", key_name));
-
     // 加密保存密钥（使用主密码保护）
     println!("🔒 请设置主密码来保护此密钥:");
     let password = rpassword::prompt_password("主密码: ")?;
@@ -51,39 +39,9 @@ pub fn cmd_generate_key(
         return Err("密码长度至少8位".into());
     }
 
-    // 使用PBKDF2派生密钥加密密钥
-    let salt: [u8; 16] = rand::random();
-    let encryption_key = [0u8; 32];
-
-    // pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(
-    //     password.as_bytes(),
-    //     &salt,
-    //     100_000,
-    //     &mut encryption_key,
-    // );
-
-    // 加密密钥数据
-    let cipher = aes_gcm::Aes256Gcm::new(&encryption_key.into());
-    let nonce: [u8; 12] = rand::random();
-
-    let encrypted_key = cipher
-        .encrypt(&nonce.into(), key_bytes.as_ref())
-        .map_err(|e| format!("加密密钥失败: {}", e))?;
-
-    // 保存加密的密钥文件
-    let key_data = KeyFile {
-        version: 1,
-        algorithm: "AES-256-GCM".to_string(),
-        key_strength,
-        salt: salt.to_vec(),
-        nonce: nonce.to_vec(),
-        encrypted_key,
-        created_at: chrono::Utc::now(),
-        last_used: None,
-    };
-
-    let json_data = serde_json::to_string_pretty(&key_data)?;
-    fs::write(&key_file, json_data)?;
+    // 使用PBKDF2派生密钥加密密钥，保存加密的密钥文件
+    let key_data = KeyFile::seal(&key_bytes, key_strength, &password)?;
+    let key_file = KeyStore::default().save(key_name, &key_data)?;
 
     // 显示密钥信息
     println!("✅ 密钥生成成功!");
@@ -107,3 +65,17 @@ pub fn cmd_generate_key(
 
     Ok(())
 }
+
+/// 任务配置了加密时，输入主密码解锁密钥库中的密钥并注册到同步引擎
+pub fn unlock_task_key(
+    engine: &mut SyncEngine,
+    task: &SyncTask,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(encryption) = &task.encryption {
+        let password =
+            rpassword::prompt_password(format!("🔒 密钥 {} 的主密码: ", encryption.key_id))?;
+        let key = KeyStore::default().load(&encryption.key_id, &password)?;
+        engine.register_encryption_key(encryption.key_id.clone(), key);
+    }
+    Ok(())
+}
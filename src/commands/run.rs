@@ -1,3 +1,4 @@
+use crate::commands::key::unlock_task_key;
 use crate::config::ConfigManager;
 use crate::services::provider_factory::create_provider;
 use crate::sync::engine::SyncEngine;
@@ -17,13 +18,15 @@ pub async fn cmd_run_task(
     let task = config_manager.get_task(task_id).ok_or("Task not found")?;
 
     let mut engine = SyncEngine::new().await?;
+    engine.apply_global_settings(config_manager.global_settings())?;
+    unlock_task_key(&mut engine, &task)?;
 
     // 注册源提供商
     let source_account = config_manager
         .get_account(&task.source_account)
         .ok_or_else(|| format!("源账户不存在: {}", task.source_account))?;
 
-    let source_provider = create_provider(&source_account).await?;
+    let source_provider = create_provider(&source_account, config_manager).await?;
     engine.register_provider(task.source_account.clone(), source_provider);
 
     // 注册目标提供商
@@ -31,7 +34,7 @@ pub async fn cmd_run_task(
         .get_account(&task.target_account)
         .ok_or_else(|| format!("目标账户不存在: {}", task.target_account))?;
 
-    let target_provider = create_provider(&target_account).await?;
+    let target_provider = create_provider(&target_account, config_manager).await?;
     engine.register_provider(task.target_account.clone(), target_provider);
 
     if dry_run {
@@ -1,6 +1,9 @@
+use crate::config::validator::ConfigValidatorImpl;
 use crate::config::{
-    ConfigManager, DiffMode, EncryptionConfig, FilterRule, Schedule, SyncPolicy, SyncTask,
+    CompressionAlgorithm, CompressionConfig, ConfigManager, DiffMode, EncryptionConfig, FilterRule,
+    Schedule, SyncPolicy, SyncTask,
 };
+use crate::core::traits::ConfigValidator;
 use crate::encryption::types::{EncryptionAlgorithm, IvMode};
 use crate::utils::interaction::{parse_account_path_or_select, select_account_and_path};
 use crate::utils::task::{find_task_id, get_task_status, remove_task_reports};
@@ -15,6 +18,7 @@ pub async fn cmd_create_task(
     target_str: Option<String>,
     schedule_str: Option<String>,
     encrypt: bool,
+    compress: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔄 创建新的同步任务...");
 
@@ -109,6 +113,20 @@ pub async fn cmd_create_task(
         None
     };
 
+    // 配置压缩，已压缩格式（图片、视频、压缩包等）上传时保持原样
+    let compression_config = match compress.as_deref().map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("zstd" | "zst") => Some(CompressionConfig {
+            algorithm: CompressionAlgorithm::Zstd,
+            level: None,
+        }),
+        Some("gzip" | "gz") => Some(CompressionConfig {
+            algorithm: CompressionAlgorithm::Gzip,
+            level: None,
+        }),
+        Some(other) => return Err(format!("不支持的压缩算法: {}", other).into()),
+    };
+
     // 配置计划任务
     let schedule = if let Some(schedule_str) = schedule_str {
         if schedule_str.to_lowercase() == "manual" {
@@ -160,6 +178,7 @@ pub async fn cmd_create_task(
         schedule,
         filters,
         encryption: encryption_config,
+        compression: compression_config,
         diff_mode,
         preserve_metadata: true,
         verify_integrity: false,
@@ -168,8 +187,12 @@ pub async fn cmd_create_task(
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
+    ConfigValidatorImpl::default().validate_task(&task)?;
+
     // 保存任务
     config_manager.add_task(task)?;
     config_manager.save()?;
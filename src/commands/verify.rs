@@ -1,4 +1,6 @@
+use crate::commands::key::unlock_task_key;
 use crate::config::ConfigManager;
+use crate::services::provider_factory::create_provider;
 use crate::sync::engine::SyncEngine;
 use crate::utils::format_bytes;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -14,7 +16,24 @@ pub async fn cmd_verify_integrity(
         .get_task(task_id)
         .ok_or_else(|| format!("任务不存在: {}", task_id))?;
 
-    let engine = SyncEngine::new().await?;
+    let mut engine = SyncEngine::new().await?;
+    unlock_task_key(&mut engine, &task)?;
+
+    // 注册源提供商
+    let source_account = config_manager
+        .get_account(&task.source_account)
+        .ok_or_else(|| format!("源账户不存在: {}", task.source_account))?;
+
+    let source_provider = create_provider(&source_account, &config_manager).await?;
+    engine.register_provider(task.source_account.clone(), source_provider);
+
+    // 注册目标提供商
+    let target_account = config_manager
+        .get_account(&task.target_account)
+        .ok_or_else(|| format!("目标账户不存在: {}", task.target_account))?;
+
+    let target_provider = create_provider(&target_account, &config_manager).await?;
+    engine.register_provider(task.target_account.clone(), target_provider);
 
     // 创建进度条
     let progress_bar = ProgressBar::new(0);
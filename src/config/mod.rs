@@ -1,10 +1,12 @@
 mod migrator;
 mod security;
 mod utils;
-mod validator;
+pub(crate) mod validator;
 
+use crate::core::resources::ResourceLimits;
 use crate::encryption::types::{EncryptionAlgorithm, IvMode};
 use crate::error::ConfigError;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
 use security::SecurityManager;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -15,6 +17,7 @@ use std::path::PathBuf;
 use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum ProviderType {
     AliYunDrive,
     OneOneFive,
@@ -22,6 +25,12 @@ pub enum ProviderType {
     WebDAV,
     SMB,
     Local,
+    S3,
+    SFTP,
+    /// 进程内的内存存储，用于演示与试运行
+    Memory,
+    /// 加密覆盖层，包装 `remote` 凭据指定的另一个账户
+    Crypt,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +41,8 @@ pub struct AccountConfig {
     pub credentials: HashMap<String, String>,
     pub rate_limit: Option<RateLimitConfig>,
     pub retry_policy: RetryPolicy,
+    /// 账户级资源限制，目前使用其中的带宽上限
+    pub resource_limits: Option<ResourceLimits>,
 }
 
 impl AccountConfig {
@@ -56,13 +67,13 @@ pub struct RetryPolicy {
 }
 
 impl Default for RetryPolicy {
+    /// 最多重试 3 次，间隔 1s、2s、4s，单次间隔不超过 30s
     fn default() -> Self {
-        // todo 默认重试策略缺失
         RetryPolicy {
-            max_retries: 0,
-            initial_delay_ms: 0,
-            max_delay_ms: 0,
-            backoff_factor: 0.0,
+            max_retries: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+            backoff_factor: 2.0,
         }
     }
 }
@@ -78,11 +89,19 @@ pub struct SyncTask {
     pub schedule: Option<Schedule>,
     pub filters: Vec<FilterRule>,
     pub encryption: Option<EncryptionConfig>,
+    /// 上传到目标前压缩内容，与加密同时启用时先压缩再加密
+    #[serde(default)]
+    pub compression: Option<CompressionConfig>,
     pub diff_mode: DiffMode,
     pub preserve_metadata: bool,
     pub verify_integrity: bool,
     /// 同步策略（删除、覆盖、扫描限频等）
     pub sync_policy: Option<SyncPolicy>,
+    /// 任务级资源限制，目前使用其中的带宽上限
+    pub resource_limits: Option<ResourceLimits>,
+    /// 任务级带宽时间表，覆盖的时段内取代 `resource_limits` 中的带宽上限
+    #[serde(default)]
+    pub bandwidth_schedule: Vec<BandwidthRule>,
 }
 
 impl SyncTask {
@@ -116,6 +135,21 @@ pub struct EncryptionConfig {
     pub iv_mode: IvMode,
 }
 
+/// 任务级压缩配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    pub algorithm: CompressionAlgorithm,
+    /// 压缩级别，为空时使用算法的默认级别
+    #[serde(default)]
+    pub level: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Zstd,
+    Gzip,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DiffMode {
     Full,
@@ -134,10 +168,61 @@ pub struct SyncPolicy {
     pub scan_cooldown_secs: u64,
 }
 
+/// 带宽时间表中的一条规则
+///
+/// 结束时间不晚于开始时间的规则跨越午夜，`weekdays` 指开始时间所在的那一天；
+/// 开始与结束时间相同时覆盖一整天。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BandwidthRule {
+    /// 生效的星期，为空表示每天
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    /// 开始时间（含）
+    pub start: NaiveTime,
+    /// 结束时间（不含）
+    pub end: NaiveTime,
+    /// 带宽上限（字节/秒），为空表示不限速
+    pub bytes_per_sec: Option<u64>,
+}
+
+impl BandwidthRule {
+    /// 规则是否覆盖 `at` 这一时刻
+    pub fn matches(&self, at: NaiveDateTime) -> bool {
+        let on = |day: Weekday| self.weekdays.is_empty() || self.weekdays.contains(&day);
+        let time = at.time();
+        let day = at.weekday();
+        if self.start < self.end {
+            on(day) && time >= self.start && time < self.end
+        } else {
+            (on(day) && time >= self.start) || (on(day.pred()) && time < self.end)
+        }
+    }
+}
+
+/// `at` 时刻适用的带宽上限：第一条覆盖该时刻的规则，没有规则覆盖时为 `default`，
+/// `None` 表示不限速
+pub fn scheduled_bandwidth(
+    schedule: &[BandwidthRule],
+    default: Option<u64>,
+    at: NaiveDateTime,
+) -> Option<u64> {
+    schedule
+        .iter()
+        .find(|rule| rule.matches(at))
+        .map_or(default, |rule| rule.bytes_per_sec)
+}
+
 pub struct ConfigManager {
     config_path: PathBuf,
     accounts: HashMap<String, AccountConfig>,
     tasks: HashMap<String, SyncTask>,
+    global_settings: GlobalSettings,
+    network_settings: Option<NetworkSettings>,
+    /// 以下各节由其他模块维护，这里只负责原样读写，避免保存时丢失
+    encryption_keys: Vec<EncryptionKey>,
+    plugins: Vec<PluginConfig>,
+    schedules: Vec<ScheduleConfig>,
+    security_settings: Option<SecuritySettings>,
     security_manager: SecurityManager,
 }
 
@@ -173,6 +258,12 @@ impl ConfigManager {
             config_path,
             accounts: HashMap::new(),
             tasks: HashMap::new(),
+            global_settings: GlobalSettings::default(),
+            network_settings: None,
+            encryption_keys: Vec::new(),
+            plugins: Vec::new(),
+            schedules: Vec::new(),
+            security_settings: None,
             security_manager,
         };
 
@@ -221,6 +312,12 @@ impl ConfigManager {
                 .into_iter()
                 .map(|t| (t.id.clone(), t))
                 .collect();
+            self.global_settings = config.global_settings;
+            self.network_settings = config.network_settings;
+            self.encryption_keys = config.encryption_keys;
+            self.plugins = config.plugins;
+            self.schedules = config.schedules;
+            self.security_settings = config.security_settings;
 
             // 如果发生了迁移，保存更新后的配置
             if migration_occurred {
@@ -249,14 +346,14 @@ impl ConfigManager {
 
         let config = ConfigFile {
             version: "0.1.0".to_string(), // Reset to 0.1.0
-            global_settings: Default::default(),
+            global_settings: self.global_settings.clone(),
             accounts,
             tasks: self.tasks.values().cloned().collect(),
-            encryption_keys: vec![],
-            plugins: vec![],
-            schedules: vec![],
-            network_settings: None,
-            security_settings: None,
+            encryption_keys: self.encryption_keys.clone(),
+            plugins: self.plugins.clone(),
+            schedules: self.schedules.clone(),
+            network_settings: self.network_settings.clone(),
+            security_settings: self.security_settings.clone(),
         };
 
         // 写入配置信息到文件！
@@ -272,6 +369,15 @@ impl ConfigManager {
         &self.tasks
     }
 
+    pub fn global_settings(&self) -> &GlobalSettings {
+        &self.global_settings
+    }
+
+    /// 网络设置，配置文件中未设置时使用默认值
+    pub fn network_settings(&self) -> NetworkSettings {
+        self.network_settings.clone().unwrap_or_default()
+    }
+
     pub fn get_accounts(&self) -> &HashMap<String, AccountConfig> {
         &self.accounts
     }
@@ -292,6 +398,22 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// 合并更新账户凭据并保存到配置文件（如刷新后的令牌）
+    pub fn update_credentials(
+        &mut self,
+        account_id: &str,
+        updates: &HashMap<String, String>,
+    ) -> Result<(), ConfigError> {
+        let account = self
+            .accounts
+            .get_mut(account_id)
+            .ok_or_else(|| ConfigError::Invalid(format!("Account not found: {}", account_id)))?;
+        for (key, value) in updates {
+            account.credentials.insert(key.clone(), value.clone());
+        }
+        self.save()
+    }
+
     /// 删除账户
     pub fn remove_account(&mut self, account_id: &str) -> Result<(), ConfigError> {
         self.accounts.remove(account_id);
@@ -341,6 +463,11 @@ pub struct GlobalSettings {
     pub enable_telemetry: bool,
     pub auto_update_check: bool,
     pub ui_language: String,
+    /// 全局资源限制，带宽上限对所有任务的传输合计生效
+    pub resource_limits: Option<ResourceLimits>,
+    /// 全局带宽时间表，覆盖的时段内取代 `resource_limits` 中的带宽上限
+    #[serde(default)]
+    pub bandwidth_schedule: Vec<BandwidthRule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -603,6 +730,8 @@ impl Default for GlobalSettings {
             enable_telemetry: false,
             auto_update_check: true,
             ui_language: "en".to_string(),
+            resource_limits: None,
+            bandwidth_schedule: Vec::new(),
         }
     }
 }
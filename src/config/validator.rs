@@ -1,8 +1,16 @@
-use crate::config::{AccountConfig, EncryptionConfig, ProviderType, SyncTask};
+use crate::config::{
+    AccountConfig, BandwidthRule, EncryptionConfig, GlobalSettings, ProviderType, SyncTask,
+};
 use crate::core::traits::ConfigValidator;
 use crate::error::{ConfigError, Result};
+use crate::providers::ProviderCapabilities;
+use tracing::warn;
 
-pub struct ConfigValidatorImpl;
+/// 配置校验器，给出目标提供商的能力时同时检查任务配置能否被满足
+#[derive(Default)]
+pub struct ConfigValidatorImpl {
+    target_capabilities: Option<ProviderCapabilities>,
+}
 
 impl ConfigValidator for ConfigValidatorImpl {
     fn validate_account(&self, account: &AccountConfig) -> Result<()> {
@@ -16,12 +24,10 @@ impl ConfigValidator for ConfigValidatorImpl {
 
         // 验证凭据
         match account.provider {
-            ProviderType::AliYunDrive => {
-                if !account.credentials.contains_key("refresh_token") {
-                    return Err(
-                        ConfigError::MissingField("refresh_token for AliYunDrive".into()).into(),
-                    );
-                }
+            ProviderType::AliYunDrive if !account.credentials.contains_key("refresh_token") => {
+                return Err(
+                    ConfigError::MissingField("refresh_token for AliYunDrive".into()).into(),
+                );
             }
             ProviderType::WebDAV => {
                 if !account.credentials.contains_key("url") {
@@ -38,6 +44,56 @@ impl ConfigValidator for ConfigValidatorImpl {
                 if !account.credentials.contains_key("share") {
                     return Err(ConfigError::MissingField("share for SMB".into()).into());
                 }
+                if !account.credentials.contains_key("username") {
+                    return Err(ConfigError::MissingField("username for SMB".into()).into());
+                }
+            }
+            ProviderType::Local if !account.credentials.contains_key("root") => {
+                return Err(ConfigError::MissingField("root for Local".into()).into());
+            }
+            ProviderType::SFTP => {
+                if !account.credentials.contains_key("host") {
+                    return Err(ConfigError::MissingField("host for SFTP".into()).into());
+                }
+                if !account.credentials.contains_key("username") {
+                    return Err(ConfigError::MissingField("username for SFTP".into()).into());
+                }
+                match account.credentials.get("private_key") {
+                    Some(key) if !std::path::Path::new(key).is_file() => {
+                        return Err(ConfigError::Invalid(format!(
+                            "SFTP private key not found: {}",
+                            key
+                        ))
+                        .into());
+                    }
+                    Some(_) => {}
+                    None if !account.credentials.contains_key("password") => {
+                        return Err(ConfigError::MissingField(
+                            "password or private_key for SFTP".into(),
+                        )
+                        .into());
+                    }
+                    None => {}
+                }
+            }
+            ProviderType::S3 => {
+                for key in ["endpoint", "bucket", "access_key", "secret_key"] {
+                    if !account.credentials.contains_key(key) {
+                        return Err(ConfigError::MissingField(format!("{} for S3", key)).into());
+                    }
+                }
+            }
+            ProviderType::Crypt => {
+                for key in ["remote", "key_id", "key_password"] {
+                    if !account.credentials.contains_key(key) {
+                        return Err(ConfigError::MissingField(format!("{} for Crypt", key)).into());
+                    }
+                }
+                if account.credentials.get("remote") == Some(&account.id) {
+                    return Err(
+                        ConfigError::Invalid("Crypt account cannot wrap itself".into()).into(),
+                    );
+                }
             }
             _ => {} // 其他提供商可能不需要额外验证
         }
@@ -58,6 +114,10 @@ impl ConfigValidator for ConfigValidatorImpl {
             }
         }
 
+        if let Some(limits) = &account.resource_limits {
+            limits.validate()?;
+        }
+
         Ok(())
     }
 
@@ -106,6 +166,16 @@ impl ConfigValidator for ConfigValidatorImpl {
             }
         }
 
+        if let Some(limits) = &task.resource_limits {
+            limits.validate()?;
+        }
+
+        validate_bandwidth_schedule(&task.bandwidth_schedule)?;
+
+        if let Some(target) = &self.target_capabilities {
+            self.validate_task_capabilities(task, target)?;
+        }
+
         Ok(())
     }
 
@@ -121,6 +191,59 @@ impl ConfigValidator for ConfigValidatorImpl {
     }
 }
 
+impl ConfigValidatorImpl {
+    /// 校验任务时按 `target` 检查目标提供商能否满足任务配置
+    pub fn for_target(target: ProviderCapabilities) -> Self {
+        Self {
+            target_capabilities: Some(target),
+        }
+    }
+
+    /// 校验全局设置中的资源限制与带宽时间表
+    pub fn validate_global_settings(&self, settings: &GlobalSettings) -> Result<()> {
+        if let Some(limits) = &settings.resource_limits {
+            limits.validate()?;
+        }
+
+        validate_bandwidth_schedule(&settings.bandwidth_schedule)
+    }
+
+    /// 根据目标提供商的能力检查任务配置能否被满足
+    fn validate_task_capabilities(
+        &self,
+        task: &SyncTask,
+        target: &ProviderCapabilities,
+    ) -> Result<()> {
+        if target.has_forbidden_chars(&task.target_path) {
+            return Err(ConfigError::Invalid(format!(
+                "Target path contains characters not allowed by provider: {}",
+                task.target_path
+            ))
+            .into());
+        }
+
+        // 同步时按能力尽力保留修改时间，旧配置默认开启该项，不能因此拒绝同步
+        if task.preserve_metadata && !target.can_set_mtime {
+            warn!(
+                task_id = %task.id,
+                "Target provider cannot preserve modification time, skipping mtime"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// 时间表中的速率为 0 会让传输永远无法进行，不限速应写为 null
+fn validate_bandwidth_schedule(schedule: &[BandwidthRule]) -> Result<()> {
+    if schedule.iter().any(|rule| rule.bytes_per_sec == Some(0)) {
+        return Err(
+            ConfigError::Invalid("Bandwidth schedule rate must be greater than 0".into()).into(),
+        );
+    }
+    Ok(())
+}
+
 fn is_valid_cron(expr: &str) -> bool {
     // 简单的cron表达式验证
     // 实际应该使用cron解析库
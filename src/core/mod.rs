@@ -1,7 +1,7 @@
 mod audit;
 mod health;
 pub mod rate_limit;
-mod resources;
-mod retry;
+pub mod resources;
+pub mod retry;
 mod scheduler;
 pub mod traits;
@@ -6,59 +6,75 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 
+/// 收到限流信号后速率乘以该系数
+const BACKOFF_FACTOR: f64 = 0.5;
+/// 速率下限为配置速率的比例，避免持续限流时降到接近 0
+const MIN_RATE_RATIO: f64 = 1.0 / 64.0;
+/// 每次恢复增加的速率为配置速率的比例
+const RECOVERY_STEP: f64 = 0.1;
+/// 两次恢复之间至少间隔的时间
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(2);
+/// 降速后该时间内的限流信号来自降速前发出的请求，不再重复降速
+const BACKOFF_GRACE: Duration = Duration::from_secs(1);
+
 /// 令牌桶算法实现
+///
+/// 速率按 AIMD 自适应：收到限流信号时乘性降低，之后请求成功时按间隔加性恢复，
+/// 最高恢复到配置的速率。
 pub struct TokenBucketRateLimiter {
     capacity: u64,
     tokens: AtomicU64,
-    refill_rate: f64, // tokens per second
+    /// 配置的速率（请求/秒），自适应恢复的上限
+    max_rate: f64,
+    /// 当前速率（请求/秒），以 f64 的位表示存储
+    rate: AtomicU64,
     last_refill: parking_lot::Mutex<Instant>,
+    adjustment: parking_lot::Mutex<Adjustment>,
     semaphore: Arc<Semaphore>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{SlidingWindowRateLimiter, TokenBucketRateLimiter};
-    use crate::core::traits::RateLimiter;
-    use std::time::Duration;
-
-    #[tokio::test]
-    async fn test_token_bucket_acquire() {
-        let limiter = TokenBucketRateLimiter::new(2, 10.0);
-        assert!(limiter.try_acquire());
-        assert!(limiter.try_acquire());
-        assert!(!limiter.try_acquire());
-        limiter.acquire().await.unwrap();
-    }
-
-    #[tokio::test]
-    async fn test_sliding_window_acquire() {
-        let limiter = SlidingWindowRateLimiter::new(Duration::from_millis(100), 1);
-        assert!(limiter.try_acquire());
-        assert!(!limiter.try_acquire());
-        limiter.acquire().await.unwrap();
-    }
+/// 最近一次调整速率的时间
+struct Adjustment {
+    adjusted_at: Instant,
+    backed_off_at: Option<Instant>,
 }
+
 impl TokenBucketRateLimiter {
     pub fn new(capacity: u64, requests_per_second: f64) -> Self {
         Self {
             capacity,
             tokens: AtomicU64::new(capacity),
-            refill_rate: requests_per_second,
+            max_rate: requests_per_second,
+            rate: AtomicU64::new(requests_per_second.to_bits()),
             last_refill: parking_lot::Mutex::new(Instant::now()),
+            adjustment: parking_lot::Mutex::new(Adjustment {
+                adjusted_at: Instant::now(),
+                backed_off_at: None,
+            }),
             semaphore: Arc::new(Semaphore::new(capacity as usize)),
         }
     }
 
+    fn rate(&self) -> f64 {
+        f64::from_bits(self.rate.load(Ordering::Relaxed))
+    }
+
+    /// 桶容量随速率降低而缩小，降速后不会再一次性放出整桶请求
+    fn effective_capacity(&self) -> u64 {
+        self.capacity.min(self.rate().ceil().max(1.0) as u64)
+    }
+
     fn refill_tokens(&self) {
         let mut last_refill = self.last_refill.lock();
         let now = Instant::now();
-        let elapsed = now.duration_since(*last_refill);
+        // `Retry-After` 暂停期间 last_refill 位于将来，不补充令牌
+        let elapsed = now.saturating_duration_since(*last_refill);
 
         if elapsed.as_secs_f64() > 0.0 {
-            let new_tokens = (elapsed.as_secs_f64() * self.refill_rate) as u64;
+            let new_tokens = (elapsed.as_secs_f64() * self.rate()) as u64;
             if new_tokens > 0 {
                 let current = self.tokens.load(Ordering::Relaxed);
-                let new_total = (current + new_tokens).min(self.capacity);
+                let new_total = (current + new_tokens).min(self.effective_capacity());
                 self.tokens.store(new_total, Ordering::Relaxed);
                 *last_refill = now;
             }
@@ -74,7 +90,7 @@ impl RateLimiter for TokenBucketRateLimiter {
         loop {
             let current = self.tokens.load(Ordering::Relaxed);
             if current == 0 {
-                tokio::time::sleep(Duration::from_secs_f64(1.0 / self.refill_rate)).await;
+                tokio::time::sleep(Duration::from_secs_f64(1.0 / self.rate())).await;
                 self.refill_tokens();
                 continue;
             }
@@ -92,11 +108,13 @@ impl RateLimiter for TokenBucketRateLimiter {
     }
 
     fn current_rate(&self) -> f64 {
-        self.refill_rate
+        self.rate()
     }
 
     fn set_rate(&mut self, requests_per_second: f64) {
-        self.refill_rate = requests_per_second;
+        self.max_rate = requests_per_second;
+        self.rate
+            .store(requests_per_second.to_bits(), Ordering::Relaxed);
     }
 
     fn try_acquire(&self) -> bool {
@@ -111,8 +129,197 @@ impl RateLimiter for TokenBucketRateLimiter {
             .compare_exchange(current, current - 1, Ordering::AcqRel, Ordering::Relaxed)
             .is_ok()
     }
+
+    fn on_throttled(&self, retry_after: Option<Duration>) -> Option<f64> {
+        let now = Instant::now();
+        // 清空令牌，有 `Retry-After` 时在其到期前不再补充
+        self.tokens.store(0, Ordering::Relaxed);
+        {
+            let mut last_refill = self.last_refill.lock();
+            *last_refill = (*last_refill).max(now + retry_after.unwrap_or_default());
+        }
+
+        let mut adjustment = self.adjustment.lock();
+        if adjustment
+            .backed_off_at
+            .is_some_and(|at| now.duration_since(at) < BACKOFF_GRACE)
+        {
+            return None;
+        }
+        let current = self.rate();
+        let rate = (current * BACKOFF_FACTOR).max(self.max_rate * MIN_RATE_RATIO);
+        adjustment.adjusted_at = now;
+        adjustment.backed_off_at = Some(now);
+        if rate >= current {
+            return None;
+        }
+        self.rate.store(rate.to_bits(), Ordering::Relaxed);
+        Some(rate)
+    }
+
+    fn on_success(&self) -> Option<f64> {
+        let current = self.rate();
+        if current >= self.max_rate {
+            return None;
+        }
+        let now = Instant::now();
+        let mut adjustment = self.adjustment.lock();
+        if now.duration_since(adjustment.adjusted_at) < RECOVERY_INTERVAL {
+            return None;
+        }
+        let rate = (current + self.max_rate * RECOVERY_STEP).min(self.max_rate);
+        adjustment.adjusted_at = now;
+        self.rate.store(rate.to_bits(), Ordering::Relaxed);
+        Some(rate)
+    }
+}
+
+/// 按字节计的令牌桶，用于限制传输带宽
+///
+/// 允许透支：每读到一块数据就扣除相应字节数，余额为负时由调用方等待余额回正，
+/// 因此不需要事先知道每次读取的大小。空闲时最多积累一秒的额度。
+pub struct BandwidthLimiter {
+    bytes_per_sec: AtomicU64,
+    state: parking_lot::Mutex<BandwidthState>,
+}
+
+struct BandwidthState {
+    /// 可用字节数，透支时为负
+    balance: f64,
+    updated: tokio::time::Instant,
 }
 
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec),
+            state: parking_lot::Mutex::new(BandwidthState {
+                balance: 0.0,
+                updated: tokio::time::Instant::now(),
+            }),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec.load(Ordering::Relaxed)
+    }
+
+    /// 调整速率，正在进行的传输从下一块数据起按新速率计算
+    pub fn set_bytes_per_sec(&self, bytes_per_sec: u64) {
+        self.bytes_per_sec.store(bytes_per_sec, Ordering::Relaxed);
+    }
+
+    /// 记录已传输 `bytes` 字节，返回继续传输前需要等待的时间
+    pub fn consume(&self, bytes: u64) -> Duration {
+        let rate = self.bytes_per_sec() as f64;
+        let mut state = self.state.lock();
+        let now = tokio::time::Instant::now();
+        let elapsed = now.duration_since(state.updated).as_secs_f64();
+        state.updated = now;
+        // 速率为 0 时不限速，配置校验会拒绝 0
+        if rate == 0.0 {
+            state.balance = 0.0;
+            return Duration::ZERO;
+        }
+        state.balance = (state.balance + elapsed * rate).min(rate) - bytes as f64;
+        if state.balance >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.balance / rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BACKOFF_GRACE, BandwidthLimiter, RECOVERY_INTERVAL, SlidingWindowRateLimiter,
+        TokenBucketRateLimiter,
+    };
+    use crate::core::traits::RateLimiter;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_token_bucket_acquire() {
+        let limiter = TokenBucketRateLimiter::new(2, 10.0);
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        limiter.acquire().await.unwrap();
+    }
+
+    #[test]
+    fn test_token_bucket_backs_off_and_recovers() {
+        let limiter = TokenBucketRateLimiter::new(10, 10.0);
+        assert_eq!(limiter.on_success(), None);
+
+        assert_eq!(limiter.on_throttled(None), Some(5.0));
+        // 同一批请求陆续返回的限流信号只降速一次
+        assert_eq!(limiter.on_throttled(None), None);
+        assert_eq!(limiter.current_rate(), 5.0);
+        assert!(!limiter.try_acquire());
+
+        // 恢复间隔内不恢复
+        assert_eq!(limiter.on_success(), None);
+        for expected in [6.0, 7.0, 8.0, 9.0, 10.0] {
+            let mut adjustment = limiter.adjustment.lock();
+            adjustment.adjusted_at = adjustment
+                .adjusted_at
+                .checked_sub(RECOVERY_INTERVAL)
+                .unwrap();
+            drop(adjustment);
+            assert_eq!(limiter.on_success(), Some(expected));
+        }
+        assert_eq!(limiter.on_success(), None);
+
+        // 持续限流时不低于下限
+        for _ in 0..10 {
+            let mut adjustment = limiter.adjustment.lock();
+            adjustment.backed_off_at = adjustment
+                .backed_off_at
+                .map(|at| at.checked_sub(BACKOFF_GRACE).unwrap());
+            drop(adjustment);
+            limiter.on_throttled(None);
+        }
+        assert_eq!(limiter.current_rate(), 10.0 / 64.0);
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_pauses_for_retry_after() {
+        let limiter = TokenBucketRateLimiter::new(100, 100.0);
+        limiter.on_throttled(Some(Duration::from_millis(200)));
+        let start = std::time::Instant::now();
+        limiter.acquire().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bandwidth_limiter_paces_bytes() {
+        let limiter = BandwidthLimiter::new(1_000);
+        assert_eq!(limiter.consume(500), Duration::from_millis(500));
+        // 等待期间补充的额度抵消之前的透支
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(limiter.consume(1_000), Duration::from_secs(1));
+
+        // 空闲时最多积累一秒的额度
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(limiter.consume(1_000), Duration::ZERO);
+        assert_eq!(limiter.consume(500), Duration::from_millis(500));
+
+        // 新速率对透支的 500 字节同样生效
+        limiter.set_bytes_per_sec(2_000);
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(limiter.consume(2_000), Duration::from_millis(750));
+    }
+
+    #[tokio::test]
+    async fn test_sliding_window_acquire() {
+        let limiter = SlidingWindowRateLimiter::new(Duration::from_millis(100), 1);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        limiter.acquire().await.unwrap();
+    }
+}
 /// 滑动窗口限流器
 pub struct SlidingWindowRateLimiter {
     pub(crate) window_size: Duration,
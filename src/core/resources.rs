@@ -124,6 +124,12 @@ pub struct ResourceUsage {
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+impl Default for ResourceUsage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ResourceUsage {
     pub fn new() -> Self {
         Self {
@@ -234,21 +240,29 @@ impl ResourceUsage {
 }
 
 /// 资源限制
+///
+/// 也用于全局设置、账户与任务配置，未写出的项视为不限制。
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ResourceLimits {
     pub max_memory_bytes: Option<usize>,
     pub max_disk_bytes: Option<u64>,
     pub max_file_descriptors: Option<usize>,
     pub max_cpu_percentage: Option<f64>,
+    /// 传输带宽上限（字节/秒）
     pub max_network_bandwidth_bytes_per_sec: Option<u64>,
+    #[serde(default = "default_max_concurrent_tasks")]
     pub max_concurrent_tasks: usize,
     pub max_file_size_bytes: Option<u64>,
     pub max_total_files: Option<usize>,
     pub max_retention_days: Option<u32>,
 }
 
-impl ResourceLimits {
-    pub fn default() -> Self {
+fn default_max_concurrent_tasks() -> usize {
+    ResourceLimits::default().max_concurrent_tasks
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
         Self {
             max_memory_bytes: Some(1024 * 1024 * 1024),    // 1GB
             max_disk_bytes: Some(10 * 1024 * 1024 * 1024), // 10GB
@@ -261,7 +275,9 @@ impl ResourceLimits {
             max_retention_days: Some(365),
         }
     }
+}
 
+impl ResourceLimits {
     pub fn validate(&self) -> Result<()> {
         if let Some(limit) = self.max_memory_bytes
             && limit < 1024 * 1024
@@ -287,6 +303,12 @@ impl ResourceLimits {
             ));
         }
 
+        if self.max_network_bandwidth_bytes_per_sec == Some(0) {
+            return Err(SyncError::Validation(
+                "Network bandwidth limit must be greater than 0".into(),
+            ));
+        }
+
         Ok(())
     }
 }
@@ -34,6 +34,12 @@ impl ExponentialBackoffRetry {
     }
 }
 
+impl Default for ExponentialBackoffRetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RetryStrategy for ExponentialBackoffRetry {
     fn should_retry(&self, attempt: u32, error: &SyncError) -> bool {
         if attempt >= self.max_attempts {
@@ -116,6 +122,12 @@ impl ExponentialBackoffRetryBuilder {
     }
 }
 
+impl Default for ExponentialBackoffRetryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Jitter重试策略
 pub struct JitterRetry {
     base: ExponentialBackoffRetry,
@@ -139,6 +151,12 @@ impl JitterRetry {
     }
 }
 
+impl Default for JitterRetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RetryStrategy for JitterRetry {
     fn should_retry(&self, attempt: u32, error: &SyncError) -> bool {
         self.base.should_retry(attempt, error)
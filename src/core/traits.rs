@@ -44,6 +44,17 @@ pub trait RateLimiter: Send + Sync {
     fn current_rate(&self) -> f64; // 请求/秒
     fn set_rate(&mut self, requests_per_second: f64);
     fn try_acquire(&self) -> bool;
+
+    /// 收到服务端限流信号后降低速率，`retry_after` 到期前不再放行请求；
+    /// 返回调整后的速率，未调整时返回 `None`
+    fn on_throttled(&self, _retry_after: Option<Duration>) -> Option<f64> {
+        None
+    }
+
+    /// 请求成功后逐步恢复速率，返回调整后的速率，未调整时返回 `None`
+    fn on_success(&self) -> Option<f64> {
+        None
+    }
 }
 
 /// 校验和计算 trait
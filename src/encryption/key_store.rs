@@ -0,0 +1,164 @@
+//! 本地密钥库
+//!
+//! `gen-key` 生成的密钥保存为 `<数据目录>/disksync/keys/<密钥ID>.key`，
+//! 密钥本身由主密码经 PBKDF2 派生的密钥以 AES-256-GCM 加密。
+
+use crate::error::EncryptionError;
+use crate::utils::crypto::KEY_DERIVATION_ITERATIONS;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::PathBuf;
+
+/// 当前写出的密钥文件版本
+///
+/// 版本 1 的文件由未接入 PBKDF2 时的 `gen-key` 生成，密钥加密密钥为全零，
+/// 读取时仍然兼容。
+pub const KEY_FILE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub algorithm: String,
+    pub key_strength: u32,
+    pub salt: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub encrypted_key: Vec<u8>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl KeyFile {
+    /// 用主密码加密 `key`，生成新的密钥文件
+    pub fn seal(key: &[u8], key_strength: u32, password: &str) -> Result<Self, EncryptionError> {
+        let salt: [u8; 16] = rand::random();
+        let nonce: [u8; 12] = rand::random();
+        let cipher = Self::cipher(KEY_FILE_VERSION, password, &salt);
+        let encrypted_key = cipher
+            .encrypt(aes_gcm::Nonce::from_slice(&nonce), key)
+            .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
+
+        Ok(Self {
+            version: KEY_FILE_VERSION,
+            algorithm: "AES-256-GCM".to_string(),
+            key_strength,
+            salt: salt.to_vec(),
+            nonce: nonce.to_vec(),
+            encrypted_key,
+            created_at: chrono::Utc::now(),
+            last_used: None,
+        })
+    }
+
+    /// 用主密码解出密钥，密码错误时返回 `InvalidKey`
+    pub fn open(&self, password: &str) -> Result<Vec<u8>, EncryptionError> {
+        if self.nonce.len() != 12 {
+            return Err(EncryptionError::InvalidIV);
+        }
+        Self::cipher(self.version, password, &self.salt)
+            .decrypt(
+                aes_gcm::Nonce::from_slice(&self.nonce),
+                self.encrypted_key.as_ref(),
+            )
+            .map_err(|_| EncryptionError::InvalidKey("wrong master password".into()))
+    }
+
+    fn cipher(version: u32, password: &str, salt: &[u8]) -> Aes256Gcm {
+        let mut key_encryption_key = [0u8; 32];
+        if version >= 2 {
+            pbkdf2::pbkdf2_hmac::<Sha256>(
+                password.as_bytes(),
+                salt,
+                KEY_DERIVATION_ITERATIONS,
+                &mut key_encryption_key,
+            );
+        }
+        Aes256Gcm::new(&key_encryption_key.into())
+    }
+}
+
+/// 按密钥 ID 存取密钥文件的目录
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl Default for KeyStore {
+    fn default() -> Self {
+        Self::new(Self::default_dir())
+    }
+}
+
+impl KeyStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `gen-key` 使用的默认目录
+    pub fn default_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("disksync")
+            .join("keys")
+    }
+
+    pub fn key_path(&self, key_id: &str) -> Result<PathBuf, EncryptionError> {
+        if key_id.is_empty() || key_id.contains(['/', '\\']) || key_id.starts_with('.') {
+            return Err(EncryptionError::InvalidKey(format!(
+                "invalid key ID: {}",
+                key_id
+            )));
+        }
+        Ok(self.dir.join(format!("{}.key", key_id)))
+    }
+
+    /// 写入密钥文件，返回文件路径
+    pub fn save(&self, key_id: &str, key_file: &KeyFile) -> Result<PathBuf, EncryptionError> {
+        let path = self.key_path(key_id)?;
+        let json = serde_json::to_string_pretty(key_file)
+            .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?;
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&path, json))
+            .map_err(|e| EncryptionError::InvalidKey(format!("{}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+
+    /// 读取密钥文件并用主密码解出密钥
+    pub fn load(&self, key_id: &str, password: &str) -> Result<Vec<u8>, EncryptionError> {
+        let path = self.key_path(key_id)?;
+        let json = std::fs::read_to_string(&path)
+            .map_err(|_| EncryptionError::KeyNotFound(key_id.to_string()))?;
+        let key_file: KeyFile = serde_json::from_str(&json)
+            .map_err(|e| EncryptionError::InvalidKey(format!("{}: {}", path.display(), e)))?;
+        key_file.open(password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyFile, KeyStore};
+    use crate::error::EncryptionError;
+
+    #[test]
+    fn test_key_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("keys_{}", uuid::Uuid::new_v4()));
+        let store = KeyStore::new(&dir);
+        let key = vec![7u8; 32];
+
+        let sealed = KeyFile::seal(&key, 256, "correct horse").unwrap();
+        store.save("backup", &sealed).unwrap();
+
+        assert_eq!(store.load("backup", "correct horse").unwrap(), key);
+        assert!(matches!(
+            store.load("backup", "wrong"),
+            Err(EncryptionError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            store.load("missing", "correct horse"),
+            Err(EncryptionError::KeyNotFound(_))
+        ));
+        assert!(store.key_path("../backup").is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
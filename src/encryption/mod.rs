@@ -1,3 +1,4 @@
+pub mod key_store;
 pub mod types;
 
 use crate::config::EncryptionConfig;
@@ -3,6 +3,7 @@ use rusqlite::Error as RusqliteError;
 use serde_json::Error as SerdeJsonError;
 use std::io::Error as IoError;
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -109,8 +110,9 @@ pub enum ProviderError {
     #[error("Provider quota exceeded: {0}")]
     QuotaExceeded(String),
 
+    /// 第二项为服务端要求的等待时间（`Retry-After`）
     #[error("Provider rate limited: {0}")]
-    RateLimited(String),
+    RateLimited(String, Option<Duration>),
 
     #[error("Provider authentication failed: {0}")]
     AuthFailed(String),
@@ -121,6 +123,10 @@ pub enum ProviderError {
     #[error("Provider API error: {0}")]
     ApiError(String),
 
+    /// 服务端 5xx 响应，通常是暂时性故障
+    #[error("Provider server error: {0}")]
+    ServerError(String),
+
     #[error("Provider timeout: {0}")]
     Timeout(String),
 
@@ -187,13 +193,32 @@ impl SyncError {
             | SyncError::Timeout(_)
             | SyncError::RateLimitExceeded(_)
             | SyncError::ResourceExhausted(_) => true,
-            SyncError::Provider(ProviderError::RateLimited(_))
+            SyncError::Provider(ProviderError::RateLimited(..))
             | SyncError::Provider(ProviderError::Timeout(_))
-            | SyncError::Provider(ProviderError::ConnectionFailed(_)) => true,
+            | SyncError::Provider(ProviderError::ConnectionFailed(_))
+            | SyncError::Provider(ProviderError::ServerError(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// 是否为服务端限流，包括未被提供商识别的 429 响应
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            SyncError::RateLimitExceeded(_)
+            | SyncError::Provider(ProviderError::RateLimited(..)) => true,
+            SyncError::Network(e) => e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS),
             _ => false,
         }
     }
 
+    /// 服务端要求的重试等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SyncError::Provider(ProviderError::RateLimited(_, retry_after)) => *retry_after,
+            _ => None,
+        }
+    }
+
     pub fn is_fatal(&self) -> bool {
         match self {
             SyncError::Provider(ProviderError::NotFound(_))
@@ -4,7 +4,6 @@ mod config;
 mod core;
 mod encryption;
 mod error;
-mod models;
 mod mount;
 mod plugins;
 mod providers;
@@ -127,6 +126,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 target,
                 schedule,
                 encrypt,
+                compress,
             } => {
                 let task_name = name_or_id.or(name).unwrap_or_default();
                 cmd_create_task(
@@ -136,6 +136,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     target,
                     schedule,
                     encrypt,
+                    compress,
                 )
                 .await?;
             }
@@ -1,101 +1,1227 @@
-use crate::config::AccountConfig;
+//! 阿里云盘存储提供者实现
+//!
+//! 基于阿里云盘开放平台接口，支持文件列表、上传（含预哈希与秒传校验）、下载、删除、
+//! 目录创建与文件详情查询。
+//!
+//! # 认证方式
+//! 凭据中至少需要 `refresh_token`，可选 `token`（access token）、`client_id`、
+//! `client_secret` 与 `drive_id`。access token 过期时自动刷新，刷新得到的新令牌通过
+//! [`TokenPersister`] 回写到账户配置中，避免长期运行的计划任务因令牌轮换而失效。
+
+use crate::config::{AccountConfig, NetworkSettings};
 use crate::core::rate_limit::SlidingWindowRateLimiter;
 use crate::core::traits::RateLimiter;
 use crate::error::{ProviderError, SyncError};
-use crate::providers::{DownloadResult, FileInfo, StorageProvider, UploadResult};
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, UploadResult,
+    http::{self, TlsSettings},
+};
+use crate::sync::diff::ChecksumType;
 use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures::StreamExt;
+use md5::Md5;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::SeekFrom;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{OnceCell, RwLock};
+use tokio_util::io::{ReaderStream, StreamReader};
+use tracing::{debug, info, warn};
+
+const API_BASE_URL: &str = "https://openapi.alipan.com";
+
+/// 预哈希使用的文件头长度
+const PRE_HASH_SIZE: u64 = 1024;
+
+/// 默认分片大小
+const DEFAULT_PART_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 单个文件允许的最大分片数
+const MAX_PARTS: u64 = 10_000;
+
+/// 列表接口单页条目数
+const LIST_PAGE_SIZE: u32 = 100;
+
+/// 刷新令牌后的回写回调，参数为账户ID与需要更新的凭据
+pub type TokenPersister =
+    Arc<dyn Fn(&str, &HashMap<String, String>) -> Result<(), SyncError> + Send + Sync>;
+
+/// 当前使用的令牌
+struct Tokens {
+    access_token: String,
+    refresh_token: String,
+}
+
+/// 接口错误响应
+#[derive(Debug, Default, Deserialize)]
+struct ApiErrorBody {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DriveInfo {
+    default_drive_id: String,
+}
+
+/// 文件或目录信息
+#[derive(Debug, Clone, Deserialize)]
+struct AliFile {
+    file_id: String,
+    name: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    content_hash: Option<String>,
+}
+
+impl AliFile {
+    fn is_dir(&self) -> bool {
+        self.file_type == "folder"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListResponse {
+    items: Vec<AliFile>,
+    #[serde(default)]
+    next_marker: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateResponse {
+    file_id: String,
+    #[serde(default)]
+    upload_id: Option<String>,
+    #[serde(default)]
+    rapid_upload: bool,
+    #[serde(default)]
+    part_info_list: Vec<PartInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PartInfo {
+    part_number: u64,
+    upload_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DownloadUrlResponse {
+    url: String,
+}
 
 // 阿里云盘实现
 pub struct AliYunDriveProvider {
     client: reqwest::Client,
-    token: String,
-    refresh_token: String,
+    account_id: String,
+    api_base: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    tokens: RwLock<Tokens>,
+    drive_id: OnceCell<String>,
     rate_limiter: Arc<dyn RateLimiter>,
+    token_persister: Option<TokenPersister>,
 }
 
 impl AliYunDriveProvider {
     pub async fn new(config: &AccountConfig) -> Result<Self, ProviderError> {
-        let token = config.credentials.get("token");
-
-        match token {
-            None => Err(ProviderError::NotFound("参数异常".into())),
-            Some(token_value) => Ok(Self {
-                client: reqwest::Client::new(),
-                token: token_value.clone(),
-                refresh_token: config
-                    .credentials
-                    .get("refresh_token")
-                    .cloned()
-                    .unwrap_or_default(),
-                rate_limiter: Arc::new(SlidingWindowRateLimiter {
-                    window_size: Duration::from_secs(1),
-                    max_requests: 1u64,
-                    requests: Mutex::new(vec![]),
-                }),
+        let credential = |key: &str| {
+            config
+                .credentials
+                .get(key)
+                .filter(|v| !v.is_empty())
+                .cloned()
+        };
+
+        let access_token = credential("token").unwrap_or_default();
+        let refresh_token = credential("refresh_token").unwrap_or_default();
+        if access_token.is_empty() && refresh_token.is_empty() {
+            return Err(ProviderError::MissingCredentials(
+                "token or refresh_token".into(),
+            ));
+        }
+
+        // 未配置限流时保持每秒一次请求，降低风控风险
+        let rate_limiter = match &config.rate_limit {
+            Some(limit) if limit.requests_per_minute > 0 => SlidingWindowRateLimiter {
+                window_size: Duration::from_secs(60),
+                max_requests: limit.requests_per_minute as u64,
+                requests: Mutex::new(vec![]),
+            },
+            _ => SlidingWindowRateLimiter {
+                window_size: Duration::from_secs(1),
+                max_requests: 1u64,
+                requests: Mutex::new(vec![]),
+            },
+        };
+
+        let drive_id = match credential("drive_id") {
+            Some(id) => OnceCell::new_with(Some(id)),
+            None => OnceCell::new(),
+        };
+
+        Ok(Self {
+            client: http::build_client(&NetworkSettings::default(), &TlsSettings::default())?,
+            account_id: config.id.clone(),
+            api_base: credential("api_base_url")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| API_BASE_URL.to_string()),
+            client_id: credential("client_id"),
+            client_secret: credential("client_secret"),
+            tokens: RwLock::new(Tokens {
+                access_token,
+                refresh_token,
             }),
+            drive_id,
+            rate_limiter: Arc::new(rate_limiter),
+            token_persister: None,
+        })
+    }
+
+    /// 按全局网络设置（代理、超时、请求头等）重建 HTTP 客户端
+    pub fn with_network_settings(
+        mut self,
+        network: &NetworkSettings,
+    ) -> Result<Self, ProviderError> {
+        self.client = http::build_client(network, &TlsSettings::default())?;
+        Ok(self)
+    }
+
+    /// 设置令牌刷新后的回写回调
+    pub fn with_token_persister(mut self, persister: TokenPersister) -> Self {
+        self.token_persister = Some(persister);
+        self
+    }
+
+    /// 使用 refresh_token 换取新的 access token
+    ///
+    /// `stale_token` 为调用方认为已失效的令牌；若其他请求已经完成刷新则直接返回。
+    async fn refresh_token_if_needed(&self, stale_token: &str) -> Result<(), SyncError> {
+        let mut tokens = self.tokens.write().await;
+        if tokens.access_token != stale_token {
+            return Ok(());
+        }
+        if tokens.refresh_token.is_empty() {
+            return Err(SyncError::Provider(ProviderError::AuthFailed(
+                "Access token expired and no refresh_token configured".into(),
+            )));
+        }
+
+        info!(account = %self.account_id, "刷新阿里云盘访问令牌");
+        let mut body = json!({
+            "grant_type": "refresh_token",
+            "refresh_token": tokens.refresh_token,
+        });
+        if let Some(client_id) = &self.client_id {
+            body["client_id"] = json!(client_id);
+        }
+        if let Some(client_secret) = &self.client_secret {
+            body["client_secret"] = json!(client_secret);
+        }
+
+        let resp = self
+            .client
+            .post(format!("{}/oauth/access_token", self.api_base))
+            .json(&body)
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let err: ApiErrorBody = resp.json().await.unwrap_or_default();
+            return Err(SyncError::Provider(ProviderError::AuthFailed(format!(
+                "Token refresh failed ({}): {} {}",
+                status, err.code, err.message
+            ))));
+        }
+        let token_resp: TokenResponse = resp.json().await.map_err(SyncError::Network)?;
+
+        tokens.access_token = token_resp.access_token;
+        if let Some(refresh_token) = token_resp.refresh_token {
+            tokens.refresh_token = refresh_token;
+        }
+
+        if let Some(persister) = &self.token_persister {
+            let mut updates = HashMap::new();
+            updates.insert("token".to_string(), tokens.access_token.clone());
+            updates.insert("refresh_token".to_string(), tokens.refresh_token.clone());
+            if let Err(e) = persister(&self.account_id, &updates) {
+                warn!(account = %self.account_id, error = %e, "保存刷新后的令牌失败");
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送接口请求，access token 失效时刷新后重试一次
+    async fn request(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, SyncError> {
+        let mut refreshed = false;
+        loop {
+            let token = self.tokens.read().await.access_token.clone();
+            if token.is_empty() && !refreshed {
+                self.refresh_token_if_needed(&token).await?;
+                refreshed = true;
+                continue;
+            }
+
+            self.rate_limiter.acquire().await?;
+            let resp = self
+                .client
+                .post(format!("{}{}", self.api_base, endpoint))
+                .bearer_auth(&token)
+                .json(body)
+                .send()
+                .await
+                .map_err(SyncError::Network)?;
+
+            if resp.status() == StatusCode::UNAUTHORIZED && !refreshed {
+                debug!(endpoint, "访问令牌失效，尝试刷新");
+                self.refresh_token_if_needed(&token).await?;
+                refreshed = true;
+                continue;
+            }
+            return Ok(resp);
+        }
+    }
+
+    /// 发送请求并解析成功响应
+    async fn post_json<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, SyncError> {
+        let resp = self.request(endpoint, body).await?;
+        if !resp.status().is_success() {
+            return Err(Self::api_error(resp).await);
+        }
+        resp.json().await.map_err(SyncError::Network)
+    }
+
+    /// 将失败的响应转换为 SyncError
+    async fn api_error(resp: reqwest::Response) -> SyncError {
+        let status = resp.status();
+        let retry_after = http::retry_after(resp.headers());
+        let err: ApiErrorBody = resp.json().await.unwrap_or_default();
+        let msg = format!("{}: {}", err.code, err.message);
+        SyncError::Provider(match status {
+            StatusCode::UNAUTHORIZED => ProviderError::AuthFailed(msg),
+            StatusCode::FORBIDDEN => ProviderError::PermissionDenied(msg),
+            StatusCode::NOT_FOUND => ProviderError::FileNotFound(msg),
+            StatusCode::TOO_MANY_REQUESTS => ProviderError::RateLimited(msg, retry_after),
+            _ => ProviderError::ApiError(format!("{} {}", status, msg)),
+        })
+    }
+
+    async fn drive_id(&self) -> Result<&str, SyncError> {
+        let id = self
+            .drive_id
+            .get_or_try_init(|| async {
+                let info: DriveInfo = self
+                    .post_json("/adrive/v1.0/user/getDriveInfo", &json!({}))
+                    .await?;
+                Ok::<_, SyncError>(info.default_drive_id)
+            })
+            .await?;
+        Ok(id.as_str())
+    }
+
+    /// 按路径查找文件，不存在时返回 None
+    async fn get_by_path(&self, path: &str) -> Result<Option<AliFile>, SyncError> {
+        let path = normalize_path(path);
+        if path == "/" {
+            return Ok(Some(AliFile {
+                file_id: "root".to_string(),
+                name: String::new(),
+                file_type: "folder".to_string(),
+                size: None,
+                updated_at: None,
+                content_hash: None,
+            }));
+        }
+
+        let drive_id = self.drive_id().await?;
+        let body = json!({ "drive_id": drive_id, "file_path": path });
+        let resp = self
+            .request("/adrive/v1.0/openFile/get_by_path", &body)
+            .await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(Self::api_error(resp).await);
         }
+        Ok(Some(resp.json().await.map_err(SyncError::Network)?))
     }
 
-    async fn refresh_token_if_needed(&mut self) -> Result<(), ProviderError> {
-        // 实现token刷新逻辑
+    /// 查找目录ID，路径不存在或不是目录时返回 FileNotFound
+    async fn dir_id(&self, path: &str) -> Result<String, SyncError> {
+        match self.get_by_path(path).await? {
+            Some(file) if file.is_dir() => Ok(file.file_id),
+            _ => Err(SyncError::Provider(ProviderError::FileNotFound(
+                path.to_string(),
+            ))),
+        }
+    }
+
+    async fn create_folder(&self, parent_id: &str, name: &str) -> Result<String, SyncError> {
+        let drive_id = self.drive_id().await?;
+        let body = json!({
+            "drive_id": drive_id,
+            "parent_file_id": parent_id,
+            "name": name,
+            "type": "folder",
+            "check_name_mode": "refuse",
+        });
+        let created: CreateResponse = self
+            .post_json("/adrive/v1.0/openFile/create", &body)
+            .await?;
+        Ok(created.file_id)
+    }
+
+    /// 上传各个分片，分片地址由创建文件接口返回
+    async fn upload_parts(
+        &self,
+        local_path: &Path,
+        part_size: u64,
+        parts: &[PartInfo],
+    ) -> Result<(), SyncError> {
+        for part in parts {
+            let offset = (part.part_number - 1) * part_size;
+            let mut file = tokio::fs::File::open(local_path)
+                .await
+                .map_err(SyncError::Io)?;
+            file.seek(SeekFrom::Start(offset))
+                .await
+                .map_err(SyncError::Io)?;
+            let len = file
+                .metadata()
+                .await
+                .map_err(SyncError::Io)?
+                .len()
+                .saturating_sub(offset)
+                .min(part_size);
+
+            debug!(part = part.part_number, len, "上传分片");
+            // 分片地址已签名，不能携带 Authorization 与 Content-Type
+            self.client
+                .put(&part.upload_url)
+                .header(reqwest::header::CONTENT_LENGTH, len)
+                .body(reqwest::Body::wrap_stream(ReaderStream::new(
+                    file.take(len),
+                )))
+                .send()
+                .await
+                .map_err(SyncError::Network)?
+                .error_for_status()
+                .map_err(SyncError::Network)?;
+        }
         Ok(())
     }
 }
 
+/// 规范化路径：以"/"开头，去除多余及末尾的"/"
+fn normalize_path(path: &str) -> String {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    format!("/{}", components.join("/"))
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    format!("{}/{}", parent.trim_end_matches('/'), name)
+}
+
+/// 拆分为 (父目录, 名称)，路径需已规范化
+fn split_parent(path: &str) -> (String, String) {
+    match path.rsplit_once('/') {
+        Some(("", name)) => ("/".to_string(), name.to_string()),
+        Some((parent, name)) => (parent.to_string(), name.to_string()),
+        None => ("/".to_string(), path.to_string()),
+    }
+}
+
+fn to_file_info(parent: &str, file: &AliFile) -> FileInfo {
+    let modified = file
+        .updated_at
+        .as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.timestamp())
+        .unwrap_or(0);
+
+    FileInfo {
+        path: join_path(parent, &file.name),
+        size: file.size.unwrap_or(0),
+        modified,
+        hash: file.content_hash.as_ref().map(|h| h.to_uppercase()),
+        is_dir: file.is_dir(),
+        permissions: None,
+    }
+}
+
+/// 分片大小：默认 10MB，超大文件按分片数上限放大
+fn part_size_for(file_size: u64) -> u64 {
+    DEFAULT_PART_SIZE.max(file_size.div_ceil(MAX_PARTS))
+}
+
+/// 计算文件头部的预哈希（SHA1，大写十六进制）
+async fn pre_hash(path: &Path) -> Result<String, SyncError> {
+    let file = tokio::fs::File::open(path).await.map_err(SyncError::Io)?;
+    let mut buf = Vec::new();
+    file.take(PRE_HASH_SIZE)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(SyncError::Io)?;
+    Ok(hex::encode_upper(Sha1::digest(&buf)))
+}
+
+/// 计算整个文件的SHA1（大写十六进制）
+async fn content_hash(path: &Path) -> Result<String, SyncError> {
+    let mut file = tokio::fs::File::open(path).await.map_err(SyncError::Io)?;
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await.map_err(SyncError::Io)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode_upper(hasher.finalize()))
+}
+
+/// 计算秒传校验码（proof_version v1）
+///
+/// 取 access token 的 MD5 前 16 位作为数字对文件大小取模得到起点，
+/// 读取起点开始的 8 个字节并做 base64 编码。
+async fn proof_code(access_token: &str, path: &Path, size: u64) -> Result<String, SyncError> {
+    if size == 0 {
+        return Ok(String::new());
+    }
+    let digest = hex::encode(Md5::digest(access_token.as_bytes()));
+    let seed = u64::from_str_radix(&digest[..16], 16).map_err(|e| {
+        SyncError::Provider(ProviderError::ApiError(format!(
+            "Invalid proof seed: {}",
+            e
+        )))
+    })?;
+    let start = seed % size;
+    let end = (start + 8).min(size);
+
+    let mut file = tokio::fs::File::open(path).await.map_err(SyncError::Io)?;
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(SyncError::Io)?;
+    let mut buf = vec![0u8; (end - start) as usize];
+    file.read_exact(&mut buf).await.map_err(SyncError::Io)?;
+    Ok(BASE64.encode(buf))
+}
+
 #[async_trait]
 impl StorageProvider for AliYunDriveProvider {
     async fn verify(&self) -> Result<(), SyncError> {
-        todo!()
+        let _: DriveInfo = self
+            .post_json("/adrive/v1.0/user/getDriveInfo", &json!({}))
+            .await?;
+        Ok(())
     }
 
     async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
-        Ok(vec![FileInfo {
-            path: path.to_string(),
-            size: 0,
-            modified: 0,
-            hash: None,
-            is_dir: true,
-        }])
+        let path = normalize_path(path);
+        let parent_id = self.dir_id(&path).await?;
+        let drive_id = self.drive_id().await?;
+
+        let mut files = Vec::new();
+        let mut marker = String::new();
+        loop {
+            let body = json!({
+                "drive_id": drive_id,
+                "parent_file_id": parent_id,
+                "limit": LIST_PAGE_SIZE,
+                "marker": marker,
+            });
+            let page: ListResponse = self.post_json("/adrive/v1.0/openFile/list", &body).await?;
+            files.extend(page.items.iter().map(|f| to_file_info(&path, f)));
+            match page.next_marker {
+                Some(next) if !next.is_empty() => marker = next,
+                _ => break,
+            }
+        }
+        Ok(files)
     }
+
     async fn upload(
         &self,
-        _local_path: &Path,
-        _remote_path: &str,
+        local_path: &Path,
+        remote_path: &str,
     ) -> Result<UploadResult, SyncError> {
-        // 实现阿里云盘上传逻辑
-        // 支持分片上传、断点续传
-        Ok(UploadResult::default())
+        let start_time = Instant::now();
+        let remote_path = normalize_path(remote_path);
+        let (parent, name) = split_parent(&remote_path);
+        let file_size = tokio::fs::metadata(local_path)
+            .await
+            .map_err(SyncError::Io)?
+            .len();
+
+        self.mkdir(&parent).await?;
+        let parent_id = self.dir_id(&parent).await?;
+
+        // 同名文件会被自动重命名，因此先删除旧文件
+        if let Some(existing) = self.get_by_path(&remote_path).await? {
+            if existing.is_dir() {
+                return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                    "Cannot overwrite directory with file: {}",
+                    remote_path
+                ))));
+            }
+            self.delete(&remote_path).await?;
+        }
+
+        let part_size = part_size_for(file_size);
+        let part_count = file_size.div_ceil(part_size).max(1);
+        let part_info_list: Vec<serde_json::Value> = (1..=part_count)
+            .map(|n| json!({ "part_number": n }))
+            .collect();
+        let drive_id = self.drive_id().await?;
+        let mut body = json!({
+            "drive_id": drive_id,
+            "parent_file_id": parent_id,
+            "name": name,
+            "type": "file",
+            "check_name_mode": "refuse",
+            "size": file_size,
+            "part_info_list": part_info_list,
+        });
+
+        // 先只提交文件头的预哈希，命中后再计算完整哈希与校验码尝试秒传
+        body["pre_hash"] = json!(pre_hash(local_path).await?);
+        let resp = self.request("/adrive/v1.0/openFile/create", &body).await?;
+        let mut checksum = None;
+        let created: CreateResponse = if resp.status() == StatusCode::CONFLICT {
+            let err: ApiErrorBody = resp.json().await.unwrap_or_default();
+            if err.code != "PreHashMatched" {
+                return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                    "{}: {}",
+                    err.code, err.message
+                ))));
+            }
+            debug!(path = %remote_path, "预哈希命中，尝试秒传");
+            let hash = content_hash(local_path).await?;
+            let access_token = self.tokens.read().await.access_token.clone();
+            let body = body.as_object_mut().expect("request body is an object");
+            body.remove("pre_hash");
+            body.insert("content_hash".into(), json!(hash));
+            body.insert("content_hash_name".into(), json!("sha1"));
+            body.insert(
+                "proof_code".into(),
+                json!(proof_code(&access_token, local_path, file_size).await?),
+            );
+            body.insert("proof_version".into(), json!("v1"));
+            checksum = Some(hash);
+            self.post_json("/adrive/v1.0/openFile/create", &json!(body))
+                .await?
+        } else if resp.status().is_success() {
+            resp.json().await.map_err(SyncError::Network)?
+        } else {
+            return Err(Self::api_error(resp).await);
+        };
+
+        if created.rapid_upload {
+            info!(path = %remote_path, "秒传成功");
+            return Ok(UploadResult {
+                bytes_uploaded: 0,
+                file_size,
+                checksum,
+                elapsed_time: start_time.elapsed(),
+                compression: None,
+            });
+        }
+
+        let upload_id = created.upload_id.clone().ok_or_else(|| {
+            SyncError::Provider(ProviderError::ApiError(
+                "Create file response missing upload_id".into(),
+            ))
+        })?;
+        self.upload_parts(local_path, part_size, &created.part_info_list)
+            .await?;
+
+        let complete = json!({
+            "drive_id": drive_id,
+            "file_id": created.file_id,
+            "upload_id": upload_id,
+        });
+        let _: serde_json::Value = self
+            .post_json("/adrive/v1.0/openFile/complete", &complete)
+            .await?;
+
+        Ok(UploadResult {
+            bytes_uploaded: file_size,
+            file_size,
+            checksum,
+            elapsed_time: start_time.elapsed(),
+            compression: None,
+        })
     }
 
     async fn download(
         &self,
-        _remote_path: &str,
-        _local_path: &Path,
+        remote_path: &str,
+        local_path: &Path,
     ) -> Result<DownloadResult, SyncError> {
-        // 实现下载逻辑
-        Ok(DownloadResult::default())
+        let start_time = Instant::now();
+        let mut reader = self.download_stream(remote_path).await?;
+
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(SyncError::Io)?;
+        }
+        let mut file = tokio::fs::File::create(local_path)
+            .await
+            .map_err(SyncError::Io)?;
+        let file_size = tokio::io::copy(&mut reader, &mut file)
+            .await
+            .map_err(SyncError::Io)?;
+        file.flush().await.map_err(SyncError::Io)?;
+
+        Ok(DownloadResult {
+            bytes_downloaded: file_size,
+            file_size,
+            checksum: None,
+            elapsed_time: start_time.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let file = match self.get_by_path(remote_path).await? {
+            Some(file) if !file.is_dir() => file,
+            _ => {
+                return Err(SyncError::Provider(ProviderError::FileNotFound(
+                    remote_path.to_string(),
+                )));
+            }
+        };
+        let drive_id = self.drive_id().await?;
+        let body = json!({ "drive_id": drive_id, "file_id": file.file_id });
+        let download: DownloadUrlResponse = self
+            .post_json("/adrive/v1.0/openFile/getDownloadUrl", &body)
+            .await?;
+
+        let resp = self
+            .client
+            .get(&download.url)
+            .send()
+            .await
+            .map_err(SyncError::Network)?
+            .error_for_status()
+            .map_err(SyncError::Network)?;
+        let stream = resp
+            .bytes_stream()
+            .map(|r| r.map_err(std::io::Error::other));
+        Ok(Box::new(StreamReader::new(stream)))
     }
 
-    async fn delete(&self, _path: &str) -> Result<(), SyncError> {
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let path = normalize_path(path);
+        if path == "/" {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete root directory".into(),
+            )));
+        }
+        let Some(file) = self.get_by_path(&path).await? else {
+            return Ok(());
+        };
+
+        // 放入回收站而不是彻底删除，误删时仍可恢复
+        let drive_id = self.drive_id().await?;
+        let body = json!({ "drive_id": drive_id, "file_id": file.file_id });
+        let _: serde_json::Value = self
+            .post_json("/adrive/v1.0/openFile/recyclebin/trash", &body)
+            .await?;
         Ok(())
     }
-    async fn mkdir(&self, _path: &str) -> Result<(), SyncError> {
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        // 逐级创建缺失的目录
+        let path = normalize_path(path);
+        let mut current_path = "/".to_string();
+        let mut current_id = "root".to_string();
+
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            let next_path = join_path(&current_path, name);
+            current_id = match self.get_by_path(&next_path).await? {
+                Some(file) if file.is_dir() => file.file_id,
+                Some(_) => {
+                    return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                        "Path exists and is not a directory: {}",
+                        next_path
+                    ))));
+                }
+                None => {
+                    debug!(path = %next_path, "创建目录");
+                    self.create_folder(&current_id, name).await?
+                }
+            };
+            current_path = next_path;
+        }
         Ok(())
     }
+
     async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
-        Ok(FileInfo {
-            path: path.to_string(),
-            size: 0,
-            modified: 0,
-            hash: None,
-            is_dir: true,
-        })
+        let path = normalize_path(path);
+        if path == "/" {
+            return Ok(FileInfo {
+                path,
+                size: 0,
+                modified: 0,
+                hash: None,
+                is_dir: true,
+                permissions: None,
+            });
+        }
+
+        let (parent, _) = split_parent(&path);
+        match self.get_by_path(&path).await? {
+            Some(file) => Ok(to_file_info(&parent, &file)),
+            None => Err(SyncError::Provider(ProviderError::FileNotFound(path))),
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_hash: Some(ChecksumType::Sha1),
+            supports_range_read: true,
+            ..ProviderCapabilities::default()
+        }
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(SyncError::Provider(ProviderError::FileNotFound(_))) => Ok(false),
+            Err(e) => Err(e),
+        }
     }
-    async fn exists(&self, _path: &str) -> Result<bool, SyncError> {
-        Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProviderType, RateLimitConfig, RetryPolicy};
+    use std::net::SocketAddr;
+    use warp::Filter;
+
+    const EXPIRED_TOKEN: &str = "access-1";
+    const VALID_TOKEN: &str = "access-2";
+
+    #[derive(Clone)]
+    struct MockFile {
+        parent: String,
+        name: String,
+        is_dir: bool,
+        content: Vec<u8>,
+    }
+
+    type PendingUpload = (String, String, HashMap<u64, Vec<u8>>);
+
+    #[derive(Default)]
+    struct MockDrive {
+        files: HashMap<String, MockFile>,
+        /// 未完成的上传：file_id -> (父目录, 文件名, 分片)
+        uploads: HashMap<String, PendingUpload>,
+        next_id: u64,
+        part_uploads: usize,
+    }
+
+    type SharedDrive = Arc<Mutex<MockDrive>>;
+
+    impl MockDrive {
+        fn alloc_id(&mut self) -> String {
+            self.next_id += 1;
+            format!("f{}", self.next_id)
+        }
+
+        fn find_child(&self, parent: &str, name: &str) -> Option<(String, MockFile)> {
+            self.files
+                .iter()
+                .find(|(_, f)| f.parent == parent && f.name == name)
+                .map(|(id, f)| (id.clone(), f.clone()))
+        }
+
+        fn to_json(id: &str, f: &MockFile) -> serde_json::Value {
+            json!({
+                "file_id": id,
+                "name": f.name,
+                "type": if f.is_dir { "folder" } else { "file" },
+                "size": f.content.len(),
+                "updated_at": "2024-01-02T03:04:05.000Z",
+                "content_hash": if f.is_dir { None } else { Some(hex::encode_upper(Sha1::digest(&f.content))) },
+            })
+        }
+    }
+
+    fn reply(status: u16, body: serde_json::Value) -> warp::reply::Response {
+        use warp::Reply;
+        warp::reply::with_status(
+            warp::reply::json(&body),
+            warp::http::StatusCode::from_u16(status).unwrap(),
+        )
+        .into_response()
+    }
+
+    fn handle_api(
+        drive: &SharedDrive,
+        addr: SocketAddr,
+        path: &str,
+        auth: Option<String>,
+        body: serde_json::Value,
+    ) -> warp::reply::Response {
+        if path == "/oauth/access_token" {
+            return if body["refresh_token"] == "refresh-1" {
+                reply(
+                    200,
+                    json!({ "access_token": VALID_TOKEN, "refresh_token": "refresh-2", "expires_in": 7200 }),
+                )
+            } else {
+                reply(
+                    400,
+                    json!({ "code": "InvalidRefreshToken", "message": "bad" }),
+                )
+            };
+        }
+        if auth.as_deref() != Some(&format!("Bearer {}", VALID_TOKEN)) {
+            return reply(
+                401,
+                json!({ "code": "AccessTokenInvalid", "message": "expired" }),
+            );
+        }
+
+        let mut d = drive.lock().unwrap();
+        let str_field = |key: &str| body[key].as_str().unwrap_or_default().to_string();
+        match path {
+            "/adrive/v1.0/user/getDriveInfo" => reply(200, json!({ "default_drive_id": "drive1" })),
+            "/adrive/v1.0/openFile/get_by_path" => {
+                let mut current = ("root".to_string(), None);
+                for name in str_field("file_path").split('/').filter(|c| !c.is_empty()) {
+                    match d.find_child(&current.0, name) {
+                        Some((id, f)) => current = (id, Some(f)),
+                        None => {
+                            return reply(404, json!({ "code": "NotFound.File", "message": "" }));
+                        }
+                    }
+                }
+                let f = current.1.expect("root is not queried by path");
+                reply(200, MockDrive::to_json(&current.0, &f))
+            }
+            "/adrive/v1.0/openFile/list" => {
+                let parent = str_field("parent_file_id");
+                let offset: usize = str_field("marker").parse().unwrap_or(0);
+                let limit = body["limit"].as_u64().unwrap_or(100) as usize;
+                let mut items: Vec<_> =
+                    d.files.iter().filter(|(_, f)| f.parent == parent).collect();
+                items.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+                let page: Vec<_> = items
+                    .iter()
+                    .skip(offset)
+                    .take(limit)
+                    .map(|(id, f)| MockDrive::to_json(id, f))
+                    .collect();
+                let next = if offset + limit < items.len() {
+                    (offset + limit).to_string()
+                } else {
+                    String::new()
+                };
+                reply(200, json!({ "items": page, "next_marker": next }))
+            }
+            "/adrive/v1.0/openFile/create" => {
+                let parent = str_field("parent_file_id");
+                let name = str_field("name");
+                if d.find_child(&parent, &name).is_some() {
+                    return reply(409, json!({ "code": "AlreadyExist.File", "message": "" }));
+                }
+                if str_field("type") == "folder" {
+                    let id = d.alloc_id();
+                    d.files.insert(
+                        id.clone(),
+                        MockFile {
+                            parent,
+                            name,
+                            is_dir: true,
+                            content: vec![],
+                        },
+                    );
+                    return reply(200, json!({ "file_id": id }));
+                }
+
+                let existing = |d: &MockDrive, pred: &dyn Fn(&[u8]) -> bool| {
+                    d.files
+                        .values()
+                        .find(|f| !f.is_dir && pred(&f.content))
+                        .cloned()
+                };
+                if let Some(pre) = body["pre_hash"].as_str() {
+                    let matched = existing(&d, &|c: &[u8]| {
+                        hex::encode_upper(Sha1::digest(&c[..c.len().min(1024)])) == pre
+                    });
+                    if matched.is_some() {
+                        return reply(409, json!({ "code": "PreHashMatched", "message": "" }));
+                    }
+                } else if let Some(hash) = body["content_hash"].as_str() {
+                    let same = existing(&d, &|c: &[u8]| hex::encode_upper(Sha1::digest(c)) == hash);
+                    if let Some(same) = same {
+                        let digest = hex::encode(Md5::digest(VALID_TOKEN.as_bytes()));
+                        let size = same.content.len() as u64;
+                        let start =
+                            (u64::from_str_radix(&digest[..16], 16).unwrap() % size) as usize;
+                        let end = (start + 8).min(size as usize);
+                        if body["proof_code"] != BASE64.encode(&same.content[start..end]) {
+                            return reply(
+                                400,
+                                json!({ "code": "ProofCodeMismatch", "message": "" }),
+                            );
+                        }
+                        let id = d.alloc_id();
+                        d.files.insert(
+                            id.clone(),
+                            MockFile {
+                                parent,
+                                name,
+                                is_dir: false,
+                                content: same.content,
+                            },
+                        );
+                        return reply(200, json!({ "file_id": id, "rapid_upload": true }));
+                    }
+                }
+
+                let id = d.alloc_id();
+                let parts: Vec<_> = body["part_info_list"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| {
+                        let n = p["part_number"].as_u64().unwrap();
+                        json!({ "part_number": n, "upload_url": format!("http://{}/upload/{}/{}", addr, id, n) })
+                    })
+                    .collect();
+                d.uploads.insert(id.clone(), (parent, name, HashMap::new()));
+                reply(
+                    200,
+                    json!({ "file_id": id, "upload_id": "u1", "rapid_upload": false, "part_info_list": parts }),
+                )
+            }
+            "/adrive/v1.0/openFile/complete" => {
+                let id = str_field("file_id");
+                let Some((parent, name, parts)) = d.uploads.remove(&id) else {
+                    return reply(404, json!({ "code": "NotFound.Upload", "message": "" }));
+                };
+                let mut numbers: Vec<_> = parts.keys().copied().collect();
+                numbers.sort();
+                let content = numbers.iter().flat_map(|n| parts[n].clone()).collect();
+                d.files.insert(
+                    id.clone(),
+                    MockFile {
+                        parent,
+                        name,
+                        is_dir: false,
+                        content,
+                    },
+                );
+                reply(200, json!({ "file_id": id }))
+            }
+            "/adrive/v1.0/openFile/getDownloadUrl" => {
+                let id = str_field("file_id");
+                reply(
+                    200,
+                    json!({ "url": format!("http://{}/download/{}", addr, id) }),
+                )
+            }
+            "/adrive/v1.0/openFile/recyclebin/trash" => {
+                let id = str_field("file_id");
+                let mut stack = vec![id];
+                while let Some(id) = stack.pop() {
+                    d.files.remove(&id);
+                    stack.extend(
+                        d.files
+                            .iter()
+                            .filter(|(_, f)| f.parent == id)
+                            .map(|(child, _)| child.clone()),
+                    );
+                }
+                reply(200, json!({}))
+            }
+            _ => reply(404, json!({ "code": "NotFound", "message": path })),
+        }
+    }
+
+    async fn start_mock_server() -> (SocketAddr, SharedDrive) {
+        let drive: SharedDrive = Arc::new(Mutex::new(MockDrive::default()));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let api_drive = drive.clone();
+        let api = warp::post()
+            .and(warp::path::full())
+            .and(warp::header::optional::<String>("authorization"))
+            .and(warp::body::json())
+            .map(move |path: warp::path::FullPath, auth, body| {
+                handle_api(&api_drive, addr, path.as_str(), auth, body)
+            });
+
+        let part_drive = drive.clone();
+        let part_upload = warp::put()
+            .and(warp::path!("upload" / String / u64))
+            .and(warp::body::bytes())
+            .map(move |id: String, n: u64, body: bytes::Bytes| {
+                let mut d = part_drive.lock().unwrap();
+                d.part_uploads += 1;
+                if let Some(upload) = d.uploads.get_mut(&id) {
+                    upload.2.insert(n, body.to_vec());
+                }
+                warp::reply()
+            });
+
+        let download_drive = drive.clone();
+        let download = warp::get()
+            .and(warp::path!("download" / String))
+            .map(move |id: String| {
+                let d = download_drive.lock().unwrap();
+                d.files
+                    .get(&id)
+                    .map(|f| f.content.clone())
+                    .unwrap_or_default()
+            });
+
+        tokio::spawn(warp::serve(api.or(part_upload).or(download)).run(addr));
+        let start = Instant::now();
+        while tokio::net::TcpStream::connect(addr).await.is_err() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "mock server did not start"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        (addr, drive)
+    }
+
+    fn account(addr: SocketAddr) -> AccountConfig {
+        let mut credentials = HashMap::new();
+        credentials.insert("token".to_string(), EXPIRED_TOKEN.to_string());
+        credentials.insert("refresh_token".to_string(), "refresh-1".to_string());
+        credentials.insert("api_base_url".to_string(), format!("http://{}", addr));
+        AccountConfig {
+            id: "aliyun_test".to_string(),
+            provider: ProviderType::AliYunDrive,
+            name: "aliyun".to_string(),
+            credentials,
+            rate_limit: Some(RateLimitConfig {
+                requests_per_minute: 60_000,
+                max_concurrent: 1,
+                chunk_size: 1024,
+            }),
+            retry_policy: RetryPolicy::default(),
+            resource_limits: None,
+        }
+    }
+
+    #[test]
+    fn test_part_size_for() {
+        assert_eq!(part_size_for(0), DEFAULT_PART_SIZE);
+        assert_eq!(
+            part_size_for(DEFAULT_PART_SIZE * MAX_PARTS * 2),
+            DEFAULT_PART_SIZE * 2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_refresh_is_persisted() {
+        let (addr, _) = start_mock_server().await;
+        let persisted = Arc::new(Mutex::new(Vec::new()));
+        let sink = persisted.clone();
+        let provider = AliYunDriveProvider::new(&account(addr))
+            .await
+            .unwrap()
+            .with_token_persister(Arc::new(move |id, updates| {
+                sink.lock().unwrap().push((id.to_string(), updates.clone()));
+                Ok(())
+            }));
+
+        provider.verify().await.unwrap();
+        provider.verify().await.unwrap();
+
+        let persisted = persisted.lock().unwrap();
+        assert_eq!(persisted.len(), 1, "token should only be refreshed once");
+        assert_eq!(persisted[0].0, "aliyun_test");
+        assert_eq!(persisted[0].1["token"], VALID_TOKEN);
+        assert_eq!(persisted[0].1["refresh_token"], "refresh-2");
+    }
+
+    #[tokio::test]
+    async fn test_upload_rapid_upload_download_delete() {
+        let (addr, drive) = start_mock_server().await;
+        let provider = AliYunDriveProvider::new(&account(addr)).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("aliyun_test_{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let local = dir.join("data.bin");
+        let content: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+        tokio::fs::write(&local, &content).await.unwrap();
+
+        let first = provider.upload(&local, "/backup/a/data.bin").await.unwrap();
+        assert_eq!(first.bytes_uploaded, content.len() as u64);
+        assert!(provider.stat("/backup/a").await.unwrap().is_dir);
+
+        // 内容相同的文件走预哈希 + 校验码秒传
+        let second = provider.upload(&local, "/backup/b/copy.bin").await.unwrap();
+        assert_eq!(second.bytes_uploaded, 0);
+        assert_eq!(drive.lock().unwrap().part_uploads, 1);
+
+        let info = provider.stat("/backup/b/copy.bin").await.unwrap();
+        assert_eq!(info.size, content.len() as u64);
+        assert_eq!(info.hash, second.checksum);
+        assert_eq!(info.modified, 1704164645);
+
+        let listed = provider.list("/backup").await.unwrap();
+        let mut names: Vec<_> = listed.iter().map(|f| f.path.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["/backup/a", "/backup/b"]);
+
+        let downloaded = dir.join("downloaded.bin");
+        provider
+            .download("/backup/a/data.bin", &downloaded)
+            .await
+            .unwrap();
+        assert_eq!(tokio::fs::read(&downloaded).await.unwrap(), content);
+
+        provider.delete("/backup/a").await.unwrap();
+        assert!(!provider.exists("/backup/a/data.bin").await.unwrap());
+        assert!(provider.exists("/backup/b/copy.bin").await.unwrap());
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
     }
 }
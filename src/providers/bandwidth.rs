@@ -0,0 +1,301 @@
+//! 带宽限制装饰器
+//!
+//! 按账户的 `ResourceLimits.max_network_bandwidth_bytes_per_sec` 限制传输速率。
+//! 传输按读取的数据块逐块限速，基于文件的上传和下载也改走流式接口，不会先全速传完再补足等待。
+//! 通过 [`BandwidthLimitedProvider::for_account`] 创建的实例按账户 ID
+//! 共享同一个限速器。
+
+use crate::core::rate_limit::BandwidthLimiter;
+use crate::error::SyncError;
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, StorageQuota,
+    UploadResult,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::{Instant, Sleep, sleep};
+use tracing::debug;
+
+/// 按账户 ID 登记的限速器
+static ACCOUNT_BANDWIDTH: LazyLock<Mutex<HashMap<String, Arc<BandwidthLimiter>>>> =
+    LazyLock::new(Default::default);
+
+/// 让读取器受 `limiters` 中每个限速器的约束，没有限速器时原样返回
+pub fn limit_stream(stream: ByteStream, limiters: Vec<Arc<BandwidthLimiter>>) -> ByteStream {
+    if limiters.is_empty() {
+        return stream;
+    }
+    Box::new(BandwidthLimitedStream {
+        inner: stream,
+        limiters,
+        delay: None,
+    })
+}
+
+/// 按已读字节数扣减额度的读取器：额度透支时，等到额度回正再继续读
+struct BandwidthLimitedStream {
+    inner: ByteStream,
+    limiters: Vec<Arc<BandwidthLimiter>>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl AsyncRead for BandwidthLimitedStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(delay) = this.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            this.delay = None;
+        }
+
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        let read = (buf.filled().len() - before) as u64;
+        if read > 0 {
+            let wait = this
+                .limiters
+                .iter()
+                .map(|limiter| limiter.consume(read))
+                .max()
+                .unwrap_or_default();
+            if !wait.is_zero() {
+                this.delay = Some(Box::pin(sleep(wait)));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct BandwidthLimitedProvider<T> {
+    inner: T,
+    limiter: Arc<BandwidthLimiter>,
+}
+
+impl<T: StorageProvider> BandwidthLimitedProvider<T> {
+    /// 与同一账户的其他实例共用限速器，速率以最新的配置为准
+    pub fn for_account(inner: T, account_id: &str, bytes_per_sec: u64) -> Self {
+        let mut registry = ACCOUNT_BANDWIDTH.lock().unwrap();
+        let limiter = registry
+            .entry(account_id.to_string())
+            .or_insert_with(|| {
+                debug!(
+                    account = account_id,
+                    bytes_per_sec = bytes_per_sec,
+                    "创建账户带宽限速器"
+                );
+                Arc::new(BandwidthLimiter::new(bytes_per_sec))
+            })
+            .clone();
+        limiter.set_bytes_per_sec(bytes_per_sec);
+        Self { inner, limiter }
+    }
+
+    /// 已传输 `bytes` 字节，等到平均速率回到上限以内
+    fn limit(&self, stream: ByteStream) -> ByteStream {
+        limit_stream(stream, vec![self.limiter.clone()])
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for BandwidthLimitedProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        self.inner.verify().await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.inner.list(path).await
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.inner.list_recursive(path).await
+    }
+
+    /// 以流的方式上传本地文件，逐块限速
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let file = tokio::fs::File::open(local_path).await?;
+        let size = file.metadata().await?.len();
+        self.upload_stream(Box::new(file), size, remote_path).await
+    }
+
+    /// 以流的方式下载到本地文件，逐块限速
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let mut reader = self.download_stream(remote_path).await?;
+        let mut file = tokio::fs::File::create(local_path).await?;
+        let written = tokio::io::copy(&mut reader, &mut file).await?;
+        Ok(DownloadResult {
+            bytes_downloaded: written,
+            file_size: written,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        self.inner.delete(path).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        self.inner.mkdir(path).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        self.inner.stat(path).await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        self.inner.exists(path).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        self.inner.quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        self.inner.set_mtime(path, modified).await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        self.inner.set_permissions(path, mode).await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inner.move_path(from, to).await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inner.copy_path(from, to).await
+    }
+
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        self.inner
+            .upload_stream(self.limit(reader), size, remote_path)
+            .await
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let stream = self.inner.download_stream(remote_path).await?;
+        Ok(self.limit(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MemoryProvider;
+    use tokio::io::AsyncReadExt;
+    use tokio::time::{Duration, Instant};
+
+    fn provider_with_file(size: usize) -> MemoryProvider {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.bin", vec![7u8; size], 0).unwrap();
+        memory
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_download_stream_is_paced() {
+        let provider = BandwidthLimitedProvider::for_account(
+            provider_with_file(10_000),
+            &format!("account_{}", uuid::Uuid::new_v4()),
+            2_000,
+        );
+        let start = Instant::now();
+        let mut data = Vec::new();
+        provider
+            .download_stream("/f.bin")
+            .await
+            .unwrap()
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), 10_000);
+        assert!(start.elapsed() >= Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_upload_stream_shares_account_limit() {
+        let account = format!("account_{}", uuid::Uuid::new_v4());
+        let first = BandwidthLimitedProvider::for_account(MemoryProvider::new(), &account, 1_000);
+        let second = BandwidthLimitedProvider::for_account(MemoryProvider::new(), &account, 1_000);
+        assert!(Arc::ptr_eq(&first.limiter, &second.limiter));
+
+        async fn upload(provider: &BandwidthLimitedProvider<MemoryProvider>) {
+            let reader: ByteStream = Box::new(std::io::Cursor::new(vec![1u8; 3_000]));
+            provider
+                .upload_stream(reader, 3_000, "/f.bin")
+                .await
+                .unwrap();
+        }
+        let start = Instant::now();
+        tokio::join!(upload(&first), upload(&second));
+        // 两次上传共 6000 字节，共用每秒 1000 字节的额度
+        assert!(start.elapsed() >= Duration::from_secs(6));
+
+        // 账户配置变化后沿用同一个限速器并采用新速率
+        let resized = BandwidthLimitedProvider::for_account(MemoryProvider::new(), &account, 5_000);
+        assert!(Arc::ptr_eq(&resized.limiter, &first.limiter));
+        assert_eq!(first.limiter.bytes_per_sec(), 5_000);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_file_upload_is_paced_per_chunk() {
+        let memory = MemoryProvider::new();
+        let provider = BandwidthLimitedProvider::for_account(
+            &memory,
+            &format!("account_{}", uuid::Uuid::new_v4()),
+            2_000,
+        );
+        let local = std::env::temp_dir().join(format!("bandwidth_{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&local, vec![3u8; 10_000]).await.unwrap();
+
+        // 传输过程中底层还没收到完整文件，而不是先全速上传再等待
+        let start = Instant::now();
+        let (result, midway) = tokio::join!(provider.upload(&local, "/f.bin"), async {
+            sleep(Duration::from_secs(2)).await;
+            memory.exists("/f.bin").await.unwrap()
+        });
+        assert_eq!(result.unwrap().bytes_uploaded, 10_000);
+        assert!(!midway);
+        assert!(start.elapsed() >= Duration::from_secs(4));
+
+        let downloaded = local.with_extension("out");
+        let start = Instant::now();
+        let result = provider.download("/f.bin", &downloaded).await.unwrap();
+        assert_eq!(result.bytes_downloaded, 10_000);
+        assert!(start.elapsed() >= Duration::from_secs(4));
+        assert_eq!(
+            tokio::fs::read(&downloaded).await.unwrap(),
+            vec![3u8; 10_000]
+        );
+
+        tokio::fs::remove_file(&local).await.ok();
+        tokio::fs::remove_file(&downloaded).await.ok();
+    }
+}
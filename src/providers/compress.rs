@@ -0,0 +1,710 @@
+//! 压缩覆盖层
+//!
+//! 上传时以 zstd 或 gzip 压缩文件内容，下载时透明解压。压缩后的文件以
+//! `<原文件名>.csz-<原始大小的十六进制>.<zst|gz>` 保存，列出时还原为原文件名与原始大小，
+//! 差异比较因此不会把压缩文件误判为已变更；解压算法按后缀确定，与当前配置无关。
+//!
+//! 图片、音视频、压缩包等已压缩的格式原样上传。
+
+use crate::config::{CompressionAlgorithm, CompressionConfig};
+use crate::error::SyncError;
+use crate::providers::{
+    ByteStream, CompressionInfo, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider,
+    StorageQuota, UploadResult, stream_temp_path,
+};
+use async_compression::Level;
+use async_compression::tokio::bufread::{GzipDecoder, GzipEncoder, ZstdDecoder, ZstdEncoder};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::io::BufReader;
+use tracing::warn;
+
+const MARKER: &str = ".csz-";
+
+/// 已压缩格式的扩展名，再次压缩几乎没有收益
+const PRECOMPRESSED_EXTENSIONS: &[&str] = &[
+    "zst", "gz", "tgz", "xz", "txz", "bz2", "lz4", "br", "zip", "7z", "rar", "jar", "apk", "epub",
+    "docx", "xlsx", "pptx", "odt", "ods", "odp", "pdf", "woff", "woff2",
+];
+
+/// 按扩展名与 MIME 类型判断文件是否已经压缩（图片、音视频、压缩包等），SVG 除外
+pub fn is_precompressed(path: &str) -> bool {
+    use mime_guess::mime;
+
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    if extension
+        .as_deref()
+        .is_some_and(|ext| PRECOMPRESSED_EXTENSIONS.contains(&ext))
+    {
+        return true;
+    }
+    mime_guess::from_path(path).first().is_some_and(|mime| {
+        (mime.type_() == mime::IMAGE && mime.subtype() != mime::SVG)
+            || mime.type_() == mime::VIDEO
+            || mime.type_() == mime::AUDIO
+    })
+}
+
+fn extension(algorithm: CompressionAlgorithm) -> &'static str {
+    match algorithm {
+        CompressionAlgorithm::Zstd => "zst",
+        CompressionAlgorithm::Gzip => "gz",
+    }
+}
+
+/// 压缩文件名相对原文件名增加的后缀
+fn stored_suffix(size: u64, algorithm: CompressionAlgorithm) -> String {
+    format!("{}{:x}.{}", MARKER, size, extension(algorithm))
+}
+
+/// 解析压缩文件名，返回原文件名、原始大小与压缩算法
+fn parse_stored_name(name: &str) -> Option<(&str, u64, CompressionAlgorithm)> {
+    let (rest, algorithm) = if let Some(rest) = name.strip_suffix(".zst") {
+        (rest, CompressionAlgorithm::Zstd)
+    } else {
+        (name.strip_suffix(".gz")?, CompressionAlgorithm::Gzip)
+    };
+    let (original, size) = rest.rsplit_once(MARKER)?;
+    if original.is_empty() {
+        return None;
+    }
+    Some((original, u64::from_str_radix(size, 16).ok()?, algorithm))
+}
+
+/// 拆分为父目录与文件名
+fn split_path(path: &str) -> (&str, &str) {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((parent, name)) => (parent, name),
+        None => ("", path),
+    }
+}
+
+/// 目录的规范形式：去掉末尾的 `/`，根目录为 `/`
+fn dir_key(path: &str) -> String {
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        dir => dir.to_string(),
+    }
+}
+
+/// 底层实际保存的文件
+struct StoredFile {
+    path: String,
+    /// 相对请求路径增加的后缀，原样保存时为空
+    suffix: String,
+    algorithm: Option<CompressionAlgorithm>,
+}
+
+/// 目录中各条目在底层的名称及是否为目录
+type Listing = Vec<(String, bool)>;
+
+pub struct CompressProvider<T> {
+    inner: T,
+    config: CompressionConfig,
+    /// 按目录缓存的底层列表，查找压缩后的文件名时不必每次重新列出父目录
+    listings: Mutex<HashMap<String, Listing>>,
+}
+
+impl<T: StorageProvider> CompressProvider<T> {
+    pub fn new(inner: T, config: CompressionConfig) -> Self {
+        Self {
+            inner,
+            config,
+            listings: Mutex::default(),
+        }
+    }
+
+    #[cfg(test)]
+    fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// 记录一组完整的目录列表，`dirs` 中的目录视为已完整列出
+    fn cache_listing<'a>(&self, dirs: impl IntoIterator<Item = &'a str>, entries: &[FileInfo]) {
+        let mut listings = self.listings.lock().unwrap();
+        let dirs: Vec<String> = dirs.into_iter().map(dir_key).collect();
+        for dir in &dirs {
+            listings.insert(dir.clone(), Vec::new());
+        }
+        for info in entries {
+            let (parent, name) = split_path(&info.path);
+            if parent.is_empty() || name.is_empty() {
+                continue;
+            }
+            let parent = dir_key(parent);
+            if dirs.contains(&parent)
+                && let Some(listing) = listings.get_mut(&parent)
+            {
+                listing.push((name.to_string(), info.is_dir));
+            }
+        }
+    }
+
+    /// 底层增删条目后同步更新父目录的缓存列表：`entry` 为 `Some(is_dir)` 时加入，
+    /// 为 `None` 时移除，同时丢弃该路径下所有目录的缓存
+    fn record(&self, path: &str, entry: Option<bool>) {
+        let (parent, name) = split_path(path);
+        let dir = dir_key(path);
+        let prefix = format!("{}/", dir);
+        let mut listings = self.listings.lock().unwrap();
+        listings.retain(|key, _| *key != dir && !key.starts_with(&prefix));
+        if !parent.is_empty()
+            && let Some(listing) = listings.get_mut(&dir_key(parent))
+        {
+            listing.retain(|(existing, _)| existing != name);
+            if let Some(is_dir) = entry {
+                listing.push((name.to_string(), is_dir));
+            }
+        }
+    }
+
+    /// 无法确定底层结果时丢弃 `path` 父目录及其下所有目录的缓存，下次查找时重新列出
+    fn invalidate(&self, path: &str) {
+        self.record(path, None);
+        let parent = dir_key(split_path(path).0);
+        self.listings.lock().unwrap().remove(&parent);
+    }
+
+    /// 父目录在底层的列表，优先使用缓存；父目录不存在时返回 `None`
+    async fn parent_listing(&self, parent: &str) -> Result<Option<Listing>, SyncError> {
+        let key = dir_key(parent);
+        if let Some(listing) = self.listings.lock().unwrap().get(&key) {
+            return Ok(Some(listing.clone()));
+        }
+        if !self.inner.exists(parent).await? {
+            return Ok(None);
+        }
+        let entries = self.inner.list(parent).await?;
+        self.cache_listing([key.as_str()], &entries);
+        Ok(self.listings.lock().unwrap().get(&key).cloned())
+    }
+
+    /// 查找 `path` 在底层对应的文件：先找原样保存的，再找压缩后的。
+    /// 查找基于父目录的列表，同一目录只列出一次
+    async fn stored_file(&self, path: &str) -> Result<Option<StoredFile>, SyncError> {
+        let (parent, name) = split_path(path);
+        if parent.is_empty() || name.is_empty() {
+            return Ok(self.inner.exists(path).await?.then(|| StoredFile {
+                path: path.to_string(),
+                suffix: String::new(),
+                algorithm: None,
+            }));
+        }
+        let Some(listing) = self.parent_listing(parent).await? else {
+            return Ok(None);
+        };
+        if listing.iter().any(|(entry, _)| entry == name) {
+            return Ok(Some(StoredFile {
+                path: path.to_string(),
+                suffix: String::new(),
+                algorithm: None,
+            }));
+        }
+        Ok(listing
+            .iter()
+            .filter(|(_, is_dir)| !is_dir)
+            .find_map(|(entry, _)| {
+                let (original, size, algorithm) = parse_stored_name(entry)?;
+                (original == name).then(|| StoredFile {
+                    path: format!("{}{}", path, stored_suffix(size, algorithm)),
+                    suffix: stored_suffix(size, algorithm),
+                    algorithm: Some(algorithm),
+                })
+            }))
+    }
+
+    /// 底层路径，找不到时返回原路径，由底层报告文件不存在
+    async fn stored_path(&self, path: &str) -> Result<String, SyncError> {
+        Ok(self
+            .stored_file(path)
+            .await?
+            .map_or_else(|| path.to_string(), |stored| stored.path))
+    }
+
+    /// 写入新文件后删除同一文件此前以其他名称保存的版本
+    async fn remove_stale(&self, previous: Option<StoredFile>, current: &str) {
+        if let Some(previous) = previous
+            && previous.path != current
+        {
+            match self.inner.delete(&previous.path).await {
+                Ok(()) => self.record(&previous.path, None),
+                Err(e) => warn!(path = %previous.path, error = %e, "删除旧版本失败"),
+            }
+        }
+    }
+
+    /// 压缩文件还原为原文件名与原始大小，其他条目原样返回
+    fn decode_info(mut info: FileInfo) -> FileInfo {
+        if info.is_dir {
+            return info;
+        }
+        let (parent, name) = split_path(&info.path);
+        if let Some((original, size, _)) = parse_stored_name(name) {
+            info.path = match parent {
+                "" => original.to_string(),
+                "/" => format!("/{}", original),
+                _ => format!("{}/{}", parent, original),
+            };
+            info.size = size;
+            // 底层哈希针对的是压缩后的内容
+            info.hash = None;
+        }
+        info
+    }
+
+    fn encoder(&self, reader: ByteStream) -> ByteStream {
+        let level = self.config.level.map_or(Level::Default, Level::Precise);
+        let reader = BufReader::new(reader);
+        match self.config.algorithm {
+            CompressionAlgorithm::Zstd => Box::new(ZstdEncoder::with_quality(reader, level)),
+            CompressionAlgorithm::Gzip => Box::new(GzipEncoder::with_quality(reader, level)),
+        }
+    }
+
+    /// 压缩到临时文件再上传，上传前需要知道压缩后的大小。
+    /// 结果中的 `compression` 记录的是交给底层之前的压缩后大小
+    async fn upload_compressed(
+        &self,
+        reader: ByteStream,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let temp_path = stream_temp_path();
+        let result = async {
+            let mut encoder = self.encoder(reader);
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            let compressed_size = tokio::io::copy(&mut encoder, &mut file).await?;
+            drop(file);
+            let mut result = self.inner.upload(&temp_path, remote_path).await?;
+            result.compression = Some(CompressionInfo {
+                algorithm: self.config.algorithm,
+                compressed_size,
+            });
+            Ok(result)
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        result
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for CompressProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        self.inner.verify().await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let entries = self.inner.list(path).await?;
+        self.cache_listing([path], &entries);
+        Ok(entries.into_iter().map(Self::decode_info).collect())
+    }
+
+    /// 递归列表同时填充各目录的缓存，同步时之后的查找无需再访问底层
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let entries = self.inner.list_recursive(path).await?;
+        let dirs = std::iter::once(path).chain(
+            entries
+                .iter()
+                .filter(|info| info.is_dir)
+                .map(|info| info.path.as_str()),
+        );
+        self.cache_listing(dirs, &entries);
+        Ok(entries.into_iter().map(Self::decode_info).collect())
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let file = tokio::fs::File::open(local_path).await?;
+        let size = file.metadata().await?.len();
+        self.upload_stream(Box::new(file), size, remote_path).await
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let mut reader = self.download_stream(remote_path).await?;
+        let mut file = tokio::fs::File::create(local_path).await?;
+        let written = tokio::io::copy(&mut reader, &mut file).await?;
+        Ok(DownloadResult {
+            bytes_downloaded: written,
+            file_size: written,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let stored = self.stored_path(path).await?;
+        self.inner.delete(&stored).await?;
+        self.record(&stored, None);
+        Ok(())
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        self.inner.mkdir(path).await?;
+        self.record(path, Some(true));
+        Ok(())
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let info = self.inner.stat(&self.stored_path(path).await?).await?;
+        Ok(Self::decode_info(info))
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        Ok(self.stored_file(path).await?.is_some())
+    }
+
+    /// 压缩后内容的哈希与范围读取对调用方没有意义
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_hash: None,
+            supports_range_read: false,
+            ..self.inner.capabilities()
+        }
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        self.inner.quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        self.inner
+            .set_mtime(&self.stored_path(path).await?, modified)
+            .await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        self.inner
+            .set_permissions(&self.stored_path(path).await?, mode)
+            .await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let Some(source) = self.stored_file(from).await? else {
+            // 目录或不存在的路径，结果不确定，丢弃相关缓存
+            let result = self.inner.move_path(from, to).await;
+            self.invalidate(from);
+            self.invalidate(to);
+            return result;
+        };
+        let target = format!("{}{}", to, source.suffix);
+        let previous = self.stored_file(to).await?;
+        self.inner.move_path(&source.path, &target).await?;
+        self.record(&source.path, None);
+        self.record(&target, Some(false));
+        self.remove_stale(previous, &target).await;
+        Ok(())
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let Some(source) = self.stored_file(from).await? else {
+            // 目录或不存在的路径，结果不确定，丢弃相关缓存
+            let result = self.inner.copy_path(from, to).await;
+            self.invalidate(from);
+            self.invalidate(to);
+            return result;
+        };
+        let target = format!("{}{}", to, source.suffix);
+        let previous = self.stored_file(to).await?;
+        self.inner.copy_path(&source.path, &target).await?;
+        self.record(&target, Some(false));
+        self.remove_stale(previous, &target).await;
+        Ok(())
+    }
+
+    /// 返回结果中的 `bytes_uploaded` 为实际写入底层的字节数，包含加密等下层的开销；
+    /// 压缩后的大小记录在 `compression` 中，原样上传时 `compression` 为空
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let previous = self.stored_file(remote_path).await?;
+        let (stored, mut result) = if is_precompressed(remote_path) {
+            let result = self.inner.upload_stream(reader, size, remote_path).await?;
+            (remote_path.to_string(), result)
+        } else {
+            let stored = format!(
+                "{}{}",
+                remote_path,
+                stored_suffix(size, self.config.algorithm)
+            );
+            let result = self.upload_compressed(reader, &stored).await?;
+            (stored, result)
+        };
+        self.record(&stored, Some(false));
+        self.remove_stale(previous, &stored).await;
+        result.file_size = size;
+        result.checksum = None;
+        Ok(result)
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let Some(stored) = self.stored_file(remote_path).await? else {
+            return self.inner.download_stream(remote_path).await;
+        };
+        let stream = BufReader::new(self.inner.download_stream(&stored.path).await?);
+        Ok(match stored.algorithm {
+            None => Box::new(stream),
+            Some(CompressionAlgorithm::Zstd) => Box::new(ZstdDecoder::new(stream)),
+            Some(CompressionAlgorithm::Gzip) => Box::new(GzipDecoder::new(stream)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MemoryProvider;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    /// 统计 `exists` 与 `list` 的调用次数
+    #[derive(Default)]
+    struct LookupCounter {
+        inner: MemoryProvider,
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl StorageProvider for LookupCounter {
+        async fn verify(&self) -> Result<(), SyncError> {
+            self.inner.verify().await
+        }
+        async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            self.inner.list(path).await
+        }
+        async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+            self.inner.list_recursive(path).await
+        }
+        async fn upload(&self, local: &Path, remote: &str) -> Result<UploadResult, SyncError> {
+            self.inner.upload(local, remote).await
+        }
+        async fn download(&self, remote: &str, local: &Path) -> Result<DownloadResult, SyncError> {
+            self.inner.download(remote, local).await
+        }
+        async fn delete(&self, path: &str) -> Result<(), SyncError> {
+            self.inner.delete(path).await
+        }
+        async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+            self.inner.mkdir(path).await
+        }
+        async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+            self.inner.stat(path).await
+        }
+        async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            self.inner.exists(path).await
+        }
+        async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+            self.inner.move_path(from, to).await
+        }
+        async fn download_stream(&self, path: &str) -> Result<ByteStream, SyncError> {
+            self.inner.download_stream(path).await
+        }
+    }
+
+    fn compress(algorithm: CompressionAlgorithm) -> CompressProvider<MemoryProvider> {
+        CompressProvider::new(
+            MemoryProvider::new(),
+            CompressionConfig {
+                algorithm,
+                level: None,
+            },
+        )
+    }
+
+    async fn put(provider: &CompressProvider<MemoryProvider>, path: &str, content: Vec<u8>) -> u64 {
+        let size = content.len() as u64;
+        provider
+            .upload_stream(Box::new(std::io::Cursor::new(content)), size, path)
+            .await
+            .unwrap()
+            .bytes_uploaded
+    }
+
+    async fn get(provider: &CompressProvider<MemoryProvider>, path: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        provider
+            .download_stream(path)
+            .await
+            .unwrap()
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_stored_names_and_precompressed_types() {
+        let name = format!("app.log{}", stored_suffix(500, CompressionAlgorithm::Zstd));
+        assert_eq!(name, "app.log.csz-1f4.zst");
+        assert_eq!(
+            parse_stored_name(&name),
+            Some(("app.log", 500, CompressionAlgorithm::Zstd))
+        );
+        assert_eq!(parse_stored_name("backup.tar.gz"), None);
+        assert_eq!(parse_stored_name(".csz-1.gz"), None);
+
+        assert!(is_precompressed("/photos/a.JPG"));
+        assert!(is_precompressed("/videos/b.mp4"));
+        assert!(is_precompressed("/archive/c.tar.gz"));
+        assert!(!is_precompressed("/logs/app.log"));
+        assert!(!is_precompressed("/icons/logo.svg"));
+    }
+
+    #[tokio::test]
+    async fn test_compress_round_trip_reports_original_sizes() {
+        for algorithm in [CompressionAlgorithm::Zstd, CompressionAlgorithm::Gzip] {
+            let provider = compress(algorithm);
+            provider.mkdir("/logs").await.unwrap();
+            let content = b"2026-10-17 INFO request served\n".repeat(1000);
+            let stored_size = put(&provider, "/logs/app.log", content.clone()).await;
+            assert!(stored_size < content.len() as u64 / 10);
+
+            assert_eq!(get(&provider, "/logs/app.log").await, content);
+            let listed = provider.list("/logs").await.unwrap();
+            assert_eq!(listed.len(), 1);
+            assert_eq!(listed[0].path, "/logs/app.log");
+            assert_eq!(listed[0].size, content.len() as u64);
+            assert_eq!(
+                provider.stat("/logs/app.log").await.unwrap().size,
+                content.len() as u64
+            );
+
+            // 底层保存的是带后缀的压缩文件
+            let raw = provider.inner().list("/logs").await.unwrap();
+            assert_eq!(
+                raw[0].path,
+                format!(
+                    "/logs/app.log{}",
+                    stored_suffix(content.len() as u64, algorithm)
+                )
+            );
+            assert_eq!(raw[0].size, stored_size);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compress_skips_precompressed_and_replaces_old_versions() {
+        let provider = compress(CompressionAlgorithm::Zstd);
+        let jpeg = vec![0xFFu8, 0xD8, 0xFF, 0xE0, 1, 2, 3];
+        put(&provider, "/a.jpg", jpeg.clone()).await;
+        assert!(provider.inner().exists("/a.jpg").await.unwrap());
+        assert_eq!(get(&provider, "/a.jpg").await, jpeg);
+
+        // 大小变化后只保留新版本
+        put(&provider, "/notes.txt", b"first".to_vec()).await;
+        put(&provider, "/notes.txt", b"second version".to_vec()).await;
+        let raw = provider.inner().list("/").await.unwrap();
+        assert_eq!(raw.len(), 2);
+        assert_eq!(get(&provider, "/notes.txt").await, b"second version");
+
+        provider
+            .move_path("/notes.txt", "/moved.txt")
+            .await
+            .unwrap();
+        assert!(!provider.exists("/notes.txt").await.unwrap());
+        assert_eq!(get(&provider, "/moved.txt").await, b"second version");
+
+        provider.delete("/moved.txt").await.unwrap();
+        assert!(!provider.exists("/moved.txt").await.unwrap());
+        assert_eq!(provider.inner().list("/").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_lookups_reuse_directory_listing() {
+        let provider = CompressProvider::new(
+            LookupCounter::default(),
+            CompressionConfig {
+                algorithm: CompressionAlgorithm::Zstd,
+                level: None,
+            },
+        );
+        let lookups = || provider.inner().lookups.load(Ordering::Relaxed);
+        provider.mkdir("/logs").await.unwrap();
+
+        // 同一目录只在第一次查找时列出
+        for i in 0..20 {
+            let path = format!("/logs/{}.log", i);
+            let content = format!("line {}\n", i).repeat(100).into_bytes();
+            provider
+                .upload_stream(
+                    Box::new(std::io::Cursor::new(content.clone())),
+                    content.len() as u64,
+                    &path,
+                )
+                .await
+                .unwrap();
+            assert!(provider.exists(&path).await.unwrap());
+        }
+        assert_eq!(lookups(), 2);
+
+        // 递归列表直接填充缓存，删除与移动同步更新缓存
+        provider.list_recursive("/").await.unwrap();
+        provider.delete("/logs/0.log").await.unwrap();
+        provider
+            .move_path("/logs/1.log", "/logs/moved.log")
+            .await
+            .unwrap();
+        assert!(!provider.exists("/logs/0.log").await.unwrap());
+        assert!(!provider.exists("/logs/1.log").await.unwrap());
+        assert!(provider.exists("/logs/moved.log").await.unwrap());
+        assert_eq!(lookups(), 2);
+        assert_eq!(provider.list("/logs").await.unwrap().len(), 19);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_size_excludes_encryption_overhead() {
+        use crate::providers::CryptProvider;
+
+        let provider = CompressProvider::new(
+            CryptProvider::new(MemoryProvider::new(), &[9u8; 32], false).unwrap(),
+            CompressionConfig {
+                algorithm: CompressionAlgorithm::Zstd,
+                level: None,
+            },
+        );
+        let content = b"2026-10-17 INFO request served\n".repeat(1000);
+        let result = provider
+            .upload_stream(
+                Box::new(std::io::Cursor::new(content.clone())),
+                content.len() as u64,
+                "/app.log",
+            )
+            .await
+            .unwrap();
+        let info = result.compression.unwrap();
+        assert_eq!(info.algorithm, CompressionAlgorithm::Zstd);
+        assert!(info.compressed_size < content.len() as u64 / 10);
+
+        // 写入底层的密文比压缩后的数据多出加密开销
+        assert!(result.bytes_uploaded > info.compressed_size);
+        let mut data = Vec::new();
+        provider
+            .download_stream("/app.log")
+            .await
+            .unwrap()
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, content);
+    }
+}
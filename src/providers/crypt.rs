@@ -0,0 +1,723 @@
+//! 加密覆盖层
+//!
+//! 包装任意提供商：上传时加密文件内容（可选加密文件名），列出、下载、查询时透明解密，
+//! 浏览、挂载、差异比较与同步因此都可以像普通账户一样使用加密的远端。
+//!
+//! 内容格式：16 字节文件头（8 字节魔数 + 8 字节随机 nonce 前缀），随后是按 64 KiB
+//! 明文分块的 AES-256-GCM 密文，每块附带 16 字节认证标签。块 nonce 为 nonce 前缀加
+//! 4 字节大端块序号，附加数据标记是否为最后一块，块被篡改、重排或截断都会解密失败。
+//! 最后一块明文不足 64 KiB（可以为空），因此密文大小与明文大小一一对应。
+//!
+//! 文件名逐段加密：以 HMAC-SHA256(名称密钥, 明文) 的前 12 字节作为 nonce，同名总是得到
+//! 同一密文，按路径查找时无需列目录。nonce 与密文以小写十六进制编码，不受后端大小写
+//! 敏感性与禁止字符影响，但名称长度约为原来的两倍再加 56 个字符。
+
+use crate::config::AccountConfig;
+use crate::encryption::key_store::KeyStore;
+use crate::error::{EncryptionError, SyncError};
+use crate::providers::{
+    ByteStream, CaseSensitivity, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider,
+    StorageQuota, UploadResult,
+};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, ReadBuf};
+use tracing::warn;
+
+const MAGIC: &[u8; 8] = b"CDSCRYPT";
+const NONCE_PREFIX_SIZE: usize = 8;
+const HEADER_SIZE: usize = MAGIC.len() + NONCE_PREFIX_SIZE;
+const BLOCK_SIZE: usize = 64 * 1024;
+const TAG_SIZE: usize = 16;
+const SEALED_BLOCK_SIZE: usize = BLOCK_SIZE + TAG_SIZE;
+const NAME_NONCE_SIZE: usize = 12;
+
+/// 明文大小为 `size` 的文件加密后的大小
+pub fn encrypted_size(size: u64) -> u64 {
+    HEADER_SIZE as u64 + size + TAG_SIZE as u64 * (size / BLOCK_SIZE as u64 + 1)
+}
+
+/// 加密文件的明文大小，`size` 不可能是加密文件的大小时为 `None`
+pub fn decrypted_size(size: u64) -> Option<u64> {
+    let body = size.checked_sub(HEADER_SIZE as u64)?;
+    let blocks = body / SEALED_BLOCK_SIZE as u64;
+    let last = (body % SEALED_BLOCK_SIZE as u64).checked_sub(TAG_SIZE as u64)?;
+    Some(blocks * BLOCK_SIZE as u64 + last)
+}
+
+/// 加密后不超过 `max` 字节的最大明文大小
+fn max_plaintext_size(max: u64) -> u64 {
+    let body = max.saturating_sub(HEADER_SIZE as u64);
+    let blocks = body / SEALED_BLOCK_SIZE as u64;
+    match (body % SEALED_BLOCK_SIZE as u64).checked_sub(TAG_SIZE as u64) {
+        Some(last) => blocks * BLOCK_SIZE as u64 + last,
+        None => (blocks * BLOCK_SIZE as u64).saturating_sub(1),
+    }
+}
+
+pub struct CryptProvider<T> {
+    inner: T,
+    content_cipher: Aes256Gcm,
+    name_cipher: Aes256Gcm,
+    name_mac: Hmac<Sha256>,
+    encrypt_filenames: bool,
+}
+
+impl<T: StorageProvider> CryptProvider<T> {
+    /// 以主密钥包装 `inner`，内容与文件名分别使用由主密钥派生的密钥
+    pub fn new(inner: T, master_key: &[u8], encrypt_filenames: bool) -> Result<Self, SyncError> {
+        if master_key.len() < 16 {
+            return Err(EncryptionError::InvalidKey(
+                "crypt master key must be at least 128 bits".into(),
+            )
+            .into());
+        }
+        let derive = |label: &[u8]| {
+            let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(master_key)
+                .expect("HMAC accepts keys of any length");
+            mac.update(label);
+            mac.finalize().into_bytes()
+        };
+        Ok(Self {
+            inner,
+            content_cipher: Aes256Gcm::new(&derive(b"cloud-disk-sync crypt content")),
+            name_cipher: Aes256Gcm::new(&derive(b"cloud-disk-sync crypt name")),
+            name_mac: <Hmac<Sha256> as Mac>::new_from_slice(&derive(
+                b"cloud-disk-sync crypt name nonce",
+            ))
+            .expect("HMAC accepts keys of any length"),
+            encrypt_filenames,
+        })
+    }
+
+    /// 按账户凭据创建：`key_id` 指定密钥库中的密钥，`key_password` 为其主密码，
+    /// `encrypt_filenames` 为 `false` 时只加密内容（默认加密文件名）
+    pub fn from_account(inner: T, account: &AccountConfig) -> Result<Self, SyncError> {
+        let credential = |key: &str| {
+            account.credentials.get(key).ok_or_else(|| {
+                SyncError::Validation(format!("Missing credential for Crypt: {}", key))
+            })
+        };
+        let master_key =
+            KeyStore::default().load(credential("key_id")?, credential("key_password")?)?;
+        let encrypt_filenames = match account.credentials.get("encrypt_filenames") {
+            None => true,
+            Some(value) => value.parse().map_err(|_| {
+                SyncError::Validation(format!("Invalid encrypt_filenames value: {}", value))
+            })?,
+        };
+        Self::new(inner, &master_key, encrypt_filenames)
+    }
+
+    #[cfg(test)]
+    fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn encrypt_name(&self, name: &str) -> String {
+        let mut mac = self.name_mac.clone();
+        mac.update(name.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let nonce = &digest[..NAME_NONCE_SIZE];
+        let ciphertext = self
+            .name_cipher
+            .encrypt(aes_gcm::Nonce::from_slice(nonce), name.as_bytes())
+            .expect("AES-GCM encryption of a short name cannot fail");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        hex::encode(sealed)
+    }
+
+    fn decrypt_name(&self, name: &str) -> Option<String> {
+        let sealed = hex::decode(name).ok()?;
+        if sealed.len() < NAME_NONCE_SIZE + TAG_SIZE {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NAME_NONCE_SIZE);
+        let plaintext = self
+            .name_cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+            .ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+
+    /// 逐段加密路径，保留分隔符与 `.`、`..`
+    fn encrypt_path(&self, path: &str) -> String {
+        if !self.encrypt_filenames {
+            return path.to_string();
+        }
+        path.split('/')
+            .map(|segment| match segment {
+                "" | "." | ".." => segment.to_string(),
+                _ => self.encrypt_name(segment),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn decrypt_path(&self, path: &str) -> Option<String> {
+        if !self.encrypt_filenames {
+            return Some(path.to_string());
+        }
+        path.split('/')
+            .map(|segment| match segment {
+                "" | "." | ".." => Some(segment.to_string()),
+                _ => self.decrypt_name(segment),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|segments| segments.join("/"))
+    }
+
+    /// 把底层条目还原为明文路径与大小，无法解密的条目返回 `None`
+    fn decrypt_info(&self, mut info: FileInfo) -> Option<FileInfo> {
+        info.path = self.decrypt_path(&info.path)?;
+        if !info.is_dir {
+            info.size = decrypted_size(info.size)?;
+        }
+        // 底层哈希针对的是密文
+        info.hash = None;
+        Some(info)
+    }
+
+    /// 列出结果中跳过无法解密的条目（如直接写入远端的明文文件）
+    fn decrypt_listing(&self, entries: Vec<FileInfo>) -> Vec<FileInfo> {
+        entries
+            .into_iter()
+            .filter_map(|info| {
+                let path = info.path.clone();
+                let decrypted = self.decrypt_info(info);
+                if decrypted.is_none() {
+                    warn!(path = %path, "跳过无法解密的条目");
+                }
+                decrypted
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for CryptProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        self.inner.verify().await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let entries = self.inner.list(&self.encrypt_path(path)).await?;
+        Ok(self.decrypt_listing(entries))
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let entries = self.inner.list_recursive(&self.encrypt_path(path)).await?;
+        Ok(self.decrypt_listing(entries))
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let file = tokio::fs::File::open(local_path).await?;
+        let size = file.metadata().await?.len();
+        self.upload_stream(Box::new(file), size, remote_path).await
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let mut reader = self.download_stream(remote_path).await?;
+        let mut file = tokio::fs::File::create(local_path).await?;
+        let written = tokio::io::copy(&mut reader, &mut file).await?;
+        Ok(DownloadResult {
+            bytes_downloaded: written,
+            file_size: written,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        self.inner.delete(&self.encrypt_path(path)).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        self.inner.mkdir(&self.encrypt_path(path)).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let info = self.inner.stat(&self.encrypt_path(path)).await?;
+        self.decrypt_info(info)
+            .ok_or_else(|| EncryptionError::InvalidData.into())
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        self.inner.exists(&self.encrypt_path(path)).await
+    }
+
+    /// 密文的哈希与范围读取对调用方没有意义；加密后的文件名不含禁止字符且区分大小写
+    fn capabilities(&self) -> ProviderCapabilities {
+        let inner = self.inner.capabilities();
+        let mut capabilities = ProviderCapabilities {
+            supports_hash: None,
+            supports_range_read: false,
+            max_file_size: inner.max_file_size.map(max_plaintext_size),
+            ..inner
+        };
+        if self.encrypt_filenames {
+            capabilities.case_sensitivity = CaseSensitivity::Sensitive;
+            capabilities.forbidden_chars = Vec::new();
+        }
+        capabilities
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        self.inner.quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        self.inner
+            .set_mtime(&self.encrypt_path(path), modified)
+            .await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        self.inner
+            .set_permissions(&self.encrypt_path(path), mode)
+            .await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inner
+            .move_path(&self.encrypt_path(from), &self.encrypt_path(to))
+            .await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inner
+            .copy_path(&self.encrypt_path(from), &self.encrypt_path(to))
+            .await
+    }
+
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let reader = EncryptingReader::new(reader, self.content_cipher.clone());
+        let mut result = self
+            .inner
+            .upload_stream(
+                Box::new(reader),
+                encrypted_size(size),
+                &self.encrypt_path(remote_path),
+            )
+            .await?;
+        result.file_size = size;
+        result.checksum = None;
+        Ok(result)
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let stream = self
+            .inner
+            .download_stream(&self.encrypt_path(remote_path))
+            .await?;
+        Ok(Box::new(DecryptingReader::new(
+            stream,
+            self.content_cipher.clone(),
+        )))
+    }
+}
+
+fn block_nonce(
+    prefix: &[u8; NONCE_PREFIX_SIZE],
+    index: u32,
+) -> aes_gcm::Nonce<aes_gcm::aead::consts::U12> {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_SIZE..].copy_from_slice(&index.to_be_bytes());
+    nonce.into()
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// 从 `inner` 读入 `buffer`，直到 `buffer` 长度达到 `target`；读到末尾时返回 `false`
+fn poll_fill(
+    inner: &mut ByteStream,
+    buffer: &mut Vec<u8>,
+    target: usize,
+    cx: &mut Context<'_>,
+) -> Poll<std::io::Result<bool>> {
+    while buffer.len() < target {
+        let start = buffer.len();
+        buffer.resize(target, 0);
+        let mut read_buf = ReadBuf::new(&mut buffer[start..]);
+        let result = Pin::new(&mut *inner).poll_read(cx, &mut read_buf);
+        let read = read_buf.filled().len();
+        buffer.truncate(start + read);
+        match result {
+            Poll::Ready(Ok(())) if read == 0 => return Poll::Ready(Ok(false)),
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+    }
+    Poll::Ready(Ok(true))
+}
+
+/// 输出缓冲：先写出已生成的数据，再生成下一段
+#[derive(Default)]
+struct OutputBuffer {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl OutputBuffer {
+    /// 写出尚未交付的数据，没有数据可写时返回 `false`
+    fn drain(&mut self, buf: &mut ReadBuf<'_>) -> bool {
+        if self.position >= self.data.len() {
+            return false;
+        }
+        let count = buf.remaining().min(self.data.len() - self.position);
+        buf.put_slice(&self.data[self.position..self.position + count]);
+        self.position += count;
+        true
+    }
+
+    fn set(&mut self, data: Vec<u8>) {
+        self.data = data;
+        self.position = 0;
+    }
+}
+
+/// 边读边加密的读取器，输出文件头与逐块密文
+struct EncryptingReader {
+    inner: ByteStream,
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+    index: u32,
+    plaintext: Vec<u8>,
+    output: OutputBuffer,
+    eof: bool,
+    finished: bool,
+}
+
+impl EncryptingReader {
+    fn new(inner: ByteStream, cipher: Aes256Gcm) -> Self {
+        let nonce_prefix: [u8; NONCE_PREFIX_SIZE] = rand::random();
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&nonce_prefix);
+        let mut output = OutputBuffer::default();
+        output.set(header);
+        Self {
+            inner,
+            cipher,
+            nonce_prefix,
+            index: 0,
+            plaintext: Vec::with_capacity(BLOCK_SIZE),
+            output,
+            eof: false,
+            finished: false,
+        }
+    }
+
+    fn seal_block(&mut self) -> std::io::Result<()> {
+        let last = self.plaintext.len() < BLOCK_SIZE;
+        let sealed = self
+            .cipher
+            .encrypt(
+                &block_nonce(&self.nonce_prefix, self.index),
+                Payload {
+                    msg: &self.plaintext,
+                    aad: &[last as u8],
+                },
+            )
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        self.index = self
+            .index
+            .checked_add(1)
+            .ok_or_else(|| invalid_data("file too large for crypt format"))?;
+        self.plaintext.clear();
+        self.output.set(sealed);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl AsyncRead for EncryptingReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.output.drain(buf) || this.finished {
+                return Poll::Ready(Ok(()));
+            }
+            if !this.eof {
+                let filled = std::task::ready!(poll_fill(
+                    &mut this.inner,
+                    &mut this.plaintext,
+                    BLOCK_SIZE,
+                    cx
+                ))?;
+                this.eof = !filled;
+            }
+            // 满块不是最后一块，读到末尾后再补一个不足整块（可以为空）的最后一块
+            this.seal_block()?;
+        }
+    }
+}
+
+/// 边读边解密的读取器，认证失败或数据被截断时返回 `InvalidData`
+struct DecryptingReader {
+    inner: ByteStream,
+    cipher: Aes256Gcm,
+    nonce_prefix: Option<[u8; NONCE_PREFIX_SIZE]>,
+    index: u32,
+    sealed: Vec<u8>,
+    output: OutputBuffer,
+    finished: bool,
+}
+
+impl DecryptingReader {
+    fn new(inner: ByteStream, cipher: Aes256Gcm) -> Self {
+        Self {
+            inner,
+            cipher,
+            nonce_prefix: None,
+            index: 0,
+            sealed: Vec::with_capacity(SEALED_BLOCK_SIZE),
+            output: OutputBuffer::default(),
+            finished: false,
+        }
+    }
+
+    fn open_block(
+        &mut self,
+        nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+        last: bool,
+    ) -> std::io::Result<()> {
+        let plaintext = self
+            .cipher
+            .decrypt(
+                &block_nonce(&nonce_prefix, self.index),
+                Payload {
+                    msg: &self.sealed,
+                    aad: &[last as u8],
+                },
+            )
+            .map_err(|_| invalid_data("crypt block failed authentication"))?;
+        self.index = self
+            .index
+            .checked_add(1)
+            .ok_or_else(|| invalid_data("crypt block index overflow"))?;
+        self.sealed.clear();
+        self.output.set(plaintext);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl AsyncRead for DecryptingReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.output.drain(buf) || this.finished {
+                return Poll::Ready(Ok(()));
+            }
+            let Some(nonce_prefix) = this.nonce_prefix else {
+                let filled = std::task::ready!(poll_fill(
+                    &mut this.inner,
+                    &mut this.sealed,
+                    HEADER_SIZE,
+                    cx
+                ))?;
+                if !filled || &this.sealed[..MAGIC.len()] != MAGIC {
+                    return Poll::Ready(Err(invalid_data("missing crypt header")));
+                }
+                let mut prefix = [0u8; NONCE_PREFIX_SIZE];
+                prefix.copy_from_slice(&this.sealed[MAGIC.len()..]);
+                this.nonce_prefix = Some(prefix);
+                this.sealed.clear();
+                continue;
+            };
+            let filled = std::task::ready!(poll_fill(
+                &mut this.inner,
+                &mut this.sealed,
+                SEALED_BLOCK_SIZE,
+                cx
+            ))?;
+            // 满块一定不是最后一块；最后一块至少包含认证标签
+            if !filled && this.sealed.len() < TAG_SIZE {
+                return Poll::Ready(Err(invalid_data("truncated crypt data")));
+            }
+            this.open_block(nonce_prefix, !filled)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MemoryProvider;
+    use tokio::io::AsyncReadExt;
+
+    fn crypt(encrypt_filenames: bool) -> CryptProvider<MemoryProvider> {
+        CryptProvider::new(MemoryProvider::new(), &[42u8; 32], encrypt_filenames).unwrap()
+    }
+
+    async fn read_all(stream: ByteStream) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut stream = stream;
+        stream.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+
+    async fn put(provider: &CryptProvider<MemoryProvider>, path: &str, content: Vec<u8>) {
+        let size = content.len() as u64;
+        provider
+            .upload_stream(Box::new(std::io::Cursor::new(content)), size, path)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_crypt_sizes_round_trip() {
+        for size in [
+            0,
+            1,
+            BLOCK_SIZE as u64 - 1,
+            BLOCK_SIZE as u64,
+            3 * BLOCK_SIZE as u64 + 5,
+        ] {
+            assert_eq!(decrypted_size(encrypted_size(size)), Some(size));
+        }
+        assert_eq!(encrypted_size(0), (HEADER_SIZE + TAG_SIZE) as u64);
+        assert_eq!(decrypted_size(HEADER_SIZE as u64 + 3), None);
+    }
+
+    #[tokio::test]
+    async fn test_crypt_round_trip_encrypts_content_and_names() {
+        let provider = crypt(true);
+        provider.mkdir("/docs").await.unwrap();
+        for size in [0, 10, BLOCK_SIZE, BLOCK_SIZE * 2 + 7] {
+            let content: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            let path = format!("/docs/file_{}.bin", size);
+            put(&provider, &path, content.clone()).await;
+
+            let downloaded = read_all(provider.download_stream(&path).await.unwrap())
+                .await
+                .unwrap();
+            assert_eq!(downloaded, content);
+            let info = provider.stat(&path).await.unwrap();
+            assert_eq!(info.size, size as u64);
+        }
+
+        let mut names: Vec<_> = provider
+            .list("/docs")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|info| info.path)
+            .collect();
+        names.sort();
+        assert_eq!(names[0], "/docs/file_0.bin");
+        assert_eq!(names.len(), 4);
+
+        // 底层只能看到加密后的名称与内容
+        let raw = provider.inner().list_recursive("/").await.unwrap();
+        assert!(raw.iter().all(|info| !info.path.contains("docs")));
+        let raw_file = raw
+            .iter()
+            .find(|info| info.size == encrypted_size(10))
+            .unwrap();
+        let ciphertext = read_all(
+            provider
+                .inner()
+                .download_stream(&raw_file.path)
+                .await
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert!(ciphertext.starts_with(MAGIC));
+        assert!(
+            !ciphertext
+                .windows(10)
+                .any(|w| w == (0..10u8).collect::<Vec<_>>())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crypt_file_download_and_server_move() {
+        let provider = crypt(false);
+        let local = std::env::temp_dir().join(format!("crypt_{}.txt", uuid::Uuid::new_v4()));
+        tokio::fs::write(&local, b"hello crypt").await.unwrap();
+
+        let result = provider.upload(&local, "/a.txt").await.unwrap();
+        assert_eq!(result.file_size, 11);
+        provider.move_path("/a.txt", "/b.txt").await.unwrap();
+        assert!(provider.inner().exists("/b.txt").await.unwrap());
+
+        let result = provider.download("/b.txt", &local).await.unwrap();
+        assert_eq!(result.file_size, 11);
+        assert_eq!(tokio::fs::read(&local).await.unwrap(), b"hello crypt");
+        let _ = tokio::fs::remove_file(&local).await;
+    }
+
+    #[tokio::test]
+    async fn test_crypt_detects_tampering_and_truncation() {
+        let provider = crypt(false);
+        put(&provider, "/f.bin", vec![1u8; BLOCK_SIZE + 100]).await;
+        let ciphertext = read_all(provider.inner().download_stream("/f.bin").await.unwrap())
+            .await
+            .unwrap();
+
+        let mut tampered = ciphertext.clone();
+        tampered[HEADER_SIZE + 5] ^= 1;
+        provider.inner().insert_file("/f.bin", tampered, 0).unwrap();
+        let err = read_all(provider.download_stream("/f.bin").await.unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // 去掉最后一块后剩下的满块不能冒充完整文件
+        let truncated = ciphertext[..HEADER_SIZE + SEALED_BLOCK_SIZE].to_vec();
+        provider
+            .inner()
+            .insert_file("/f.bin", truncated, 0)
+            .unwrap();
+        assert!(
+            read_all(provider.download_stream("/f.bin").await.unwrap())
+                .await
+                .is_err()
+        );
+
+        // 未加密的文件在列出时被跳过
+        let crypt_names = crypt(true);
+        crypt_names
+            .inner()
+            .insert_file("/plain.txt", b"plain".to_vec(), 0)
+            .unwrap();
+        assert!(crypt_names.list("/").await.unwrap().is_empty());
+    }
+}
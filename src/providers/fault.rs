@@ -0,0 +1,683 @@
+//! 故障注入装饰器
+//!
+//! 包装任意 `StorageProvider`，按配置为每次调用注入延迟、错误与带宽限制，
+//! 用于在没有真实服务的情况下复现网络不稳定时 `SyncEngine` 的行为。
+//! 指定随机种子后，相同的调用顺序总会得到相同的故障序列；
+//! 并发调用的先后顺序本身不确定，需要完全复现时应串行执行。
+//!
+//! # 账户配置
+//! 设置环境变量 `DISKSYNC_FAULT_INJECTION=1` 后，账户凭证中出现任一 `fault_*` 项时，
+//! 提供商工厂会包装该账户的提供商；未设置时忽略这些项，避免误留的凭证影响正常同步：
+//! - `fault_error_rate`：所有操作的默认错误率（0.0 ~ 1.0）
+//! - `fault_error_rate_<op>`：单个操作的错误率，`<op>` 取值见 [`FaultOp::name`]
+//! - `fault_error_kind`：`timeout`、`connection`、`rate_limited`、`auth` 或 `server`（默认）
+//! - `fault_latency_ms`：`50` 为固定延迟，`20-80` 为均匀分布，`exp:50` 为均值 50ms 的指数分布
+//! - `fault_bandwidth`：传输带宽上限（字节/秒）
+//! - `fault_seed`：随机种子
+
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, StorageQuota,
+    UploadResult,
+};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::{Instant, Sleep, sleep, sleep_until};
+use tracing::{debug, info};
+
+/// 可单独配置错误率的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultOp {
+    Verify,
+    /// `list` 与 `list_recursive`
+    List,
+    /// `upload` 与 `upload_stream`
+    Upload,
+    /// `download` 与 `download_stream`
+    Download,
+    Delete,
+    Mkdir,
+    Stat,
+    Exists,
+    Quota,
+    /// `set_mtime` 与 `set_permissions`
+    SetMetadata,
+    Move,
+    Copy,
+}
+
+impl FaultOp {
+    pub const ALL: [FaultOp; 12] = [
+        Self::Verify,
+        Self::List,
+        Self::Upload,
+        Self::Download,
+        Self::Delete,
+        Self::Mkdir,
+        Self::Stat,
+        Self::Exists,
+        Self::Quota,
+        Self::SetMetadata,
+        Self::Move,
+        Self::Copy,
+    ];
+
+    /// 账户凭证 `fault_error_rate_<op>` 中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Verify => "verify",
+            Self::List => "list",
+            Self::Upload => "upload",
+            Self::Download => "download",
+            Self::Delete => "delete",
+            Self::Mkdir => "mkdir",
+            Self::Stat => "stat",
+            Self::Exists => "exists",
+            Self::Quota => "quota",
+            Self::SetMetadata => "metadata",
+            Self::Move => "move",
+            Self::Copy => "copy",
+        }
+    }
+}
+
+/// 注入的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FaultKind {
+    Timeout,
+    Connection,
+    RateLimited,
+    Auth,
+    #[default]
+    Server,
+}
+
+impl FaultKind {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "timeout" => Some(Self::Timeout),
+            "connection" => Some(Self::Connection),
+            "rate_limited" => Some(Self::RateLimited),
+            "auth" => Some(Self::Auth),
+            "server" => Some(Self::Server),
+            _ => None,
+        }
+    }
+
+    fn to_error(self, op: FaultOp, path: &str) -> SyncError {
+        let message = format!("Simulated {} failure: {}", op.name(), path);
+        SyncError::Provider(match self {
+            Self::Timeout => ProviderError::Timeout(message),
+            Self::Connection => ProviderError::ConnectionFailed(message),
+            Self::RateLimited => ProviderError::RateLimited(message, None),
+            Self::Auth => ProviderError::AuthFailed(message),
+            Self::Server => ProviderError::ApiError(message),
+        })
+    }
+}
+
+/// 每次调用前注入的延迟分布
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Latency {
+    #[default]
+    None,
+    Fixed(Duration),
+    /// 在 `[min, max]` 内均匀分布
+    Uniform {
+        min: Duration,
+        max: Duration,
+    },
+    /// 指数分布，少数调用会有很长的延迟
+    Exponential {
+        mean: Duration,
+    },
+}
+
+impl Latency {
+    /// 解析 `50`、`20-80` 或 `exp:50`（毫秒）
+    fn parse_ms(value: &str) -> Option<Self> {
+        let ms = |v: &str| v.trim().parse().ok().map(Duration::from_millis);
+        if let Some(mean) = value.strip_prefix("exp:") {
+            return Some(Self::Exponential { mean: ms(mean)? });
+        }
+        match value.split_once('-') {
+            Some((min, max)) => {
+                let (min, max) = (ms(min)?, ms(max)?);
+                (min <= max).then_some(Self::Uniform { min, max })
+            }
+            None => Some(Self::Fixed(ms(value)?)),
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        match *self {
+            Self::None => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Uniform { min, max } => rng.random_range(min..=max),
+            Self::Exponential { mean } => {
+                let u: f64 = rng.random();
+                mean.mul_f64(-(1.0 - u).ln())
+            }
+        }
+    }
+}
+
+/// 故障注入配置
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    /// 未在 `op_error_rates` 中单独配置的操作使用的错误率（0.0 ~ 1.0）
+    pub error_rate: f64,
+    /// 按操作覆盖的错误率
+    pub op_error_rates: HashMap<FaultOp, f64>,
+    pub error_kind: FaultKind,
+    pub latency: Latency,
+    /// 上传、下载的带宽上限（字节/秒）
+    pub bandwidth_bytes_per_sec: Option<u64>,
+    /// 随机种子，`None` 时每次运行使用不同的随机序列
+    pub seed: Option<u64>,
+}
+
+impl FaultConfig {
+    /// 指定操作的错误率
+    pub fn error_rate_for(&self, op: FaultOp) -> f64 {
+        self.op_error_rates
+            .get(&op)
+            .copied()
+            .unwrap_or(self.error_rate)
+    }
+
+    /// 读取账户凭证中的 `fault_*` 项，没有任何 `fault_*` 项时返回 `None`
+    pub fn from_credentials(
+        credentials: &HashMap<String, String>,
+    ) -> Result<Option<Self>, ProviderError> {
+        let mut keys: Vec<&String> = credentials
+            .keys()
+            .filter(|k| k.starts_with("fault_"))
+            .collect();
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        keys.sort();
+
+        let invalid = |key: &str, value: &str| {
+            ProviderError::InvalidCredentials(format!("Invalid {}: {}", key, value))
+        };
+        let rate = |key: &str| {
+            credentials
+                .get(key)
+                .map(|v| {
+                    v.parse::<f64>()
+                        .ok()
+                        .filter(|r| (0.0..=1.0).contains(r))
+                        .ok_or_else(|| invalid(key, v))
+                })
+                .transpose()
+        };
+        let number = |key: &str| {
+            credentials
+                .get(key)
+                .map(|v| v.parse::<u64>().map_err(|_| invalid(key, v)))
+                .transpose()
+        };
+
+        let mut config = Self {
+            error_rate: rate("fault_error_rate")?.unwrap_or(0.0),
+            bandwidth_bytes_per_sec: number("fault_bandwidth")?,
+            seed: number("fault_seed")?,
+            ..Default::default()
+        };
+        if config.bandwidth_bytes_per_sec == Some(0) {
+            return Err(invalid("fault_bandwidth", "0"));
+        }
+        for op in FaultOp::ALL {
+            if let Some(r) = rate(&format!("fault_error_rate_{}", op.name()))? {
+                config.op_error_rates.insert(op, r);
+            }
+        }
+        if let Some(v) = credentials.get("fault_error_kind") {
+            config.error_kind =
+                FaultKind::parse(v).ok_or_else(|| invalid("fault_error_kind", v))?;
+        }
+        if let Some(v) = credentials.get("fault_latency_ms") {
+            config.latency = Latency::parse_ms(v).ok_or_else(|| invalid("fault_latency_ms", v))?;
+        }
+
+        // 拼错的键会被静默忽略，导致以为注入了故障实际却没有
+        let known = |key: &str| {
+            matches!(
+                key,
+                "fault_error_rate"
+                    | "fault_error_kind"
+                    | "fault_latency_ms"
+                    | "fault_bandwidth"
+                    | "fault_seed"
+            ) || key
+                .strip_prefix("fault_error_rate_")
+                .is_some_and(|op| FaultOp::ALL.iter().any(|o| o.name() == op))
+        };
+        if let Some(key) = keys.into_iter().find(|k| !known(k)) {
+            return Err(ProviderError::InvalidCredentials(format!(
+                "Unknown fault injection option: {}",
+                key
+            )));
+        }
+        Ok(Some(config))
+    }
+}
+
+/// 启用账户级故障注入的环境变量
+pub const FAULT_INJECTION_ENV: &str = "DISKSYNC_FAULT_INJECTION";
+
+/// 是否通过 [`FAULT_INJECTION_ENV`] 显式启用了账户级故障注入
+pub fn fault_injection_enabled() -> bool {
+    is_enabled(std::env::var(FAULT_INJECTION_ENV).ok().as_deref())
+}
+
+fn is_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// 故障注入装饰器
+pub struct FaultInjectionProvider<T> {
+    inner: T,
+    config: FaultConfig,
+    rng: Mutex<StdRng>,
+    injected: AtomicU64,
+}
+
+impl<T: StorageProvider> FaultInjectionProvider<T> {
+    pub fn new(inner: T, config: FaultConfig) -> Self {
+        info!(config = ?config, "启用故障注入");
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Self {
+            inner,
+            config,
+            rng: Mutex::new(rng),
+            injected: AtomicU64::new(0),
+        }
+    }
+
+    /// 到目前为止注入的错误次数
+    pub fn injected_faults(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    /// 注入延迟，并按错误率决定是否让本次调用失败
+    async fn inject(&self, op: FaultOp, path: &str) -> Result<(), SyncError> {
+        // 每次调用固定消耗两个随机数，保证故障序列只取决于种子与调用顺序
+        let (delay, fail) = {
+            let mut rng = self.rng.lock().unwrap();
+            let delay = self.config.latency.sample(&mut rng);
+            let roll: f64 = rng.random();
+            (delay, roll < self.config.error_rate_for(op))
+        };
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+        if fail {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            debug!(op = op.name(), path = %path, "注入模拟故障");
+            return Err(self.config.error_kind.to_error(op, path));
+        }
+        Ok(())
+    }
+
+    /// 按带宽上限等待传输 `bytes` 字节所需的时间
+    async fn throttle(&self, bytes: u64) {
+        if let Some(rate) = self.config.bandwidth_bytes_per_sec {
+            sleep(Duration::from_secs_f64(bytes as f64 / rate as f64)).await;
+        }
+    }
+
+    fn throttle_stream(&self, stream: ByteStream) -> ByteStream {
+        match self.config.bandwidth_bytes_per_sec {
+            Some(rate) => Box::new(ThrottledStream::new(stream, rate)),
+            None => stream,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for FaultInjectionProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        self.inject(FaultOp::Verify, "/").await?;
+        self.inner.verify().await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.inject(FaultOp::List, path).await?;
+        self.inner.list(path).await
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.inject(FaultOp::List, path).await?;
+        self.inner.list_recursive(path).await
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        self.inject(FaultOp::Upload, remote_path).await?;
+        self.throttle(tokio::fs::metadata(local_path).await?.len())
+            .await;
+        self.inner.upload(local_path, remote_path).await
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        self.inject(FaultOp::Download, remote_path).await?;
+        let result = self.inner.download(remote_path, local_path).await?;
+        self.throttle(result.bytes_downloaded).await;
+        Ok(result)
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        self.inject(FaultOp::Delete, path).await?;
+        self.inner.delete(path).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        self.inject(FaultOp::Mkdir, path).await?;
+        self.inner.mkdir(path).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        self.inject(FaultOp::Stat, path).await?;
+        self.inner.stat(path).await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        self.inject(FaultOp::Exists, path).await?;
+        self.inner.exists(path).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        self.inject(FaultOp::Quota, "/").await?;
+        self.inner.quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        self.inject(FaultOp::SetMetadata, path).await?;
+        self.inner.set_mtime(path, modified).await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        self.inject(FaultOp::SetMetadata, path).await?;
+        self.inner.set_permissions(path, mode).await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inject(FaultOp::Move, from).await?;
+        self.inner.move_path(from, to).await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.inject(FaultOp::Copy, from).await?;
+        self.inner.copy_path(from, to).await
+    }
+
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        self.inject(FaultOp::Upload, remote_path).await?;
+        self.inner
+            .upload_stream(self.throttle_stream(reader), size, remote_path)
+            .await
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        self.inject(FaultOp::Download, remote_path).await?;
+        let stream = self.inner.download_stream(remote_path).await?;
+        Ok(self.throttle_stream(stream))
+    }
+}
+
+/// 按固定速率放行数据的读取器：已读字节数超出速率允许的量时，等到允许的时刻再继续读
+struct ThrottledStream {
+    inner: ByteStream,
+    bytes_per_sec: u64,
+    started: Instant,
+    transferred: u64,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl ThrottledStream {
+    fn new(inner: ByteStream, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_sec,
+            started: Instant::now(),
+            transferred: 0,
+            delay: None,
+        }
+    }
+}
+
+impl AsyncRead for ThrottledStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                this.delay = None;
+            }
+            let allowed_at = this.started
+                + Duration::from_secs_f64(this.transferred as f64 / this.bytes_per_sec as f64);
+            if allowed_at <= Instant::now() {
+                break;
+            }
+            this.delay = Some(Box::pin(sleep_until(allowed_at)));
+        }
+
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.transferred += (buf.filled().len() - before) as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::MemoryProvider;
+    use tokio::io::AsyncReadExt;
+
+    fn credentials(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// 依次调用 `stat`，记录每次是否失败
+    async fn failure_pattern(provider: &FaultInjectionProvider<MemoryProvider>) -> Vec<bool> {
+        let mut pattern = Vec::new();
+        for _ in 0..64 {
+            pattern.push(provider.stat("/f.txt").await.is_err());
+        }
+        pattern
+    }
+
+    fn seeded(seed: u64) -> FaultInjectionProvider<MemoryProvider> {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        FaultInjectionProvider::new(
+            memory,
+            FaultConfig {
+                error_rate: 0.5,
+                seed: Some(seed),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_reproduces_faults() {
+        let first = failure_pattern(&seeded(7)).await;
+        assert_eq!(first, failure_pattern(&seeded(7)).await);
+        assert_ne!(first, failure_pattern(&seeded(8)).await);
+
+        let provider = seeded(7);
+        let failures = failure_pattern(&provider).await;
+        let count = failures.iter().filter(|f| **f).count() as u64;
+        assert_eq!(provider.injected_faults(), count);
+        assert!(count > 0 && count < 64);
+    }
+
+    #[tokio::test]
+    async fn test_per_operation_rates_and_error_kind() {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        let provider = FaultInjectionProvider::new(
+            memory,
+            FaultConfig {
+                error_rate: 1.0,
+                op_error_rates: HashMap::from([(FaultOp::Stat, 0.0)]),
+                error_kind: FaultKind::Timeout,
+                seed: Some(1),
+                ..Default::default()
+            },
+        );
+
+        assert!(provider.stat("/f.txt").await.is_ok());
+        let err = provider.list("/").await.unwrap_err();
+        assert!(matches!(
+            err,
+            SyncError::Provider(ProviderError::Timeout(_))
+        ));
+        assert!(err.is_retryable());
+        assert!(provider.mkdir("/d").await.is_err());
+        assert!(!provider.inner.exists("/d").await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_and_bandwidth() {
+        let memory = MemoryProvider::new();
+        memory
+            .insert_file("/big.bin", vec![0u8; 10_000], 0)
+            .unwrap();
+        let provider = FaultInjectionProvider::new(
+            memory,
+            FaultConfig {
+                latency: Latency::Fixed(Duration::from_millis(200)),
+                bandwidth_bytes_per_sec: Some(5_000),
+                ..Default::default()
+            },
+        );
+
+        let start = Instant::now();
+        provider.exists("/big.bin").await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        let start = Instant::now();
+        let mut content = Vec::new();
+        let mut stream = provider.download_stream("/big.bin").await.unwrap();
+        let mut chunk = [0u8; 1_000];
+        loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            content.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(content.len(), 10_000);
+        // 200ms 延迟 + 10000 字节按 5000 B/s 放行，读到流末尾时需要等全部字节的配额
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(2_000), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(2_300), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_fault_injection_opt_in() {
+        assert!(!is_enabled(None));
+        assert!(!is_enabled(Some("")));
+        assert!(!is_enabled(Some("0")));
+        assert!(is_enabled(Some("1")));
+        assert!(is_enabled(Some("TRUE")));
+    }
+
+    #[test]
+    fn test_from_credentials() {
+        assert!(
+            FaultConfig::from_credentials(&credentials(&[("url", "http://x")]))
+                .unwrap()
+                .is_none()
+        );
+
+        let config = FaultConfig::from_credentials(&credentials(&[
+            ("fault_error_rate", "0.1"),
+            ("fault_error_rate_upload", "0.5"),
+            ("fault_error_kind", "connection"),
+            ("fault_latency_ms", "20-80"),
+            ("fault_bandwidth", "1024"),
+            ("fault_seed", "42"),
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.error_rate_for(FaultOp::List), 0.1);
+        assert_eq!(config.error_rate_for(FaultOp::Upload), 0.5);
+        assert_eq!(config.error_kind, FaultKind::Connection);
+        assert_eq!(
+            config.latency,
+            Latency::Uniform {
+                min: Duration::from_millis(20),
+                max: Duration::from_millis(80)
+            }
+        );
+        assert_eq!(config.bandwidth_bytes_per_sec, Some(1024));
+        assert_eq!(config.seed, Some(42));
+
+        let exp = FaultConfig::from_credentials(&credentials(&[("fault_latency_ms", "exp:50")]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            exp.latency,
+            Latency::Exponential {
+                mean: Duration::from_millis(50)
+            }
+        );
+
+        for bad in [
+            ("fault_error_rate", "1.5"),
+            ("fault_error_kind", "flaky"),
+            ("fault_latency_ms", "80-20"),
+            ("fault_bandwidth", "0"),
+            ("fault_error_rate_uplaod", "0.5"),
+        ] {
+            assert!(
+                FaultConfig::from_credentials(&credentials(&[bad])).is_err(),
+                "{:?}",
+                bad
+            );
+        }
+    }
+}
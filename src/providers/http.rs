@@ -0,0 +1,605 @@
+//! HTTP 提供商共用的客户端构建
+//!
+//! 按全局 `NetworkSettings` 配置代理、超时、连接池、User-Agent 与自定义请求头，
+//! 按账户的 `TlsSettings` 配置证书校验，各提供商在返回的构建器上再追加自身需要的设置。
+
+use crate::config::NetworkSettings;
+use crate::error::ProviderError;
+use hyper_util::client::legacy::connect::Connection;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use reqwest::tls::{Certificate, Identity, TlsInfo};
+use reqwest::{ClientBuilder, NoProxy, Proxy, Response, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `bypass_for_local` 时不经代理的地址：回环、私有网段与 `.local` 域名
+const LOCAL_BYPASS: &[&str] = &[
+    "localhost",
+    "127.0.0.0/8",
+    "::1",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "fc00::/7",
+    ".local",
+];
+
+/// 账户级 TLS 设置，来自账户凭证中的 `tls_*` 项
+#[derive(Debug, Clone, Default)]
+pub struct TlsSettings {
+    /// 额外信任的 CA 证书文件（PEM，可包含多个证书）
+    pub ca_bundle: Option<PathBuf>,
+    /// 双向 TLS 的客户端证书（PEM）
+    pub client_cert: Option<PathBuf>,
+    /// 客户端证书的私钥（PKCS#8 PEM）
+    pub client_key: Option<PathBuf>,
+    /// 服务器证书（DER）的 SHA-256 指纹
+    pub pin_sha256: Option<[u8; 32]>,
+    /// 不校验服务器证书链与主机名，设置了指纹时仍校验指纹
+    pub insecure: bool,
+}
+
+impl TlsSettings {
+    /// 读取 `tls_ca_bundle`、`tls_client_cert`、`tls_client_key`、`tls_pin_sha256` 与 `tls_insecure`
+    pub fn from_credentials(credentials: &HashMap<String, String>) -> Result<Self, ProviderError> {
+        let path = |key: &str| credentials.get(key).map(PathBuf::from);
+        let settings = Self {
+            ca_bundle: path("tls_ca_bundle"),
+            client_cert: path("tls_client_cert"),
+            client_key: path("tls_client_key"),
+            pin_sha256: credentials
+                .get("tls_pin_sha256")
+                .map(|pin| parse_pin(pin))
+                .transpose()?,
+            insecure: match credentials.get("tls_insecure").map(String::as_str) {
+                None | Some("false") => false,
+                Some("true") => true,
+                Some(other) => {
+                    return Err(ProviderError::InvalidCredentials(format!(
+                        "tls_insecure must be true or false: {}",
+                        other
+                    )));
+                }
+            },
+        };
+        if settings.client_cert.is_some() != settings.client_key.is_some() {
+            return Err(ProviderError::InvalidCredentials(
+                "tls_client_cert and tls_client_key must be set together".to_string(),
+            ));
+        }
+        Ok(settings)
+    }
+}
+
+/// 解析十六进制指纹，允许 `openssl x509 -fingerprint` 输出中的冒号分隔
+fn parse_pin(pin: &str) -> Result<[u8; 32], ProviderError> {
+    let digits: String = pin.chars().filter(|c| *c != ':').collect();
+    hex::decode(&digits)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            ProviderError::InvalidCredentials(format!("Invalid SHA-256 certificate pin: {}", pin))
+        })
+}
+
+/// 按网络设置与账户 TLS 设置创建 HTTP 客户端构建器
+///
+/// `timeout_seconds` 作为连接超时和读取超时（两次收到数据的最长间隔），
+/// 而不是整个请求的超时，避免大文件传输因总时长超限而中断。
+pub fn client_builder(
+    settings: &NetworkSettings,
+    tls: &TlsSettings,
+) -> Result<ClientBuilder, ProviderError> {
+    let mut builder =
+        reqwest::Client::builder().pool_max_idle_per_host(settings.connection_pool_size);
+
+    if settings.timeout_seconds > 0 {
+        let timeout = Duration::from_secs(settings.timeout_seconds);
+        builder = builder.connect_timeout(timeout).read_timeout(timeout);
+    }
+
+    if let Some(user_agent) = &settings.user_agent {
+        builder = builder.user_agent(user_agent.as_str());
+    }
+
+    if !settings.custom_headers.is_empty() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &settings.custom_headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                ProviderError::ConnectionFailed(format!("Invalid header name {}: {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                ProviderError::ConnectionFailed(format!("Invalid value for header {}: {}", name, e))
+            })?;
+            headers.insert(name, value);
+        }
+        builder = builder.default_headers(headers);
+    }
+
+    if let Some(proxy_config) = &settings.proxy {
+        let mut proxy = Proxy::all(&proxy_config.url).map_err(|e| {
+            ProviderError::ConnectionFailed(format!("Invalid proxy {}: {}", proxy_config.url, e))
+        })?;
+        if let Some(username) = &proxy_config.username {
+            proxy = proxy.basic_auth(
+                username,
+                proxy_config.password.as_deref().unwrap_or_default(),
+            );
+        }
+
+        let mut bypass: Vec<&str> = proxy_config
+            .bypass_list
+            .iter()
+            .map(String::as_str)
+            .collect();
+        if proxy_config.bypass_for_local {
+            bypass.extend_from_slice(LOCAL_BYPASS);
+        }
+        if !bypass.is_empty() {
+            proxy = proxy.no_proxy(NoProxy::from_string(&bypass.join(",")));
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    apply_tls(builder, tls)
+}
+
+/// 按网络设置与账户 TLS 设置创建 HTTP 客户端
+pub fn build_client(
+    settings: &NetworkSettings,
+    tls: &TlsSettings,
+) -> Result<reqwest::Client, ProviderError> {
+    client_builder(settings, tls)?
+        .build()
+        .map_err(|e| ProviderError::ConnectionFailed(e.to_string()))
+}
+
+/// 服务端限流响应（429，或带 `Retry-After` 的 503）转换为 `RateLimited`，其他响应原样返回
+pub fn check_throttled(response: Response) -> Result<Response, ProviderError> {
+    let status = response.status();
+    let retry_after = retry_after(response.headers());
+    let throttled = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::SERVICE_UNAVAILABLE && retry_after.is_some());
+    if !throttled {
+        return Ok(response);
+    }
+    Err(ProviderError::RateLimited(
+        format!("{} {}", status, response.url().path()),
+        retry_after,
+    ))
+}
+
+/// 非成功响应转换为错误：5xx 为可重试的 `ServerError`，其余为 `ApiError`
+pub fn status_error(status: StatusCode, context: &str) -> ProviderError {
+    let message = format!("{} failed: {}", context, status);
+    if status.is_server_error() {
+        ProviderError::ServerError(message)
+    } else {
+        ProviderError::ApiError(message)
+    }
+}
+
+/// 解析 `Retry-After`，支持秒数与 HTTP 日期两种格式
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    // 已过去的时间点表示可以立即重试
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+fn apply_tls(
+    mut builder: ClientBuilder,
+    tls: &TlsSettings,
+) -> Result<ClientBuilder, ProviderError> {
+    let read = |path: &PathBuf| {
+        std::fs::read(path).map_err(|e| {
+            ProviderError::InvalidCredentials(format!("Cannot read {}: {}", path.display(), e))
+        })
+    };
+    let invalid = |path: &PathBuf, e: reqwest::Error| {
+        ProviderError::InvalidCredentials(format!("Invalid PEM in {}: {}", path.display(), e))
+    };
+
+    if let Some(path) = &tls.ca_bundle {
+        for cert in Certificate::from_pem_bundle(&read(path)?).map_err(|e| invalid(path, e))? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if let (Some(cert_path), Some(key_path)) = (&tls.client_cert, &tls.client_key) {
+        let identity = Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?)
+            .map_err(|e| invalid(cert_path, e))?;
+        builder = builder.identity(identity);
+    }
+
+    if tls.insecure {
+        tracing::warn!("已关闭 TLS 证书校验，连接可能被中间人劫持");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    if let Some(pin) = tls.pin_sha256 {
+        builder = builder.tls_info(true).connector_layer(PinLayer { pin });
+    }
+
+    Ok(builder)
+}
+
+/// 握手完成后校验服务器证书指纹，不匹配时连接失败，请求不会发出
+#[derive(Clone)]
+struct PinLayer {
+    pin: [u8; 32],
+}
+
+impl<S> tower::Layer<S> for PinLayer {
+    type Service = PinService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PinService {
+            inner,
+            pin: self.pin,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PinService<S> {
+    inner: S,
+    pin: [u8; 32],
+}
+
+impl<S, R> tower::Service<R> for PinService<S>
+where
+    S: tower::Service<R, Error = BoxError>,
+    S::Response: Connection + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let connecting = self.inner.call(req);
+        let pin = self.pin;
+        Box::pin(async move {
+            let conn = connecting.await?;
+            let mut extras = Default::default();
+            conn.connected().get_extras(&mut extras);
+            let certificate = extras
+                .get::<TlsInfo>()
+                .and_then(|info| info.peer_certificate())
+                .ok_or("Certificate pinning requires a TLS connection")?;
+            if Sha256::digest(certificate).as_slice() != pin {
+                return Err(format!(
+                    "Server certificate does not match pinned SHA-256 (got {})",
+                    hex::encode(Sha256::digest(certificate))
+                )
+                .into());
+            }
+            Ok(conn)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProxyConfig;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// 记录收到的请求头并返回 200 的 HTTP 服务器，既可作为目标也可作为代理
+    async fn start_recording_server() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests: Arc<Mutex<Vec<String>>> = Arc::default();
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let mut head = Vec::new();
+                let mut byte = [0u8; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    if conn.read(&mut byte).await.unwrap_or(0) == 0 {
+                        break;
+                    }
+                    head.push(byte[0]);
+                }
+                log.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&head).to_lowercase());
+                let _ = conn
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+        (addr, requests)
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_and_custom_headers() {
+        let (addr, requests) = start_recording_server().await;
+        let mut settings = NetworkSettings {
+            user_agent: Some("DiskSync-Test/1.0".to_string()),
+            ..NetworkSettings::default()
+        };
+        settings
+            .custom_headers
+            .insert("X-Team".to_string(), "storage".to_string());
+
+        let client = build_client(&settings, &TlsSettings::default()).unwrap();
+        client
+            .get(format!("http://{}/a", addr))
+            .send()
+            .await
+            .unwrap();
+
+        let request = requests.lock().unwrap()[0].clone();
+        assert!(request.contains("user-agent: disksync-test/1.0"));
+        assert!(request.contains("x-team: storage"));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_with_auth_and_bypass_list() {
+        let (proxy_addr, proxy_requests) = start_recording_server().await;
+        let (direct_addr, direct_requests) = start_recording_server().await;
+        let settings = NetworkSettings {
+            proxy: Some(ProxyConfig {
+                url: format!("http://{}", proxy_addr),
+                username: Some("user".to_string()),
+                password: Some("secret".to_string()),
+                bypass_for_local: false,
+                bypass_list: vec!["direct.test".to_string(), direct_addr.ip().to_string()],
+            }),
+            ..NetworkSettings::default()
+        };
+        let client = build_client(&settings, &TlsSettings::default()).unwrap();
+
+        // 不在绕过列表中的主机经由代理，请求行为绝对地址
+        client
+            .get("http://files.example.invalid/x")
+            .send()
+            .await
+            .unwrap();
+        let request = proxy_requests.lock().unwrap()[0].clone();
+        assert!(request.starts_with("get http://files.example.invalid/x http/1.1"));
+        assert!(request.contains("proxy-authorization: basic dxnlcjpzzwnyzxq="));
+
+        // 绕过列表中的地址直接连接
+        client
+            .get(format!("http://{}/y", direct_addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(proxy_requests.lock().unwrap().len(), 1);
+        assert!(direct_requests.lock().unwrap()[0].starts_with("get /y http/1.1"));
+    }
+
+    #[test]
+    fn test_retry_after_seconds_and_http_date() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_invalid_header_is_rejected() {
+        let mut settings = NetworkSettings::default();
+        settings
+            .custom_headers
+            .insert("Bad Header".to_string(), "v".to_string());
+        assert!(matches!(
+            build_client(&settings, &TlsSettings::default()),
+            Err(ProviderError::ConnectionFailed(_))
+        ));
+    }
+
+    mod tls {
+        use super::*;
+        use openssl::nid::Nid;
+        use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+        use std::io::{Read, Write};
+        use std::path::Path;
+
+        fn fixture(name: &str) -> PathBuf {
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/tls")
+                .join(name)
+        }
+
+        /// 收到的请求行及客户端证书的 CN
+        type TlsRequestLog = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+        /// 使用测试 CA 签发证书的 HTTPS 服务器
+        fn start_tls_server(require_client_cert: bool) -> (SocketAddr, TlsRequestLog) {
+            let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+            acceptor
+                .set_private_key_file(fixture("server.key"), SslFiletype::PEM)
+                .unwrap();
+            acceptor
+                .set_certificate_chain_file(fixture("server.pem"))
+                .unwrap();
+            if require_client_cert {
+                acceptor.set_ca_file(fixture("ca.pem")).unwrap();
+                acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            }
+            let acceptor = Arc::new(acceptor.build());
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let requests: TlsRequestLog = Arc::default();
+            let log = requests.clone();
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let acceptor = acceptor.clone();
+                    let log = log.clone();
+                    std::thread::spawn(move || {
+                        let Ok(mut tls) = acceptor.accept(stream) else {
+                            return;
+                        };
+                        let mut head = Vec::new();
+                        let mut byte = [0u8; 1];
+                        while !head.ends_with(b"\r\n\r\n") {
+                            if tls.read(&mut byte).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            head.push(byte[0]);
+                        }
+                        let client_cn = tls.ssl().peer_certificate().and_then(|cert| {
+                            let entry =
+                                cert.subject_name().entries_by_nid(Nid::COMMONNAME).next()?;
+                            Some(entry.data().as_utf8().ok()?.to_string())
+                        });
+                        let line = String::from_utf8_lossy(&head)
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .to_string();
+                        log.lock().unwrap().push((line, client_cn));
+                        let _ = tls.write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        );
+                        let _ = tls.shutdown();
+                    });
+                }
+            });
+            (addr, requests)
+        }
+
+        fn credentials(pairs: &[(&str, String)]) -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect()
+        }
+
+        async fn get(addr: SocketAddr, tls: &HashMap<String, String>) -> Result<(), String> {
+            let tls = TlsSettings::from_credentials(tls).map_err(|e| e.to_string())?;
+            let client =
+                build_client(&NetworkSettings::default(), &tls).map_err(|e| e.to_string())?;
+            client
+                .get(format!("https://127.0.0.1:{}/dav", addr.port()))
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("{:?}", e))
+        }
+
+        fn ca() -> (&'static str, String) {
+            ("tls_ca_bundle", fixture("ca.pem").display().to_string())
+        }
+
+        #[tokio::test]
+        async fn test_private_ca_bundle_and_insecure() {
+            let (addr, requests) = start_tls_server(false);
+
+            assert!(get(addr, &HashMap::new()).await.is_err());
+            get(addr, &credentials(&[ca()])).await.unwrap();
+            get(addr, &credentials(&[("tls_insecure", "true".to_string())]))
+                .await
+                .unwrap();
+            assert_eq!(requests.lock().unwrap().len(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_certificate_pin_is_checked_before_request() {
+            let (addr, requests) = start_tls_server(false);
+            let server_cert =
+                openssl::x509::X509::from_pem(&std::fs::read(fixture("server.pem")).unwrap())
+                    .unwrap();
+            let pin = hex::encode(Sha256::digest(server_cert.to_der().unwrap()));
+
+            get(
+                addr,
+                &credentials(&[ca(), ("tls_pin_sha256", pin.to_uppercase())]),
+            )
+            .await
+            .unwrap();
+            assert_eq!(requests.lock().unwrap().len(), 1);
+
+            // 即使关闭了证书链校验，指纹不符时也不发出请求
+            let wrong = credentials(&[
+                ("tls_insecure", "true".to_string()),
+                ("tls_pin_sha256", "00".repeat(32)),
+            ]);
+            let error = get(addr, &wrong).await.unwrap_err();
+            assert!(error.contains("pinned SHA-256"), "{}", error);
+            assert_eq!(requests.lock().unwrap().len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_client_certificate_for_mutual_tls() {
+            let (addr, requests) = start_tls_server(true);
+
+            assert!(get(addr, &credentials(&[ca()])).await.is_err());
+            let mtls = credentials(&[
+                ca(),
+                (
+                    "tls_client_cert",
+                    fixture("client.pem").display().to_string(),
+                ),
+                (
+                    "tls_client_key",
+                    fixture("client.key").display().to_string(),
+                ),
+            ]);
+            get(addr, &mtls).await.unwrap();
+            assert_eq!(
+                requests.lock().unwrap()[0],
+                (
+                    "GET /dav HTTP/1.1".to_string(),
+                    Some("disksync-client".to_string())
+                )
+            );
+        }
+
+        #[test]
+        fn test_tls_settings_validation() {
+            let pin = "AB:".repeat(31) + "AB";
+            let settings =
+                TlsSettings::from_credentials(&credentials(&[("tls_pin_sha256", pin)])).unwrap();
+            assert_eq!(settings.pin_sha256, Some([0xab; 32]));
+
+            for bad in [
+                credentials(&[("tls_pin_sha256", "abcd".to_string())]),
+                credentials(&[("tls_insecure", "yes".to_string())]),
+                credentials(&[("tls_client_cert", "client.pem".to_string())]),
+            ] {
+                assert!(matches!(
+                    TlsSettings::from_credentials(&bad),
+                    Err(ProviderError::InvalidCredentials(_))
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! 本地文件系统存储提供者实现
+//!
+//! 以一个根目录为边界，把本地文件夹当作普通的存储后端使用，
+//! 常用于“本地目录 → WebDAV/网盘”的备份场景。
+//!
+//! # 路径约定
+//! 所有远端路径都以 `/` 开头并相对于根目录解析，`list` 返回的路径同样以 `/` 开头，
+//! 与 `WebDavProvider` 保持一致，便于 `SyncEngine` 计算相对路径。
+//! 包含 `..` 的路径会被拒绝，保证操作不会越出根目录。
+
+use crate::config::AccountConfig;
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    ByteStream, CaseSensitivity, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider,
+    UploadResult,
+};
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, instrument, warn};
+
+/// 本地文件系统存储提供者
+pub struct LocalProvider {
+    root: PathBuf,
+}
+
+impl LocalProvider {
+    /// 根据账户配置创建本地提供者，需要 `root` 凭据指定根目录
+    pub async fn new(config: &AccountConfig) -> Result<Self, ProviderError> {
+        let root = config
+            .credentials
+            .get("root")
+            .ok_or_else(|| ProviderError::MissingCredentials("root".to_string()))?;
+
+        info!(root = %root, "初始化 Local Provider");
+        Ok(Self::with_root(root))
+    }
+
+    /// 直接以指定目录作为根目录创建提供者
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 将远端路径解析为根目录下的本地路径，拒绝越界访问
+    fn resolve(&self, path: &str) -> Result<PathBuf, SyncError> {
+        let mut resolved = self.root.clone();
+        for component in Path::new(path.trim_start_matches(['/', '\\'])).components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                _ => {
+                    return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                        format!("Path escapes provider root: {}", path),
+                    )));
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// 拼接目录与子项名称，得到以 `/` 开头的远端路径
+    fn join_remote(dir: &str, name: &str) -> String {
+        let dir = dir.trim_end_matches('/');
+        if dir.is_empty() {
+            format!("/{}", name)
+        } else if dir.starts_with('/') {
+            format!("{}/{}", dir, name)
+        } else {
+            format!("/{}/{}", dir, name)
+        }
+    }
+
+    fn to_file_info(path: String, metadata: &std::fs::Metadata) -> FileInfo {
+        FileInfo {
+            path,
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().map(system_time_to_secs).unwrap_or(0),
+            hash: None,
+            is_dir: metadata.is_dir(),
+            permissions: file_mode(metadata),
+        }
+    }
+
+    /// 目标文件同目录下的临时文件路径
+    fn part_path(target: &Path) -> PathBuf {
+        let file_name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        target.with_file_name(format!(".{}.{}.part", file_name, uuid::Uuid::new_v4()))
+    }
+
+    /// 复制文件并保留源文件的修改时间
+    ///
+    /// 先写入同目录下的临时文件再重命名，避免中断时留下不完整的目标文件。
+    async fn copy_preserving_mtime(from: &Path, to: &Path) -> Result<u64, SyncError> {
+        if let Some(parent) = to.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let temp_path = Self::part_path(to);
+
+        let bytes = match tokio::fs::copy(from, &temp_path).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(map_io_error(e, &from.to_string_lossy()));
+            }
+        };
+
+        let mtime = tokio::fs::metadata(from).await?.modified()?;
+        let temp_clone = temp_path.clone();
+        tokio::task::spawn_blocking(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&temp_clone)?
+                .set_modified(mtime)
+        })
+        .await
+        .map_err(|e| SyncError::Unknown(e.to_string()))??;
+
+        if let Err(e) = tokio::fs::rename(&temp_path, to).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(SyncError::Io(e));
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn system_time_to_secs(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// 文件的 Unix 权限位，非 Unix 平台返回 `None`
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+fn map_io_error(error: std::io::Error, path: &str) -> SyncError {
+    match error.kind() {
+        ErrorKind::NotFound => SyncError::Provider(ProviderError::FileNotFound(path.to_string())),
+        ErrorKind::PermissionDenied => {
+            SyncError::Provider(ProviderError::PermissionDenied(path.to_string()))
+        }
+        _ => SyncError::Io(error),
+    }
+}
+
+#[async_trait]
+impl StorageProvider for LocalProvider {
+    async fn verify(&self) -> Result<(), SyncError> {
+        match tokio::fs::metadata(&self.root).await {
+            Ok(meta) if meta.is_dir() => Ok(()),
+            Ok(_) => Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Root is not a directory: {}",
+                self.root.display()
+            )))),
+            Err(e) => Err(map_io_error(e, &self.root.to_string_lossy())),
+        }
+    }
+
+    /// 列出目录内容
+    #[instrument(skip(self), fields(root = %self.root.display()))]
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let dir = self.resolve(path)?;
+        let mut entries = tokio::fs::read_dir(&dir)
+            .await
+            .map_err(|e| map_io_error(e, path))?;
+
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // 跳过上传过程中的临时文件
+            if name.starts_with('.') && name.ends_with(".part") {
+                continue;
+            }
+            let metadata = match entry.metadata().await {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!(name = %name, error = %e, "读取文件元数据失败，跳过");
+                    continue;
+                }
+            };
+            files.push(Self::to_file_info(
+                Self::join_remote(path, &name),
+                &metadata,
+            ));
+        }
+
+        debug!(count = files.len(), "列出本地目录完成");
+        Ok(files)
+    }
+
+    /// 上传文件（复制到根目录下）
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start = Instant::now();
+        let target = self.resolve(remote_path)?;
+        let bytes = Self::copy_preserving_mtime(local_path, &target).await?;
+
+        Ok(UploadResult {
+            bytes_uploaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            compression: None,
+        })
+    }
+
+    /// 下载文件（从根目录复制出去）
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let source = self.resolve(remote_path)?;
+        let bytes = Self::copy_preserving_mtime(&source, local_path)
+            .await
+            .map_err(|e| match e {
+                SyncError::Provider(ProviderError::FileNotFound(_)) => {
+                    SyncError::Provider(ProviderError::FileNotFound(remote_path.to_string()))
+                }
+                other => other,
+            })?;
+
+        Ok(DownloadResult {
+            bytes_downloaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    /// 删除文件或目录，不允许删除根目录本身
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let target = self.resolve(path)?;
+        if target == self.root {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete provider root".to_string(),
+            )));
+        }
+
+        let metadata = match tokio::fs::symlink_metadata(&target).await {
+            Ok(m) => m,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                warn!(path = %path, "文件或目录不存在，视为删除成功");
+                return Ok(());
+            }
+            Err(e) => return Err(map_io_error(e, path)),
+        };
+
+        let result = if metadata.is_dir() {
+            tokio::fs::remove_dir_all(&target).await
+        } else {
+            tokio::fs::remove_file(&target).await
+        };
+        result.map_err(|e| map_io_error(e, path))
+    }
+
+    /// 创建目录（包括缺失的父目录）
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        let target = self.resolve(path)?;
+        tokio::fs::create_dir_all(&target)
+            .await
+            .map_err(|e| map_io_error(e, path))
+    }
+
+    /// 获取文件或目录信息
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let target = self.resolve(path)?;
+        let metadata = tokio::fs::metadata(&target)
+            .await
+            .map_err(|e| map_io_error(e, path))?;
+        Ok(Self::to_file_info(path.to_string(), &metadata))
+    }
+
+    /// 检查文件或目录是否存在
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        let target = self.resolve(path)?;
+        Ok(tokio::fs::try_exists(&target).await?)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        let windows = cfg!(windows);
+        ProviderCapabilities {
+            supports_hash: None,
+            can_set_mtime: true,
+            can_set_permissions: cfg!(unix),
+            supports_range_read: true,
+            supports_server_copy: true,
+            supports_server_move: true,
+            max_file_size: None,
+            case_sensitivity: if windows || cfg!(target_os = "macos") {
+                CaseSensitivity::Insensitive
+            } else {
+                CaseSensitivity::Sensitive
+            },
+            forbidden_chars: if windows {
+                vec!['<', '>', ':', '"', '|', '?', '*', '\\']
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// 设置本地文件的修改时间
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let target = self.resolve(path)?;
+        let mtime = if modified >= 0 {
+            UNIX_EPOCH + std::time::Duration::from_secs(modified as u64)
+        } else {
+            UNIX_EPOCH - std::time::Duration::from_secs(modified.unsigned_abs())
+        };
+        tokio::task::spawn_blocking(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&target)?
+                .set_modified(mtime)
+        })
+        .await
+        .map_err(|e| SyncError::Unknown(e.to_string()))?
+        .map_err(|e| map_io_error(e, path))
+    }
+
+    /// 设置本地文件的权限位，仅 Unix 平台支持
+    #[cfg(unix)]
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let target = self.resolve(path)?;
+        tokio::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode & 0o7777))
+            .await
+            .map_err(|e| map_io_error(e, path))
+    }
+
+    /// 重命名文件或目录，必要时创建目标父目录
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let source = self.resolve(from)?;
+        let target = self.resolve(to)?;
+        if source == self.root || target == self.root {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to move provider root".to_string(),
+            )));
+        }
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&source, &target)
+            .await
+            .map_err(|e| map_io_error(e, from))
+    }
+
+    /// 复制文件并保留修改时间（不支持复制目录）
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let source = self.resolve(from)?;
+        let target = self.resolve(to)?;
+        if tokio::fs::metadata(&source)
+            .await
+            .map_err(|e| map_io_error(e, from))?
+            .is_dir()
+        {
+            return Err(SyncError::Unsupported(format!(
+                "Copying directories is not supported: {}",
+                from
+            )));
+        }
+        Self::copy_preserving_mtime(&source, &target).await?;
+        Ok(())
+    }
+
+    /// 流式写入，先写入临时文件再重命名
+    async fn upload_stream(
+        &self,
+        mut reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start = Instant::now();
+        let target = self.resolve(remote_path)?;
+        let temp_path = Self::part_path(&target);
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            let written = tokio::io::copy(&mut reader, &mut file).await?;
+            file.sync_all().await?;
+            if written != size {
+                return Err(SyncError::IntegrityCheckFailed(format!(
+                    "Stream size mismatch for {}: expected {}, got {}",
+                    remote_path, size, written
+                )));
+            }
+            drop(file);
+            tokio::fs::rename(&temp_path, &target).await?;
+            Ok(written)
+        }
+        .await;
+
+        match result {
+            Ok(written) => Ok(UploadResult {
+                bytes_uploaded: written,
+                file_size: written,
+                checksum: None,
+                elapsed_time: start.elapsed(),
+                compression: None,
+            }),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// 直接返回本地文件的读取器
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let source = self.resolve(remote_path)?;
+        let file = tokio::fs::File::open(&source)
+            .await
+            .map_err(|e| map_io_error(e, remote_path))?;
+        Ok(Box::new(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("local_provider_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_resolve_rejects_parent_dir() {
+        let provider = LocalProvider::with_root("/data");
+        assert_eq!(
+            provider.resolve("/a/b.txt").unwrap(),
+            PathBuf::from("/data/a/b.txt")
+        );
+        assert_eq!(provider.resolve("/").unwrap(), PathBuf::from("/data"));
+        assert!(provider.resolve("/../etc/passwd").is_err());
+        assert!(provider.resolve("a/../../b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_list_download_delete() {
+        let root = temp_root();
+        let provider = LocalProvider::with_root(&root);
+        provider.verify().await.unwrap();
+
+        let src = std::env::temp_dir().join(format!("local_src_{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&src, b"hello local").unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&src)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let result = provider.upload(&src, "/docs/a.txt").await.unwrap();
+        assert_eq!(result.file_size, 11);
+
+        let listing = provider.list("/docs").await.unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].path, "/docs/a.txt");
+        assert_eq!(listing[0].modified, 1_600_000_000);
+
+        let root_listing = provider.list("/").await.unwrap();
+        assert_eq!(root_listing.len(), 1);
+        assert_eq!(root_listing[0].path, "/docs");
+        assert!(root_listing[0].is_dir);
+
+        let dst = std::env::temp_dir().join(format!("local_dst_{}.txt", uuid::Uuid::new_v4()));
+        provider.download("/docs/a.txt", &dst).await.unwrap();
+        assert_eq!(std::fs::read(&dst).unwrap(), b"hello local");
+
+        let reader = provider.download_stream("/docs/a.txt").await.unwrap();
+        provider
+            .upload_stream(reader, 11, "/docs/b.txt")
+            .await
+            .unwrap();
+        assert_eq!(provider.stat("/docs/b.txt").await.unwrap().size, 11);
+        provider
+            .set_mtime("/docs/b.txt", 1_600_000_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            provider.stat("/docs/b.txt").await.unwrap().modified,
+            1_600_000_000
+        );
+        if cfg!(unix) {
+            provider
+                .set_permissions("/docs/b.txt", 0o640)
+                .await
+                .unwrap();
+            assert_eq!(
+                provider.stat("/docs/b.txt").await.unwrap().permissions,
+                Some(0o640)
+            );
+        }
+
+        provider
+            .move_path("/docs/b.txt", "/moved/c.txt")
+            .await
+            .unwrap();
+        assert!(!provider.exists("/docs/b.txt").await.unwrap());
+        provider
+            .copy_path("/moved/c.txt", "/docs/d.txt")
+            .await
+            .unwrap();
+        assert_eq!(provider.stat("/docs/d.txt").await.unwrap().size, 11);
+        assert!(provider.move_path("/", "/elsewhere").await.is_err());
+
+        provider.delete("/docs").await.unwrap();
+        assert!(!provider.exists("/docs/a.txt").await.unwrap());
+        assert!(provider.delete("/").await.is_err());
+        assert!(matches!(
+            provider.stat("/docs").await,
+            Err(SyncError::Provider(ProviderError::FileNotFound(_)))
+        ));
+
+        std::fs::remove_file(&src).ok();
+        std::fs::remove_file(&dst).ok();
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,684 @@
+//! 内存存储提供者实现
+//!
+//! 所有文件与目录都保存在进程内存中，进程退出后丢失。
+//! 用于测试、试运行（dry-run）模拟以及嵌入方的演示，
+//! 支持完整的 `StorageProvider` 接口：目录、修改时间、权限位、SHA-256 哈希、
+//! 服务端复制/移动与流式读写。
+//!
+//! # 路径约定
+//! 与 `LocalProvider` 相同：路径以 `/` 开头，`list` 返回以 `/` 开头的完整路径，
+//! 包含 `..` 的路径会被拒绝。根目录 `/` 始终存在。
+
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    ByteStream, CaseSensitivity, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider,
+    StorageQuota, UploadResult,
+};
+use crate::sync::diff::ChecksumType;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Instant, UNIX_EPOCH};
+use tokio::io::AsyncReadExt;
+use tracing::{debug, info, warn};
+
+const FILE_MODE: u32 = 0o644;
+const DIR_MODE: u32 = 0o755;
+
+/// 内存中的一个文件或目录
+#[derive(Debug, Clone)]
+struct Entry {
+    /// 目录的内容为空
+    content: Arc<[u8]>,
+    is_dir: bool,
+    modified: i64,
+    permissions: u32,
+    /// 内容的 SHA-256（小写十六进制），目录为 `None`
+    hash: Option<String>,
+}
+
+impl Entry {
+    fn dir(modified: i64) -> Self {
+        Self {
+            content: Arc::from(Vec::new()),
+            is_dir: true,
+            modified,
+            permissions: DIR_MODE,
+            hash: None,
+        }
+    }
+
+    fn file(content: Vec<u8>, modified: i64) -> Self {
+        let hash = hex::encode(Sha256::digest(&content));
+        Self {
+            content: Arc::from(content),
+            is_dir: false,
+            modified,
+            permissions: FILE_MODE,
+            hash: Some(hash),
+        }
+    }
+
+    fn to_file_info(&self, path: &str) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size: self.content.len() as u64,
+            modified: self.modified,
+            hash: self.hash.clone(),
+            is_dir: self.is_dir,
+            permissions: Some(self.permissions),
+        }
+    }
+}
+
+/// 内存存储提供者
+pub struct MemoryProvider {
+    /// 规范化路径 -> 条目，按路径排序便于按前缀取出子树
+    entries: RwLock<BTreeMap<String, Entry>>,
+}
+
+impl Default for MemoryProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryProvider {
+    /// 创建只包含根目录的空提供者
+    pub fn new() -> Self {
+        info!("初始化 Memory Provider");
+        let mut entries = BTreeMap::new();
+        entries.insert("/".to_string(), Entry::dir(now()));
+        Self {
+            entries: RwLock::new(entries),
+        }
+    }
+
+    /// 直接写入文件并指定修改时间，缺失的父目录会自动创建
+    pub fn insert_file(
+        &self,
+        path: &str,
+        content: impl Into<Vec<u8>>,
+        modified: i64,
+    ) -> Result<(), SyncError> {
+        let path = normalize(path)?;
+        let mut entries = self.entries.write().unwrap();
+        write_file(&mut entries, &path, content.into(), modified)
+    }
+
+    fn entry(&self, path: &str) -> Result<Entry, SyncError> {
+        let key = normalize(path)?;
+        self.entries
+            .read()
+            .unwrap()
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn file_entry(&self, path: &str) -> Result<Entry, SyncError> {
+        let entry = self.entry(path)?;
+        if entry.is_dir {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Is a directory: {}",
+                path
+            ))));
+        }
+        Ok(entry)
+    }
+
+    /// 复制或移动整个子树，目标已存在时先删除
+    fn transfer(&self, from: &str, to: &str, remove_source: bool) -> Result<(), SyncError> {
+        let source = normalize(from)?;
+        let target = normalize(to)?;
+        if source == "/" || target == "/" {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to move or copy provider root".to_string(),
+            )));
+        }
+        if source == target || is_descendant(&target, &source) || is_descendant(&source, &target) {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Source and target overlap: {} -> {}",
+                from, to
+            ))));
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        if !entries.contains_key(&source) {
+            return Err(not_found(from));
+        }
+        ensure_parents(&mut entries, &target)?;
+
+        let subtree: Vec<(String, Entry)> = subtree_keys(&entries, &source)
+            .into_iter()
+            .map(|key| {
+                let entry = entries[&key].clone();
+                (key, entry)
+            })
+            .collect();
+        for key in subtree_keys(&entries, &target) {
+            entries.remove(&key);
+        }
+        for (key, entry) in subtree {
+            if remove_source {
+                entries.remove(&key);
+            }
+            entries.insert(format!("{}{}", target, &key[source.len()..]), entry);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageProvider for MemoryProvider {
+    async fn verify(&self) -> Result<(), SyncError> {
+        Ok(())
+    }
+
+    /// 列出目录的直接子项
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let dir = self.entry(path)?;
+        if !dir.is_dir {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Not a directory: {}",
+                path
+            ))));
+        }
+
+        let prefix = child_prefix(&normalize(path)?);
+        let entries = self.entries.read().unwrap();
+        let files: Vec<FileInfo> = descendants(&entries, &prefix)
+            .filter(|(key, _)| !key[prefix.len()..].contains('/'))
+            .map(|(key, entry)| entry.to_file_info(key))
+            .collect();
+
+        debug!(count = files.len(), "列出内存目录完成");
+        Ok(files)
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let root = normalize(path)?;
+        let entries = self.entries.read().unwrap();
+        if !entries.get(&root).is_some_and(|e| e.is_dir) {
+            return Err(not_found(path));
+        }
+
+        Ok(descendants(&entries, &child_prefix(&root))
+            .map(|(key, entry)| entry.to_file_info(key))
+            .collect())
+    }
+
+    /// 读取本地文件写入内存，保留本地文件的修改时间
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start = Instant::now();
+        let content = tokio::fs::read(local_path).await?;
+        let modified = tokio::fs::metadata(local_path)
+            .await?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let size = content.len() as u64;
+        self.insert_file(remote_path, content, modified)?;
+
+        Ok(UploadResult {
+            bytes_uploaded: size,
+            file_size: size,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            compression: None,
+        })
+    }
+
+    /// 写出到本地文件，并把本地文件的修改时间设为内存中的修改时间
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let entry = self.file_entry(remote_path)?;
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let local_path = local_path.to_path_buf();
+        let content = entry.content.clone();
+        let mtime = if entry.modified >= 0 {
+            UNIX_EPOCH + std::time::Duration::from_secs(entry.modified as u64)
+        } else {
+            UNIX_EPOCH - std::time::Duration::from_secs(entry.modified.unsigned_abs())
+        };
+        tokio::task::spawn_blocking(move || {
+            std::fs::write(&local_path, &content)?;
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&local_path)?
+                .set_modified(mtime)
+        })
+        .await
+        .map_err(|e| SyncError::Unknown(e.to_string()))??;
+
+        let size = entry.content.len() as u64;
+        Ok(DownloadResult {
+            bytes_downloaded: size,
+            file_size: size,
+            checksum: entry.hash,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    /// 递归删除文件或目录，不允许删除根目录本身
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let key = normalize(path)?;
+        if key == "/" {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete provider root".to_string(),
+            )));
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        if !entries.contains_key(&key) {
+            warn!(path = %path, "文件或目录不存在，视为删除成功");
+            return Ok(());
+        }
+        for key in subtree_keys(&entries, &key) {
+            entries.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// 创建目录（包括缺失的父目录）
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        let key = normalize(path)?;
+        let mut entries = self.entries.write().unwrap();
+        ensure_parents(&mut entries, &key)?;
+        match entries.get(&key) {
+            Some(entry) if entry.is_dir => Ok(()),
+            Some(_) => Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "File exists: {}",
+                path
+            )))),
+            None => {
+                entries.insert(key, Entry::dir(now()));
+                Ok(())
+            }
+        }
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        Ok(self.entry(path)?.to_file_info(&normalize(path)?))
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        let key = normalize(path)?;
+        Ok(self.entries.read().unwrap().contains_key(&key))
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_hash: Some(ChecksumType::Sha256),
+            can_set_mtime: true,
+            can_set_permissions: true,
+            supports_range_read: true,
+            supports_server_copy: true,
+            supports_server_move: true,
+            max_file_size: None,
+            case_sensitivity: CaseSensitivity::Sensitive,
+            forbidden_chars: Vec::new(),
+        }
+    }
+
+    /// 已用空间为全部文件大小之和，容量不限
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        let used = self
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.content.len() as u64)
+            .sum();
+        Ok(StorageQuota {
+            used_bytes: Some(used),
+            available_bytes: None,
+        })
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let key = normalize(path)?;
+        let mut entries = self.entries.write().unwrap();
+        let entry = entries.get_mut(&key).ok_or_else(|| not_found(path))?;
+        entry.modified = modified;
+        Ok(())
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        let key = normalize(path)?;
+        let mut entries = self.entries.write().unwrap();
+        let entry = entries.get_mut(&key).ok_or_else(|| not_found(path))?;
+        entry.permissions = mode & 0o7777;
+        Ok(())
+    }
+
+    /// 移动文件或目录，必要时创建目标父目录
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.transfer(from, to, true)
+    }
+
+    /// 复制文件或目录（递归），保留修改时间与权限位
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.transfer(from, to, false)
+    }
+
+    /// 把整个流读入内存，读取的字节数与 `size` 不符时不写入
+    async fn upload_stream(
+        &self,
+        mut reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start = Instant::now();
+        let mut content = Vec::with_capacity(size as usize);
+        let written = reader.read_to_end(&mut content).await? as u64;
+        if written != size {
+            return Err(SyncError::IntegrityCheckFailed(format!(
+                "Stream size mismatch for {}: expected {}, got {}",
+                remote_path, size, written
+            )));
+        }
+        self.insert_file(remote_path, content, now())?;
+
+        Ok(UploadResult {
+            bytes_uploaded: written,
+            file_size: written,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            compression: None,
+        })
+    }
+
+    /// 返回内容快照的读取器，之后的写入不影响已返回的流
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let entry = self.file_entry(remote_path)?;
+        Ok(Box::new(std::io::Cursor::new(entry.content)))
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn not_found(path: &str) -> SyncError {
+    SyncError::Provider(ProviderError::FileNotFound(path.to_string()))
+}
+
+/// 规范化为以 `/` 开头、不以 `/` 结尾的路径，拒绝 `..`
+fn normalize(path: &str) -> Result<String, SyncError> {
+    let mut normalized = String::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                    format!("Path escapes provider root: {}", path),
+                )));
+            }
+            _ => {
+                normalized.push('/');
+                normalized.push_str(part);
+            }
+        }
+    }
+    if normalized.is_empty() {
+        normalized.push('/');
+    }
+    Ok(normalized)
+}
+
+/// 目录下子项路径的公共前缀
+fn child_prefix(dir: &str) -> String {
+    if dir == "/" {
+        dir.to_string()
+    } else {
+        format!("{}/", dir)
+    }
+}
+
+fn is_descendant(path: &str, ancestor: &str) -> bool {
+    path.starts_with(&child_prefix(ancestor))
+}
+
+/// 以 `prefix` 开头的全部条目，不含与前缀相同的根目录 `/`
+fn descendants<'a>(
+    entries: &'a BTreeMap<String, Entry>,
+    prefix: &str,
+) -> impl Iterator<Item = (&'a String, &'a Entry)> {
+    let prefix = prefix.to_string();
+    entries
+        .range::<str, _>((Bound::Excluded(prefix.as_str()), Bound::Unbounded))
+        .take_while(move |(key, _)| key.starts_with(&prefix))
+}
+
+/// 路径本身及其下全部条目的键
+fn subtree_keys(entries: &BTreeMap<String, Entry>, path: &str) -> Vec<String> {
+    let mut keys: Vec<String> = descendants(entries, &child_prefix(path))
+        .map(|(key, _)| key.clone())
+        .collect();
+    if entries.contains_key(path) {
+        keys.push(path.to_string());
+    }
+    keys
+}
+
+/// 创建 `path` 缺失的各级父目录，某一级已是文件时失败
+fn ensure_parents(entries: &mut BTreeMap<String, Entry>, path: &str) -> Result<(), SyncError> {
+    let mut parent = String::new();
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    for part in parts.iter().take(parts.len().saturating_sub(1)) {
+        parent.push('/');
+        parent.push_str(part);
+        match entries.get(&parent) {
+            Some(entry) if !entry.is_dir => {
+                return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                    "Not a directory: {}",
+                    parent
+                ))));
+            }
+            Some(_) => {}
+            None => {
+                entries.insert(parent.clone(), Entry::dir(now()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_file(
+    entries: &mut BTreeMap<String, Entry>,
+    path: &str,
+    content: Vec<u8>,
+    modified: i64,
+) -> Result<(), SyncError> {
+    if path == "/" || entries.get(path).is_some_and(|e| e.is_dir) {
+        return Err(SyncError::Provider(ProviderError::ApiError(format!(
+            "Is a directory: {}",
+            path
+        ))));
+    }
+    ensure_parents(entries, path)?;
+    entries.insert(path.to_string(), Entry::file(content, modified));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(provider: &MemoryProvider, path: &str) -> Vec<u8> {
+        let mut content = Vec::new();
+        provider
+            .download_stream(path)
+            .await
+            .unwrap()
+            .read_to_end(&mut content)
+            .await
+            .unwrap();
+        content
+    }
+
+    fn paths(files: &[FileInfo]) -> Vec<&str> {
+        let mut paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("").unwrap(), "/");
+        assert_eq!(normalize("/").unwrap(), "/");
+        assert_eq!(normalize("a//b/./c/").unwrap(), "/a/b/c");
+        assert!(normalize("/a/../b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_directories_and_listing() {
+        let provider = MemoryProvider::new();
+        provider.verify().await.unwrap();
+        provider.insert_file("/docs/a.txt", "a", 100).unwrap();
+        provider.insert_file("/docs/sub/b.txt", "bb", 200).unwrap();
+        provider.mkdir("/empty").await.unwrap();
+
+        assert_eq!(
+            paths(&provider.list("/").await.unwrap()),
+            vec!["/docs", "/empty"]
+        );
+        assert_eq!(
+            paths(&provider.list("/docs").await.unwrap()),
+            vec!["/docs/a.txt", "/docs/sub"]
+        );
+        assert_eq!(
+            paths(&provider.list_recursive("/docs").await.unwrap()),
+            vec!["/docs/a.txt", "/docs/sub", "/docs/sub/b.txt"]
+        );
+        assert!(provider.stat("/docs/sub").await.unwrap().is_dir);
+        assert!(matches!(
+            provider.list("/missing").await,
+            Err(SyncError::Provider(ProviderError::FileNotFound(_)))
+        ));
+        // 父路径是文件时无法在其下创建
+        assert!(provider.insert_file("/docs/a.txt/c", "c", 0).is_err());
+        assert!(provider.mkdir("/docs/a.txt").await.is_err());
+
+        provider.delete("/docs").await.unwrap();
+        assert!(!provider.exists("/docs/sub/b.txt").await.unwrap());
+        assert!(provider.delete("/").await.is_err());
+        assert_eq!(paths(&provider.list("/").await.unwrap()), vec!["/empty"]);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_hash_and_quota() {
+        let provider = MemoryProvider::new();
+        provider
+            .insert_file("/f.txt", "hello", 1_600_000_000)
+            .unwrap();
+
+        let info = provider.stat("/f.txt").await.unwrap();
+        assert_eq!(info.size, 5);
+        assert_eq!(info.modified, 1_600_000_000);
+        assert_eq!(info.permissions, Some(0o644));
+        assert_eq!(
+            info.hash.as_deref(),
+            Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+
+        provider.set_mtime("/f.txt", 42).await.unwrap();
+        provider.set_permissions("/f.txt", 0o100600).await.unwrap();
+        let info = provider.stat("/f.txt").await.unwrap();
+        assert_eq!(info.modified, 42);
+        assert_eq!(info.permissions, Some(0o600));
+        assert!(provider.set_mtime("/missing", 1).await.is_err());
+
+        provider.insert_file("/g.txt", "world!", 0).unwrap();
+        let quota = provider.quota().await.unwrap();
+        assert_eq!(quota.used_bytes, Some(11));
+        assert_eq!(quota.available_bytes, None);
+    }
+
+    #[tokio::test]
+    async fn test_move_and_copy_subtrees() {
+        let provider = MemoryProvider::new();
+        provider.insert_file("/src/a.txt", "a", 10).unwrap();
+        provider.insert_file("/src/sub/b.txt", "b", 20).unwrap();
+        provider.insert_file("/dst/old.txt", "old", 0).unwrap();
+
+        provider.copy_path("/src", "/copy/of/src").await.unwrap();
+        assert_eq!(read(&provider, "/copy/of/src/sub/b.txt").await, b"b");
+        assert_eq!(
+            provider.stat("/copy/of/src/a.txt").await.unwrap().modified,
+            10
+        );
+        assert!(provider.exists("/src/a.txt").await.unwrap());
+
+        // 目标已存在时整体覆盖
+        provider.move_path("/src", "/dst").await.unwrap();
+        assert!(!provider.exists("/src").await.unwrap());
+        assert!(!provider.exists("/dst/old.txt").await.unwrap());
+        assert_eq!(
+            paths(&provider.list_recursive("/dst").await.unwrap()),
+            vec!["/dst/a.txt", "/dst/sub", "/dst/sub/b.txt"]
+        );
+
+        assert!(provider.move_path("/dst", "/dst/inner").await.is_err());
+        assert!(provider.copy_path("/missing", "/x").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_download_and_streams() {
+        let dir = std::env::temp_dir().join(format!("memory_provider_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let local = dir.join("in.txt");
+        std::fs::write(&local, b"payload").unwrap();
+        let mtime = UNIX_EPOCH + std::time::Duration::from_secs(1_500_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&local)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let provider = MemoryProvider::new();
+        let result = provider.upload(&local, "/up/in.txt").await.unwrap();
+        assert_eq!(result.bytes_uploaded, 7);
+        assert_eq!(
+            provider.stat("/up/in.txt").await.unwrap().modified,
+            1_500_000_000
+        );
+
+        let out = dir.join("nested/out.txt");
+        provider.download("/up/in.txt", &out).await.unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), b"payload");
+        assert_eq!(std::fs::metadata(&out).unwrap().modified().unwrap(), mtime);
+        assert!(provider.download("/up", &out).await.is_err());
+
+        let reader: ByteStream = Box::new(std::io::Cursor::new(b"streamed".to_vec()));
+        provider.upload_stream(reader, 8, "/s.bin").await.unwrap();
+        assert_eq!(read(&provider, "/s.bin").await, b"streamed");
+
+        let short: ByteStream = Box::new(std::io::Cursor::new(b"abc".to_vec()));
+        assert!(matches!(
+            provider.upload_stream(short, 4, "/short.bin").await,
+            Err(SyncError::IntegrityCheckFailed(_))
+        ));
+        assert!(!provider.exists("/short.bin").await.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
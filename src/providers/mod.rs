@@ -1,19 +1,46 @@
 pub mod aliyun;
+pub mod bandwidth;
+pub mod compress;
+pub mod crypt;
+pub mod fault;
+pub mod http;
+pub mod local;
+pub mod memory;
 pub mod oneonefive;
+pub mod rate_limit;
+pub mod retry;
+pub mod s3;
+pub mod sftp;
+pub mod smb;
 pub mod webdav;
 
 pub use aliyun::AliYunDriveProvider;
+pub use bandwidth::BandwidthLimitedProvider;
+pub use compress::CompressProvider;
+pub use crypt::CryptProvider;
+pub use fault::{FaultConfig, FaultInjectionProvider};
+pub use local::LocalProvider;
+pub use memory::MemoryProvider;
 pub use oneonefive::OneOneFiveProvider;
+pub use rate_limit::RateLimitedProvider;
+pub use retry::RetryingProvider;
+pub use s3::S3Provider;
+pub use sftp::SftpProvider;
+pub use smb::SmbProvider;
 pub use webdav::WebDavProvider;
 
-use crate::config::RateLimitConfig;
-use crate::core::rate_limit::TokenBucketRateLimiter;
-use crate::core::traits::RateLimiter;
+use crate::config::CompressionAlgorithm;
 use crate::error::SyncError;
+use crate::sync::diff::ChecksumType;
 use async_trait::async_trait;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// 流式传输使用的字节读取器
+pub type ByteStream = Box<dyn AsyncRead + Send + Unpin>;
 
 #[async_trait]
 pub trait StorageProvider: Send + Sync {
@@ -30,58 +57,203 @@ pub trait StorageProvider: Send + Sync {
     async fn mkdir(&self, path: &str) -> Result<(), SyncError>;
     async fn stat(&self, path: &str) -> Result<FileInfo, SyncError>;
     async fn exists(&self, path: &str) -> Result<bool, SyncError>;
-}
 
-#[derive(Debug, Clone)]
-pub struct FileInfo {
-    pub path: String,
-    pub size: u64,
-    pub modified: i64,
-    pub hash: Option<String>,
-    pub is_dir: bool,
-}
+    /// 提供商能力描述，调用方据此避免请求后端不支持的操作
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
 
-#[derive(Debug, Default)]
-pub struct UploadResult {
-    pub bytes_uploaded: u64,
-    pub file_size: u64,
-    pub checksum: Option<String>,
-    pub elapsed_time: Duration,
-}
+    /// 一次请求递归列出目录下的全部条目（不含目录本身）
+    ///
+    /// 默认返回 `Unsupported`，调用方应退回逐级 `list`。
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        Err(SyncError::Unsupported(format!(
+            "Recursive listing is not supported: {}",
+            path
+        )))
+    }
 
-#[derive(Debug, Default)]
-pub struct DownloadResult {
-    pub bytes_downloaded: u64,
-    pub file_size: u64,
-    pub checksum: Option<String>,
-    pub elapsed_time: Duration,
-}
+    /// 查询存储空间的已用量与可用量
+    ///
+    /// 默认返回 `Unsupported`，调用方应视为无法获取而非出错。
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        Err(SyncError::Unsupported(
+            "Quota reporting is not supported".to_string(),
+        ))
+    }
 
-pub struct RateLimitedProvider<T> {
-    inner: T,
-    limiter: Arc<dyn RateLimiter>,
+    /// 设置文件修改时间（Unix 秒），仅在 `can_set_mtime` 为真时可用
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        Err(SyncError::Unsupported(format!(
+            "Setting modification time is not supported: {} ({})",
+            path, modified
+        )))
+    }
+
+    /// 设置文件的 Unix 权限位，仅在 `can_set_permissions` 为真时可用
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        Err(SyncError::Unsupported(format!(
+            "Setting permissions is not supported: {} ({:o})",
+            path, mode
+        )))
+    }
+
+    /// 在服务端移动或重命名文件/目录，目标已存在时覆盖
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        Err(SyncError::Unsupported(format!(
+            "Server-side move is not supported: {} -> {}",
+            from, to
+        )))
+    }
+
+    /// 在服务端复制文件/目录，目标已存在时覆盖
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        Err(SyncError::Unsupported(format!(
+            "Server-side copy is not supported: {} -> {}",
+            from, to
+        )))
+    }
+
+    /// 以流的方式上传，`size` 为流的总字节数
+    ///
+    /// 默认实现先把流写入临时文件再调用 `upload`，内存占用与文件大小无关；
+    /// 支持流式请求体的提供商应覆盖此方法以避免落盘。
+    async fn upload_stream(
+        &self,
+        mut reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let temp_path = stream_temp_path();
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            let written = tokio::io::copy(&mut reader, &mut file).await?;
+            if written != size {
+                return Err(SyncError::IntegrityCheckFailed(format!(
+                    "Stream size mismatch for {}: expected {}, got {}",
+                    remote_path, size, written
+                )));
+            }
+            drop(file);
+            self.upload(&temp_path, remote_path).await
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        result
+    }
+
+    /// 以流的方式下载
+    ///
+    /// 默认实现先调用 `download` 写入临时文件，再返回该文件的读取器，
+    /// 读取器释放时删除临时文件。
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let temp_path = stream_temp_path();
+        if let Err(e) = self.download(remote_path, &temp_path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+        let file = tokio::fs::File::open(&temp_path).await?;
+        Ok(Box::new(TempFileReader {
+            file: Some(file),
+            path: temp_path,
+        }))
+    }
 }
 
-impl<T: StorageProvider> RateLimitedProvider<T> {
-    pub fn new(inner: T, config: RateLimitConfig) -> Self {
-        let limiter = Arc::new(TokenBucketRateLimiter::new(
-            config.max_concurrent as u64,
-            config.requests_per_minute as f64 / 60.0, // 转换为每秒请求数
-        ));
+/// 转发到被装箱的提供商，使装饰器（如 `FaultInjectionProvider`）可以包装 `Box<dyn StorageProvider>`
+#[async_trait]
+impl<P: StorageProvider + ?Sized> StorageProvider for Box<P> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        (**self).verify().await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        (**self).list(path).await
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        (**self).upload(local_path, remote_path).await
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        (**self).download(remote_path, local_path).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        (**self).delete(path).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        (**self).mkdir(path).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        (**self).stat(path).await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        (**self).exists(path).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        (**self).capabilities()
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        (**self).list_recursive(path).await
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        (**self).quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        (**self).set_mtime(path, modified).await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        (**self).set_permissions(path, mode).await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        (**self).move_path(from, to).await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        (**self).copy_path(from, to).await
+    }
 
-        Self { inner, limiter }
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        (**self).upload_stream(reader, size, remote_path).await
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        (**self).download_stream(remote_path).await
     }
 }
 
+/// 转发到被借用的提供商，使按任务启用的装饰器（如 `CompressProvider`）可以临时包装引擎持有的提供商
 #[async_trait]
-impl<T: StorageProvider> StorageProvider for RateLimitedProvider<T> {
+impl<P: StorageProvider + ?Sized> StorageProvider for &P {
     async fn verify(&self) -> Result<(), SyncError> {
-        Ok(())
+        (**self).verify().await
     }
 
     async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.list(path).await
+        (**self).list(path).await
     }
 
     async fn upload(
@@ -89,8 +261,7 @@ impl<T: StorageProvider> StorageProvider for RateLimitedProvider<T> {
         local_path: &Path,
         remote_path: &str,
     ) -> Result<UploadResult, SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.upload(local_path, remote_path).await
+        (**self).upload(local_path, remote_path).await
     }
 
     async fn download(
@@ -98,27 +269,208 @@ impl<T: StorageProvider> StorageProvider for RateLimitedProvider<T> {
         remote_path: &str,
         local_path: &Path,
     ) -> Result<DownloadResult, SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.download(remote_path, local_path).await
+        (**self).download(remote_path, local_path).await
     }
 
     async fn delete(&self, path: &str) -> Result<(), SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.delete(path).await
+        (**self).delete(path).await
     }
 
     async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.mkdir(path).await
+        (**self).mkdir(path).await
     }
 
     async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.stat(path).await
+        (**self).stat(path).await
     }
 
     async fn exists(&self, path: &str) -> Result<bool, SyncError> {
-        self.limiter.acquire().await?;
-        self.inner.exists(path).await
+        (**self).exists(path).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        (**self).capabilities()
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        (**self).list_recursive(path).await
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        (**self).quota().await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        (**self).set_mtime(path, modified).await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        (**self).set_permissions(path, mode).await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        (**self).move_path(from, to).await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        (**self).copy_path(from, to).await
+    }
+
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        (**self).upload_stream(reader, size, remote_path).await
+    }
+
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        (**self).download_stream(remote_path).await
+    }
+}
+
+fn stream_temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!("stream_{}.tmp", uuid::Uuid::new_v4()))
+}
+
+/// 读取完毕（释放）后自动删除的临时文件读取器
+struct TempFileReader {
+    file: Option<tokio::fs::File>,
+    path: PathBuf,
+}
+
+impl AsyncRead for TempFileReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.file.as_mut() {
+            Some(file) => Pin::new(file).poll_read(cx, buf),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl Drop for TempFileReader {
+    fn drop(&mut self) {
+        // 先关闭文件句柄，Windows 下无法删除仍被打开的文件
+        drop(self.file.take());
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub path: String,
+    pub size: u64,
+    pub modified: i64,
+    pub hash: Option<String>,
+    pub is_dir: bool,
+    /// Unix 权限位（如 0o644），后端不提供时为 `None`
+    pub permissions: Option<u32>,
+}
+
+/// 文件名大小写敏感性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// 区分大小写
+    Sensitive,
+    /// 不区分大小写（如 Windows、macOS 默认文件系统）
+    Insensitive,
+}
+
+/// 存储提供商能力描述
+#[derive(Debug, Clone)]
+pub struct ProviderCapabilities {
+    /// 支持的文件哈希类型，`None` 表示 `FileInfo.hash` 不可用
+    pub supports_hash: Option<ChecksumType>,
+    /// 能否设置文件修改时间（不能时远端时间为上传时间）
+    pub can_set_mtime: bool,
+    /// 能否读取（`FileInfo.permissions`）并设置 Unix 权限位
+    pub can_set_permissions: bool,
+    /// 是否支持按范围读取
+    pub supports_range_read: bool,
+    /// 是否支持服务端复制
+    pub supports_server_copy: bool,
+    /// 是否支持服务端移动/重命名
+    pub supports_server_move: bool,
+    /// 单文件大小上限（字节）
+    pub max_file_size: Option<u64>,
+    /// 文件名大小写敏感性
+    pub case_sensitivity: CaseSensitivity,
+    /// 文件名中禁止出现的字符
+    pub forbidden_chars: Vec<char>,
+}
+
+impl Default for ProviderCapabilities {
+    /// 保守的默认值：不假设后端支持任何可选能力
+    fn default() -> Self {
+        Self {
+            supports_hash: None,
+            can_set_mtime: false,
+            can_set_permissions: false,
+            supports_range_read: false,
+            supports_server_copy: false,
+            supports_server_move: false,
+            max_file_size: None,
+            case_sensitivity: CaseSensitivity::Sensitive,
+            forbidden_chars: Vec::new(),
+        }
+    }
+}
+
+impl ProviderCapabilities {
+    /// 文件名是否包含禁止字符
+    pub fn has_forbidden_chars(&self, name: &str) -> bool {
+        name.chars().any(|c| self.forbidden_chars.contains(&c))
+    }
+
+    /// 文件大小是否超过上限
+    pub fn exceeds_max_file_size(&self, size: u64) -> bool {
+        self.max_file_size.is_some_and(|max| size > max)
+    }
+}
+
+/// 存储空间用量，服务器未报告的项为 `None`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageQuota {
+    pub used_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+}
+
+impl StorageQuota {
+    /// 总容量，已用量与可用量都已知时才能得出
+    pub fn total_bytes(&self) -> Option<u64> {
+        Some(self.used_bytes? + self.available_bytes?)
     }
 }
+
+#[derive(Debug, Default)]
+pub struct UploadResult {
+    pub bytes_uploaded: u64,
+    pub file_size: u64,
+    pub checksum: Option<String>,
+    pub elapsed_time: Duration,
+    /// 上传时应用的压缩，原样保存时为空
+    pub compression: Option<CompressionInfo>,
+}
+
+/// 压缩上传的算法与压缩后的大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionInfo {
+    pub algorithm: CompressionAlgorithm,
+    /// 压缩后、交给下层（如加密）之前的字节数，不含下层增加的开销
+    pub compressed_size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct DownloadResult {
+    pub bytes_downloaded: u64,
+    pub file_size: u64,
+    pub checksum: Option<String>,
+    pub elapsed_time: Duration,
+    /// 续传时沿用之前中断留下的字节数，这部分没有重新传输
+    pub resumed_bytes: u64,
+}
@@ -6,25 +6,61 @@
 //! - ✅ 文件列表获取
 //! - ✅ 连接验证
 //! - ✅ 文件存在性检查
-//! - ⬜ 文件上传（待实现）
-//! - ⬜ 文件下载（待实现）
-//! - ⬜ 目录创建（待实现）
-//! - ⬜ 文件详情查询（待实现）
+//! - ✅ 文件上传（支持基于SHA1的秒传）
+//! - ✅ 文件下载
+//! - ✅ 目录创建
+//! - ✅ 文件详情查询
 //!
 //! # 认证方式
 //! 使用Cookie进行认证，需要在配置中提供有效的115网盘会话Cookie。
+//!
+//! # 路径解析
+//! 115网盘的接口以目录ID（cid）为参数，本模块将路径逐级解析为cid并缓存，
+//! 根目录的cid固定为"0"。
 
-use crate::config::AccountConfig;
-use crate::error::SyncError;
-use crate::providers::{DownloadResult, FileInfo, StorageProvider, UploadResult};
+use crate::config::{AccountConfig, NetworkSettings};
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, UploadResult,
+    http::{self, TlsSettings},
+};
+use crate::sync::diff::ChecksumType;
 use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use dashmap::DashMap;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, DATE};
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::SeekFrom;
 use std::path::Path;
-use std::time::Duration;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::{ReaderStream, StreamReader};
+use tracing::{debug, info};
 
 const API_BASE_URL: &str = "https://proapi.115.com";
 
+/// 单次列表请求的最大条目数
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// 秒传预校验使用的文件头长度
+const PREID_SIZE: u64 = 128 * 1024;
+
+/// 秒传状态：文件已存在，无需上传数据
+const UPLOAD_STATUS_FAST: i64 = 2;
+
+/// 秒传状态：需要上传文件数据
+const UPLOAD_STATUS_NEED_UPLOAD: i64 = 1;
+
+/// 秒传状态：服务端要求校验指定区间的SHA1
+const UPLOAD_STATUS_SIGN_CHECK: i64 = 7;
+
 /// 文件列表API响应结构
 #[derive(Debug, Deserialize)]
 struct FileListResponse {
@@ -40,14 +76,35 @@ struct FileListData {
 }
 
 /// 文件项信息
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct FileItem {
-    fid: String,
-    cid: String,
+    #[serde(default)]
+    fid: Option<String>, // 文件ID，目录没有该字段
+    cid: String,    // 目录ID（文件则为所在目录）
     n: String,      // name
     s: Option<u64>, // size
     t: String,      // time (timestamp string)
-                    // sha: String,    // hash
+    #[serde(default)]
+    sha: Option<String>, // SHA1
+    #[serde(default)]
+    pc: Option<String>, // pick code，下载时使用
+}
+
+impl FileItem {
+    fn is_dir(&self) -> bool {
+        match &self.fid {
+            Some(fid) => *fid == self.cid || self.s.is_none(),
+            None => true,
+        }
+    }
+
+    /// 删除等操作使用的ID：目录为cid，文件为fid
+    fn id(&self) -> &str {
+        match &self.fid {
+            Some(fid) if !self.is_dir() => fid,
+            _ => &self.cid,
+        }
+    }
 }
 
 /// 基础API响应结构
@@ -57,20 +114,83 @@ struct BaseResponse {
     error: Option<String>,
 }
 
+/// 带数据的API响应结构
+#[derive(Debug, Deserialize)]
+struct DataResponse<T> {
+    state: bool,
+    error: Option<String>,
+    data: Option<T>,
+}
+
+/// 新建目录返回的数据
+#[derive(Debug, Deserialize)]
+struct FolderAddData {
+    file_id: String,
+}
+
+/// 下载地址数据，按文件ID索引
+#[derive(Debug, Deserialize)]
+struct DownUrlItem {
+    url: DownUrl,
+}
+
+#[derive(Debug, Deserialize)]
+struct DownUrl {
+    url: String,
+}
+
+/// 上传初始化（秒传检查）返回的数据
+#[derive(Debug, Deserialize)]
+struct UploadInitData {
+    status: i64,
+    #[serde(default)]
+    bucket: Option<String>,
+    #[serde(default)]
+    object: Option<String>,
+    #[serde(default)]
+    callback: Option<UploadCallback>,
+    #[serde(default)]
+    sign_key: Option<String>,
+    #[serde(default)]
+    sign_check: Option<String>,
+}
+
+/// 对象存储上传完成后的回调参数
+#[derive(Debug, Deserialize)]
+struct UploadCallback {
+    callback: String,
+    callback_var: String,
+}
+
+/// 对象存储临时凭证
+#[derive(Debug, Deserialize)]
+struct UploadToken {
+    endpoint: String,
+    #[serde(rename = "AccessKeyId")]
+    access_key_id: String,
+    #[serde(rename = "AccessKeySecret")]
+    access_key_secret: String,
+    #[serde(rename = "SecurityToken")]
+    security_token: String,
+}
+
 /// 115网盘存储提供者
 ///
 /// 负责与115网盘API进行交互，实现文件存储相关操作。
 pub struct OneOneFiveProvider {
     client: reqwest::Client,
-    #[allow(dead_code)]
-    cookie: String,
+    /// 115 接口要求的 User-Agent 与 Cookie，重建客户端时覆盖全局设置中的同名请求头
+    headers: HeaderMap,
+    api_base: String,
+    /// 目录路径到cid的缓存
+    dir_cache: DashMap<String, String>,
 }
 
 impl OneOneFiveProvider {
     /// 创建新的115网盘提供者实例
     ///
     /// # 参数
-    /// - `config`: 账户配置，必须包含有效的cookie凭证
+    /// - `config`: 账户配置，必须包含有效的cookie凭证，可选 `api_base_url` 覆盖接口地址
     ///
     /// # 返回
     /// - 成功时返回 `OneOneFiveProvider` 实例
@@ -107,13 +227,58 @@ impl OneOneFiveProvider {
             })?,
         );
 
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .timeout(Duration::from_secs(30))
+        let client = http::client_builder(&NetworkSettings::default(), &TlsSettings::default())?
+            .default_headers(headers.clone())
+            .build()
+            .map_err(SyncError::Network)?;
+
+        let api_base = config
+            .credentials
+            .get("api_base_url")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| API_BASE_URL.to_string());
+
+        let dir_cache = DashMap::new();
+        dir_cache.insert("/".to_string(), "0".to_string());
+
+        Ok(Self {
+            client,
+            headers,
+            api_base,
+            dir_cache,
+        })
+    }
+
+    /// 按全局网络设置（代理、超时、请求头等）重建 HTTP 客户端
+    pub fn with_network_settings(mut self, network: &NetworkSettings) -> Result<Self, SyncError> {
+        self.client = http::client_builder(network, &TlsSettings::default())?
+            .default_headers(self.headers.clone())
             .build()
             .map_err(SyncError::Network)?;
+        Ok(self)
+    }
 
-        Ok(Self { client, cookie })
+    /// 解析API响应，`state` 为 false 时转换为 ApiError
+    async fn parse_response<T: DeserializeOwned>(
+        resp: reqwest::Response,
+        context: &str,
+    ) -> Result<T, SyncError> {
+        let resp = http::check_throttled(resp)?;
+        let resp = resp.error_for_status().map_err(SyncError::Network)?;
+        let data_resp: DataResponse<T> = resp.json().await.map_err(SyncError::Network)?;
+        if !data_resp.state {
+            return Err(SyncError::Provider(ProviderError::ApiError(
+                data_resp
+                    .error
+                    .unwrap_or_else(|| format!("{} failed", context)),
+            )));
+        }
+        data_resp.data.ok_or_else(|| {
+            SyncError::Provider(ProviderError::ApiError(format!(
+                "{}: missing data in response",
+                context
+            )))
+        })
     }
 
     /// 获取指定目录的文件列表
@@ -122,59 +287,420 @@ impl OneOneFiveProvider {
     /// - `cid`: 目录ID，根目录为"0"
     ///
     /// # 返回
-    /// - 成功时返回文件信息列表
+    /// - 成功时返回目录下的全部条目（自动翻页）
     /// - 失败时返回 SyncError
-    async fn get_file_list(&self, cid: &str) -> Result<Vec<FileInfo>, SyncError> {
-        let url = format!("{}/open/ufile/files", API_BASE_URL);
+    async fn get_file_list(&self, cid: &str) -> Result<Vec<FileItem>, SyncError> {
+        let url = format!("{}/open/ufile/files", self.api_base);
+        let limit = LIST_PAGE_SIZE.to_string();
+        let mut items = Vec::new();
+
+        loop {
+            let offset = items.len().to_string();
+            let resp = self
+                .client
+                .get(&url)
+                .query(&[
+                    ("aid", "1"),
+                    ("cid", cid),
+                    ("limit", limit.as_str()),
+                    ("offset", offset.as_str()),
+                    ("show_dir", "1"),
+                ])
+                .send()
+                .await
+                .map_err(SyncError::Network)?;
 
+            let list_resp: FileListResponse = resp.json().await.map_err(SyncError::Network)?;
+
+            if !list_resp.state {
+                return Err(SyncError::Provider(ProviderError::ApiError(
+                    list_resp.error.unwrap_or_else(|| "Unknown error".into()),
+                )));
+            }
+
+            let page = list_resp.data.map(|d| d.data).unwrap_or_default();
+            let page_len = page.len();
+            items.extend(page);
+            if page_len < LIST_PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// 列出目录条目，并缓存其中子目录的cid
+    async fn list_dir(&self, dir_path: &str, cid: &str) -> Result<Vec<FileItem>, SyncError> {
+        let items = self.get_file_list(cid).await?;
+        for item in items.iter().filter(|item| item.is_dir()) {
+            self.dir_cache
+                .insert(join_path(dir_path, &item.n), item.cid.clone());
+        }
+        Ok(items)
+    }
+
+    /// 将目录路径解析为cid，目录不存在时返回 None
+    async fn resolve_dir(&self, path: &str) -> Result<Option<String>, SyncError> {
+        let path = normalize_path(path);
+        if let Some(cid) = self.dir_cache.get(&path) {
+            return Ok(Some(cid.clone()));
+        }
+
+        let mut current_path = "/".to_string();
+        let mut current_cid = "0".to_string();
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            let next_path = join_path(&current_path, name);
+            let cached = self.dir_cache.get(&next_path).map(|cid| cid.clone());
+            let next_cid = match cached {
+                Some(cid) => cid,
+                None => {
+                    debug!(path = %next_path, "解析目录cid");
+                    self.list_dir(&current_path, &current_cid).await?;
+                    match self.dir_cache.get(&next_path) {
+                        Some(cid) => cid.clone(),
+                        None => return Ok(None),
+                    }
+                }
+            };
+            current_path = next_path;
+            current_cid = next_cid;
+        }
+
+        Ok(Some(current_cid))
+    }
+
+    /// 查找路径对应的条目，不存在时返回 None
+    async fn find_entry(&self, path: &str) -> Result<Option<FileItem>, SyncError> {
+        let path = normalize_path(path);
+        let (parent, name) = split_parent(&path);
+        let Some(parent_cid) = self.resolve_dir(&parent).await? else {
+            return Ok(None);
+        };
+        let items = self.list_dir(&parent, &parent_cid).await?;
+        Ok(items.into_iter().find(|item| item.n == name))
+    }
+
+    /// 查找文件条目，不存在或为目录时返回 FileNotFound
+    async fn find_file(&self, path: &str) -> Result<FileItem, SyncError> {
+        match self.find_entry(path).await? {
+            Some(item) if !item.is_dir() => Ok(item),
+            _ => Err(SyncError::Provider(ProviderError::FileNotFound(
+                path.to_string(),
+            ))),
+        }
+    }
+
+    /// 在父目录下新建目录，返回新目录的cid
+    async fn create_folder(&self, parent_cid: &str, name: &str) -> Result<String, SyncError> {
+        let url = format!("{}/open/folder/add", self.api_base);
+        let resp = self
+            .client
+            .post(&url)
+            .form(&[("pid", parent_cid), ("file_name", name)])
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        let data: FolderAddData = Self::parse_response(resp, "Create folder").await?;
+        Ok(data.file_id)
+    }
+
+    /// 按文件ID（目录为cid）删除条目
+    async fn delete_by_id(&self, id: &str) -> Result<(), SyncError> {
+        let url = format!("{}/open/ufile/delete", self.api_base);
+        let resp = self
+            .client
+            .post(&url)
+            .form(&[("fid", id)])
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+
+        let base_resp: BaseResponse = resp.json().await.map_err(SyncError::Network)?;
+
+        if !base_resp.state {
+            return Err(SyncError::Provider(ProviderError::ApiError(
+                base_resp.error.unwrap_or_else(|| "Delete failed".into()),
+            )));
+        }
+        Ok(())
+    }
+
+    /// 按文件ID重命名文件
+    async fn rename(&self, file_id: &str, name: &str) -> Result<(), SyncError> {
+        let url = format!("{}/open/ufile/update", self.api_base);
+        let resp = self
+            .client
+            .post(&url)
+            .form(&[("file_id", file_id), ("file_name", name)])
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+
+        let base_resp: BaseResponse = resp.json().await.map_err(SyncError::Network)?;
+
+        if !base_resp.state {
+            return Err(SyncError::Provider(ProviderError::ApiError(
+                base_resp.error.unwrap_or_else(|| "Rename failed".into()),
+            )));
+        }
+        Ok(())
+    }
+
+    /// 移除路径及其子目录的cid缓存
+    fn invalidate_cache(&self, path: &str) {
+        let prefix = format!("{}/", path);
+        self.dir_cache
+            .retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+
+    /// 获取文件的下载地址
+    async fn get_download_url(&self, pick_code: &str) -> Result<String, SyncError> {
+        let url = format!("{}/open/ufile/downurl", self.api_base);
+        let resp = self
+            .client
+            .post(&url)
+            .form(&[("pick_code", pick_code)])
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        let data: HashMap<String, DownUrlItem> =
+            Self::parse_response(resp, "Get download url").await?;
+        data.into_values()
+            .next()
+            .map(|item| item.url.url)
+            .ok_or_else(|| {
+                SyncError::Provider(ProviderError::ApiError("Empty download url".into()))
+            })
+    }
+
+    /// 上传初始化，服务端已有相同文件时直接完成秒传
+    async fn upload_init(&self, form: &[(&str, String)]) -> Result<UploadInitData, SyncError> {
+        let url = format!("{}/open/upload/init", self.api_base);
+        let resp = self
+            .client
+            .post(&url)
+            .form(form)
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        Self::parse_response(resp, "Upload init").await
+    }
+
+    /// 获取对象存储的临时上传凭证
+    async fn get_upload_token(&self) -> Result<UploadToken, SyncError> {
+        let url = format!("{}/open/upload/get_token", self.api_base);
         let resp = self
             .client
             .get(&url)
-            .query(&[
-                ("aid", "1"),
-                ("cid", cid),
-                ("limit", "1000"),
-                ("show_dir", "1"),
-            ])
             .send()
             .await
             .map_err(SyncError::Network)?;
+        Self::parse_response(resp, "Get upload token").await
+    }
+
+    /// 将文件数据上传到对象存储，上传完成后由回调在115中创建文件
+    async fn upload_to_oss(
+        &self,
+        local_path: &Path,
+        size: u64,
+        init: &UploadInitData,
+    ) -> Result<(), SyncError> {
+        let missing = |field: &str| {
+            SyncError::Provider(ProviderError::ApiError(format!(
+                "Upload init response missing {}",
+                field
+            )))
+        };
+        let bucket = init.bucket.as_deref().ok_or_else(|| missing("bucket"))?;
+        let object = init.object.as_deref().ok_or_else(|| missing("object"))?;
+        let callback = init.callback.as_ref().ok_or_else(|| missing("callback"))?;
 
-        let list_resp: FileListResponse = resp.json().await.map_err(SyncError::Network)?;
+        let token = self.get_upload_token().await?;
+        let url = oss_object_url(&token.endpoint, bucket, object)?;
 
-        if !list_resp.state {
-            return Err(SyncError::Provider(crate::error::ProviderError::ApiError(
-                list_resp.error.unwrap_or_else(|| "Unknown error".into()),
+        let content_type = "application/octet-stream";
+        let date = chrono::Utc::now()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let oss_headers = [
+            ("x-oss-callback", BASE64.encode(&callback.callback)),
+            ("x-oss-callback-var", BASE64.encode(&callback.callback_var)),
+            ("x-oss-security-token", token.security_token.clone()),
+        ];
+        let canonical_headers: String = oss_headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v))
+            .collect();
+        let string_to_sign = format!(
+            "PUT\n\n{}\n{}\n{}/{}/{}",
+            content_type, date, canonical_headers, bucket, object
+        );
+        let signature = hmac_sha1_base64(&token.access_key_secret, &string_to_sign);
+
+        let file = tokio::fs::File::open(local_path)
+            .await
+            .map_err(SyncError::Io)?;
+        let mut request = self
+            .client
+            .put(&url)
+            .header(DATE, date)
+            .header(CONTENT_TYPE, content_type)
+            .header(CONTENT_LENGTH, size)
+            .header(
+                AUTHORIZATION,
+                format!("OSS {}:{}", token.access_key_id, signature),
+            )
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)));
+        for (key, value) in oss_headers {
+            request = request.header(key, value);
+        }
+
+        let resp = request.send().await.map_err(SyncError::Network)?;
+        let resp = http::check_throttled(resp)?;
+        let resp = resp.error_for_status().map_err(SyncError::Network)?;
+        let callback_resp: BaseResponse = resp.json().await.map_err(SyncError::Network)?;
+        if !callback_resp.state {
+            return Err(SyncError::Provider(ProviderError::ApiError(
+                callback_resp
+                    .error
+                    .unwrap_or_else(|| "Upload callback failed".into()),
             )));
         }
+        Ok(())
+    }
+}
 
-        let mut files = Vec::new();
-        if let Some(data) = list_resp.data {
-            for item in data.data {
-                // 更准确地判断是否为目录
-                let is_dir = item.fid == item.cid || item.s.is_none();
-
-                // 解析修改时间，处理可能的错误
-                let modified = item.t.parse::<i64>().unwrap_or_else(|_| {
-                    // 如果解析失败，使用当前时间戳
-                    std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_secs() as i64
-                });
-
-                files.push(FileInfo {
-                    path: item.n,
-                    size: item.s.unwrap_or(0),
-                    modified,
-                    hash: None, // item.sha is not always present or reliable in list
-                    is_dir,
-                });
-            }
+/// 规范化路径：以"/"开头，去除多余及末尾的"/"
+fn normalize_path(path: &str) -> String {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    format!("/{}", components.join("/"))
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    format!("{}/{}", parent.trim_end_matches('/'), name)
+}
+
+/// 拆分为 (父目录, 名称)，路径需已规范化
+fn split_parent(path: &str) -> (String, String) {
+    match path.rsplit_once('/') {
+        Some(("", name)) => ("/".to_string(), name.to_string()),
+        Some((parent, name)) => (parent.to_string(), name.to_string()),
+        None => ("/".to_string(), path.to_string()),
+    }
+}
+
+fn to_file_info(parent: &str, item: &FileItem) -> FileInfo {
+    // 解析修改时间，处理可能的错误
+    let modified = item.t.parse::<i64>().unwrap_or_else(|_| {
+        // 如果解析失败，使用当前时间戳
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    });
+
+    FileInfo {
+        path: join_path(parent, &item.n),
+        size: item.s.unwrap_or(0),
+        modified,
+        hash: item.sha.as_ref().map(|sha| sha.to_uppercase()),
+        is_dir: item.is_dir(),
+        permissions: None,
+    }
+}
+
+/// 计算文件整体的SHA1与文件头（预校验）的SHA1，均为大写十六进制
+async fn file_sha1(path: &Path) -> Result<(String, String), SyncError> {
+    let mut file = tokio::fs::File::open(path).await.map_err(SyncError::Io)?;
+    let mut full = Sha1::new();
+    let mut head = Sha1::new();
+    let mut read_total = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = file.read(&mut buf).await.map_err(SyncError::Io)?;
+        if n == 0 {
+            break;
+        }
+        full.update(&buf[..n]);
+        if read_total < PREID_SIZE {
+            let take = ((PREID_SIZE - read_total) as usize).min(n);
+            head.update(&buf[..take]);
         }
+        read_total += n as u64;
+    }
+
+    Ok((
+        hex::encode_upper(full.finalize()),
+        hex::encode_upper(head.finalize()),
+    ))
+}
+
+/// 计算文件指定区间（闭区间，格式 "start-end"）的SHA1
+async fn range_sha1(path: &Path, range: &str) -> Result<String, SyncError> {
+    let invalid = || {
+        SyncError::Provider(ProviderError::ApiError(format!(
+            "Invalid sign check range: {}",
+            range
+        )))
+    };
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let start: u64 = start.trim().parse().map_err(|_| invalid())?;
+    let end: u64 = end.trim().parse().map_err(|_| invalid())?;
+    let mut file = tokio::fs::File::open(path).await.map_err(SyncError::Io)?;
+    let file_size = file.metadata().await.map_err(SyncError::Io)?.len();
+    if end < start || end >= file_size {
+        return Err(invalid());
+    }
+
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(SyncError::Io)?;
+    let mut hasher = Sha1::new();
+    let mut remaining = end - start + 1;
+    let mut buf = vec![0u8; 64 * 1024];
+    while remaining > 0 {
+        let take = remaining.min(buf.len() as u64) as usize;
+        file.read_exact(&mut buf[..take])
+            .await
+            .map_err(SyncError::Io)?;
+        hasher.update(&buf[..take]);
+        remaining -= take as u64;
+    }
+    Ok(hex::encode_upper(hasher.finalize()))
+}
+
+fn hmac_sha1_base64(key: &str, data: &str) -> String {
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    BASE64.encode(mac.finalize().into_bytes())
+}
 
-        Ok(files)
+/// 构造对象存储的上传地址，域名端点使用虚拟主机风格，IP或localhost使用路径风格
+fn oss_object_url(endpoint: &str, bucket: &str, object: &str) -> Result<String, SyncError> {
+    let endpoint = if endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("https://{}", endpoint)
+    };
+    let mut url = reqwest::Url::parse(&endpoint).map_err(|e| {
+        SyncError::Provider(ProviderError::ApiError(format!(
+            "Invalid upload endpoint {}: {}",
+            endpoint, e
+        )))
+    })?;
+
+    let host = url.host_str().unwrap_or_default().to_string();
+    let is_domain = host != "localhost" && host.parse::<std::net::IpAddr>().is_err();
+    if is_domain {
+        url.set_host(Some(&format!("{}.{}", bucket, host)))
+            .map_err(|e| SyncError::Provider(ProviderError::ApiError(e.to_string())))?;
+        url.set_path(object);
+    } else {
+        url.set_path(&format!("{}/{}", bucket, object));
     }
+    Ok(url.to_string())
 }
 
 #[async_trait]
@@ -186,86 +712,284 @@ impl StorageProvider for OneOneFiveProvider {
     }
 
     async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
-        // For simplicity, assuming path is a CID or mapping root to "0"
-        let cid = if path == "/" { "0" } else { path };
-        self.get_file_list(cid).await
+        let path = normalize_path(path);
+        let cid = self
+            .resolve_dir(&path)
+            .await?
+            .ok_or_else(|| SyncError::Provider(ProviderError::FileNotFound(path.clone())))?;
+        let items = self.list_dir(&path, &cid).await?;
+        Ok(items.iter().map(|item| to_file_info(&path, item)).collect())
     }
 
     async fn upload(
         &self,
-        _local_path: &Path,
-        _remote_path: &str,
+        local_path: &Path,
+        remote_path: &str,
     ) -> Result<UploadResult, SyncError> {
-        // TODO: Implement actual upload logic (requires complex multi-step process)
-        // 1. Pre-upload check (fast upload)
-        // 2. Get upload token/server
-        // 3. Upload file data
-        Err(SyncError::Provider(
-            crate::error::ProviderError::NotImplemented("Upload not implemented yet".into()),
-        ))
+        let start_time = Instant::now();
+        let remote_path = normalize_path(remote_path);
+        let (parent, name) = split_parent(&remote_path);
+        let file_size = tokio::fs::metadata(local_path)
+            .await
+            .map_err(SyncError::Io)?
+            .len();
+        let (sha1, preid) = file_sha1(local_path).await?;
+
+        self.mkdir(&parent).await?;
+        let parent_cid = self
+            .resolve_dir(&parent)
+            .await?
+            .ok_or_else(|| SyncError::Provider(ProviderError::FileNotFound(parent.clone())))?;
+
+        // 115 不会覆盖同名文件，而是自动重命名。已有旧文件时先以临时名称上传，
+        // 成功后再按文件ID删除旧文件并改名，上传失败不影响旧文件
+        let existing = self.find_entry(&remote_path).await?;
+        if existing.as_ref().is_some_and(|item| item.is_dir()) {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Cannot overwrite directory with file: {}",
+                remote_path
+            ))));
+        }
+        let upload_name = match &existing {
+            Some(_) => format!(".{}.{}.uploading", name, uuid::Uuid::new_v4().simple()),
+            None => name.clone(),
+        };
+
+        let mut form = vec![
+            ("file_name", upload_name.clone()),
+            ("file_size", file_size.to_string()),
+            ("target", format!("U_1_{}", parent_cid)),
+            ("fileid", sha1.clone()),
+            ("preid", preid),
+        ];
+        let mut init = self.upload_init(&form).await?;
+
+        // 服务端可能要求校验某个区间的SHA1，以证明确实持有该文件
+        if init.status == UPLOAD_STATUS_SIGN_CHECK {
+            let sign_key = init.sign_key.clone().unwrap_or_default();
+            let sign_check = init.sign_check.clone().unwrap_or_default();
+            let sign_val = range_sha1(local_path, &sign_check).await?;
+            form.push(("sign_key", sign_key));
+            form.push(("sign_val", sign_val));
+            init = self.upload_init(&form).await?;
+        }
+
+        let bytes_uploaded = match init.status {
+            UPLOAD_STATUS_FAST => {
+                info!(path = %remote_path, "秒传成功");
+                0
+            }
+            UPLOAD_STATUS_NEED_UPLOAD => {
+                debug!(path = %remote_path, size = file_size, "秒传未命中，上传文件数据");
+                self.upload_to_oss(local_path, file_size, &init).await?;
+                file_size
+            }
+            status => {
+                return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                    "Unexpected upload status: {}",
+                    status
+                ))));
+            }
+        };
+
+        if let Some(existing) = existing {
+            let uploaded = self.find_file(&join_path(&parent, &upload_name)).await?;
+            self.delete_by_id(existing.id()).await?;
+            self.rename(uploaded.id(), &name).await?;
+        }
+
+        Ok(UploadResult {
+            bytes_uploaded,
+            file_size,
+            checksum: Some(sha1),
+            elapsed_time: start_time.elapsed(),
+            compression: None,
+        })
     }
 
     async fn download(
         &self,
-        _remote_path: &str,
-        _local_path: &Path,
+        remote_path: &str,
+        local_path: &Path,
     ) -> Result<DownloadResult, SyncError> {
-        // TODO: Implement download logic (get download url -> download)
-        Err(SyncError::Provider(
-            crate::error::ProviderError::NotImplemented("Download not implemented yet".into()),
-        ))
+        let start_time = Instant::now();
+        let mut reader = self.download_stream(remote_path).await?;
+
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(SyncError::Io)?;
+        }
+        let mut file = tokio::fs::File::create(local_path)
+            .await
+            .map_err(SyncError::Io)?;
+        let file_size = tokio::io::copy(&mut reader, &mut file)
+            .await
+            .map_err(SyncError::Io)?;
+        file.flush().await.map_err(SyncError::Io)?;
+
+        Ok(DownloadResult {
+            bytes_downloaded: file_size,
+            file_size,
+            checksum: None,
+            elapsed_time: start_time.elapsed(),
+            resumed_bytes: 0,
+        })
     }
 
-    async fn delete(&self, path: &str) -> Result<(), SyncError> {
-        // API: /rb/delete
-        let url = format!("{}/open/ufile/delete", API_BASE_URL);
-        let params = [("fid", path)]; // Assuming path is FID for now
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let item = self.find_file(remote_path).await?;
+        let pick_code = item.pc.as_deref().ok_or_else(|| {
+            SyncError::Provider(ProviderError::ApiError(format!(
+                "Missing pick code for {}",
+                remote_path
+            )))
+        })?;
+        let url = self.get_download_url(pick_code).await?;
 
         let resp = self
             .client
-            .post(&url)
-            .form(&params)
+            .get(&url)
             .send()
             .await
             .map_err(SyncError::Network)?;
+        let resp = http::check_throttled(resp)?
+            .error_for_status()
+            .map_err(SyncError::Network)?;
+        let stream = resp
+            .bytes_stream()
+            .map(|r| r.map_err(std::io::Error::other));
+        Ok(Box::new(StreamReader::new(stream)))
+    }
 
-        let base_resp: BaseResponse = resp.json().await.map_err(SyncError::Network)?;
-
-        if !base_resp.state {
-            return Err(SyncError::Provider(crate::error::ProviderError::ApiError(
-                base_resp.error.unwrap_or_else(|| "Delete failed".into()),
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let path = normalize_path(path);
+        if path == "/" {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete root directory".into(),
             )));
         }
+        let Some(item) = self.find_entry(&path).await? else {
+            return Ok(());
+        };
+
+        self.delete_by_id(item.id()).await?;
+        self.invalidate_cache(&path);
+        Ok(())
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        // 逐级创建缺失的目录
+        let path = normalize_path(path);
+        let mut current_path = "/".to_string();
+        let mut current_cid = "0".to_string();
+
+        for name in path.split('/').filter(|c| !c.is_empty()) {
+            let next_path = join_path(&current_path, name);
+            let next_cid = match self.resolve_dir(&next_path).await? {
+                Some(cid) => cid,
+                None => {
+                    if self.find_entry(&next_path).await?.is_some() {
+                        return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                            "Path exists and is not a directory: {}",
+                            next_path
+                        ))));
+                    }
+                    debug!(path = %next_path, "创建目录");
+                    let cid = self.create_folder(&current_cid, name).await?;
+                    self.dir_cache.insert(next_path.clone(), cid.clone());
+                    cid
+                }
+            };
+            current_path = next_path;
+            current_cid = next_cid;
+        }
 
         Ok(())
     }
 
-    async fn mkdir(&self, _path: &str) -> Result<(), SyncError> {
-        // API: /open/folder/add
-        // Need parent CID and new folder name. Path parsing needed.
-        // For now, assuming path structure is "pid/new_name" is too simple.
-        // Usually providers take a full path.
-        // Implementing full path to CID resolution is complex without a cache.
-        Err(SyncError::Provider(
-            crate::error::ProviderError::NotImplemented("Mkdir not implemented yet".into()),
-        ))
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let path = normalize_path(path);
+        if path == "/" {
+            return Ok(FileInfo {
+                path,
+                size: 0,
+                modified: 0,
+                hash: None,
+                is_dir: true,
+                permissions: None,
+            });
+        }
+
+        let (parent, _) = split_parent(&path);
+        match self.find_entry(&path).await? {
+            Some(item) => Ok(to_file_info(&parent, &item)),
+            None => Err(SyncError::Provider(ProviderError::FileNotFound(path))),
+        }
     }
 
-    async fn stat(&self, _path: &str) -> Result<FileInfo, SyncError> {
-        // Use list to find the file/dir
-        // This is inefficient but works without dedicated stat API for path
-        // For CID, we can query details.
-        // Assuming path is a CID for now:
-        Err(SyncError::Provider(
-            crate::error::ProviderError::NotImplemented("Stat not implemented yet".into()),
-        ))
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_hash: Some(ChecksumType::Sha1),
+            forbidden_chars: vec!['\\', ':', '*', '?', '"', '<', '>', '|'],
+            ..ProviderCapabilities::default()
+        }
     }
 
     async fn exists(&self, path: &str) -> Result<bool, SyncError> {
-        // Check if file/dir exists via stat or list
         match self.stat(path).await {
             Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            Err(SyncError::Provider(ProviderError::FileNotFound(_))) => Ok(false),
+            Err(e) => Err(e),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_helpers() {
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("a//b/"), "/a/b");
+        assert_eq!(
+            split_parent("/a/b.txt"),
+            ("/a".to_string(), "b.txt".to_string())
+        );
+        assert_eq!(
+            split_parent("/b.txt"),
+            ("/".to_string(), "b.txt".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_sha1_bounds() {
+        let path = std::env::temp_dir().join(format!("115_range_{}", uuid::Uuid::new_v4()));
+        let content: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        tokio::fs::write(&path, &content).await.unwrap();
+
+        assert_eq!(
+            range_sha1(&path, "10-150009").await.unwrap(),
+            hex::encode_upper(Sha1::digest(&content[10..150_010]))
+        );
+        // 区间超出本地文件时拒绝，而不是按服务端给出的长度分配内存
+        assert!(range_sha1(&path, "0-200000").await.is_err());
+        assert!(range_sha1(&path, "0-18446744073709551615").await.is_err());
+        assert!(range_sha1(&path, "9-3").await.is_err());
+
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+
+    #[test]
+    fn test_oss_object_url() {
+        assert_eq!(
+            oss_object_url("http://oss-cn-shenzhen.aliyuncs.com", "fhnfile", "a/b").unwrap(),
+            "http://fhnfile.oss-cn-shenzhen.aliyuncs.com/a/b"
+        );
+        assert_eq!(
+            oss_object_url("http://127.0.0.1:8080", "fhnfile", "a/b").unwrap(),
+            "http://127.0.0.1:8080/fhnfile/a/b"
+        );
+    }
+}
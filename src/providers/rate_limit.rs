@@ -0,0 +1,404 @@
+//! 限流装饰器
+//!
+//! 按账户的 `RateLimitConfig` 同时限制请求速率（`requests_per_minute`，令牌桶）
+//! 与同时进行的请求数（`max_concurrent`）。通过 [`RateLimitedProvider::for_account`]
+//! 创建的实例按账户 ID 共享同一份限流状态，同一账户被多个任务并发使用时总量仍受限。
+//!
+//! 请求速率随服务端的限流信号自适应调整（见 `TokenBucketRateLimiter`），
+//! 调整记录可通过 [`rate_changes`] 查询，由同步引擎写入同步报告。
+
+use crate::config::RateLimitConfig;
+use crate::core::rate_limit::TokenBucketRateLimiter;
+use crate::core::traits::RateLimiter;
+use crate::error::SyncError;
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, StorageQuota,
+    UploadResult,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info, warn};
+
+/// 创建限流状态时的 (requests_per_minute, max_concurrent)，用于发现配置变化
+type LimitsKey = (u32, usize);
+
+/// 按账户 ID 登记的限流状态
+static ACCOUNT_LIMITS: LazyLock<Mutex<HashMap<String, (LimitsKey, AccountLimits)>>> =
+    LazyLock::new(Default::default);
+
+/// 每个账户保留的速率调整记录条数
+const MAX_RATE_CHANGES: usize = 100;
+
+/// 自适应限流的一次速率调整
+#[derive(Debug, Clone)]
+pub struct RateChange {
+    pub time: DateTime<Utc>,
+    /// 调整后的速率（请求/秒）
+    pub requests_per_second: f64,
+    /// 因限流降速为 true，恢复为 false
+    pub throttled: bool,
+}
+
+/// 账户在 `since` 之后的速率调整记录
+pub fn rate_changes(account_id: &str, since: DateTime<Utc>) -> Vec<RateChange> {
+    let registry = ACCOUNT_LIMITS.lock().unwrap();
+    let Some((_, limits)) = registry.get(account_id) else {
+        return Vec::new();
+    };
+    limits
+        .changes
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|change| change.time >= since)
+        .cloned()
+        .collect()
+}
+
+/// 一个账户的限流状态
+#[derive(Clone)]
+struct AccountLimits {
+    limiter: Arc<dyn RateLimiter>,
+    concurrency: Arc<Semaphore>,
+    changes: Arc<Mutex<VecDeque<RateChange>>>,
+}
+
+impl AccountLimits {
+    fn new(config: &RateLimitConfig) -> Self {
+        // 配置校验会拒绝 0，这里仍按每分钟至少 1 次处理，避免令牌永不补充
+        let per_second = config.requests_per_minute.max(1) as f64 / 60.0;
+        Self {
+            // 桶容量为一秒的请求量，允许短暂突发
+            limiter: Arc::new(TokenBucketRateLimiter::new(
+                per_second.ceil() as u64,
+                per_second,
+            )),
+            concurrency: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            changes: Arc::default(),
+        }
+    }
+
+    fn record(&self, change: RateChange) {
+        let mut changes = self.changes.lock().unwrap();
+        if changes.len() == MAX_RATE_CHANGES {
+            changes.pop_front();
+        }
+        changes.push_back(change);
+    }
+}
+
+pub struct RateLimitedProvider<T> {
+    inner: T,
+    limits: AccountLimits,
+}
+
+impl<T: StorageProvider> RateLimitedProvider<T> {
+    /// 使用独立的限流状态
+    pub fn new(inner: T, config: RateLimitConfig) -> Self {
+        Self {
+            inner,
+            limits: AccountLimits::new(&config),
+        }
+    }
+
+    /// 与同一账户的其他实例共用限流状态，账户的限流配置变化后重新创建
+    pub fn for_account(inner: T, account_id: &str, config: &RateLimitConfig) -> Self {
+        let key = (config.requests_per_minute, config.max_concurrent);
+        let mut registry = ACCOUNT_LIMITS.lock().unwrap();
+        let limits = match registry.get(account_id) {
+            Some((existing, limits)) if *existing == key => limits.clone(),
+            _ => {
+                debug!(
+                    account = account_id,
+                    requests_per_minute = config.requests_per_minute,
+                    max_concurrent = config.max_concurrent,
+                    "创建账户限流状态"
+                );
+                let limits = AccountLimits::new(config);
+                registry.insert(account_id.to_string(), (key, limits.clone()));
+                limits
+            }
+        };
+        Self { inner, limits }
+    }
+
+    /// 先占用并发名额再取令牌，返回的名额在请求结束前不能释放
+    async fn permit(&self) -> Result<OwnedSemaphorePermit, SyncError> {
+        let permit = self
+            .limits
+            .concurrency
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| SyncError::Unknown(e.to_string()))?;
+        self.limits.limiter.acquire().await?;
+        Ok(permit)
+    }
+
+    /// 按请求结果调整速率：限流时降速，成功时逐步恢复
+    fn observe<R>(&self, result: Result<R, SyncError>) -> Result<R, SyncError> {
+        let limiter = &self.limits.limiter;
+        let (rate, throttled) = match &result {
+            Ok(_) => (limiter.on_success(), false),
+            Err(e) if e.is_throttled() => (limiter.on_throttled(e.retry_after()), true),
+            Err(_) => (None, false),
+        };
+        if let Some(rate) = rate {
+            if throttled {
+                warn!(requests_per_second = rate, "服务端限流，降低请求速率");
+            } else {
+                info!(requests_per_second = rate, "恢复请求速率");
+            }
+            self.limits.record(RateChange {
+                time: Utc::now(),
+                requests_per_second: rate,
+                throttled,
+            });
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for RateLimitedProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.verify().await)
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.list(path).await)
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.list_recursive(path).await)
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.upload(local_path, remote_path).await)
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.download(remote_path, local_path).await)
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.delete(path).await)
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.mkdir(path).await)
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.stat(path).await)
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.exists(path).await)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.quota().await)
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.set_mtime(path, modified).await)
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.set_permissions(path, mode).await)
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.move_path(from, to).await)
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.copy_path(from, to).await)
+    }
+
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.upload_stream(reader, size, remote_path).await)
+    }
+
+    /// 并发名额只覆盖建立下载的请求，流打开后即归还
+    ///
+    /// 调用方读取流的同时常会对同一账户发起其他请求（如上传到同一账户），
+    /// 名额随流持有会在 `max_concurrent` 较小时互相等待而卡死；传输速率由带宽限速负责。
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let _permit = self.permit().await?;
+        self.observe(self.inner.download_stream(remote_path).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::fault::{FaultKind, FaultOp, Latency};
+    use crate::providers::{FaultConfig, FaultInjectionProvider, MemoryProvider};
+    use std::time::{Duration, Instant};
+
+    fn config(requests_per_minute: u32, max_concurrent: usize) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_minute,
+            max_concurrent,
+            chunk_size: 0,
+        }
+    }
+
+    /// 每次调用耗时 50ms 的内存提供商
+    fn slow() -> FaultInjectionProvider<MemoryProvider> {
+        FaultInjectionProvider::new(
+            MemoryProvider::new(),
+            FaultConfig {
+                latency: Latency::Fixed(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrent_caps_in_flight_requests() {
+        let provider = RateLimitedProvider::new(slow(), config(60_000, 2));
+        let start = Instant::now();
+        let results = futures::future::join_all((0..4).map(|_| provider.exists("/"))).await;
+        assert!(results.into_iter().all(|r| r.unwrap()));
+        // 4 个请求分两批执行
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_requests_per_minute_limits_rate() {
+        // 每秒 20 次，桶容量 20
+        let provider = RateLimitedProvider::new(MemoryProvider::new(), config(1_200, 100));
+        let start = Instant::now();
+        for _ in 0..30 {
+            provider.exists("/").await.unwrap();
+        }
+        // 超出桶容量的 10 次请求需要等待补充令牌
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_accounts_share_limits() {
+        let account = format!("account_{}", uuid::Uuid::new_v4());
+        let first = RateLimitedProvider::for_account(slow(), &account, &config(60_000, 1));
+        let second = RateLimitedProvider::for_account(slow(), &account, &config(60_000, 1));
+        let other = RateLimitedProvider::for_account(
+            slow(),
+            &format!("other_{}", uuid::Uuid::new_v4()),
+            &config(60_000, 1),
+        );
+
+        let start = Instant::now();
+        let (a, b) = tokio::join!(first.exists("/"), second.exists("/"));
+        assert!(a.unwrap() && b.unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let start = Instant::now();
+        let (a, b) = tokio::join!(first.exists("/"), other.exists("/"));
+        assert!(a.unwrap() && b.unwrap());
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // 配置变化后不再沿用旧的并发上限
+        let resized = RateLimitedProvider::for_account(slow(), &account, &config(60_000, 2));
+        assert!(!Arc::ptr_eq(
+            &resized.limits.concurrency,
+            &first.limits.concurrency
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_reaches_inner_provider() {
+        let provider = RateLimitedProvider::new(
+            FaultInjectionProvider::new(
+                MemoryProvider::new(),
+                FaultConfig {
+                    op_error_rates: HashMap::from([(FaultOp::Verify, 1.0)]),
+                    ..Default::default()
+                },
+            ),
+            config(60, 1),
+        );
+        assert!(provider.verify().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_stream_releases_permit_once_open() {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        let provider = RateLimitedProvider::new(memory, config(60_000, 1));
+
+        // 流未读完时仍可对同一账户发起请求
+        let _stream = provider.download_stream("/f.txt").await.unwrap();
+        assert_eq!(provider.limits.concurrency.available_permits(), 1);
+        assert!(provider.exists("/f.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_throttling_lowers_rate_and_is_recorded() {
+        let account = format!("account_{}", uuid::Uuid::new_v4());
+        let since = Utc::now();
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        let provider = RateLimitedProvider::for_account(
+            FaultInjectionProvider::new(
+                memory,
+                FaultConfig {
+                    op_error_rates: HashMap::from([(FaultOp::Stat, 1.0)]),
+                    error_kind: FaultKind::RateLimited,
+                    ..Default::default()
+                },
+            ),
+            &account,
+            &config(600, 10),
+        );
+
+        assert!(provider.exists("/f.txt").await.unwrap());
+        assert!(rate_changes(&account, since).is_empty());
+
+        assert!(provider.stat("/f.txt").await.unwrap_err().is_throttled());
+        assert_eq!(provider.limits.limiter.current_rate(), 5.0);
+        let changes = rate_changes(&account, since);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].throttled);
+        assert_eq!(changes[0].requests_per_second, 5.0);
+        assert!(rate_changes(&account, Utc::now()).is_empty());
+    }
+}
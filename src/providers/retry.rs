@@ -0,0 +1,358 @@
+//! 自动重试装饰器
+//!
+//! 按账户的 `RetryPolicy` 以指数退避重试失败的操作，只重试
+//! `SyncError::is_retryable` 为真的错误（网络、超时、限流等），
+//! 认证失败、文件不存在之类的错误立即返回。流式上传边传边写入临时文件，重试时从临时文件重放。
+
+use crate::config::RetryPolicy;
+use crate::core::retry::ExponentialBackoffRetry;
+use crate::core::traits::RetryStrategy;
+use crate::error::SyncError;
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, StorageQuota,
+    UploadResult, stream_temp_path,
+};
+use async_trait::async_trait;
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf, sink};
+use tokio::time::sleep;
+use tracing::warn;
+
+pub struct RetryingProvider<T> {
+    inner: T,
+    strategy: ExponentialBackoffRetry,
+}
+
+impl<T: StorageProvider> RetryingProvider<T> {
+    pub fn new(inner: T, policy: &RetryPolicy) -> Self {
+        let strategy = ExponentialBackoffRetry::builder()
+            .max_attempts(policy.max_retries)
+            .initial_delay(Duration::from_millis(policy.initial_delay_ms))
+            .max_delay(Duration::from_millis(policy.max_delay_ms))
+            .backoff_factor(policy.backoff_factor)
+            .build();
+        Self { inner, strategy }
+    }
+
+    /// 执行操作，失败且可重试时等待退避时间后再次执行
+    async fn retry<R, F, Fut>(&self, operation: &str, path: &str, mut f: F) -> Result<R, SyncError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<R, SyncError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(e) if self.strategy.should_retry(attempt, &e) => {
+                    let delay = self.strategy.delay_before_retry(attempt);
+                    attempt += 1;
+                    warn!(
+                        operation = operation,
+                        path = %path,
+                        attempt = attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %e,
+                        "操作失败，稍后重试"
+                    );
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T: StorageProvider> StorageProvider for RetryingProvider<T> {
+    async fn verify(&self) -> Result<(), SyncError> {
+        self.retry("verify", "/", || self.inner.verify()).await
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.retry("list", path, || self.inner.list(path)).await
+    }
+
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        self.retry("list_recursive", path, || self.inner.list_recursive(path))
+            .await
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        self.retry("upload", remote_path, || {
+            self.inner.upload(local_path, remote_path)
+        })
+        .await
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        self.retry("download", remote_path, || {
+            self.inner.download(remote_path, local_path)
+        })
+        .await
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        self.retry("delete", path, || self.inner.delete(path)).await
+    }
+
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        self.retry("mkdir", path, || self.inner.mkdir(path)).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        self.retry("stat", path, || self.inner.stat(path)).await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        self.retry("exists", path, || self.inner.exists(path)).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        self.retry("quota", "/", || self.inner.quota()).await
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        self.retry("set_mtime", path, || self.inner.set_mtime(path, modified))
+            .await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        self.retry("set_permissions", path, || {
+            self.inner.set_permissions(path, mode)
+        })
+        .await
+    }
+
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.retry("move", from, || self.inner.move_path(from, to))
+            .await
+    }
+
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.retry("copy", from, || self.inner.copy_path(from, to))
+            .await
+    }
+
+    /// 首次上传边读取边写入临时文件，失败且可重试时读完源流剩余部分，再从临时文件重新上传
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let temp_path = stream_temp_path();
+        let result = async {
+            let spool = Arc::new(Mutex::new(Spool {
+                reader,
+                file: std::fs::File::create(&temp_path)?,
+            }));
+            let mut first = Some(SpoolReader {
+                spool: spool.clone(),
+            });
+            self.retry("upload_stream", remote_path, || {
+                let first = first.take();
+                let spool = spool.clone();
+                let temp_path = &temp_path;
+                async move {
+                    if let Some(reader) = first {
+                        return self
+                            .inner
+                            .upload_stream(Box::new(reader), size, remote_path)
+                            .await;
+                    }
+                    tokio::io::copy(
+                        &mut SpoolReader {
+                            spool: spool.clone(),
+                        },
+                        &mut sink(),
+                    )
+                    .await?;
+                    spool.lock().unwrap().file.flush()?;
+                    let file = tokio::fs::File::open(temp_path).await?;
+                    self.inner
+                        .upload_stream(Box::new(file), size, remote_path)
+                        .await
+                }
+            })
+            .await
+        }
+        .await;
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        result
+    }
+
+    /// 只重试打开流的请求，读取过程中的错误由调用方处理
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        self.retry("download_stream", remote_path, || {
+            self.inner.download_stream(remote_path)
+        })
+        .await
+    }
+}
+
+/// 源流与保存已读内容的临时文件
+struct Spool {
+    reader: ByteStream,
+    file: std::fs::File,
+}
+
+/// 从源流读取的同时把读到的内容追加到临时文件
+struct SpoolReader {
+    spool: Arc<Mutex<Spool>>,
+}
+
+impl AsyncRead for SpoolReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut guard = self.spool.lock().unwrap();
+        let spool = &mut *guard;
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut spool.reader).poll_read(cx, buf))?;
+        spool.file.write_all(&buf.filled()[filled..])?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ProviderError;
+    use crate::providers::fault::{FaultKind, FaultOp};
+    use crate::providers::{FaultConfig, FaultInjectionProvider, MemoryProvider};
+    use std::collections::HashMap;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay_ms: 100,
+            max_delay_ms: 1_000,
+            backoff_factor: 2.0,
+        }
+    }
+
+    fn failing(kind: FaultKind) -> FaultInjectionProvider<MemoryProvider> {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        FaultInjectionProvider::new(
+            memory,
+            FaultConfig {
+                op_error_rates: HashMap::from([(FaultOp::Stat, 1.0)]),
+                error_kind: kind,
+                seed: Some(1),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_retryable_errors_with_backoff() {
+        let provider = RetryingProvider::new(failing(FaultKind::Timeout), &policy(3));
+        let start = tokio::time::Instant::now();
+        let err = provider.stat("/f.txt").await.unwrap_err();
+        assert!(matches!(
+            err,
+            SyncError::Provider(ProviderError::Timeout(_))
+        ));
+        // 首次调用 + 3 次重试，退避 100 + 200 + 400ms
+        assert_eq!(provider.inner.injected_faults(), 4);
+        assert_eq!(start.elapsed(), Duration::from_millis(700));
+
+        // 未注入故障的操作直接成功
+        assert!(provider.exists("/f.txt").await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_does_not_retry_fatal_errors() {
+        let provider = RetryingProvider::new(failing(FaultKind::Auth), &policy(3));
+        assert!(provider.stat("/f.txt").await.is_err());
+        assert_eq!(provider.inner.injected_faults(), 1);
+
+        let provider = RetryingProvider::new(MemoryProvider::new(), &policy(3));
+        assert!(matches!(
+            provider.stat("/missing").await,
+            Err(SyncError::Provider(ProviderError::FileNotFound(_)))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recovers_from_intermittent_errors() {
+        let memory = MemoryProvider::new();
+        memory.insert_file("/f.txt", "x", 0).unwrap();
+        let provider = RetryingProvider::new(
+            FaultInjectionProvider::new(
+                memory,
+                FaultConfig {
+                    error_rate: 0.5,
+                    error_kind: FaultKind::Connection,
+                    seed: Some(3),
+                    ..Default::default()
+                },
+            ),
+            &policy(10),
+        );
+        for _ in 0..20 {
+            provider.stat("/f.txt").await.unwrap();
+        }
+        assert!(provider.inner.injected_faults() > 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_upload_stream_retries_from_spooled_copy() {
+        let provider = RetryingProvider::new(
+            FaultInjectionProvider::new(
+                MemoryProvider::new(),
+                FaultConfig {
+                    op_error_rates: HashMap::from([(FaultOp::Upload, 0.5)]),
+                    error_kind: FaultKind::Connection,
+                    seed: Some(5),
+                    ..Default::default()
+                },
+            ),
+            &policy(10),
+        );
+        for i in 0..8 {
+            let content = format!("content {}", i).repeat(1000).into_bytes();
+            let path = format!("/{}.txt", i);
+            provider
+                .upload_stream(
+                    Box::new(std::io::Cursor::new(content.clone())),
+                    content.len() as u64,
+                    &path,
+                )
+                .await
+                .unwrap();
+
+            let mut stored = Vec::new();
+            tokio::io::AsyncReadExt::read_to_end(
+                &mut provider.download_stream(&path).await.unwrap(),
+                &mut stored,
+            )
+            .await
+            .unwrap();
+            assert_eq!(stored, content);
+        }
+        assert!(provider.inner.injected_faults() > 0);
+    }
+}
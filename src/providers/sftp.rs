@@ -0,0 +1,770 @@
+//! SFTP 存储提供者实现
+//!
+//! 通过 SSH 连接访问远程主机上的目录，支持密码与私钥认证，可保留文件的修改时间与权限位。
+//! libssh2 的接口是阻塞的，所有操作都在阻塞线程池中执行，并复用同一个会话。
+//!
+//! # 凭据
+//! - `host`: 主机地址
+//! - `port`（可选）：默认 22
+//! - `username`: 用户名
+//! - `password`: 密码，与 `private_key` 至少提供一个
+//! - `private_key`: 私钥文件路径，`passphrase`（可选）为私钥口令
+//! - `root`（可选）：远程根目录，默认为登录用户的主目录
+//! - `host_fingerprint`（可选）：主机公钥指纹，格式同 `ssh-keygen -lf` 输出的 `SHA256:...`
+//! - `known_hosts`（可选）：未配置指纹时用于校验主机公钥的文件，默认 `~/.ssh/known_hosts`
+//!
+//! 主机公钥既不匹配指纹也不在 known_hosts 中时拒绝连接。
+
+use crate::config::AccountConfig;
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, UploadResult,
+};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD as BASE64_NO_PAD;
+use ssh2::{
+    CheckResult, ErrorCode, FileStat, HashType, KnownHostFileKind, RenameFlags, Session, Sftp,
+};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const DEFAULT_PORT: u16 = 22;
+
+/// 建立 TCP 连接的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 单个 SSH 操作的超时时间（毫秒）
+const SESSION_TIMEOUT_MS: u32 = 60_000;
+
+/// libssh2 中 SFTP 状态码
+const SFTP_NO_SUCH_FILE: i32 = 2;
+const SFTP_PERMISSION_DENIED: i32 = 3;
+
+/// libssh2 认证失败的错误码
+const LIBSSH2_ERROR_AUTHENTICATION_FAILED: i32 = -18;
+const LIBSSH2_ERROR_PUBLICKEY_UNVERIFIED: i32 = -19;
+const LIBSSH2_ERROR_FILE: i32 = -16;
+
+/// 认证方式
+#[derive(Clone)]
+enum SftpAuth {
+    Password(String),
+    PrivateKey {
+        path: PathBuf,
+        passphrase: Option<String>,
+    },
+}
+
+/// 连接参数
+struct SftpSettings {
+    host: String,
+    port: u16,
+    username: String,
+    auth: SftpAuth,
+    host_fingerprint: Option<String>,
+    known_hosts: PathBuf,
+}
+
+/// 已建立的连接，会话需要与 SFTP 通道一同保持存活
+struct Connection {
+    _session: Session,
+    sftp: Sftp,
+}
+
+/// SFTP 存储提供者
+pub struct SftpProvider {
+    settings: Arc<SftpSettings>,
+    root: String,
+    connection: Arc<Mutex<Option<Connection>>>,
+}
+
+impl SftpProvider {
+    pub async fn new(config: &AccountConfig) -> Result<Self, SyncError> {
+        let credential = |key: &str| {
+            config
+                .credentials
+                .get(key)
+                .filter(|v| !v.is_empty())
+                .cloned()
+        };
+        let required = |key: &str| {
+            credential(key)
+                .ok_or_else(|| SyncError::Provider(ProviderError::MissingCredentials(key.into())))
+        };
+
+        let auth = match (credential("private_key"), credential("password")) {
+            (Some(path), _) => SftpAuth::PrivateKey {
+                path: PathBuf::from(path),
+                passphrase: credential("passphrase"),
+            },
+            (None, Some(password)) => SftpAuth::Password(password),
+            (None, None) => {
+                return Err(SyncError::Provider(ProviderError::MissingCredentials(
+                    "password or private_key".into(),
+                )));
+            }
+        };
+
+        let port = match credential("port") {
+            Some(port) => port.parse().map_err(|_| {
+                SyncError::Config(crate::error::ConfigError::Invalid(format!(
+                    "Invalid SFTP port: {}",
+                    port
+                )))
+            })?,
+            None => DEFAULT_PORT,
+        };
+
+        Ok(Self {
+            settings: Arc::new(SftpSettings {
+                host: required("host")?,
+                port,
+                username: required("username")?,
+                auth,
+                host_fingerprint: credential("host_fingerprint"),
+                known_hosts: credential("known_hosts")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(default_known_hosts),
+            }),
+            root: credential("root").unwrap_or_else(|| ".".to_string()),
+            connection: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// 将提供者内路径转换为远程主机上的路径
+    fn remote(&self, path: &str) -> Result<String, SyncError> {
+        remote_path(&self.root, path)
+    }
+
+    /// 在阻塞线程池中使用 SFTP 会话执行操作
+    ///
+    /// 连接尚未建立时先建立连接；操作因会话层错误失败时丢弃连接，下次调用会重新连接。
+    async fn run<T, F>(&self, op: F) -> Result<T, SyncError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Sftp) -> Result<T, SyncError> + Send + 'static,
+    {
+        let settings = self.settings.clone();
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut guard = connection.lock().unwrap_or_else(|e| e.into_inner());
+            if guard.is_none() {
+                *guard = Some(connect(&settings)?);
+            }
+            let conn = guard.as_ref().expect("connection was just established");
+
+            let result = op(&conn.sftp);
+            if let Err(SyncError::Provider(ProviderError::ConnectionFailed(msg))) = &result {
+                warn!(error = %msg, "SFTP 会话异常，将在下次操作时重新连接");
+                *guard = None;
+            }
+            result
+        })
+        .await
+        .map_err(|e| SyncError::Unknown(e.to_string()))?
+    }
+}
+
+/// 建立 SSH 会话并完成认证
+fn connect(settings: &SftpSettings) -> Result<Connection, SyncError> {
+    let conn_failed = |msg: String| SyncError::Provider(ProviderError::ConnectionFailed(msg));
+    let address = format!("{}:{}", settings.host, settings.port);
+    debug!(address = %address, "连接 SFTP 服务器");
+
+    let addrs = address
+        .to_socket_addrs()
+        .map_err(|e| conn_failed(format!("{}: {}", address, e)))?;
+    let mut last_error = None;
+    let mut tcp = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                tcp = Some(stream);
+                break;
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let tcp = tcp.ok_or_else(|| {
+        conn_failed(format!(
+            "{}: {}",
+            address,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    })?;
+
+    let mut session = Session::new().map_err(|e| conn_failed(e.to_string()))?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SESSION_TIMEOUT_MS);
+    session
+        .handshake()
+        .map_err(|e| conn_failed(format!("SSH handshake with {} failed: {}", address, e)))?;
+
+    let actual = session
+        .host_key_hash(HashType::Sha256)
+        .map(|hash| BASE64_NO_PAD.encode(hash))
+        .unwrap_or_default();
+    match &settings.host_fingerprint {
+        Some(expected) if !fingerprint_matches(expected, &actual) => {
+            return Err(SyncError::Provider(ProviderError::AuthFailed(format!(
+                "Host key fingerprint mismatch for {}: SHA256:{}",
+                address, actual
+            ))));
+        }
+        Some(_) => {}
+        None => {
+            let (key, _) = session
+                .host_key()
+                .ok_or_else(|| conn_failed(format!("{} did not send a host key", address)))?;
+            check_known_hosts(&session, settings, key).map_err(|reason| {
+                SyncError::Provider(ProviderError::AuthFailed(format!(
+                    "{}; add the host to known_hosts or set host_fingerprint to SHA256:{}",
+                    reason, actual
+                )))
+            })?;
+        }
+    }
+
+    let auth_result = match &settings.auth {
+        SftpAuth::Password(password) => session.userauth_password(&settings.username, password),
+        SftpAuth::PrivateKey { path, passphrase } => {
+            session.userauth_pubkey_file(&settings.username, None, path, passphrase.as_deref())
+        }
+    };
+    auth_result.map_err(|e| map_ssh_error(e, &address))?;
+    if !session.authenticated() {
+        return Err(SyncError::Provider(ProviderError::AuthFailed(format!(
+            "SSH authentication failed for {}@{}",
+            settings.username, address
+        ))));
+    }
+
+    let sftp = session.sftp().map_err(|e| map_ssh_error(e, &address))?;
+    info!(address = %address, user = %settings.username, "SFTP 连接成功");
+    Ok(Connection {
+        _session: session,
+        sftp,
+    })
+}
+
+fn default_known_hosts() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".ssh")
+        .join("known_hosts")
+}
+
+/// 在 known_hosts 中查找主机公钥，未找到或不匹配时返回原因
+fn check_known_hosts(session: &Session, settings: &SftpSettings, key: &[u8]) -> Result<(), String> {
+    let path = &settings.known_hosts;
+    let mut known_hosts = session.known_hosts().map_err(|e| e.to_string())?;
+    if path.is_file() {
+        known_hosts
+            .read_file(path, KnownHostFileKind::OpenSSH)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    match known_hosts.check_port(&settings.host, settings.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(format!(
+            "Host key for {} does not match {}",
+            settings.host,
+            path.display()
+        )),
+        CheckResult::NotFound | CheckResult::Failure => Err(format!(
+            "Host {} is not listed in {}",
+            settings.host,
+            path.display()
+        )),
+    }
+}
+
+/// 比较主机指纹，允许省略 `SHA256:` 前缀与 base64 填充
+fn fingerprint_matches(expected: &str, actual: &str) -> bool {
+    let expected = expected.trim();
+    let expected = expected.strip_prefix("SHA256:").unwrap_or(expected);
+    !actual.is_empty() && expected.trim_end_matches('=') == actual
+}
+
+/// 拼接远程根目录与提供者内路径，拒绝 `..` 以免越出根目录
+fn remote_path(root: &str, path: &str) -> Result<String, SyncError> {
+    let mut components = Vec::new();
+    for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if component == ".." {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                format!("Path escapes provider root: {}", path),
+            )));
+        }
+        components.push(component);
+    }
+
+    let root = if root == "/" {
+        ""
+    } else {
+        root.trim_end_matches('/')
+    };
+    Ok(match (root.is_empty(), components.is_empty()) {
+        (true, true) => "/".to_string(),
+        (false, true) => root.to_string(),
+        (_, false) => format!("{}/{}", root, components.join("/")),
+    })
+}
+
+/// 拼接目录与子项名称，得到以 `/` 开头的提供者内路径
+fn join_virtual(dir: &str, name: &str) -> String {
+    let dir = dir.trim_matches('/');
+    if dir.is_empty() {
+        format!("/{}", name)
+    } else {
+        format!("/{}/{}", dir, name)
+    }
+}
+
+fn map_ssh_error(error: ssh2::Error, path: &str) -> SyncError {
+    let msg = format!("{}: {}", path, error.message());
+    SyncError::Provider(match error.code() {
+        ErrorCode::SFTP(SFTP_NO_SUCH_FILE) => ProviderError::FileNotFound(path.to_string()),
+        ErrorCode::SFTP(SFTP_PERMISSION_DENIED) => ProviderError::PermissionDenied(msg),
+        ErrorCode::SFTP(_) => ProviderError::ApiError(msg),
+        ErrorCode::Session(
+            LIBSSH2_ERROR_AUTHENTICATION_FAILED
+            | LIBSSH2_ERROR_PUBLICKEY_UNVERIFIED
+            | LIBSSH2_ERROR_FILE,
+        ) => ProviderError::AuthFailed(msg),
+        ErrorCode::Session(_) => ProviderError::ConnectionFailed(msg),
+    })
+}
+
+fn io_error(error: std::io::Error, path: &str) -> SyncError {
+    // ssh2 的 File 读写错误会转换为 io::Error，需要还原为提供者错误
+    match error.kind() {
+        std::io::ErrorKind::NotFound => {
+            SyncError::Provider(ProviderError::FileNotFound(path.to_string()))
+        }
+        std::io::ErrorKind::PermissionDenied => SyncError::Provider(
+            ProviderError::PermissionDenied(format!("{}: {}", path, error)),
+        ),
+        std::io::ErrorKind::TimedOut
+        | std::io::ErrorKind::ConnectionReset
+        | std::io::ErrorKind::ConnectionAborted
+        | std::io::ErrorKind::BrokenPipe
+        | std::io::ErrorKind::UnexpectedEof => SyncError::Provider(
+            ProviderError::ConnectionFailed(format!("{}: {}", path, error)),
+        ),
+        _ => SyncError::Io(error),
+    }
+}
+
+fn to_file_info(path: String, stat: &FileStat) -> FileInfo {
+    FileInfo {
+        path,
+        size: if stat.is_dir() {
+            0
+        } else {
+            stat.size.unwrap_or(0)
+        },
+        modified: stat.mtime.unwrap_or(0) as i64,
+        hash: None,
+        is_dir: stat.is_dir(),
+        permissions: stat.perm.map(|p| p & 0o7777),
+    }
+}
+
+/// 仅修改部分属性时使用的空属性集
+fn empty_stat() -> FileStat {
+    FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm: None,
+        atime: None,
+        mtime: None,
+    }
+}
+
+/// 逐级创建目录，已存在的目录会被跳过
+fn mkdir_all(sftp: &Sftp, remote: &str) -> Result<(), SyncError> {
+    let mut current = String::new();
+    for (i, component) in remote.split('/').enumerate() {
+        if i > 0 || component.is_empty() {
+            current.push('/');
+        }
+        current.push_str(component);
+        if component.is_empty() || component == "." {
+            continue;
+        }
+
+        let path = Path::new(&current);
+        match sftp.stat(path) {
+            Ok(stat) if stat.is_dir() => continue,
+            Ok(_) => {
+                return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                    "{} exists and is not a directory",
+                    current
+                ))));
+            }
+            Err(_) => {}
+        }
+        if let Err(e) = sftp.mkdir(path, 0o755) {
+            // 并发创建时目录可能已被其他操作建立
+            if !sftp.stat(path).map(|s| s.is_dir()).unwrap_or(false) {
+                return Err(map_ssh_error(e, &current));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 递归删除目录
+fn remove_dir_all(sftp: &Sftp, remote: &Path) -> Result<(), SyncError> {
+    let display = remote.to_string_lossy().to_string();
+    for (child, stat) in sftp
+        .readdir(remote)
+        .map_err(|e| map_ssh_error(e, &display))?
+    {
+        let child_display = child.to_string_lossy().to_string();
+        if stat.is_dir() {
+            remove_dir_all(sftp, &child)?;
+        } else {
+            sftp.unlink(&child)
+                .map_err(|e| map_ssh_error(e, &child_display))?;
+        }
+    }
+    sftp.rmdir(remote).map_err(|e| map_ssh_error(e, &display))
+}
+
+fn parent_of(remote: &str) -> Option<&str> {
+    remote
+        .rsplit_once('/')
+        .map(|(parent, _)| parent)
+        .filter(|p| !p.is_empty())
+}
+
+#[async_trait]
+impl StorageProvider for SftpProvider {
+    async fn verify(&self) -> Result<(), SyncError> {
+        let root = self.remote("/")?;
+        let stat = self
+            .run(move |sftp| {
+                sftp.stat(Path::new(&root))
+                    .map_err(|e| map_ssh_error(e, &root))
+            })
+            .await?;
+        if !stat.is_dir() {
+            return Err(SyncError::Provider(ProviderError::ApiError(
+                "SFTP root is not a directory".into(),
+            )));
+        }
+        Ok(())
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let remote = self.remote(path)?;
+        let dir = path.to_string();
+        self.run(move |sftp| {
+            let entries = sftp
+                .readdir(Path::new(&remote))
+                .map_err(|e| map_ssh_error(e, &remote))?;
+            Ok(entries
+                .iter()
+                .filter_map(|(child, stat)| {
+                    let name = child.file_name()?.to_string_lossy().to_string();
+                    Some(to_file_info(join_virtual(&dir, &name), stat))
+                })
+                .collect())
+        })
+        .await
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start_time = Instant::now();
+        let remote = self.remote(remote_path)?;
+        let local = local_path.to_path_buf();
+
+        let bytes = self
+            .run(move |sftp| {
+                if let Some(parent) = parent_of(&remote) {
+                    mkdir_all(sftp, parent)?;
+                }
+                let mut source = std::fs::File::open(&local).map_err(SyncError::Io)?;
+                let mut target = sftp
+                    .create(Path::new(&remote))
+                    .map_err(|e| map_ssh_error(e, &remote))?;
+                std::io::copy(&mut source, &mut target).map_err(|e| io_error(e, &remote))
+            })
+            .await?;
+
+        Ok(UploadResult {
+            bytes_uploaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start_time.elapsed(),
+            compression: None,
+        })
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start_time = Instant::now();
+        let remote = self.remote(remote_path)?;
+        let local = local_path.to_path_buf();
+
+        let bytes = self
+            .run(move |sftp| {
+                let mut source = sftp
+                    .open(Path::new(&remote))
+                    .map_err(|e| map_ssh_error(e, &remote))?;
+                if let Some(parent) = local.parent() {
+                    std::fs::create_dir_all(parent).map_err(SyncError::Io)?;
+                }
+                let mut target = std::fs::File::create(&local).map_err(SyncError::Io)?;
+                std::io::copy(&mut source, &mut target).map_err(|e| io_error(e, &remote))
+            })
+            .await?;
+
+        Ok(DownloadResult {
+            bytes_downloaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start_time.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let remote = self.remote(path)?;
+        if remote == self.remote("/")? {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete provider root".to_string(),
+            )));
+        }
+
+        self.run(move |sftp| {
+            let target = Path::new(&remote);
+            let stat = match sftp.lstat(target) {
+                Ok(stat) => stat,
+                Err(e) if e.code() == ErrorCode::SFTP(SFTP_NO_SUCH_FILE) => {
+                    warn!(path = %remote, "文件或目录不存在，视为删除成功");
+                    return Ok(());
+                }
+                Err(e) => return Err(map_ssh_error(e, &remote)),
+            };
+            if stat.is_dir() {
+                remove_dir_all(sftp, target)
+            } else {
+                sftp.unlink(target).map_err(|e| map_ssh_error(e, &remote))
+            }
+        })
+        .await
+    }
+
+    /// 创建目录（包括缺失的父目录）
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        let remote = self.remote(path)?;
+        self.run(move |sftp| mkdir_all(sftp, &remote)).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let remote = self.remote(path)?;
+        let virtual_path = format!("/{}", path.trim_matches('/'));
+        self.run(move |sftp| {
+            let stat = sftp
+                .stat(Path::new(&remote))
+                .map_err(|e| map_ssh_error(e, &remote))?;
+            Ok(to_file_info(virtual_path, &stat))
+        })
+        .await
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(SyncError::Provider(ProviderError::FileNotFound(_))) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            can_set_mtime: true,
+            can_set_permissions: true,
+            supports_range_read: true,
+            supports_server_move: true,
+            ..ProviderCapabilities::default()
+        }
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let remote = self.remote(path)?;
+        let modified = modified.max(0) as u64;
+        self.run(move |sftp| {
+            let stat = FileStat {
+                atime: Some(modified),
+                mtime: Some(modified),
+                ..empty_stat()
+            };
+            sftp.setstat(Path::new(&remote), stat)
+                .map_err(|e| map_ssh_error(e, &remote))
+        })
+        .await
+    }
+
+    async fn set_permissions(&self, path: &str, mode: u32) -> Result<(), SyncError> {
+        let remote = self.remote(path)?;
+        self.run(move |sftp| {
+            let stat = FileStat {
+                perm: Some(mode & 0o7777),
+                ..empty_stat()
+            };
+            sftp.setstat(Path::new(&remote), stat)
+                .map_err(|e| map_ssh_error(e, &remote))
+        })
+        .await
+    }
+
+    /// 重命名文件或目录，必要时创建目标父目录
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let source = self.remote(from)?;
+        let target = self.remote(to)?;
+        self.run(move |sftp| {
+            if let Some(parent) = parent_of(&target) {
+                mkdir_all(sftp, parent)?;
+            }
+            let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+            match sftp.rename(Path::new(&source), Path::new(&target), flags) {
+                Ok(()) => Ok(()),
+                // SFTPv3 服务器忽略覆盖标志，目标文件存在时先删除再重命名
+                Err(e) => match sftp.lstat(Path::new(&target)) {
+                    Ok(stat) if !stat.is_dir() => {
+                        sftp.unlink(Path::new(&target))
+                            .map_err(|e| map_ssh_error(e, &target))?;
+                        sftp.rename(Path::new(&source), Path::new(&target), None)
+                            .map_err(|e| map_ssh_error(e, &source))
+                    }
+                    _ => Err(map_ssh_error(e, &source)),
+                },
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProviderType, RetryPolicy};
+    use std::collections::HashMap;
+
+    fn account(credentials: &[(&str, &str)]) -> AccountConfig {
+        AccountConfig {
+            id: "sftp_test".to_string(),
+            provider: ProviderType::SFTP,
+            name: "sftp".to_string(),
+            credentials: credentials
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            rate_limit: None,
+            retry_policy: RetryPolicy::default(),
+            resource_limits: None,
+        }
+    }
+
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(remote_path("/", "/").unwrap(), "/");
+        assert_eq!(remote_path("/", "/a/b").unwrap(), "/a/b");
+        assert_eq!(
+            remote_path("/srv/backup/", "a//./b/").unwrap(),
+            "/srv/backup/a/b"
+        );
+        assert_eq!(remote_path(".", "/").unwrap(), ".");
+        assert_eq!(remote_path(".", "/a").unwrap(), "./a");
+        assert!(remote_path("/srv", "/a/../../etc").is_err());
+    }
+
+    #[test]
+    fn test_check_known_hosts() {
+        // ssh-ed25519 公钥格式：类型名与 32 字节公钥，各带 4 字节长度前缀
+        let key_blob = |fill: u8| {
+            let mut blob = Vec::new();
+            blob.extend_from_slice(&11u32.to_be_bytes());
+            blob.extend_from_slice(b"ssh-ed25519");
+            blob.extend_from_slice(&32u32.to_be_bytes());
+            blob.extend_from_slice(&[fill; 32]);
+            blob
+        };
+        let path = std::env::temp_dir().join(format!("known_hosts_{}", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            format!(
+                "sftp.example.com ssh-ed25519 {}\n",
+                base64::engine::general_purpose::STANDARD.encode(key_blob(1))
+            ),
+        )
+        .unwrap();
+
+        let settings = |host: &str, known_hosts: PathBuf| SftpSettings {
+            host: host.to_string(),
+            port: DEFAULT_PORT,
+            username: "user".to_string(),
+            auth: SftpAuth::Password("secret".to_string()),
+            host_fingerprint: None,
+            known_hosts,
+        };
+        let session = Session::new().unwrap();
+        let known = settings("sftp.example.com", path.clone());
+        assert!(check_known_hosts(&session, &known, &key_blob(1)).is_ok());
+        assert!(check_known_hosts(&session, &known, &key_blob(2)).is_err());
+        let unknown = settings("other.example.com", path.clone());
+        assert!(check_known_hosts(&session, &unknown, &key_blob(1)).is_err());
+        let missing = settings("sftp.example.com", path.with_extension("missing"));
+        assert!(check_known_hosts(&session, &missing, &key_blob(1)).is_err());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_fingerprint_matches() {
+        let actual = "nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8";
+        assert!(fingerprint_matches(&format!("SHA256:{}", actual), actual));
+        assert!(fingerprint_matches(&format!("{}=", actual), actual));
+        assert!(!fingerprint_matches("SHA256:other", actual));
+        assert!(!fingerprint_matches("", ""));
+    }
+
+    #[tokio::test]
+    async fn test_new_requires_credentials() {
+        let missing_auth = SftpProvider::new(&account(&[("host", "h"), ("username", "u")])).await;
+        assert!(matches!(
+            missing_auth,
+            Err(SyncError::Provider(ProviderError::MissingCredentials(_)))
+        ));
+
+        let provider = SftpProvider::new(&account(&[
+            ("host", "h"),
+            ("username", "u"),
+            ("private_key", "/home/u/.ssh/id_ed25519"),
+            ("password", "ignored"),
+            ("port", "2222"),
+        ]))
+        .await
+        .unwrap();
+        assert_eq!(provider.settings.port, 2222);
+        assert!(matches!(
+            provider.settings.auth,
+            SftpAuth::PrivateKey { .. }
+        ));
+        assert_eq!(provider.root, ".");
+    }
+}
@@ -0,0 +1,730 @@
+//! SMB2/3 协议客户端
+//!
+//! 只实现文件同步所需的命令子集：协商、NTLMv2 会话建立、树连接、打开/关闭、读写、
+//! 目录查询与属性设置。请求严格串行发送，每个请求只消耗一个信用额度，
+//! 因此单次读写不超过 64KB。支持 SMB 2.0.2 ~ 3.0.2 方言，服务端要求签名时
+//! 按方言使用 HMAC-SHA256 或 AES-CMAC 签名；不支持 SMB3 加密。
+
+use super::ntlm::{self, NtlmCredentials};
+use crate::error::{ProviderError, SyncError};
+use aes::Aes128;
+use cmac::Cmac;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+pub(super) const NEGOTIATE: u16 = 0x0000;
+pub(super) const SESSION_SETUP: u16 = 0x0001;
+pub(super) const TREE_CONNECT: u16 = 0x0003;
+pub(super) const CREATE: u16 = 0x0005;
+pub(super) const CLOSE: u16 = 0x0006;
+pub(super) const READ: u16 = 0x0008;
+pub(super) const WRITE: u16 = 0x0009;
+pub(super) const QUERY_DIRECTORY: u16 = 0x000e;
+pub(super) const SET_INFO: u16 = 0x0011;
+
+pub(super) const STATUS_SUCCESS: u32 = 0x0000_0000;
+pub(super) const STATUS_PENDING: u32 = 0x0000_0103;
+pub(super) const STATUS_NO_MORE_FILES: u32 = 0x8000_0006;
+pub(super) const STATUS_END_OF_FILE: u32 = 0xc000_0011;
+pub(super) const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xc000_0016;
+pub(super) const STATUS_ACCESS_DENIED: u32 = 0xc000_0022;
+pub(super) const STATUS_OBJECT_NAME_NOT_FOUND: u32 = 0xc000_0034;
+pub(super) const STATUS_OBJECT_PATH_NOT_FOUND: u32 = 0xc000_003a;
+pub(super) const STATUS_LOGON_FAILURE: u32 = 0xc000_006d;
+pub(super) const STATUS_DISK_FULL: u32 = 0xc000_007f;
+pub(super) const STATUS_BAD_NETWORK_NAME: u32 = 0xc000_00cc;
+const STATUS_NO_SUCH_FILE: u32 = 0xc000_000f;
+const STATUS_WRONG_PASSWORD: u32 = 0xc000_006a;
+const STATUS_ACCOUNT_RESTRICTION: u32 = 0xc000_006e;
+const STATUS_PASSWORD_EXPIRED: u32 = 0xc000_0071;
+const STATUS_ACCOUNT_DISABLED: u32 = 0xc000_0072;
+const STATUS_QUOTA_EXCEEDED: u32 = 0xc000_0044;
+
+/// 访问掩码
+pub(super) const FILE_READ_DATA: u32 = 0x0000_0001;
+pub(super) const FILE_WRITE_DATA: u32 = 0x0000_0002;
+pub(super) const FILE_READ_ATTRIBUTES: u32 = 0x0000_0080;
+pub(super) const FILE_WRITE_ATTRIBUTES: u32 = 0x0000_0100;
+pub(super) const DELETE: u32 = 0x0001_0000;
+pub(super) const SYNCHRONIZE: u32 = 0x0010_0000;
+
+/// 创建方式
+pub(super) const FILE_OPEN: u32 = 1;
+pub(super) const FILE_OPEN_IF: u32 = 3;
+pub(super) const FILE_OVERWRITE_IF: u32 = 5;
+
+/// 创建选项
+pub(super) const FILE_DIRECTORY_FILE: u32 = 0x0000_0001;
+pub(super) const FILE_NON_DIRECTORY_FILE: u32 = 0x0000_0040;
+pub(super) const FILE_DELETE_ON_CLOSE: u32 = 0x0000_1000;
+
+pub(super) const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0000_0010;
+pub(super) const FILE_ATTRIBUTE_NORMAL: u32 = 0x0000_0080;
+
+/// SET_INFO 使用的文件信息类
+pub(super) const FILE_BASIC_INFORMATION: u8 = 4;
+pub(super) const FILE_RENAME_INFORMATION: u8 = 10;
+/// QUERY_DIRECTORY 使用的文件信息类
+pub(super) const FILE_DIRECTORY_INFORMATION: u8 = 1;
+
+const HEADER_SIZE: usize = 64;
+const FLAGS_SERVER_TO_REDIR: u32 = 0x0000_0001;
+const FLAGS_ASYNC_COMMAND: u32 = 0x0000_0002;
+const FLAGS_SIGNED: u32 = 0x0000_0008;
+
+const SECURITY_MODE_SIGNING_ENABLED: u16 = 0x0001;
+const SECURITY_MODE_SIGNING_REQUIRED: u16 = 0x0002;
+
+const SESSION_FLAG_IS_GUEST: u16 = 0x0001;
+const SESSION_FLAG_IS_NULL: u16 = 0x0002;
+const SESSION_FLAG_ENCRYPT_DATA: u16 = 0x0004;
+
+const DIALECTS: [u16; 4] = [0x0202, 0x0210, 0x0300, 0x0302];
+
+/// 单个信用额度允许的最大读写长度
+const MAX_IO_SIZE: u32 = 64 * 1024;
+
+/// Windows FILETIME 纪元（1601-01-01）与 Unix 纪元之间的秒数
+const FILETIME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub(super) type FileId = [u8; 16];
+
+/// 文件属性
+#[derive(Debug, Clone)]
+pub(super) struct FileAttrs {
+    pub size: u64,
+    pub modified: i64,
+    pub is_dir: bool,
+}
+
+/// 目录项
+#[derive(Debug, Clone)]
+pub(super) struct DirEntry {
+    pub name: String,
+    pub attrs: FileAttrs,
+}
+
+/// 会话签名密钥
+enum SigningKey {
+    /// SMB 2.x：HMAC-SHA256
+    HmacSha256([u8; 16]),
+    /// SMB 3.x：AES-128-CMAC
+    AesCmac([u8; 16]),
+}
+
+/// 服务端响应
+pub(super) struct Response {
+    pub status: u32,
+    /// 完整消息（含 64 字节头部），响应中的偏移量均相对于消息起始位置
+    pub data: Vec<u8>,
+}
+
+pub(super) fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn from_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+pub(super) fn filetime_to_unix(filetime: u64) -> i64 {
+    (filetime / 10_000_000) as i64 - FILETIME_EPOCH_OFFSET
+}
+
+pub(super) fn unix_to_filetime(secs: i64) -> u64 {
+    ((secs + FILETIME_EPOCH_OFFSET).max(0) as u64) * 10_000_000
+}
+
+fn malformed(what: &str) -> SyncError {
+    SyncError::Provider(ProviderError::ConnectionFailed(format!(
+        "Malformed SMB response: {}",
+        what
+    )))
+}
+
+pub(super) fn le_u16(buf: &[u8], offset: usize) -> Result<u16, SyncError> {
+    buf.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| malformed("truncated message"))
+}
+
+pub(super) fn le_u32(buf: &[u8], offset: usize) -> Result<u32, SyncError> {
+    buf.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| malformed("truncated message"))
+}
+
+pub(super) fn le_u64(buf: &[u8], offset: usize) -> Result<u64, SyncError> {
+    buf.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| malformed("truncated message"))
+}
+
+fn slice(buf: &[u8], offset: usize, len: usize) -> Result<&[u8], SyncError> {
+    buf.get(offset..offset + len)
+        .ok_or_else(|| malformed("buffer out of range"))
+}
+
+/// 将 NTSTATUS 转换为提供者错误
+pub(super) fn status_error(status: u32, context: &str) -> SyncError {
+    let msg = format!("{}: NTSTATUS 0x{:08x}", context, status);
+    SyncError::Provider(match status {
+        STATUS_OBJECT_NAME_NOT_FOUND | STATUS_OBJECT_PATH_NOT_FOUND | STATUS_NO_SUCH_FILE => {
+            ProviderError::FileNotFound(context.to_string())
+        }
+        STATUS_ACCESS_DENIED => ProviderError::PermissionDenied(msg),
+        STATUS_LOGON_FAILURE
+        | STATUS_WRONG_PASSWORD
+        | STATUS_ACCOUNT_RESTRICTION
+        | STATUS_PASSWORD_EXPIRED
+        | STATUS_ACCOUNT_DISABLED => ProviderError::AuthFailed(msg),
+        STATUS_DISK_FULL | STATUS_QUOTA_EXCEEDED => ProviderError::QuotaExceeded(msg),
+        _ => ProviderError::ApiError(msg),
+    })
+}
+
+fn io_failed(e: impl std::fmt::Display) -> SyncError {
+    SyncError::Provider(ProviderError::ConnectionFailed(e.to_string()))
+}
+
+/// SP800-108 计数器模式 KDF（HMAC-SHA256），用于派生 SMB3 签名密钥
+pub(super) fn smb3_kdf(key: &[u8; 16], label: &[u8], context: &[u8]) -> [u8; 16] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(&1u32.to_be_bytes());
+    mac.update(label);
+    mac.update(&[0]);
+    mac.update(context);
+    mac.update(&128u32.to_be_bytes());
+    let mut out = [0u8; 16];
+    out.copy_from_slice(&mac.finalize().into_bytes()[..16]);
+    out
+}
+
+impl SigningKey {
+    fn for_dialect(dialect: u16, session_key: [u8; 16]) -> Self {
+        if dialect >= 0x0300 {
+            SigningKey::AesCmac(smb3_kdf(&session_key, b"SMB2AESCMAC\0", b"SmbSign\0"))
+        } else {
+            SigningKey::HmacSha256(session_key)
+        }
+    }
+
+    fn signature(&self, message: &[u8]) -> [u8; 16] {
+        let mut out = [0u8; 16];
+        match self {
+            SigningKey::HmacSha256(key) => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+                mac.update(message);
+                out.copy_from_slice(&mac.finalize().into_bytes()[..16]);
+            }
+            SigningKey::AesCmac(key) => {
+                let mut mac =
+                    <Cmac<Aes128> as Mac>::new_from_slice(key).expect("AES-128 key is 16 bytes");
+                mac.update(message);
+                out.copy_from_slice(&mac.finalize().into_bytes());
+            }
+        }
+        out
+    }
+
+    /// 置签名标志并写入签名
+    fn sign(&self, message: &mut [u8]) {
+        let flags = u32::from_le_bytes(message[16..20].try_into().unwrap()) | FLAGS_SIGNED;
+        message[16..20].copy_from_slice(&flags.to_le_bytes());
+        message[48..64].fill(0);
+        let signature = self.signature(message);
+        message[48..64].copy_from_slice(&signature);
+    }
+}
+
+/// 计算消息签名（签名字段按全零处理），供测试中的模拟服务端校验
+#[cfg(test)]
+pub(super) fn expected_signature(dialect: u16, session_key: [u8; 16], message: &[u8]) -> [u8; 16] {
+    let mut copy = message.to_vec();
+    copy[48..64].fill(0);
+    SigningKey::for_dialect(dialect, session_key).signature(&copy)
+}
+
+/// 已完成认证并连接到共享的 SMB 客户端
+pub(super) struct SmbClient {
+    stream: TcpStream,
+    dialect: u16,
+    message_id: u64,
+    session_id: u64,
+    tree_id: u32,
+    signing_required: bool,
+    signing_key: Option<SigningKey>,
+    max_read: u32,
+    max_write: u32,
+    max_transact: u32,
+}
+
+impl SmbClient {
+    /// 连接服务器并完成协商、认证与树连接
+    pub(super) async fn connect(
+        host: &str,
+        port: u16,
+        share: &str,
+        creds: &NtlmCredentials<'_>,
+    ) -> Result<Self, SyncError> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port)))
+            .await
+            .map_err(|_| SyncError::Provider(ProviderError::Timeout(format!("{}:{}", host, port))))?
+            .map_err(|e| io_failed(format!("{}:{}: {}", host, port, e)))?;
+        stream.set_nodelay(true).map_err(io_failed)?;
+
+        let mut client = Self {
+            stream,
+            dialect: 0,
+            message_id: 0,
+            session_id: 0,
+            tree_id: 0,
+            signing_required: false,
+            signing_key: None,
+            max_read: MAX_IO_SIZE,
+            max_write: MAX_IO_SIZE,
+            max_transact: MAX_IO_SIZE,
+        };
+        client.negotiate().await?;
+        client.session_setup(creds).await?;
+        client.tree_connect(host, share).await?;
+        debug!(
+            host,
+            share,
+            dialect = format!("0x{:04x}", client.dialect),
+            "SMB 连接成功"
+        );
+        Ok(client)
+    }
+
+    pub(super) fn max_read(&self) -> usize {
+        self.max_read as usize
+    }
+
+    pub(super) fn max_write(&self) -> usize {
+        self.max_write as usize
+    }
+
+    fn header(&self, command: u16, message_id: u64) -> Vec<u8> {
+        let credit_charge: u16 = if self.dialect >= 0x0210 { 1 } else { 0 };
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(&[0xfe, b'S', b'M', b'B']);
+        header.extend_from_slice(&(HEADER_SIZE as u16).to_le_bytes());
+        header.extend_from_slice(&credit_charge.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // ChannelSequence/Reserved
+        header.extend_from_slice(&command.to_le_bytes());
+        header.extend_from_slice(&32u16.to_le_bytes()); // CreditRequest
+        header.extend_from_slice(&0u32.to_le_bytes()); // Flags
+        header.extend_from_slice(&0u32.to_le_bytes()); // NextCommand
+        header.extend_from_slice(&message_id.to_le_bytes());
+        header.extend_from_slice(&0xfeffu32.to_le_bytes()); // ProcessId
+        header.extend_from_slice(&self.tree_id.to_le_bytes());
+        header.extend_from_slice(&self.session_id.to_le_bytes());
+        header.extend_from_slice(&[0u8; 16]); // Signature
+        header
+    }
+
+    async fn read_frame(&mut self) -> Result<Vec<u8>, SyncError> {
+        let mut prefix = [0u8; 4];
+        self.stream
+            .read_exact(&mut prefix)
+            .await
+            .map_err(io_failed)?;
+        let len = u32::from_be_bytes([0, prefix[1], prefix[2], prefix[3]]) as usize;
+        let mut data = vec![0u8; len];
+        self.stream.read_exact(&mut data).await.map_err(io_failed)?;
+        Ok(data)
+    }
+
+    /// 发送请求并等待对应的最终响应（跳过异步处理中的临时响应）
+    pub(super) async fn request(
+        &mut self,
+        command: u16,
+        body: &[u8],
+    ) -> Result<Response, SyncError> {
+        let message_id = self.message_id;
+        self.message_id += 1;
+
+        let mut message = self.header(command, message_id);
+        message.extend_from_slice(body);
+        if let Some(key) = &self.signing_key
+            && command != NEGOTIATE
+            && command != SESSION_SETUP
+        {
+            key.sign(&mut message);
+        }
+
+        let mut frame = (message.len() as u32).to_be_bytes().to_vec();
+        frame[0] = 0;
+        frame.extend_from_slice(&message);
+
+        tokio::time::timeout(IO_TIMEOUT, async {
+            self.stream.write_all(&frame).await.map_err(io_failed)?;
+            loop {
+                let data = self.read_frame().await?;
+                if data.len() < HEADER_SIZE || data[..4] != [0xfe, b'S', b'M', b'B'] {
+                    return Err(malformed("bad protocol id"));
+                }
+                let flags = le_u32(&data, 16)?;
+                if flags & FLAGS_SERVER_TO_REDIR == 0 || le_u64(&data, 24)? != message_id {
+                    // 不是本请求的响应（例如机会锁中断通知），忽略
+                    continue;
+                }
+                let status = le_u32(&data, 8)?;
+                if status == STATUS_PENDING && flags & FLAGS_ASYNC_COMMAND != 0 {
+                    continue;
+                }
+                return Ok(Response { status, data });
+            }
+        })
+        .await
+        .map_err(|_| SyncError::Provider(ProviderError::Timeout("SMB request timed out".into())))?
+    }
+
+    /// 发送请求，非成功状态视为错误
+    async fn request_ok(
+        &mut self,
+        command: u16,
+        body: &[u8],
+        context: &str,
+    ) -> Result<Response, SyncError> {
+        let response = self.request(command, body).await?;
+        if response.status == STATUS_SUCCESS {
+            Ok(response)
+        } else {
+            Err(status_error(response.status, context))
+        }
+    }
+
+    async fn negotiate(&mut self) -> Result<(), SyncError> {
+        let mut body = Vec::with_capacity(36 + DIALECTS.len() * 2);
+        body.extend_from_slice(&36u16.to_le_bytes());
+        body.extend_from_slice(&(DIALECTS.len() as u16).to_le_bytes());
+        body.extend_from_slice(&SECURITY_MODE_SIGNING_ENABLED.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+        body.extend_from_slice(uuid::Uuid::new_v4().as_bytes()); // ClientGuid
+        body.extend_from_slice(&0u64.to_le_bytes()); // ClientStartTime
+        for dialect in DIALECTS {
+            body.extend_from_slice(&dialect.to_le_bytes());
+        }
+
+        let resp = self.request_ok(NEGOTIATE, &body, "negotiate").await?;
+        let b = HEADER_SIZE;
+        let security_mode = le_u16(&resp.data, b + 2)?;
+        self.dialect = le_u16(&resp.data, b + 4)?;
+        if !DIALECTS.contains(&self.dialect) {
+            return Err(SyncError::Provider(ProviderError::NotSupported(format!(
+                "SMB dialect 0x{:04x}",
+                self.dialect
+            ))));
+        }
+        self.signing_required = security_mode & SECURITY_MODE_SIGNING_REQUIRED != 0;
+        self.max_transact = le_u32(&resp.data, b + 28)?.clamp(1, MAX_IO_SIZE);
+        self.max_read = le_u32(&resp.data, b + 32)?.clamp(1, MAX_IO_SIZE);
+        self.max_write = le_u32(&resp.data, b + 36)?.clamp(1, MAX_IO_SIZE);
+        Ok(())
+    }
+
+    async fn session_setup_request(&mut self, token: &[u8]) -> Result<Response, SyncError> {
+        let mut body = Vec::with_capacity(24 + token.len());
+        body.extend_from_slice(&25u16.to_le_bytes());
+        body.push(0); // Flags
+        body.push(SECURITY_MODE_SIGNING_ENABLED as u8);
+        body.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+        body.extend_from_slice(&0u32.to_le_bytes()); // Channel
+        body.extend_from_slice(&((HEADER_SIZE + 24) as u16).to_le_bytes());
+        body.extend_from_slice(&(token.len() as u16).to_le_bytes());
+        body.extend_from_slice(&0u64.to_le_bytes()); // PreviousSessionId
+        body.extend_from_slice(token);
+        self.request(SESSION_SETUP, &body).await
+    }
+
+    async fn session_setup(&mut self, creds: &NtlmCredentials<'_>) -> Result<(), SyncError> {
+        let resp = self
+            .session_setup_request(&ntlm::spnego_init(&ntlm::negotiate_message()))
+            .await?;
+        if resp.status != STATUS_MORE_PROCESSING_REQUIRED {
+            return Err(status_error(resp.status, "session setup"));
+        }
+        self.session_id = le_u64(&resp.data, 40)?;
+
+        let offset = le_u16(&resp.data, HEADER_SIZE + 4)? as usize;
+        let len = le_u16(&resp.data, HEADER_SIZE + 6)? as usize;
+        let challenge = ntlm::parse_challenge(slice(&resp.data, offset, len)?)
+            .ok_or_else(|| malformed("invalid NTLM challenge"))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let filetime = unix_to_filetime(now.as_secs() as i64) + now.subsec_nanos() as u64 / 100;
+        let (auth, session_key) =
+            ntlm::authenticate_message(creds, &challenge, rand::random(), filetime);
+
+        let resp = self
+            .session_setup_request(&ntlm::spnego_response(&auth))
+            .await?;
+        if resp.status != STATUS_SUCCESS {
+            return Err(status_error(resp.status, "session setup"));
+        }
+
+        let session_flags = le_u16(&resp.data, HEADER_SIZE + 2)?;
+        if session_flags & SESSION_FLAG_ENCRYPT_DATA != 0 {
+            return Err(SyncError::Provider(ProviderError::NotSupported(
+                "SMB3 encryption is required by the server".into(),
+            )));
+        }
+        if self.signing_required
+            && session_flags & (SESSION_FLAG_IS_GUEST | SESSION_FLAG_IS_NULL) == 0
+        {
+            self.signing_key = Some(SigningKey::for_dialect(self.dialect, session_key));
+        }
+        Ok(())
+    }
+
+    async fn tree_connect(&mut self, host: &str, share: &str) -> Result<(), SyncError> {
+        let path = utf16le(&format!("\\\\{}\\{}", host, share));
+        let mut body = Vec::with_capacity(8 + path.len());
+        body.extend_from_slice(&9u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&((HEADER_SIZE + 8) as u16).to_le_bytes());
+        body.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        body.extend_from_slice(&path);
+
+        let resp = self.request(TREE_CONNECT, &body).await?;
+        match resp.status {
+            STATUS_SUCCESS => {
+                self.tree_id = le_u32(&resp.data, 36)?;
+                Ok(())
+            }
+            STATUS_BAD_NETWORK_NAME => Err(SyncError::Provider(ProviderError::NotFound(format!(
+                "SMB share {}",
+                share
+            )))),
+            status => Err(status_error(status, share)),
+        }
+    }
+
+    /// 打开或创建文件/目录，返回文件句柄与属性
+    pub(super) async fn create(
+        &mut self,
+        path: &str,
+        access: u32,
+        disposition: u32,
+        options: u32,
+    ) -> Result<(FileId, FileAttrs), SyncError> {
+        let name = utf16le(path);
+        let attributes = if options & FILE_DIRECTORY_FILE != 0 {
+            FILE_ATTRIBUTE_DIRECTORY
+        } else {
+            FILE_ATTRIBUTE_NORMAL
+        };
+
+        let mut body = Vec::with_capacity(56 + name.len().max(1));
+        body.extend_from_slice(&57u16.to_le_bytes());
+        body.push(0); // SecurityFlags
+        body.push(0); // RequestedOplockLevel: none
+        body.extend_from_slice(&2u32.to_le_bytes()); // ImpersonationLevel: Impersonation
+        body.extend_from_slice(&[0u8; 16]); // SmbCreateFlags + Reserved
+        body.extend_from_slice(&access.to_le_bytes());
+        body.extend_from_slice(&attributes.to_le_bytes());
+        body.extend_from_slice(&7u32.to_le_bytes()); // ShareAccess: read | write | delete
+        body.extend_from_slice(&disposition.to_le_bytes());
+        body.extend_from_slice(&options.to_le_bytes());
+        body.extend_from_slice(&((HEADER_SIZE + 56) as u16).to_le_bytes());
+        body.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        body.extend_from_slice(&[0u8; 8]); // CreateContexts
+        if name.is_empty() {
+            body.push(0);
+        } else {
+            body.extend_from_slice(&name);
+        }
+
+        let resp = self.request_ok(CREATE, &body, path).await?;
+        let b = HEADER_SIZE;
+        let attrs = FileAttrs {
+            size: le_u64(&resp.data, b + 48)?,
+            modified: filetime_to_unix(le_u64(&resp.data, b + 24)?),
+            is_dir: le_u32(&resp.data, b + 56)? & FILE_ATTRIBUTE_DIRECTORY != 0,
+        };
+        let file_id = slice(&resp.data, b + 64, 16)?.try_into().unwrap();
+        Ok((file_id, attrs))
+    }
+
+    pub(super) async fn close(&mut self, file_id: &FileId) -> Result<(), SyncError> {
+        let mut body = Vec::with_capacity(24);
+        body.extend_from_slice(&24u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(file_id);
+        self.request_ok(CLOSE, &body, "close").await?;
+        Ok(())
+    }
+
+    /// 从指定偏移读取数据，到达文件末尾时返回空
+    pub(super) async fn read(
+        &mut self,
+        file_id: &FileId,
+        offset: u64,
+        len: usize,
+    ) -> Result<Vec<u8>, SyncError> {
+        let len = len.min(self.max_read()) as u32;
+        let mut body = Vec::with_capacity(49);
+        body.extend_from_slice(&49u16.to_le_bytes());
+        body.push(0x50); // Padding
+        body.push(0); // Flags
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(file_id);
+        body.extend_from_slice(&[0u8; 16]); // MinimumCount, Channel, RemainingBytes, ReadChannelInfo
+        body.push(0);
+
+        let resp = self.request(READ, &body).await?;
+        match resp.status {
+            STATUS_SUCCESS => {
+                let data_offset = resp.data.get(HEADER_SIZE + 2).copied().unwrap_or(0) as usize;
+                let data_len = le_u32(&resp.data, HEADER_SIZE + 4)? as usize;
+                Ok(slice(&resp.data, data_offset, data_len)?.to_vec())
+            }
+            STATUS_END_OF_FILE => Ok(Vec::new()),
+            status => Err(status_error(status, "read")),
+        }
+    }
+
+    /// 在指定偏移写入数据，返回写入的字节数
+    pub(super) async fn write(
+        &mut self,
+        file_id: &FileId,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<usize, SyncError> {
+        let mut body = Vec::with_capacity(48 + data.len());
+        body.extend_from_slice(&49u16.to_le_bytes());
+        body.extend_from_slice(&((HEADER_SIZE + 48) as u16).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(file_id);
+        body.extend_from_slice(&[0u8; 16]); // Channel, RemainingBytes, WriteChannelInfo, Flags
+        body.extend_from_slice(data);
+
+        let resp = self.request_ok(WRITE, &body, "write").await?;
+        Ok(le_u32(&resp.data, HEADER_SIZE + 4)? as usize)
+    }
+
+    /// 列出已打开目录中的所有条目（不含 "." 与 ".."）
+    pub(super) async fn query_directory(
+        &mut self,
+        file_id: &FileId,
+    ) -> Result<Vec<DirEntry>, SyncError> {
+        let pattern = utf16le("*");
+        let mut entries = Vec::new();
+        let mut first = true;
+
+        loop {
+            let mut body = Vec::with_capacity(32 + pattern.len());
+            body.extend_from_slice(&33u16.to_le_bytes());
+            body.push(FILE_DIRECTORY_INFORMATION);
+            body.push(if first { 0x01 } else { 0x00 }); // RESTART_SCANS
+            body.extend_from_slice(&0u32.to_le_bytes()); // FileIndex
+            body.extend_from_slice(file_id);
+            body.extend_from_slice(&((HEADER_SIZE + 32) as u16).to_le_bytes());
+            body.extend_from_slice(&(pattern.len() as u16).to_le_bytes());
+            body.extend_from_slice(&self.max_transact.to_le_bytes());
+            body.extend_from_slice(&pattern);
+            first = false;
+
+            let resp = self.request(QUERY_DIRECTORY, &body).await?;
+            match resp.status {
+                STATUS_SUCCESS => {}
+                STATUS_NO_MORE_FILES => break,
+                status => return Err(status_error(status, "query directory")),
+            }
+            let offset = le_u16(&resp.data, HEADER_SIZE + 2)? as usize;
+            let len = le_u32(&resp.data, HEADER_SIZE + 4)? as usize;
+            entries.extend(
+                parse_directory_entries(slice(&resp.data, offset, len)?)?
+                    .into_iter()
+                    .filter(|e| e.name != "." && e.name != ".."),
+            );
+        }
+        Ok(entries)
+    }
+
+    pub(super) async fn set_info(
+        &mut self,
+        file_id: &FileId,
+        class: u8,
+        info: &[u8],
+    ) -> Result<(), SyncError> {
+        let mut body = Vec::with_capacity(32 + info.len());
+        body.extend_from_slice(&33u16.to_le_bytes());
+        body.push(1); // InfoType: SMB2_0_INFO_FILE
+        body.push(class);
+        body.extend_from_slice(&(info.len() as u32).to_le_bytes());
+        body.extend_from_slice(&((HEADER_SIZE + 32) as u16).to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes()); // AdditionalInformation
+        body.extend_from_slice(file_id);
+        body.extend_from_slice(info);
+        self.request_ok(SET_INFO, &body, "set info").await?;
+        Ok(())
+    }
+}
+
+/// 解析 FileDirectoryInformation 条目列表
+pub(super) fn parse_directory_entries(buf: &[u8]) -> Result<Vec<DirEntry>, SyncError> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    loop {
+        let next = le_u32(buf, pos)? as usize;
+        let name_len = le_u32(buf, pos + 60)? as usize;
+        entries.push(DirEntry {
+            name: from_utf16le(slice(buf, pos + 64, name_len)?),
+            attrs: FileAttrs {
+                size: le_u64(buf, pos + 40)?,
+                modified: filetime_to_unix(le_u64(buf, pos + 24)?),
+                is_dir: le_u32(buf, pos + 56)? & FILE_ATTRIBUTE_DIRECTORY != 0,
+            },
+        });
+        if next == 0 {
+            return Ok(entries);
+        }
+        pos += next;
+    }
+}
+
+/// 构造 FileBasicInformation，只修改最后写入时间
+pub(super) fn basic_info_with_mtime(modified: i64) -> Vec<u8> {
+    let mut info = Vec::with_capacity(40);
+    info.extend_from_slice(&0u64.to_le_bytes()); // CreationTime
+    info.extend_from_slice(&0u64.to_le_bytes()); // LastAccessTime
+    info.extend_from_slice(&unix_to_filetime(modified).to_le_bytes());
+    info.extend_from_slice(&0u64.to_le_bytes()); // ChangeTime
+    info.extend_from_slice(&0u32.to_le_bytes()); // FileAttributes
+    info.extend_from_slice(&0u32.to_le_bytes());
+    info
+}
+
+/// 构造 FileRenameInformation，目标路径相对于共享根目录
+pub(super) fn rename_info(target: &str) -> Vec<u8> {
+    let name = utf16le(target);
+    let mut info = Vec::with_capacity(20 + name.len());
+    info.push(1); // ReplaceIfExists
+    info.extend_from_slice(&[0u8; 7]);
+    info.extend_from_slice(&0u64.to_le_bytes()); // RootDirectory
+    info.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    info.extend_from_slice(&name);
+    info
+}
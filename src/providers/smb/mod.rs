@@ -0,0 +1,1054 @@
+//! SMB 存储提供者实现
+//!
+//! 使用内置的 SMB2/3 客户端访问 NAS 或 Windows 共享，不依赖系统挂载。
+//!
+//! # 凭据
+//! - `server`: 服务器地址，可带端口，例如 `nas.local` 或 `192.168.1.2:445`
+//! - `share`: 共享名称
+//! - `username` / `password`: 登录账户，访客访问时可使用 `guest` 与空密码
+//! - `domain`（可选）：域或工作组名称
+//! - `root`（可选）：共享内的根目录
+//!
+//! # 路径约定
+//! 与其他提供者一致，路径以 `/` 开头；发送给服务器时转换为以 `\` 分隔的共享内路径。
+
+mod client;
+mod ntlm;
+
+use self::client::{
+    DELETE, FILE_DELETE_ON_CLOSE, FILE_DIRECTORY_FILE, FILE_NON_DIRECTORY_FILE, FILE_OPEN,
+    FILE_OPEN_IF, FILE_OVERWRITE_IF, FILE_READ_ATTRIBUTES, FILE_READ_DATA, FILE_WRITE_ATTRIBUTES,
+    FILE_WRITE_DATA, FileAttrs, SYNCHRONIZE, SmbClient,
+};
+use crate::config::AccountConfig;
+use crate::error::{ProviderError, SyncError};
+use crate::providers::{
+    CaseSensitivity, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, UploadResult,
+};
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+const DEFAULT_PORT: u16 = 445;
+
+/// SMB 存储提供者
+pub struct SmbProvider {
+    host: String,
+    port: u16,
+    share: String,
+    username: String,
+    password: String,
+    domain: String,
+    root: Vec<String>,
+    client: Mutex<Option<SmbClient>>,
+}
+
+impl SmbProvider {
+    pub async fn new(config: &AccountConfig) -> Result<Self, SyncError> {
+        let credential = |key: &str| config.credentials.get(key).cloned().unwrap_or_default();
+        let required = |key: &str| {
+            config
+                .credentials
+                .get(key)
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .ok_or_else(|| SyncError::Provider(ProviderError::MissingCredentials(key.into())))
+        };
+
+        let server = required("server")?;
+        let server = server
+            .trim_start_matches("smb://")
+            .trim_start_matches("\\\\")
+            .trim_end_matches(['/', '\\']);
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) => (
+                host.to_string(),
+                port.parse().map_err(|_| {
+                    SyncError::Config(crate::error::ConfigError::Invalid(format!(
+                        "Invalid SMB port: {}",
+                        port
+                    )))
+                })?,
+            ),
+            None => (server.to_string(), DEFAULT_PORT),
+        };
+
+        info!(host = %host, share = %credential("share"), "初始化 SMB Provider");
+        Ok(Self {
+            host,
+            port,
+            share: required("share")?.trim_matches(['/', '\\']).to_string(),
+            username: required("username")?,
+            password: credential("password"),
+            domain: credential("domain"),
+            root: split_components(&credential("root"))?,
+            client: Mutex::new(None),
+        })
+    }
+
+    /// 将提供者内路径转换为共享内路径
+    fn smb_path(&self, path: &str) -> Result<String, SyncError> {
+        let mut components = self.root.clone();
+        components.extend(split_components(path)?);
+        Ok(components.join("\\"))
+    }
+
+    /// 获取已连接的客户端，尚未连接时先建立连接
+    async fn connected<'a>(
+        &self,
+        guard: &'a mut Option<SmbClient>,
+    ) -> Result<&'a mut SmbClient, SyncError> {
+        if guard.is_none() {
+            let creds = ntlm::NtlmCredentials {
+                username: &self.username,
+                password: &self.password,
+                domain: &self.domain,
+            };
+            *guard = Some(SmbClient::connect(&self.host, self.port, &self.share, &creds).await?);
+        }
+        Ok(guard.as_mut().expect("client was just connected"))
+    }
+
+    /// 连接层错误时丢弃客户端，下次操作重新连接
+    fn reset_on_connection_error<T>(
+        guard: &mut Option<SmbClient>,
+        result: Result<T, SyncError>,
+    ) -> Result<T, SyncError> {
+        if let Err(SyncError::Provider(
+            ProviderError::ConnectionFailed(_) | ProviderError::Timeout(_),
+        )) = &result
+        {
+            warn!("SMB 连接异常，将在下次操作时重新连接");
+            *guard = None;
+        }
+        result
+    }
+
+    /// 打开文件执行操作，无论成功与否都关闭句柄
+    async fn with_open<T>(
+        client: &mut SmbClient,
+        path: &str,
+        access: u32,
+        disposition: u32,
+        options: u32,
+        op: impl AsyncFnOnce(&mut SmbClient, &client::FileId, &FileAttrs) -> Result<T, SyncError>,
+    ) -> Result<T, SyncError> {
+        let (file_id, attrs) = client.create(path, access, disposition, options).await?;
+        let result = op(client, &file_id, &attrs).await;
+        let closed = client.close(&file_id).await;
+        let value = result?;
+        closed?;
+        Ok(value)
+    }
+
+    async fn stat_path(client: &mut SmbClient, path: &str) -> Result<FileAttrs, SyncError> {
+        Self::with_open(
+            client,
+            path,
+            FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            0,
+            async |_, _, attrs| Ok(attrs.clone()),
+        )
+        .await
+    }
+
+    async fn list_path(
+        client: &mut SmbClient,
+        path: &str,
+    ) -> Result<Vec<client::DirEntry>, SyncError> {
+        Self::with_open(
+            client,
+            path,
+            FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            FILE_DIRECTORY_FILE,
+            async |client, file_id, _| client.query_directory(file_id).await,
+        )
+        .await
+    }
+
+    /// 逐级创建目录
+    async fn mkdir_all(client: &mut SmbClient, path: &str) -> Result<(), SyncError> {
+        let mut current = String::new();
+        for component in path.split('\\').filter(|c| !c.is_empty()) {
+            if !current.is_empty() {
+                current.push('\\');
+            }
+            current.push_str(component);
+            Self::with_open(
+                client,
+                &current,
+                FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+                FILE_OPEN_IF,
+                FILE_DIRECTORY_FILE,
+                async |_, _, _| Ok(()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// 删除文件或递归删除目录
+    async fn remove_path(
+        client: &mut SmbClient,
+        path: &str,
+        is_dir: bool,
+    ) -> Result<(), SyncError> {
+        if is_dir {
+            for entry in Self::list_path(client, path).await? {
+                let child = format!("{}\\{}", path, entry.name);
+                Box::pin(Self::remove_path(client, &child, entry.attrs.is_dir)).await?;
+            }
+        }
+        let options = FILE_DELETE_ON_CLOSE
+            | if is_dir {
+                FILE_DIRECTORY_FILE
+            } else {
+                FILE_NON_DIRECTORY_FILE
+            };
+        Self::with_open(client, path, DELETE, FILE_OPEN, options, async |_, _, _| {
+            Ok(())
+        })
+        .await
+    }
+
+    async fn upload_file(
+        client: &mut SmbClient,
+        local_path: &Path,
+        path: &str,
+    ) -> Result<u64, SyncError> {
+        if let Some((parent, _)) = path.rsplit_once('\\') {
+            Self::mkdir_all(client, parent).await?;
+        }
+        let mut file = tokio::fs::File::open(local_path).await?;
+        Self::with_open(
+            client,
+            path,
+            FILE_WRITE_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OVERWRITE_IF,
+            FILE_NON_DIRECTORY_FILE,
+            async |client, file_id, _| {
+                let mut buf = vec![0u8; client.max_write()];
+                let mut offset = 0u64;
+                loop {
+                    let n = file.read(&mut buf).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    let mut written = 0;
+                    while written < n {
+                        written += client
+                            .write(file_id, offset + written as u64, &buf[written..n])
+                            .await?;
+                    }
+                    offset += n as u64;
+                }
+                Ok(offset)
+            },
+        )
+        .await
+    }
+
+    async fn download_file(
+        client: &mut SmbClient,
+        path: &str,
+        local_path: &Path,
+    ) -> Result<u64, SyncError> {
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(local_path).await?;
+        let bytes = Self::with_open(
+            client,
+            path,
+            FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            FILE_NON_DIRECTORY_FILE,
+            async |client, file_id, attrs| {
+                let mut offset = 0u64;
+                while offset < attrs.size {
+                    let max = client.max_read();
+                    let chunk = client.read(file_id, offset, max).await?;
+                    if chunk.is_empty() {
+                        break;
+                    }
+                    file.write_all(&chunk).await?;
+                    offset += chunk.len() as u64;
+                }
+                Ok(offset)
+            },
+        )
+        .await?;
+        file.flush().await?;
+        Ok(bytes)
+    }
+}
+
+/// 拆分路径组件，拒绝 `..` 以免越出根目录
+fn split_components(path: &str) -> Result<Vec<String>, SyncError> {
+    path.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .map(|c| {
+            if c == ".." {
+                Err(SyncError::Provider(ProviderError::PermissionDenied(
+                    format!("Path escapes provider root: {}", path),
+                )))
+            } else {
+                Ok(c.to_string())
+            }
+        })
+        .collect()
+}
+
+/// 拼接目录与子项名称，得到以 `/` 开头的提供者内路径
+fn join_virtual(dir: &str, name: &str) -> String {
+    let dir = dir.trim_matches('/');
+    if dir.is_empty() {
+        format!("/{}", name)
+    } else {
+        format!("/{}/{}", dir, name)
+    }
+}
+
+fn to_file_info(path: String, attrs: &FileAttrs) -> FileInfo {
+    FileInfo {
+        path,
+        size: if attrs.is_dir { 0 } else { attrs.size },
+        modified: attrs.modified,
+        hash: None,
+        is_dir: attrs.is_dir,
+        permissions: None,
+    }
+}
+
+#[async_trait]
+impl StorageProvider for SmbProvider {
+    async fn verify(&self) -> Result<(), SyncError> {
+        let path = self.smb_path("/")?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            let attrs = Self::stat_path(client, &path).await?;
+            if attrs.is_dir {
+                Ok(())
+            } else {
+                Err(SyncError::Provider(ProviderError::ApiError(
+                    "SMB root is not a directory".into(),
+                )))
+            }
+        }
+        .await;
+        Self::reset_on_connection_error(&mut guard, result)
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        let smb_path = self.smb_path(path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::list_path(client, &smb_path).await
+        }
+        .await;
+        let entries = Self::reset_on_connection_error(&mut guard, result)?;
+        Ok(entries
+            .iter()
+            .map(|e| to_file_info(join_virtual(path, &e.name), &e.attrs))
+            .collect())
+    }
+
+    async fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        let start = Instant::now();
+        let smb_path = self.smb_path(remote_path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::upload_file(client, local_path, &smb_path).await
+        }
+        .await;
+        let bytes = Self::reset_on_connection_error(&mut guard, result)?;
+
+        Ok(UploadResult {
+            bytes_uploaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            compression: None,
+        })
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> Result<DownloadResult, SyncError> {
+        let start = Instant::now();
+        let smb_path = self.smb_path(remote_path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::download_file(client, &smb_path, local_path).await
+        }
+        .await;
+        let bytes = Self::reset_on_connection_error(&mut guard, result)?;
+
+        Ok(DownloadResult {
+            bytes_downloaded: bytes,
+            file_size: bytes,
+            checksum: None,
+            elapsed_time: start.elapsed(),
+            resumed_bytes: 0,
+        })
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), SyncError> {
+        let smb_path = self.smb_path(path)?;
+        if smb_path == self.smb_path("/")? {
+            return Err(SyncError::Provider(ProviderError::PermissionDenied(
+                "Refusing to delete provider root".to_string(),
+            )));
+        }
+
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            let attrs = match Self::stat_path(client, &smb_path).await {
+                Ok(attrs) => attrs,
+                Err(SyncError::Provider(ProviderError::FileNotFound(_))) => {
+                    warn!(path = %path, "文件或目录不存在，视为删除成功");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            Self::remove_path(client, &smb_path, attrs.is_dir).await
+        }
+        .await;
+        Self::reset_on_connection_error(&mut guard, result)
+    }
+
+    /// 创建目录（包括缺失的父目录）
+    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
+        let smb_path = self.smb_path(path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::mkdir_all(client, &smb_path).await
+        }
+        .await;
+        Self::reset_on_connection_error(&mut guard, result)
+    }
+
+    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
+        let smb_path = self.smb_path(path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::stat_path(client, &smb_path).await
+        }
+        .await;
+        let attrs = Self::reset_on_connection_error(&mut guard, result)?;
+        Ok(to_file_info(format!("/{}", path.trim_matches('/')), &attrs))
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(SyncError::Provider(ProviderError::FileNotFound(_))) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            can_set_mtime: true,
+            supports_range_read: true,
+            supports_server_move: true,
+            case_sensitivity: CaseSensitivity::Insensitive,
+            forbidden_chars: vec!['<', '>', ':', '"', '|', '?', '*', '\\'],
+            ..ProviderCapabilities::default()
+        }
+    }
+
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let smb_path = self.smb_path(path)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            Self::with_open(
+                client,
+                &smb_path,
+                FILE_WRITE_ATTRIBUTES | SYNCHRONIZE,
+                FILE_OPEN,
+                0,
+                async |client, file_id, _| {
+                    client
+                        .set_info(
+                            file_id,
+                            client::FILE_BASIC_INFORMATION,
+                            &client::basic_info_with_mtime(modified),
+                        )
+                        .await
+                },
+            )
+            .await
+        }
+        .await;
+        Self::reset_on_connection_error(&mut guard, result)
+    }
+
+    /// 重命名文件或目录，必要时创建目标父目录
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        let source = self.smb_path(from)?;
+        let target = self.smb_path(to)?;
+        let mut guard = self.client.lock().await;
+        let result = async {
+            let client = self.connected(&mut guard).await?;
+            if let Some((parent, _)) = target.rsplit_once('\\') {
+                Self::mkdir_all(client, parent).await?;
+            }
+            Self::with_open(
+                client,
+                &source,
+                DELETE | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+                FILE_OPEN,
+                0,
+                async |client, file_id, _| {
+                    client
+                        .set_info(
+                            file_id,
+                            client::FILE_RENAME_INFORMATION,
+                            &client::rename_info(&target),
+                        )
+                        .await
+                },
+            )
+            .await
+        }
+        .await;
+        Self::reset_on_connection_error(&mut guard, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::client::*;
+    use super::*;
+    use crate::config::{ProviderType, RetryPolicy};
+    use hmac::{Hmac, Mac};
+    use md5::Md5;
+    use std::collections::{BTreeMap, HashMap};
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex as StdMutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const PASSWORD: &str = "secret";
+    const DIALECT: u16 = 0x0302;
+    const SERVER_CHALLENGE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    const STATUS_OBJECT_NAME_COLLISION: u32 = 0xc000_0035;
+    const STATUS_FILE_IS_A_DIRECTORY: u32 = 0xc000_00ba;
+    const STATUS_NOT_A_DIRECTORY: u32 = 0xc000_0103;
+    const STATUS_DIRECTORY_NOT_EMPTY: u32 = 0xc000_0101;
+
+    #[derive(Clone, Default)]
+    struct Node {
+        is_dir: bool,
+        data: Vec<u8>,
+        mtime: u64,
+    }
+
+    type Share = Arc<StdMutex<BTreeMap<String, Node>>>;
+
+    struct Handle {
+        path: String,
+        delete_on_close: bool,
+        listed: bool,
+    }
+
+    /// 单个连接的会话状态
+    #[derive(Default)]
+    struct Session {
+        session_key: Option<[u8; 16]>,
+        handles: HashMap<[u8; 16], Handle>,
+        next_handle: u8,
+    }
+
+    fn u16_at(b: &[u8], o: usize) -> u16 {
+        le_u16(b, o).unwrap()
+    }
+    fn u32_at(b: &[u8], o: usize) -> u32 {
+        le_u32(b, o).unwrap()
+    }
+    fn u64_at(b: &[u8], o: usize) -> u64 {
+        le_u64(b, o).unwrap()
+    }
+
+    fn utf16_string(bytes: &[u8]) -> String {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    fn parent(path: &str) -> &str {
+        path.rsplit_once('\\').map(|(p, _)| p).unwrap_or("")
+    }
+
+    fn children<'a>(share: &'a BTreeMap<String, Node>, dir: &str) -> Vec<(&'a String, &'a Node)> {
+        share
+            .iter()
+            .filter(|(p, _)| !p.is_empty() && parent(p) == dir)
+            .collect()
+    }
+
+    fn response(request: &[u8], status: u32, session_id: u64, body: &[u8]) -> Vec<u8> {
+        let mut msg = request[..64].to_vec();
+        msg[8..12].copy_from_slice(&status.to_le_bytes());
+        msg[14..16].copy_from_slice(&32u16.to_le_bytes());
+        msg[16..20].copy_from_slice(&1u32.to_le_bytes());
+        msg[36..40].copy_from_slice(&7u32.to_le_bytes());
+        msg[40..48].copy_from_slice(&session_id.to_le_bytes());
+        msg[48..64].fill(0);
+        msg.extend_from_slice(body);
+        msg
+    }
+
+    fn hmac_md5(key: &[u8], data: &[&[u8]]) -> [u8; 16] {
+        let mut mac = Hmac::<Md5>::new_from_slice(key).unwrap();
+        for d in data {
+            mac.update(d);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    fn challenge_message() -> Vec<u8> {
+        let target_info = [&[0x02, 0x00, 0x08, 0x00][..], &utf16le("TEST"), &[0u8; 4]].concat();
+        let mut msg = b"NTLMSSP\0".to_vec();
+        msg.extend_from_slice(&2u32.to_le_bytes());
+        msg.extend_from_slice(&[0, 0, 0, 0, 48, 0, 0, 0]);
+        msg.extend_from_slice(&0xe289_8215u32.to_le_bytes());
+        msg.extend_from_slice(&SERVER_CHALLENGE);
+        msg.extend_from_slice(&[0u8; 8]);
+        let len = target_info.len() as u16;
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&48u32.to_le_bytes());
+        msg.extend_from_slice(&target_info);
+        msg
+    }
+
+    /// 校验 AUTHENTICATE 消息中的 NTLMv2 证明，成功时返回会话密钥
+    fn verify_authenticate(token: &[u8]) -> Option<[u8; 16]> {
+        let field = |o: usize| {
+            let len = u16_at(token, o) as usize;
+            let off = u32_at(token, o + 4) as usize;
+            &token[off..off + len]
+        };
+        let nt = field(20);
+        let creds = ntlm::NtlmCredentials {
+            username: &utf16_string(field(36)),
+            password: PASSWORD,
+            domain: &utf16_string(field(28)),
+        };
+        let key = ntlm::ntowf_v2(&creds);
+        let proof = hmac_md5(&key, &[&SERVER_CHALLENGE, &nt[16..]]);
+        (proof[..] == nt[..16]).then(|| hmac_md5(&key, &[&proof]))
+    }
+
+    fn handle_request(share: &Share, session: &mut Session, req: &[u8]) -> Vec<u8> {
+        let command = u16_at(req, 12);
+        let b = 64;
+        let session_id = 0x1122;
+
+        if let Some(key) = session.session_key
+            && command != NEGOTIATE
+            && command != SESSION_SETUP
+        {
+            let signed = u32_at(req, 16) & 0x8 != 0;
+            if !signed || req[48..64] != expected_signature(DIALECT, key, req) {
+                return response(req, STATUS_ACCESS_DENIED, session_id, &[9, 0]);
+            }
+        }
+
+        let mut nodes = share.lock().unwrap();
+        match command {
+            NEGOTIATE => {
+                let mut body = vec![65, 0, 3, 0];
+                body.extend_from_slice(&DIALECT.to_le_bytes());
+                body.extend_from_slice(&[0u8; 18]);
+                body.extend_from_slice(&0u32.to_le_bytes());
+                for _ in 0..3 {
+                    body.extend_from_slice(&(1u32 << 20).to_le_bytes());
+                }
+                body.extend_from_slice(&[0u8; 16]);
+                body.extend_from_slice(&[128, 0, 0, 0, 0, 0, 0, 0]);
+                response(req, STATUS_SUCCESS, 0, &body)
+            }
+            SESSION_SETUP => {
+                let offset = u16_at(req, b + 12) as usize;
+                let len = u16_at(req, b + 14) as usize;
+                let buffer = &req[offset..offset + len];
+                let start = buffer.windows(8).position(|w| w == b"NTLMSSP\0").unwrap();
+                let token = &buffer[start..];
+                if u32_at(token, 8) == 1 {
+                    let challenge = challenge_message();
+                    let mut body = vec![9, 0, 0, 0, 72, 0];
+                    body.extend_from_slice(&(challenge.len() as u16).to_le_bytes());
+                    body.extend_from_slice(&challenge);
+                    return response(req, STATUS_MORE_PROCESSING_REQUIRED, session_id, &body);
+                }
+                match verify_authenticate(token) {
+                    Some(key) => {
+                        session.session_key = Some(key);
+                        response(req, STATUS_SUCCESS, session_id, &[9, 0, 0, 0, 72, 0, 0, 0])
+                    }
+                    None => response(req, STATUS_LOGON_FAILURE, session_id, &[9, 0]),
+                }
+            }
+            TREE_CONNECT => {
+                let mut body = vec![16, 0, 1, 0];
+                body.extend_from_slice(&[0u8; 8]);
+                body.extend_from_slice(&0x001f_01ffu32.to_le_bytes());
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            CREATE => {
+                let disposition = u32_at(req, b + 36);
+                let options = u32_at(req, b + 40);
+                let name_offset = u16_at(req, b + 44) as usize;
+                let name_len = u16_at(req, b + 46) as usize;
+                let path = utf16_string(&req[name_offset..name_offset + name_len]);
+
+                let status = match (nodes.get(&path), disposition) {
+                    (None, FILE_OPEN) if nodes.contains_key(parent(&path)) => {
+                        STATUS_OBJECT_NAME_NOT_FOUND
+                    }
+                    (None, _) if !nodes.get(parent(&path)).is_some_and(|n| n.is_dir) => {
+                        STATUS_OBJECT_PATH_NOT_FOUND
+                    }
+                    (None, _) => {
+                        let node = Node {
+                            is_dir: options & FILE_DIRECTORY_FILE != 0,
+                            ..Node::default()
+                        };
+                        nodes.insert(path.clone(), node);
+                        STATUS_SUCCESS
+                    }
+                    (Some(n), _) if options & FILE_DIRECTORY_FILE != 0 && !n.is_dir => {
+                        STATUS_NOT_A_DIRECTORY
+                    }
+                    (Some(n), _) if options & FILE_NON_DIRECTORY_FILE != 0 && n.is_dir => {
+                        STATUS_FILE_IS_A_DIRECTORY
+                    }
+                    (Some(_), 2) => STATUS_OBJECT_NAME_COLLISION,
+                    (Some(n), _)
+                        if options & FILE_DELETE_ON_CLOSE != 0
+                            && n.is_dir
+                            && !children(&nodes, &path).is_empty() =>
+                    {
+                        STATUS_DIRECTORY_NOT_EMPTY
+                    }
+                    (Some(_), FILE_OVERWRITE_IF) => {
+                        nodes.get_mut(&path).unwrap().data.clear();
+                        STATUS_SUCCESS
+                    }
+                    (Some(_), _) => STATUS_SUCCESS,
+                };
+                if status != STATUS_SUCCESS {
+                    return response(req, status, session_id, &[9, 0]);
+                }
+
+                session.next_handle += 1;
+                let file_id = [session.next_handle; 16];
+                session.handles.insert(
+                    file_id,
+                    Handle {
+                        path: path.clone(),
+                        delete_on_close: options & FILE_DELETE_ON_CLOSE != 0,
+                        listed: false,
+                    },
+                );
+                let node = &nodes[&path];
+                let mut body = vec![89, 0, 0, 0, 1, 0, 0, 0];
+                body.extend_from_slice(&[0u8; 16]);
+                body.extend_from_slice(&node.mtime.to_le_bytes());
+                body.extend_from_slice(&[0u8; 16]);
+                body.extend_from_slice(&(node.data.len() as u64).to_le_bytes());
+                let attributes = if node.is_dir {
+                    FILE_ATTRIBUTE_DIRECTORY
+                } else {
+                    FILE_ATTRIBUTE_NORMAL
+                };
+                body.extend_from_slice(&attributes.to_le_bytes());
+                body.extend_from_slice(&[0u8; 4]);
+                body.extend_from_slice(&file_id);
+                body.extend_from_slice(&[0u8; 8]);
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            CLOSE => {
+                let file_id: [u8; 16] = req[b + 8..b + 24].try_into().unwrap();
+                let handle = session.handles.remove(&file_id).unwrap();
+                if handle.delete_on_close {
+                    nodes.remove(&handle.path);
+                }
+                let mut body = vec![60, 0];
+                body.extend_from_slice(&[0u8; 58]);
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            READ => {
+                let len = u32_at(req, b + 4) as usize;
+                let offset = u64_at(req, b + 8) as usize;
+                let file_id: [u8; 16] = req[b + 16..b + 32].try_into().unwrap();
+                let data = &nodes[&session.handles[&file_id].path].data;
+                if offset >= data.len() {
+                    return response(req, STATUS_END_OF_FILE, session_id, &[9, 0]);
+                }
+                let chunk = &data[offset..(offset + len).min(data.len())];
+                let mut body = vec![17, 0, 80, 0];
+                body.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+                body.extend_from_slice(&[0u8; 8]);
+                body.extend_from_slice(chunk);
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            WRITE => {
+                let data_offset = u16_at(req, b + 2) as usize;
+                let len = u32_at(req, b + 4) as usize;
+                let offset = u64_at(req, b + 8) as usize;
+                let file_id: [u8; 16] = req[b + 16..b + 32].try_into().unwrap();
+                let node = nodes.get_mut(&session.handles[&file_id].path).unwrap();
+                if node.data.len() < offset + len {
+                    node.data.resize(offset + len, 0);
+                }
+                node.data[offset..offset + len]
+                    .copy_from_slice(&req[data_offset..data_offset + len]);
+                let mut body = vec![17, 0, 0, 0];
+                body.extend_from_slice(&(len as u32).to_le_bytes());
+                body.extend_from_slice(&[0u8; 8]);
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            QUERY_DIRECTORY => {
+                let file_id: [u8; 16] = req[b + 8..b + 24].try_into().unwrap();
+                let handle = session.handles.get_mut(&file_id).unwrap();
+                if handle.listed {
+                    return response(req, STATUS_NO_MORE_FILES, session_id, &[9, 0]);
+                }
+                handle.listed = true;
+
+                let mut names = vec![(
+                    ".".to_string(),
+                    Node {
+                        is_dir: true,
+                        ..Node::default()
+                    },
+                )];
+                names.extend(
+                    children(&nodes, &handle.path)
+                        .into_iter()
+                        .map(|(p, n)| (p.rsplit('\\').next().unwrap().to_string(), n.clone())),
+                );
+                let mut buffer = Vec::new();
+                for (i, (name, node)) in names.iter().enumerate() {
+                    let name = utf16le(name);
+                    let mut entry = vec![0u8; 64];
+                    entry[24..32].copy_from_slice(&node.mtime.to_le_bytes());
+                    entry[40..48].copy_from_slice(&(node.data.len() as u64).to_le_bytes());
+                    let attributes = if node.is_dir {
+                        FILE_ATTRIBUTE_DIRECTORY
+                    } else {
+                        FILE_ATTRIBUTE_NORMAL
+                    };
+                    entry[56..60].copy_from_slice(&attributes.to_le_bytes());
+                    entry[60..64].copy_from_slice(&(name.len() as u32).to_le_bytes());
+                    entry.extend_from_slice(&name);
+                    entry.resize(entry.len().div_ceil(8) * 8, 0);
+                    if i + 1 < names.len() {
+                        let next = entry.len() as u32;
+                        entry[0..4].copy_from_slice(&next.to_le_bytes());
+                    }
+                    buffer.extend_from_slice(&entry);
+                }
+                let mut body = vec![9, 0, 72, 0];
+                body.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+                body.extend_from_slice(&buffer);
+                response(req, STATUS_SUCCESS, session_id, &body)
+            }
+            SET_INFO => {
+                let class = req[b + 3];
+                let buffer_offset = u16_at(req, b + 8) as usize;
+                let file_id: [u8; 16] = req[b + 16..b + 32].try_into().unwrap();
+                let info = &req[buffer_offset..];
+                let path = session.handles[&file_id].path.clone();
+                match class {
+                    FILE_BASIC_INFORMATION => {
+                        let mtime = u64_at(info, 16);
+                        if mtime != 0 {
+                            nodes.get_mut(&path).unwrap().mtime = mtime;
+                        }
+                    }
+                    FILE_RENAME_INFORMATION => {
+                        let len = u32_at(info, 16) as usize;
+                        let target = utf16_string(&info[20..20 + len]);
+                        let moved: Vec<String> = nodes
+                            .keys()
+                            .filter(|p| **p == path || p.starts_with(&format!("{}\\", path)))
+                            .cloned()
+                            .collect();
+                        for old in moved {
+                            let node = nodes.remove(&old).unwrap();
+                            nodes.insert(format!("{}{}", target, &old[path.len()..]), node);
+                        }
+                        session.handles.get_mut(&file_id).unwrap().path = target;
+                    }
+                    _ => unreachable!("unexpected info class {}", class),
+                }
+                response(req, STATUS_SUCCESS, session_id, &[2, 0])
+            }
+            _ => unreachable!("unexpected command {}", command),
+        }
+    }
+
+    async fn start_mock_server() -> (SocketAddr, Share) {
+        let share: Share = Arc::new(StdMutex::new(BTreeMap::new()));
+        {
+            let mut nodes = share.lock().unwrap();
+            for dir in ["", "backup"] {
+                nodes.insert(
+                    dir.to_string(),
+                    Node {
+                        is_dir: true,
+                        ..Node::default()
+                    },
+                );
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = share.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let share = state.clone();
+                tokio::spawn(async move {
+                    let mut session = Session::default();
+                    loop {
+                        let mut prefix = [0u8; 4];
+                        if stream.read_exact(&mut prefix).await.is_err() {
+                            return;
+                        }
+                        let len = u32::from_be_bytes(prefix) as usize;
+                        let mut req = vec![0u8; len];
+                        stream.read_exact(&mut req).await.unwrap();
+                        let resp = handle_request(&share, &mut session, &req);
+                        let mut frame = (resp.len() as u32).to_be_bytes().to_vec();
+                        frame.extend_from_slice(&resp);
+                        stream.write_all(&frame).await.unwrap();
+                    }
+                });
+            }
+        });
+        (addr, share)
+    }
+
+    fn account(addr: SocketAddr, password: &str) -> AccountConfig {
+        let credentials = [
+            ("server", addr.to_string()),
+            ("share", "data".to_string()),
+            ("username", "alice".to_string()),
+            ("password", password.to_string()),
+            ("domain", "WORKGROUP".to_string()),
+            ("root", "/backup".to_string()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        AccountConfig {
+            id: "smb_test".to_string(),
+            provider: ProviderType::SMB,
+            name: "nas".to_string(),
+            credentials,
+            rate_limit: None,
+            retry_policy: RetryPolicy::default(),
+            resource_limits: None,
+        }
+    }
+
+    #[test]
+    fn test_smb_path_and_filetime() {
+        assert_eq!(split_components("/a//./b/").unwrap(), vec!["a", "b"]);
+        assert!(split_components("/a/../..").is_err());
+        assert_eq!(
+            filetime_to_unix(unix_to_filetime(1_600_000_000)),
+            1_600_000_000
+        );
+        assert_eq!(unix_to_filetime(0), 116_444_736_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_signed_session_file_operations() {
+        let (addr, share) = start_mock_server().await;
+        let provider = SmbProvider::new(&account(addr, PASSWORD)).await.unwrap();
+        provider.verify().await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("smb_test_{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let local = dir.join("data.bin");
+        // 超过单次读写上限，覆盖分块读写
+        let content: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+        tokio::fs::write(&local, &content).await.unwrap();
+
+        let result = provider.upload(&local, "/docs/a.bin").await.unwrap();
+        assert_eq!(result.bytes_uploaded, content.len() as u64);
+        assert_eq!(share.lock().unwrap()["backup\\docs\\a.bin"].data, content);
+
+        let root = provider.list("/").await.unwrap();
+        assert_eq!(root.len(), 1);
+        assert_eq!(root[0].path, "/docs");
+        assert!(root[0].is_dir);
+        let docs = provider.list("/docs").await.unwrap();
+        assert_eq!(docs[0].path, "/docs/a.bin");
+        assert_eq!(docs[0].size, content.len() as u64);
+
+        provider
+            .set_mtime("/docs/a.bin", 1_600_000_000)
+            .await
+            .unwrap();
+        let info = provider.stat("/docs/a.bin").await.unwrap();
+        assert_eq!(info.modified, 1_600_000_000);
+        assert!(!info.is_dir);
+
+        let downloaded = dir.join("downloaded.bin");
+        provider.download("/docs/a.bin", &downloaded).await.unwrap();
+        assert_eq!(tokio::fs::read(&downloaded).await.unwrap(), content);
+
+        provider
+            .move_path("/docs/a.bin", "/moved/b.bin")
+            .await
+            .unwrap();
+        assert!(!provider.exists("/docs/a.bin").await.unwrap());
+        assert!(provider.exists("/moved/b.bin").await.unwrap());
+
+        provider.delete("/moved").await.unwrap();
+        provider.delete("/moved").await.unwrap();
+        assert!(!provider.exists("/moved").await.unwrap());
+        assert!(provider.delete("/").await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_is_auth_error() {
+        let (addr, _) = start_mock_server().await;
+        let provider = SmbProvider::new(&account(addr, "wrong")).await.unwrap();
+        assert!(matches!(
+            provider.verify().await,
+            Err(SyncError::Provider(ProviderError::AuthFailed(_)))
+        ));
+    }
+}
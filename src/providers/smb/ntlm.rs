@@ -0,0 +1,308 @@
+//! NTLMv2 认证消息与 SPNEGO 封装
+//!
+//! 只实现 SMB 会话建立所需的部分：构造 NEGOTIATE/AUTHENTICATE 消息、解析 CHALLENGE 消息，
+//! 并计算会话基础密钥用于签名。参见 MS-NLMP 与 RFC 4178。
+
+use super::client::utf16le;
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_SIGN: u32 = 0x0000_0010;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+const NEGOTIATE_128: u32 = 0x2000_0000;
+const NEGOTIATE_56: u32 = 0x8000_0000;
+
+/// 客户端请求的协商标志（不使用密钥交换，会话密钥即会话基础密钥）
+const CLIENT_FLAGS: u32 = NEGOTIATE_UNICODE
+    | REQUEST_TARGET
+    | NEGOTIATE_SIGN
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY
+    | NEGOTIATE_TARGET_INFO
+    | NEGOTIATE_128
+    | NEGOTIATE_56;
+
+/// AV_PAIR 中的时间戳类型
+const MSV_AV_TIMESTAMP: u16 = 7;
+const MSV_AV_EOL: u16 = 0;
+
+/// SPNEGO 与 NTLMSSP 的 OID（DER 编码）
+const SPNEGO_OID: &[u8] = &[0x06, 0x06, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+const NTLMSSP_OID: &[u8] = &[
+    0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a,
+];
+
+/// 服务端 CHALLENGE 消息中需要的字段
+#[derive(Debug, Clone)]
+pub(super) struct Challenge {
+    pub server_challenge: [u8; 8],
+    pub flags: u32,
+    pub target_info: Vec<u8>,
+}
+
+/// 认证所需的用户信息
+pub(super) struct NtlmCredentials<'a> {
+    pub username: &'a str,
+    pub password: &'a str,
+    pub domain: &'a str,
+}
+
+/// 构造 NEGOTIATE 消息
+pub(super) fn negotiate_message() -> Vec<u8> {
+    let mut msg = Vec::with_capacity(32);
+    msg.extend_from_slice(SIGNATURE);
+    msg.extend_from_slice(&1u32.to_le_bytes());
+    msg.extend_from_slice(&CLIENT_FLAGS.to_le_bytes());
+    // DomainNameFields 与 WorkstationFields 为空
+    msg.extend_from_slice(&[0u8; 16]);
+    msg
+}
+
+/// 从安全缓冲区中找到并解析 CHALLENGE 消息，SPNEGO 封装会被跳过
+pub(super) fn parse_challenge(buffer: &[u8]) -> Option<Challenge> {
+    let start = buffer
+        .windows(SIGNATURE.len())
+        .position(|w| w == SIGNATURE)?;
+    let msg = &buffer[start..];
+    if u32::from_le_bytes(msg.get(8..12)?.try_into().ok()?) != 2 {
+        return None;
+    }
+    let flags = u32::from_le_bytes(msg.get(20..24)?.try_into().ok()?);
+    let server_challenge = msg.get(24..32)?.try_into().ok()?;
+    let info_len = u16::from_le_bytes(msg.get(40..42)?.try_into().ok()?) as usize;
+    let info_offset = u32::from_le_bytes(msg.get(44..48)?.try_into().ok()?) as usize;
+    let target_info = msg.get(info_offset..info_offset + info_len)?.to_vec();
+
+    Some(Challenge {
+        server_challenge,
+        flags,
+        target_info,
+    })
+}
+
+/// 在 AV_PAIR 列表中查找服务端时间戳
+fn av_timestamp(target_info: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    while pos + 4 <= target_info.len() {
+        let id = u16::from_le_bytes([target_info[pos], target_info[pos + 1]]);
+        let len = u16::from_le_bytes([target_info[pos + 2], target_info[pos + 3]]) as usize;
+        let value = target_info.get(pos + 4..pos + 4 + len)?;
+        match id {
+            MSV_AV_EOL => return None,
+            MSV_AV_TIMESTAMP => return Some(u64::from_le_bytes(value.try_into().ok()?)),
+            _ => pos += 4 + len,
+        }
+    }
+    None
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// NTOWFv2 = HMAC_MD5(MD4(UNICODE(password)), UNICODE(UPPER(user) + domain))
+pub(super) fn ntowf_v2(creds: &NtlmCredentials) -> [u8; 16] {
+    let nt_hash = Md4::digest(utf16le(creds.password));
+    let identity = utf16le(&format!(
+        "{}{}",
+        creds.username.to_uppercase(),
+        creds.domain
+    ));
+    hmac_md5(&nt_hash, &[&identity])
+}
+
+/// NTLMv2 响应中跟在 NTProofStr 之后的客户端数据
+fn client_blob(timestamp: u64, client_challenge: &[u8; 8], target_info: &[u8]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(32 + target_info.len());
+    blob.extend_from_slice(&[0x01, 0x01, 0, 0, 0, 0, 0, 0]);
+    blob.extend_from_slice(&timestamp.to_le_bytes());
+    blob.extend_from_slice(client_challenge);
+    blob.extend_from_slice(&[0u8; 4]);
+    blob.extend_from_slice(target_info);
+    blob.extend_from_slice(&[0u8; 4]);
+    blob
+}
+
+/// 计算 NTLMv2 响应，返回 (NtChallengeResponse, SessionBaseKey)
+pub(super) fn ntlmv2_response(
+    creds: &NtlmCredentials,
+    challenge: &Challenge,
+    client_challenge: &[u8; 8],
+    timestamp: u64,
+) -> (Vec<u8>, [u8; 16]) {
+    let key = ntowf_v2(creds);
+    let blob = client_blob(timestamp, client_challenge, &challenge.target_info);
+    let proof = hmac_md5(&key, &[&challenge.server_challenge, &blob]);
+    let session_key = hmac_md5(&key, &[&proof]);
+
+    let mut response = proof.to_vec();
+    response.extend_from_slice(&blob);
+    (response, session_key)
+}
+
+/// 构造 AUTHENTICATE 消息，返回消息与会话密钥
+///
+/// `now` 为当前时间（FILETIME），服务端提供时间戳时优先使用服务端时间。
+pub(super) fn authenticate_message(
+    creds: &NtlmCredentials,
+    challenge: &Challenge,
+    client_challenge: [u8; 8],
+    now: u64,
+) -> (Vec<u8>, [u8; 16]) {
+    let server_timestamp = av_timestamp(&challenge.target_info);
+    let (nt_response, session_key) = ntlmv2_response(
+        creds,
+        challenge,
+        &client_challenge,
+        server_timestamp.unwrap_or(now),
+    );
+    // 服务端提供时间戳时 LMv2 响应必须为全零
+    let lm_response = if server_timestamp.is_some() {
+        vec![0u8; 24]
+    } else {
+        let mut lm = hmac_md5(
+            &ntowf_v2(creds),
+            &[&challenge.server_challenge, &client_challenge],
+        )
+        .to_vec();
+        lm.extend_from_slice(&client_challenge);
+        lm
+    };
+
+    let fields = [
+        lm_response,
+        nt_response,
+        utf16le(creds.domain),
+        utf16le(creds.username),
+        Vec::new(), // Workstation
+        Vec::new(), // EncryptedRandomSessionKey
+    ];
+
+    let mut msg = Vec::with_capacity(64 + fields.iter().map(Vec::len).sum::<usize>());
+    msg.extend_from_slice(SIGNATURE);
+    msg.extend_from_slice(&3u32.to_le_bytes());
+    let mut offset = 64u32;
+    for field in &fields {
+        let len = field.len() as u16;
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&offset.to_le_bytes());
+        offset += field.len() as u32;
+    }
+    msg.extend_from_slice(&(challenge.flags & CLIENT_FLAGS).to_le_bytes());
+    for field in &fields {
+        msg.extend_from_slice(field);
+    }
+    (msg, session_key)
+}
+
+/// DER 编码 TLV
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// 以 SPNEGO NegTokenInit 封装首个 NTLM 消息
+pub(super) fn spnego_init(token: &[u8]) -> Vec<u8> {
+    let mech_types = der(0xa0, &der(0x30, NTLMSSP_OID));
+    let mech_token = der(0xa2, &der(0x04, token));
+    let neg_token_init = der(0xa0, &der(0x30, &[mech_types, mech_token].concat()));
+    der(0x60, &[SPNEGO_OID, &neg_token_init].concat())
+}
+
+/// 以 SPNEGO NegTokenResp 封装后续 NTLM 消息
+pub(super) fn spnego_response(token: &[u8]) -> Vec<u8> {
+    der(0xa1, &der(0x30, &der(0xa2, &der(0x04, token))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MS-NLMP 4.2.4 NTLMv2 认证示例
+    fn example() -> (NtlmCredentials<'static>, Challenge) {
+        let target_info = [
+            &[0x02, 0x00, 0x0c, 0x00][..],
+            &utf16le("Domain"),
+            &[0x01, 0x00, 0x0c, 0x00],
+            &utf16le("Server"),
+            &[0x00, 0x00, 0x00, 0x00],
+        ]
+        .concat();
+        (
+            NtlmCredentials {
+                username: "User",
+                password: "Password",
+                domain: "Domain",
+            },
+            Challenge {
+                server_challenge: [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+                flags: 0xe28a8233,
+                target_info,
+            },
+        )
+    }
+
+    #[test]
+    fn test_ntlmv2_matches_specification_example() {
+        let (creds, challenge) = example();
+        assert_eq!(
+            hex::encode(ntowf_v2(&creds)),
+            "0c868a403bfd7a93a3001ef22ef02e3f"
+        );
+
+        let (response, session_key) = ntlmv2_response(&creds, &challenge, &[0xaa; 8], 0);
+        assert_eq!(
+            hex::encode(&response[..16]),
+            "68cd0ab851e51c96aabc927bebef6a1c"
+        );
+        assert_eq!(hex::encode(session_key), "8de40ccadbc14a82f15cb0ad0de95ca3");
+    }
+
+    #[test]
+    fn test_parse_challenge_inside_spnego() {
+        let (_, challenge) = example();
+        let mut msg = SIGNATURE.to_vec();
+        msg.extend_from_slice(&2u32.to_le_bytes());
+        msg.extend_from_slice(&[0u8; 8]); // TargetNameFields
+        msg.extend_from_slice(&challenge.flags.to_le_bytes());
+        msg.extend_from_slice(&challenge.server_challenge);
+        msg.extend_from_slice(&[0u8; 8]);
+        let len = challenge.target_info.len() as u16;
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&len.to_le_bytes());
+        msg.extend_from_slice(&48u32.to_le_bytes());
+        msg.extend_from_slice(&challenge.target_info);
+
+        let parsed = parse_challenge(&spnego_response(&msg)).unwrap();
+        assert_eq!(parsed.server_challenge, challenge.server_challenge);
+        assert_eq!(parsed.target_info, challenge.target_info);
+        assert_eq!(av_timestamp(&parsed.target_info), None);
+    }
+}
@@ -1,20 +1,153 @@
-use crate::config::AccountConfig;
+use crate::config::{AccountConfig, NetworkSettings};
 use crate::error::{ProviderError, SyncError};
-use crate::providers::{DownloadResult, FileInfo, StorageProvider, UploadResult};
+use crate::providers::{
+    ByteStream, DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, StorageQuota,
+    UploadResult,
+    http::{self, TlsSettings},
+};
+use crate::sync::diff::ChecksumType;
+use crate::sync::resume::ChunkResumeStore;
 use async_trait::async_trait;
 use base64::Engine;
-use reqwest::{Client, Method, StatusCode, Url};
+use futures::StreamExt;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{Body, Client, Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWriteExt};
+use tokio_util::io::{ReaderStream, StreamReader};
 use tracing::{debug, error, info, instrument, warn};
 
+/// PROPFIND 请求的属性列表
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+    <d:prop>
+        <d:displayname/>
+        <d:getcontentlength/>
+        <d:getlastmodified/>
+        <d:getetag/>
+        <d:resourcetype/>
+        <oc:checksums/>
+    </d:prop>
+</d:propfind>"#;
+
+/// RFC 4331 配额查询的属性列表
+const QUOTA_PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+    <d:prop>
+        <d:quota-available-bytes/>
+        <d:quota-used-bytes/>
+    </d:prop>
+</d:propfind>"#;
+
+/// 上传期间持有的独占写锁
+const LOCK_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:lockinfo xmlns:d="DAV:">
+    <d:lockscope><d:exclusive/></d:lockscope>
+    <d:locktype><d:write/></d:locktype>
+    <d:owner>disksync</d:owner>
+</d:lockinfo>"#;
+
+/// 写锁的超时时间，进程异常退出时锁会在此之后自动释放
+const LOCK_TIMEOUT_SECS: u64 = 600;
+
+/// 默认分块大小
+const DEFAULT_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+/// Nextcloud 分块上传 v2 要求除最后一块外每块不小于 5MiB
+const MIN_CHUNK_SIZE: u64 = 5 * 1024 * 1024;
+/// 单次上传最多的分块数
+const MAX_CHUNKS: u64 = 10_000;
+/// 单次下载中断后最多续传的次数
+const MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// Nextcloud/ownCloud 分块上传 v2 使用的地址
+#[derive(Debug, Clone)]
+struct ChunkedUpload {
+    /// `.../remote.php/dav/uploads/{user}`
+    uploads_url: String,
+    /// 与 `base_url` 对应的 `.../remote.php/dav/files/{user}/...`，用作 `Destination`
+    files_url: String,
+}
+
+impl ChunkedUpload {
+    /// 由 Nextcloud/ownCloud 的 WebDAV 地址推导分块上传地址
+    fn from_base_url(base_url: &str, username: &str) -> Option<Self> {
+        let (root, rest) = base_url.split_once("/remote.php/")?;
+        let (user, subpath) = if let Some(rest) = rest.strip_prefix("dav/files/") {
+            let (user, subpath) = rest.split_once('/').unwrap_or((rest, ""));
+            (user.to_string(), subpath)
+        } else if let Some(rest) = rest.strip_prefix("webdav") {
+            (
+                urlencoding::encode(username).to_string(),
+                rest.trim_start_matches('/'),
+            )
+        } else {
+            return None;
+        };
+
+        let mut files_url = format!("{}/remote.php/dav/files/{}", root, user);
+        if !subpath.is_empty() {
+            files_url = format!("{}/{}", files_url, subpath);
+        }
+        Some(Self {
+            uploads_url: format!("{}/remote.php/dav/uploads/{}", root, user),
+            files_url,
+        })
+    }
+}
+
+/// 设置远端修改时间的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MtimeMethod {
+    /// RFC 4918 PROPPATCH `DAV:getlastmodified`
+    PropPatch,
+    /// Nextcloud/ownCloud：上传时带 `X-OC-Mtime`，之后 PROPPATCH `DAV:lastmodified`（Unix 秒）
+    OwnCloud,
+}
+
 /// WebDAV 存储提供商
+///
+/// 凭证中的可选项：
+/// - `depth_infinity`：设为 `false` 可禁止使用 `Depth: infinity` 递归列举
+/// - `mtime`：`proppatch`、`oc` 或 `none`，地址包含 `/remote.php/` 时默认为 `oc`
+/// - `checksum`：服务器通过 `oc:checksums` 提供的校验和类型（`sha1`、`md5`、`sha256`），
+///   设置后 `FileInfo.hash` 为该校验和，否则为 ETag
+/// - `chunked_upload`：大于分块大小的文件是否使用 Nextcloud 分块上传，
+///   地址包含 `/remote.php/` 时默认为 `true`
 pub struct WebDavProvider {
     client: Client,
     base_url: String,
     path_prefix: String,
     username: String,
     password: String,
+    /// 是否尝试 `Depth: infinity`，服务器拒绝后置为 false
+    depth_infinity: AtomicBool,
+    mtime_method: Option<MtimeMethod>,
+    /// 服务器拒绝设置修改时间后置为 false
+    can_set_mtime: AtomicBool,
+    checksum: Option<ChecksumType>,
+    chunked_upload: Option<ChunkedUpload>,
+    chunk_size: u64,
+    resume_store: Option<Arc<ChunkResumeStore>>,
+    /// 上传前是否加写锁，服务器不支持 LOCK 时置为 false
+    lock_uploads: AtomicBool,
+    /// 重建 HTTP 客户端时沿用的账户 TLS 设置
+    tls: TlsSettings,
+}
+
+/// PROPFIND 多状态响应中单个 `<response>` 的解析结果
+#[derive(Default)]
+struct PropfindEntry {
+    href: Option<String>,
+    size: u64,
+    is_collection: bool,
+    modified: Option<i64>,
+    etag: Option<String>,
+    checksums: Option<String>,
 }
 
 impl WebDavProvider {
@@ -40,19 +173,63 @@ impl WebDavProvider {
 
         debug!(url = %url, username = %username, "解析 WebDAV 凭证");
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .map_err(|e| {
-                error!(error = %e, "创建 HTTP 客户端失败");
-                ProviderError::ConnectionFailed(e.to_string())
-            })?;
+        let tls = TlsSettings::from_credentials(&config.credentials)?;
+        let client = http::build_client(&NetworkSettings::default(), &tls).inspect_err(|e| {
+            error!(error = %e, "创建 HTTP 客户端失败");
+        })?;
 
         let parsed_url = Url::parse(url).map_err(|e| {
             error!(error = %e, "URL 解析失败");
             ProviderError::ConnectionFailed(format!("Invalid URL: {}", e))
         })?;
 
+        let mtime_method = match config.credentials.get("mtime").map(String::as_str) {
+            Some("proppatch") => Some(MtimeMethod::PropPatch),
+            Some("oc") => Some(MtimeMethod::OwnCloud),
+            Some("none") => None,
+            None if parsed_url.path().contains("/remote.php/") => Some(MtimeMethod::OwnCloud),
+            None => Some(MtimeMethod::PropPatch),
+            Some(other) => {
+                return Err(ProviderError::InvalidCredentials(format!(
+                    "Unknown mtime method: {}",
+                    other
+                )));
+            }
+        };
+
+        let checksum = match config.credentials.get("checksum").map(|v| v.to_lowercase()) {
+            None => None,
+            Some(v) if v == "sha1" => Some(ChecksumType::Sha1),
+            Some(v) if v == "md5" => Some(ChecksumType::Md5),
+            Some(v) if v == "sha256" => Some(ChecksumType::Sha256),
+            Some(other) => {
+                return Err(ProviderError::InvalidCredentials(format!(
+                    "Unsupported checksum type: {}",
+                    other
+                )));
+            }
+        };
+
+        let base_url = url.trim_end_matches('/').to_string();
+        let chunked_upload = match config.credentials.get("chunked_upload").map(String::as_str) {
+            Some("false") => None,
+            Some("true") => Some(
+                ChunkedUpload::from_base_url(&base_url, username).ok_or_else(|| {
+                    ProviderError::InvalidCredentials(
+                        "Chunked upload requires a Nextcloud/ownCloud remote.php URL".to_string(),
+                    )
+                })?,
+            ),
+            _ => ChunkedUpload::from_base_url(&base_url, username),
+        };
+        // 分块大小沿用账户限流配置中的 chunk_size，但不能低于服务器的下限
+        let chunk_size = config
+            .rate_limit
+            .as_ref()
+            .map(|r| r.chunk_size as u64)
+            .unwrap_or(DEFAULT_CHUNK_SIZE)
+            .max(MIN_CHUNK_SIZE);
+
         let path_prefix = urlencoding::decode(parsed_url.path())
             .unwrap_or(std::borrow::Cow::Borrowed(parsed_url.path()))
             .trim_end_matches('/')
@@ -62,26 +239,48 @@ impl WebDavProvider {
 
         Ok(Self {
             client,
-            base_url: url.trim_end_matches('/').to_string(),
+            base_url,
             path_prefix,
             username: username.clone(),
             password: password.clone(),
+            depth_infinity: AtomicBool::new(
+                config
+                    .credentials
+                    .get("depth_infinity")
+                    .map(|v| v != "false")
+                    .unwrap_or(true),
+            ),
+            mtime_method,
+            can_set_mtime: AtomicBool::new(mtime_method.is_some()),
+            checksum,
+            chunked_upload,
+            chunk_size,
+            resume_store: None,
+            lock_uploads: AtomicBool::new(
+                config.credentials.get("lock").is_some_and(|v| v == "true"),
+            ),
+            tls,
         })
     }
 
-    /// 获取完整的 URL
-    fn get_full_url(&self, path: &str) -> String {
-        let path = path.trim_start_matches('/');
-
-        // Split path into components and encode each one
-        let encoded_path: Vec<String> = path
-            .split('/')
-            .map(|component| urlencoding::encode(component).to_string())
-            .collect();
+    /// 按全局网络设置（代理、超时、请求头等）重建 HTTP 客户端
+    pub fn with_network_settings(
+        mut self,
+        network: &NetworkSettings,
+    ) -> Result<Self, ProviderError> {
+        self.client = http::build_client(network, &self.tls)?;
+        Ok(self)
+    }
 
-        let encoded_path_str = encoded_path.join("/");
+    /// 记录分块上传进度，中断的上传可从断点继续
+    pub fn with_resume_store(mut self, store: Arc<ChunkResumeStore>) -> Self {
+        self.resume_store = Some(store);
+        self
+    }
 
-        let url = format!("{}/{}", self.base_url, encoded_path_str);
+    /// 获取完整的 URL
+    fn get_full_url(&self, path: &str) -> String {
+        let url = format!("{}/{}", self.base_url, encode_path(path));
         debug!(path = %path, url = %url, "构建完整 URL");
         url
     }
@@ -93,146 +292,596 @@ impl WebDavProvider {
         format!("Basic {}", encoded)
     }
 
-    /// 解析 WebDAV PROPFIND 响应
-    #[instrument(skip(self, xml), fields(base_path = %base_path))]
-    fn parse_propfind_response(
+    /// 发送 MOVE/COPY 请求，`Destination` 为目标的完整 URL
+    async fn transfer_on_server(
+        &self,
+        method: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<(), SyncError> {
+        let url = self.get_full_url(from);
+        let destination = self.get_full_url(to);
+
+        debug!(method = %method, destination = %destination, "发送服务端传输请求");
+        let response = self
+            .client
+            .request(Method::from_bytes(method.as_bytes()).unwrap(), &url)
+            .header("Authorization", self.create_auth_header())
+            .header("Destination", destination)
+            .header("Overwrite", "T")
+            .send()
+            .await
+            .map_err(|e| {
+                error!(error = %e, "{} 请求失败", method);
+                SyncError::Network(e)
+            })?;
+        let response = http::check_throttled(response)?;
+
+        let status = response.status();
+        debug!(status = %status, "收到 {} 响应", method);
+
+        if status.is_success() {
+            Ok(())
+        } else if status == StatusCode::NOT_FOUND {
+            Err(SyncError::Provider(ProviderError::FileNotFound(
+                from.to_string(),
+            )))
+        } else {
+            Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "{} failed: {}",
+                method, status
+            ))))
+        }
+    }
+
+    /// 上传文件，启用 `lock` 时在上传期间对目标加写锁
+    async fn put(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+        mtime: Option<i64>,
+    ) -> Result<UploadResult, SyncError> {
+        let lock_token = if self.lock_uploads.load(Ordering::Relaxed) {
+            self.lock(remote_path).await?
+        } else {
+            None
+        };
+
+        let result = self
+            .put_unlocked(reader, size, remote_path, mtime, lock_token.as_deref())
+            .await;
+
+        if let Some(token) = &lock_token {
+            self.unlock(remote_path, token).await;
+        }
+        result
+    }
+
+    /// PUT 上传，Nextcloud/ownCloud 模式下通过 `X-OC-Mtime` 同时设置修改时间
+    ///
+    /// `lock_token` 为目标上已持有的锁，随请求通过 `If` 头提交。
+    async fn put_unlocked(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+        mtime: Option<i64>,
+        lock_token: Option<&str>,
+    ) -> Result<UploadResult, SyncError> {
+        if let Some(chunked) = &self.chunked_upload
+            && size > self.chunk_size
+        {
+            return self
+                .chunked_put(chunked, reader, size, remote_path, mtime, lock_token)
+                .await;
+        }
+
+        let url = self.get_full_url(remote_path);
+        let start_time = SystemTime::now();
+
+        let body = Body::wrap_stream(ReaderStream::new(reader));
+
+        // 上传文件
+        let mut request = self
+            .client
+            .put(&url)
+            .header("Authorization", self.create_auth_header())
+            .header(CONTENT_LENGTH, size);
+        if self.mtime_method == Some(MtimeMethod::OwnCloud)
+            && let Some(mtime) = mtime
+        {
+            request = request.header("X-OC-Mtime", mtime);
+        }
+        if let Some(token) = lock_token {
+            request = request.header("If", format!("(<{}>)", token));
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        let response = http::check_throttled(response)?;
+
+        if response.status() == StatusCode::LOCKED {
+            return Err(SyncError::Conflict(format!(
+                "Resource is locked: {}",
+                remote_path
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(SyncError::Provider(http::status_error(
+                response.status(),
+                "Upload",
+            )));
+        }
+
+        let elapsed = SystemTime::now()
+            .duration_since(start_time)
+            .unwrap_or(Duration::from_secs(0));
+
+        Ok(UploadResult {
+            bytes_uploaded: size,
+            file_size: size,
+            checksum: None,
+            elapsed_time: elapsed,
+            compression: None,
+        })
+    }
+
+    /// Nextcloud 分块上传 v2：MKCOL 上传目录、逐块 PUT、MOVE `.file` 到目标
+    ///
+    /// 每个分块上传成功后写入断点续传记录；再次上传同一文件时重新读取数据并与
+    /// 记录的 SHA-256 比较，一致的分块直接跳过。
+    #[instrument(skip(self, chunked, reader), fields(remote_path = %remote_path, size = %size))]
+    async fn chunked_put(
+        &self,
+        chunked: &ChunkedUpload,
+        mut reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+        mtime: Option<i64>,
+        lock_token: Option<&str>,
+    ) -> Result<UploadResult, SyncError> {
+        let start_time = SystemTime::now();
+        let chunk_size = self.chunk_size.max(size.div_ceil(MAX_CHUNKS));
+        let destination = format!("{}/{}", chunked.files_url, encode_path(remote_path));
+        let upload_key = format!("webdav:{}:{}", destination, size);
+        let store = self.resume_store.as_deref();
+
+        let session = match store {
+            Some(store) => store
+                .session(&upload_key)?
+                .filter(|s| s.chunk_size == chunk_size),
+            None => None,
+        };
+        let (upload_id, uploaded_chunks) = match session {
+            Some(session) => {
+                info!(
+                    upload_id = %session.upload_id,
+                    chunks = session.chunks.len(),
+                    "继续未完成的分块上传"
+                );
+                (session.upload_id, session.chunks)
+            }
+            None => {
+                let upload_id = format!("disksync-{}", uuid::Uuid::new_v4().simple());
+                let response = self
+                    .client
+                    .request(
+                        Method::from_bytes(b"MKCOL").unwrap(),
+                        format!("{}/{}", chunked.uploads_url, upload_id),
+                    )
+                    .header("Authorization", self.create_auth_header())
+                    .header("Destination", &destination)
+                    .send()
+                    .await
+                    .map_err(SyncError::Network)?;
+                let response = http::check_throttled(response)?;
+                if !response.status().is_success() {
+                    return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                        "Failed to create upload directory: {}",
+                        response.status()
+                    ))));
+                }
+                if let Some(store) = store {
+                    store.start_session(&upload_key, &upload_id, chunk_size)?;
+                }
+                (upload_id, HashMap::new())
+            }
+        };
+        let upload_url = format!("{}/{}", chunked.uploads_url, upload_id);
+
+        // 服务器上的上传目录已过期时清除记录，下次从头上传
+        let expired = || -> SyncError {
+            if let Some(store) = store
+                && let Err(e) = store.finish_session(&upload_key)
+            {
+                warn!(error = %e, "清除分块上传记录失败");
+            }
+            SyncError::Provider(ProviderError::ApiError(format!(
+                "Upload session {} no longer exists on server",
+                upload_id
+            )))
+        };
+
+        let mut bytes_uploaded = 0;
+        let mut remaining = size;
+        let mut index = 1u32;
+        while remaining > 0 {
+            let len = remaining.min(chunk_size);
+            let mut chunk = vec![0u8; len as usize];
+            reader.read_exact(&mut chunk).await?;
+            let checksum = hex::encode(Sha256::digest(&chunk));
+
+            if uploaded_chunks.get(&index) == Some(&checksum) {
+                debug!(index = index, "分块已上传，跳过");
+            } else {
+                let response = self
+                    .client
+                    .put(format!("{}/{}", upload_url, index))
+                    .header("Authorization", self.create_auth_header())
+                    .header("Destination", &destination)
+                    .header("OC-Total-Length", size)
+                    .header(CONTENT_LENGTH, len)
+                    .body(chunk)
+                    .send()
+                    .await
+                    .map_err(SyncError::Network)?;
+                let response = http::check_throttled(response)?;
+                let status = response.status();
+                if status == StatusCode::NOT_FOUND {
+                    return Err(expired());
+                }
+                if !status.is_success() {
+                    return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                        "Chunk {} upload failed: {}",
+                        index, status
+                    ))));
+                }
+                if let Some(store) = store {
+                    store.record_chunk(&upload_key, index, &checksum)?;
+                }
+                bytes_uploaded += len;
+                debug!(index = index, size = len, "分块上传完成");
+            }
+
+            remaining -= len;
+            index += 1;
+        }
+
+        // 组装分块
+        let mut request = self
+            .client
+            .request(
+                Method::from_bytes(b"MOVE").unwrap(),
+                format!("{}/.file", upload_url),
+            )
+            .header("Authorization", self.create_auth_header())
+            .header("Destination", &destination)
+            .header("OC-Total-Length", size)
+            .header("Overwrite", "T");
+        if self.mtime_method == Some(MtimeMethod::OwnCloud)
+            && let Some(mtime) = mtime
+        {
+            request = request.header("X-OC-Mtime", mtime);
+        }
+        if let Some(token) = lock_token {
+            // 请求地址是上传目录，锁属于 Destination，需用带资源标记的形式
+            request = request.header("If", format!("<{}> (<{}>)", destination, token));
+        }
+        let response = request.send().await.map_err(SyncError::Network)?;
+        let response = http::check_throttled(response)?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(expired());
+        }
+        if status == StatusCode::LOCKED {
+            return Err(SyncError::Conflict(format!(
+                "Resource is locked: {}",
+                remote_path
+            )));
+        }
+        if !status.is_success() {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Assembling chunks failed: {}",
+                status
+            ))));
+        }
+        if let Some(store) = store {
+            store.finish_session(&upload_key)?;
+        }
+
+        info!(
+            chunks = index - 1,
+            bytes_uploaded = bytes_uploaded,
+            "分块上传完成"
+        );
+        Ok(UploadResult {
+            bytes_uploaded,
+            file_size: size,
+            checksum: None,
+            elapsed_time: SystemTime::now()
+                .duration_since(start_time)
+                .unwrap_or(Duration::from_secs(0)),
+            compression: None,
+        })
+    }
+
+    /// 对目标加独占写锁，返回锁令牌
+    ///
+    /// 目标已被其他客户端锁定时返回 `Conflict`；服务器不支持 LOCK 时记录警告，
+    /// 之后的上传不再加锁。
+    async fn lock(&self, path: &str) -> Result<Option<String>, SyncError> {
+        let url = self.get_full_url(path);
+        debug!(url = %url, "发送 LOCK 请求");
+        let response = self
+            .client
+            .request(Method::from_bytes(b"LOCK").unwrap(), &url)
+            .header("Authorization", self.create_auth_header())
+            .header("Depth", "0")
+            .header("Timeout", format!("Second-{}", LOCK_TIMEOUT_SECS))
+            .header("Content-Type", "application/xml")
+            .body(LOCK_BODY)
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        let response = http::check_throttled(response)?;
+
+        let status = response.status();
+        if status == StatusCode::LOCKED {
+            return Err(SyncError::Conflict(format!(
+                "Resource is locked by another client: {}",
+                path
+            )));
+        }
+        if matches!(
+            status,
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            warn!(status = %status, "服务器不支持 LOCK，上传时不再加锁");
+            self.lock_uploads.store(false, Ordering::Relaxed);
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "LOCK failed: {}",
+                status
+            ))));
+        }
+
+        // RFC 4918 要求新建锁的响应带 Lock-Token 头，值形如 `<urn:uuid:...>`
+        let token = response
+            .headers()
+            .get("Lock-Token")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| {
+                SyncError::Provider(ProviderError::ApiError(
+                    "LOCK response has no Lock-Token header".to_string(),
+                ))
+            })?;
+        debug!(token = %token, "已加写锁");
+        Ok(Some(token))
+    }
+
+    /// 释放写锁，失败时只记录警告，锁会在超时后自动释放
+    async fn unlock(&self, path: &str, token: &str) {
+        let url = self.get_full_url(path);
+        let result = self
+            .client
+            .request(Method::from_bytes(b"UNLOCK").unwrap(), &url)
+            .header("Authorization", self.create_auth_header())
+            .header("Lock-Token", format!("<{}>", token))
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                warn!(path = %path, status = %response.status(), "释放写锁失败");
+            }
+            Err(e) => warn!(path = %path, error = %e, "释放写锁失败"),
+        }
+    }
+
+    /// 发送 PROPFIND 请求，`body` 为要查询的属性列表
+    async fn propfind(
         &self,
-        xml: &str,
-        base_path: &str,
+        path: &str,
+        depth: &str,
+        body: &'static str,
+    ) -> Result<reqwest::Response, SyncError> {
+        let url = self.get_full_url(path);
+        debug!(url = %url, depth = %depth, "发送 PROPFIND 请求");
+
+        let response = self
+            .client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
+            .header("Authorization", self.create_auth_header())
+            .header("Depth", depth)
+            .header("Content-Type", "application/xml")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                error!(error = %e, "PROPFIND 请求失败");
+                SyncError::Network(e)
+            })?;
+        Ok(http::check_throttled(response)?)
+    }
+
+    /// 将 href 转换为提供商内的路径
+    fn href_to_path(&self, href: &str) -> String {
+        let decoded_href = urlencoding::decode(href).unwrap_or(std::borrow::Cow::Borrowed(href));
+        let mut path = decoded_href.to_string();
+
+        if path.starts_with(&self.base_url) {
+            path = path.trim_start_matches(&self.base_url).to_string();
+        } else if path.starts_with(&self.path_prefix) {
+            path = path.trim_start_matches(&self.path_prefix).to_string();
+        }
+
+        // 确保路径以 / 开头（如果是根目录下的文件）
+        if !path.starts_with('/') {
+            path = format!("/{}", path);
+        }
+        path
+    }
+
+    /// 由 ETag 与 `oc:checksums` 得到 `FileInfo.hash`
+    ///
+    /// 配置了校验和类型时只返回该类型的校验和，保证两端可以直接比较；否则返回 ETag。
+    fn file_hash(&self, etag: Option<String>, checksums: Option<&str>) -> Option<String> {
+        let Some(kind) = self.checksum else {
+            return etag;
+        };
+        let prefix = match kind {
+            ChecksumType::Sha1 => "SHA1:",
+            ChecksumType::Sha256 => "SHA256:",
+            _ => "MD5:",
+        };
+        // 格式如 "SHA1:abc MD5:def ADLER32:123"
+        checksums?.split_whitespace().find_map(|c| {
+            c.get(..prefix.len())
+                .filter(|p| p.eq_ignore_ascii_case(prefix))
+                .map(|_| c[prefix.len()..].to_lowercase())
+        })
+    }
+
+    /// 解析 WebDAV PROPFIND 响应，返回的条目包含被查询的路径本身
+    ///
+    /// 边读取响应体边解析，`Depth: infinity` 的响应体可能很大，不会整体读入内存。
+    #[instrument(skip(self, body))]
+    async fn parse_propfind_response<R: AsyncBufRead + Unpin>(
+        &self,
+        body: R,
     ) -> Result<Vec<FileInfo>, SyncError> {
         debug!("开始解析 PROPFIND 响应");
         use quick_xml::events::Event;
         use quick_xml::reader::Reader;
 
+        let invalid = |e: &dyn std::fmt::Display| {
+            error!("Error parsing XML: {}", e);
+            SyncError::Provider(ProviderError::ApiError(format!(
+                "Invalid PROPFIND response: {}",
+                e
+            )))
+        };
+
         let mut files = Vec::new();
-        let mut reader = Reader::from_str(xml);
+        let mut reader = Reader::from_reader(body);
         reader.config_mut().trim_text(true);
 
         let mut buf = Vec::new();
-
-        let mut current_path: Option<String> = None;
-        let mut current_size: u64 = 0;
-        let mut is_collection = false;
-
-        // 状态标记
-        let mut in_response = false;
-        let mut in_href = false;
-        let mut in_prop = false;
-        let mut in_getcontentlength = false;
-        let mut in_resourcetype = false;
-        let _in_collection = false;
+        // 当前所在元素的本地名称栈
+        let mut stack: Vec<String> = Vec::new();
+        let mut text = String::new();
+        let mut entry: Option<PropfindEntry> = None;
 
         loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(ref e)) => {
-                    let name = e.name();
-                    let name_str = String::from_utf8_lossy(name.as_ref()).to_lowercase();
-
-                    if name_str.ends_with("response") {
-                        in_response = true;
-                        current_path = None;
-                        current_size = 0;
-                        is_collection = false;
-                    } else if in_response {
-                        if name_str.ends_with("href") {
-                            in_href = true;
-                        } else if name_str.ends_with("prop") {
-                            in_prop = true;
-                        } else if in_prop {
-                            if name_str.ends_with("getcontentlength") {
-                                in_getcontentlength = true;
-                            } else if name_str.ends_with("resourcetype") {
-                                in_resourcetype = true;
-                            } else if in_resourcetype && name_str.ends_with("collection") {
-                                is_collection = true;
-                            }
-                        }
+            match reader
+                .read_event_into_async(&mut buf)
+                .await
+                .map_err(|e| invalid(&e))?
+            {
+                Event::Start(e) => {
+                    let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                    if name == "response" {
+                        entry = Some(PropfindEntry::default());
                     }
+                    stack.push(name);
+                    text.clear();
                 }
-                Ok(Event::Empty(ref e)) => {
-                    let name = e.name();
-                    let name_str = String::from_utf8_lossy(name.as_ref()).to_lowercase();
-                    if in_resourcetype && name_str.ends_with("collection") {
-                        is_collection = true;
+                Event::Empty(e) => {
+                    if e.local_name().as_ref() == b"collection"
+                        && stack.last().is_some_and(|n| n == "resourcetype")
+                        && let Some(entry) = entry.as_mut()
+                    {
+                        entry.is_collection = true;
                     }
                 }
-                Ok(Event::Text(e)) => {
-                    if in_href {
-                        // Workaround for unescape compilation error: use raw string conversion
-                        // This assumes standard URLs without complex XML entities needing unescape
-                        let href = String::from_utf8_lossy(e.as_ref()).to_string();
-
-                        // Decode URL encoding
-                        let decoded_href =
-                            urlencoding::decode(&href).unwrap_or(std::borrow::Cow::Borrowed(&href));
-                        let mut path = decoded_href.to_string();
-
-                        if path.starts_with(&self.base_url) {
-                            path = path.trim_start_matches(&self.base_url).to_string();
-                        } else if path.starts_with(&self.path_prefix) {
-                            path = path.trim_start_matches(&self.path_prefix).to_string();
+                Event::Text(e) => text.push_str(&e.decode().map_err(|e| invalid(&e))?),
+                Event::CData(e) => text.push_str(&e.decode().map_err(|e| invalid(&e))?),
+                Event::GeneralRef(e) => {
+                    let resolved = match e.resolve_char_ref().map_err(|e| invalid(&e))? {
+                        Some(c) => c,
+                        None => match e.decode().map_err(|e| invalid(&e))?.as_ref() {
+                            "amp" => '&',
+                            "lt" => '<',
+                            "gt" => '>',
+                            "quot" => '"',
+                            "apos" => '\'',
+                            other => return Err(invalid(&format!("unknown entity &{};", other))),
+                        },
+                    };
+                    text.push(resolved);
+                }
+                Event::End(_) => {
+                    let name = stack.pop().unwrap_or_default();
+                    let parent = stack.last().map(String::as_str);
+                    match (name.as_str(), entry.as_mut()) {
+                        ("href", Some(entry)) if parent == Some("response") => {
+                            entry.href = Some(self.href_to_path(text.trim()));
                         }
-
-                        // 确保路径以 / 开头（如果是根目录下的文件）
-                        if !path.starts_with('/') && !path.is_empty() {
-                            path = format!("/{}", path);
+                        ("getcontentlength", Some(entry)) => {
+                            entry.size = text.trim().parse().unwrap_or(0);
                         }
-
-                        // Handle cases where the path might be just "/" after trimming
-                        if path.is_empty() {
-                            path = "/".to_string();
+                        ("getlastmodified", Some(entry)) => {
+                            entry.modified = chrono::DateTime::parse_from_rfc2822(text.trim())
+                                .ok()
+                                .map(|t| t.timestamp());
                         }
-
-                        current_path = Some(path);
-                    } else if in_getcontentlength {
-                        let size_str = String::from_utf8_lossy(e.as_ref()).to_string();
-                        if let Ok(size) = size_str.parse::<u64>() {
-                            current_size = size;
+                        ("getetag", Some(entry)) if !text.trim().is_empty() => {
+                            let etag = text.trim().trim_start_matches("W/").trim_matches('"');
+                            entry.etag = Some(etag.to_string());
                         }
-                    }
-                }
-                Ok(Event::End(ref e)) => {
-                    let name = e.name();
-                    let name_str = String::from_utf8_lossy(name.as_ref()).to_lowercase();
-
-                    if name_str.ends_with("response") {
-                        if let Some(path) = current_path.take() {
-                            // 跳过基础路径本身
-                            // Normalize paths for comparison (remove trailing slashes)
-                            let norm_path = path.trim_end_matches('/');
-                            let norm_base = base_path.trim_end_matches('/');
-
-                            // Debug logging to help trace path issues
-                            debug!(path = %path, norm_path = %norm_path, base = %base_path, norm_base = %norm_base, "Checking if path is base path");
-
-                            if norm_path != norm_base && !path.is_empty() {
+                        ("checksum", Some(entry)) if parent == Some("checksums") => {
+                            entry.checksums = Some(text.trim().to_string());
+                        }
+                        ("collection", Some(entry)) if parent == Some("resourcetype") => {
+                            entry.is_collection = true;
+                        }
+                        ("response", _) => {
+                            if let Some(entry) = entry.take()
+                                && let Some(path) = entry.href
+                            {
+                                let hash = if entry.is_collection {
+                                    None
+                                } else {
+                                    self.file_hash(entry.etag, entry.checksums.as_deref())
+                                };
                                 files.push(FileInfo {
                                     path, // Keep original path (maybe with trailing slash for dirs)
-                                    size: current_size,
-                                    modified: SystemTime::now()
-                                        .duration_since(UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_secs()
-                                        as i64,
-                                    hash: None,
-                                    is_dir: is_collection,
+                                    size: entry.size,
+                                    // 服务器未返回修改时间时退回当前时间
+                                    modified: entry.modified.unwrap_or_else(|| {
+                                        SystemTime::now()
+                                            .duration_since(UNIX_EPOCH)
+                                            .unwrap()
+                                            .as_secs()
+                                            as i64
+                                    }),
+                                    hash,
+                                    is_dir: entry.is_collection,
+                                    permissions: None,
                                 });
                             }
                         }
-                        in_response = false;
-                    } else if name_str.ends_with("href") {
-                        in_href = false;
-                    } else if name_str.ends_with("prop") {
-                        in_prop = false;
-                    } else if name_str.ends_with("getcontentlength") {
-                        in_getcontentlength = false;
-                    } else if name_str.ends_with("resourcetype") {
-                        in_resourcetype = false;
+                        _ => {}
                     }
+                    text.clear();
                 }
-                Ok(Event::Eof) => break,
-                Err(e) => {
-                    error!("Error parsing XML: {:?}", e);
+                Event::Eof => {
+                    // 截断的响应会导致列表不完整，不能当作成功处理
+                    if !stack.is_empty() {
+                        return Err(invalid(&"unexpected end of document"));
+                    }
                     break;
                 }
                 _ => {}
@@ -249,6 +898,193 @@ impl WebDavProvider {
     }
 }
 
+/// 逐段进行 URL 编码，去掉开头的 `/`
+fn encode_path(path: &str) -> String {
+    path.trim_start_matches('/')
+        .split('/')
+        .map(|component| urlencoding::encode(component).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 在文件名后追加后缀得到同目录下的辅助文件路径
+fn sibling_path(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    name.into()
+}
+
+/// 解析 `Content-Range: bytes <start>-<end>/<total>` 中的起始位置
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    range.split_once('-')?.0.trim().parse().ok()
+}
+
+/// 续传时 `If-Range` 使用的校验值：弱 ETag 不能用于 If-Range，退回 Last-Modified；
+/// 两者都没有时无法安全续传
+fn resume_validator(response: &reqwest::Response) -> Option<&str> {
+    let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok());
+    header(ETAG)
+        .filter(|v| !v.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+}
+
+/// 流式下载的续传状态：读取中断时从已读位置重新请求剩余部分
+struct ResumableBody {
+    client: Client,
+    url: String,
+    auth: String,
+    /// 首次响应的校验值，为空时不续传
+    validator: Option<String>,
+    offset: u64,
+    failures: u32,
+    body: futures::stream::BoxStream<'static, reqwest::Result<bytes::Bytes>>,
+}
+
+impl ResumableBody {
+    fn new(client: Client, url: String, auth: String, response: reqwest::Response) -> Self {
+        Self {
+            client,
+            url,
+            auth,
+            validator: resume_validator(&response).map(str::to_string),
+            offset: 0,
+            failures: 0,
+            body: response.bytes_stream().boxed(),
+        }
+    }
+
+    /// 以 `Range`/`If-Range` 请求剩余部分，远端文件已变化时返回错误
+    async fn reopen(&mut self, validator: &str) -> std::io::Result<()> {
+        let response = self
+            .client
+            .get(&self.url)
+            .header("Authorization", &self.auth)
+            .header(RANGE, format!("bytes={}-", self.offset))
+            .header(IF_RANGE, validator)
+            .send()
+            .await
+            .map_err(std::io::Error::other)?;
+        if response.status() != StatusCode::PARTIAL_CONTENT
+            || content_range_start(&response) != Some(self.offset)
+        {
+            return Err(std::io::Error::other(format!(
+                "Cannot resume download at byte {}: {}",
+                self.offset,
+                response.status()
+            )));
+        }
+        self.body = response.bytes_stream().boxed();
+        Ok(())
+    }
+
+    /// 下一块数据，中断时最多续传 `MAX_RESUME_ATTEMPTS` 次
+    async fn next_chunk(&mut self) -> Option<std::io::Result<bytes::Bytes>> {
+        loop {
+            let error = match self.body.next().await? {
+                Ok(bytes) => {
+                    self.offset += bytes.len() as u64;
+                    return Some(Ok(bytes));
+                }
+                Err(e) => e,
+            };
+            let Some(validator) = self
+                .validator
+                .clone()
+                .filter(|_| self.failures < MAX_RESUME_ATTEMPTS)
+            else {
+                return Some(Err(std::io::Error::other(error)));
+            };
+            self.failures += 1;
+            warn!(
+                error = %error,
+                offset = self.offset,
+                attempt = self.failures,
+                "下载中断，从断点续传"
+            );
+            if let Err(e) = self.reopen(&validator).await {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// 去掉被列举目录本身的条目
+fn without_base(mut files: Vec<FileInfo>, base_path: &str) -> Vec<FileInfo> {
+    let norm_base = base_path.trim_end_matches('/');
+    files.retain(|f| f.path.trim_end_matches('/') != norm_base);
+    files
+}
+
+/// 判断 PROPPATCH 的多状态响应中所有属性是否都设置成功
+fn proppatch_succeeded(xml: &[u8]) -> bool {
+    use quick_xml::events::Event;
+    use quick_xml::reader::Reader;
+
+    let mut reader = Reader::from_reader(xml);
+    let mut in_status = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => in_status = e.local_name().as_ref() == b"status",
+            Ok(Event::Text(e)) if in_status => {
+                // 形如 "HTTP/1.1 200 OK"
+                let status = String::from_utf8_lossy(e.as_ref()).to_string();
+                if status
+                    .split_whitespace()
+                    .nth(1)
+                    .is_none_or(|code| !code.starts_with('2'))
+                {
+                    return false;
+                }
+            }
+            Ok(Event::End(_)) => in_status = false,
+            Ok(Event::Eof) => return true,
+            Err(_) => return false,
+            _ => {}
+        }
+    }
+}
+
+/// 解析 RFC 4331 配额属性，负值（如 Nextcloud 的 -3 表示不限）视为未报告
+fn parse_quota(xml: &[u8]) -> StorageQuota {
+    use quick_xml::events::Event;
+    use quick_xml::reader::Reader;
+
+    let mut reader = Reader::from_reader(xml);
+    let mut quota = StorageQuota::default();
+    let mut current: Option<Vec<u8>> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => current = Some(e.local_name().as_ref().to_vec()),
+            Ok(Event::Text(e)) => {
+                let value = String::from_utf8_lossy(e.as_ref())
+                    .trim()
+                    .parse::<u64>()
+                    .ok();
+                match current.as_deref() {
+                    Some(b"quota-available-bytes") => quota.available_bytes = value,
+                    Some(b"quota-used-bytes") => quota.used_bytes = value,
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => current = None,
+            Ok(Event::Eof) | Err(_) => return quota,
+            _ => {}
+        }
+    }
+}
+
+/// 以流的方式读取响应体
+fn body_reader(response: reqwest::Response) -> impl AsyncBufRead + Unpin {
+    StreamReader::new(
+        response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(std::io::Error::other)),
+    )
+}
+
 #[async_trait]
 impl StorageProvider for WebDavProvider {
     async fn verify(&self) -> Result<(), SyncError> {
@@ -257,28 +1093,7 @@ impl StorageProvider for WebDavProvider {
 
     /// 列出目录内容
     async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
-        let url = self.get_full_url(path);
-
-        let response = self
-            .client
-            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-            .header("Authorization", self.create_auth_header())
-            .header("Depth", "1")
-            .header("Content-Type", "application/xml")
-            .body(
-                r#"<?xml version="1.0" encoding="utf-8"?>
-                <d:propfind xmlns:d="DAV:">
-                    <d:prop>
-                        <d:displayname/>
-                        <d:getcontentlength/>
-                        <d:getlastmodified/>
-                        <d:resourcetype/>
-                    </d:prop>
-                </d:propfind>"#,
-            )
-            .send()
-            .await
-            .map_err(SyncError::Network)?;
+        let response = self.propfind(path, "1", PROPFIND_BODY).await?;
 
         if !response.status().is_success() {
             return Err(SyncError::Provider(ProviderError::ApiError(format!(
@@ -287,11 +1102,44 @@ impl StorageProvider for WebDavProvider {
             ))));
         }
 
-        let body = response.text().await.map_err(SyncError::Network)?;
+        let files = self.parse_propfind_response(body_reader(response)).await?;
+        Ok(without_base(files, path))
+    }
+
+    /// 使用 `Depth: infinity` 一次列出整棵目录树
+    ///
+    /// 服务器拒绝无限深度时记住该结果并返回 `Unsupported`，由调用方逐级列举。
+    async fn list_recursive(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
+        if !self.depth_infinity.load(Ordering::Relaxed) {
+            return Err(SyncError::Unsupported(
+                "Depth: infinity PROPFIND is disabled".to_string(),
+            ));
+        }
 
-        debug!("PROPFIND Response Body: {}", body);
+        let response = self.propfind(path, "infinity", PROPFIND_BODY).await?;
+        let status = response.status();
+        if status.is_success() {
+            let files = self.parse_propfind_response(body_reader(response)).await?;
+            return Ok(without_base(files, path));
+        }
 
-        self.parse_propfind_response(&body, path)
+        // RFC 4918 9.1：不支持无限深度的服务器以 403 propfind-finite-depth 拒绝
+        if matches!(
+            status,
+            StatusCode::FORBIDDEN | StatusCode::BAD_REQUEST | StatusCode::NOT_IMPLEMENTED
+        ) {
+            warn!(status = %status, "服务器不支持 Depth: infinity，改为逐级列举");
+            self.depth_infinity.store(false, Ordering::Relaxed);
+            return Err(SyncError::Unsupported(format!(
+                "Depth: infinity PROPFIND rejected: {}",
+                status
+            )));
+        }
+
+        Err(SyncError::Provider(ProviderError::ApiError(format!(
+            "PROPFIND failed: {}",
+            status
+        ))))
     }
 
     /// 上传文件
@@ -300,44 +1148,24 @@ impl StorageProvider for WebDavProvider {
         local_path: &Path,
         remote_path: &str,
     ) -> Result<UploadResult, SyncError> {
-        let url = self.get_full_url(remote_path);
-        let start_time = SystemTime::now();
-
-        // 读取文件内容
-        let file_data = tokio::fs::read(local_path).await.map_err(SyncError::Io)?;
-
-        let file_size = file_data.len() as u64;
-
-        // 上传文件
-        let response = self
-            .client
-            .put(&url)
-            .header("Authorization", self.create_auth_header())
-            .body(file_data)
-            .send()
+        let file = tokio::fs::File::open(local_path)
+            .await
+            .map_err(SyncError::Io)?;
+        let metadata = file.metadata().await.map_err(SyncError::Io)?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        self.put(Box::new(file), metadata.len(), remote_path, mtime)
             .await
-            .map_err(SyncError::Network)?;
-
-        if !response.status().is_success() {
-            return Err(SyncError::Provider(ProviderError::ApiError(format!(
-                "Upload failed: {}",
-                response.status()
-            ))));
-        }
-
-        let elapsed = SystemTime::now()
-            .duration_since(start_time)
-            .unwrap_or(Duration::from_secs(0));
-
-        Ok(UploadResult {
-            bytes_uploaded: file_size,
-            file_size,
-            checksum: None,
-            elapsed_time: elapsed,
-        })
     }
 
     /// 下载文件
+    ///
+    /// 数据先写入 `<local_path>.part`，完成后重命名。已有 `.part` 时以 `Range` 续传，
+    /// 并用 `If-Range` 携带开始下载时的 ETag，远端文件变化后服务器会返回完整内容。
     #[instrument(skip(self), fields(remote_path = %remote_path, local_path = %local_path.display()))]
     async fn download(
         &self,
@@ -345,38 +1173,8 @@ impl StorageProvider for WebDavProvider {
         local_path: &Path,
     ) -> Result<DownloadResult, SyncError> {
         info!("开始下载文件");
-        let url = self.get_full_url(remote_path);
         let start_time = SystemTime::now();
-
-        debug!("发送 GET 请求");
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", self.create_auth_header())
-            .send()
-            .await
-            .map_err(|e| {
-                error!(error = %e, "下载请求失败");
-                SyncError::Network(e)
-            })?;
-
-        let status = response.status();
-        debug!(status = %status, "收到下载响应");
-
-        if !status.is_success() {
-            warn!(status = %status, "文件不存在或下载失败");
-            return Err(SyncError::Provider(ProviderError::FileNotFound(
-                remote_path.to_string(),
-            )));
-        }
-
-        let bytes = response.bytes().await.map_err(|e| {
-            error!(error = %e, "读取响应数据失败");
-            SyncError::Network(e)
-        })?;
-
-        let file_size = bytes.len() as u64;
-        debug!(file_size = %file_size, "下载数据大小: {} 字节", file_size);
+        let url = self.get_full_url(remote_path);
 
         // 确保父目录存在
         if let Some(parent) = local_path.parent() {
@@ -387,13 +1185,128 @@ impl StorageProvider for WebDavProvider {
             })?;
         }
 
-        // 写入文件
-        debug!("写入本地文件");
-        tokio::fs::write(local_path, bytes).await.map_err(|e| {
-            error!(error = %e, "写入本地文件失败");
-            SyncError::Io(e)
-        })?;
+        // 数据先写入 .part，旁边的 .part.etag 保存开始下载时远端文件的校验值
+        let part_path = sibling_path(local_path, "part");
+        let validator_path = sibling_path(local_path, "part.etag");
+        let cleanup = async || {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            let _ = tokio::fs::remove_file(&validator_path).await;
+        };
+
+        let mut resumed_bytes = None;
+        let mut failures = 0;
+        loop {
+            let validator = tokio::fs::read_to_string(&validator_path).await.ok();
+            let offset = match (&validator, tokio::fs::metadata(&part_path).await) {
+                (Some(_), Ok(metadata)) => metadata.len(),
+                _ => 0,
+            };
+
+            let mut request = self
+                .client
+                .get(&url)
+                .header("Authorization", self.create_auth_header());
+            if let Some(validator) = validator.as_ref().filter(|_| offset > 0) {
+                debug!(offset = offset, "请求续传");
+                request = request
+                    .header(RANGE, format!("bytes={}-", offset))
+                    .header(IF_RANGE, validator);
+            }
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) if failures < MAX_RESUME_ATTEMPTS => {
+                    failures += 1;
+                    warn!(error = %e, attempt = failures, "下载请求失败，重试");
+                    continue;
+                }
+                Err(e) => {
+                    error!(error = %e, "下载请求失败");
+                    return Err(SyncError::Network(e));
+                }
+            };
+            let response = http::check_throttled(response)?;
+
+            let status = response.status();
+            debug!(status = %status, "收到下载响应");
+            let (mut file, start) = if status == StatusCode::PARTIAL_CONTENT
+                && offset > 0
+                && content_range_start(&response) == Some(offset)
+            {
+                let file = tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&part_path)
+                    .await?;
+                (file, offset)
+            } else if status.is_success() && status != StatusCode::PARTIAL_CONTENT {
+                if offset > 0 {
+                    info!("远端文件已变化或服务器不支持范围请求，重新下载");
+                }
+                match resume_validator(&response) {
+                    Some(validator) => tokio::fs::write(&validator_path, validator).await?,
+                    None => {
+                        let _ = tokio::fs::remove_file(&validator_path).await;
+                    }
+                }
+                (tokio::fs::File::create(&part_path).await?, 0)
+            } else if status == StatusCode::NOT_FOUND {
+                warn!("文件不存在");
+                cleanup().await;
+                return Err(SyncError::Provider(ProviderError::FileNotFound(
+                    remote_path.to_string(),
+                )));
+            } else if offset > 0 && failures < MAX_RESUME_ATTEMPTS {
+                // 416 或与请求不符的范围响应，丢弃已有数据重新下载
+                warn!(status = %status, "续传请求未被接受，重新下载");
+                failures += 1;
+                cleanup().await;
+                continue;
+            } else {
+                return Err(SyncError::Provider(http::status_error(status, "Download")));
+            };
+
+            // 只统计本次调用开始前就已存在并被沿用的数据
+            match resumed_bytes {
+                None => resumed_bytes = Some(start),
+                Some(_) if start == 0 => resumed_bytes = Some(0),
+                Some(_) => {}
+            }
+
+            // 边读边写入文件，传输中断时保留已写入的部分
+            let mut stream = response.bytes_stream();
+            let mut interrupted = None;
+            while let Some(chunk) = stream.next().await {
+                match chunk {
+                    Ok(bytes) => file.write_all(&bytes).await.map_err(|e| {
+                        error!(error = %e, "写入本地文件失败");
+                        SyncError::Io(e)
+                    })?,
+                    Err(e) => {
+                        interrupted = Some(e);
+                        break;
+                    }
+                }
+            }
+            file.flush().await.map_err(SyncError::Io)?;
+            drop(file);
+
+            match interrupted {
+                None => break,
+                Some(e) if failures < MAX_RESUME_ATTEMPTS => {
+                    failures += 1;
+                    warn!(error = %e, attempt = failures, "下载中断，从断点续传");
+                }
+                Some(e) => {
+                    error!(error = %e, "下载中断，保留已下载部分供下次续传");
+                    return Err(SyncError::Network(e));
+                }
+            }
+        }
+
+        tokio::fs::rename(&part_path, local_path).await?;
+        let _ = tokio::fs::remove_file(&validator_path).await;
 
+        let file_size = tokio::fs::metadata(local_path).await?.len();
+        let resumed_bytes = resumed_bytes.unwrap_or(0);
         let elapsed = SystemTime::now()
             .duration_since(start_time)
             .unwrap_or(Duration::from_secs(0));
@@ -406,6 +1319,7 @@ impl StorageProvider for WebDavProvider {
 
         info!(
             file_size = %file_size,
+            resumed_bytes = %resumed_bytes,
             elapsed_ms = elapsed.as_millis(),
             speed_mbps = %format!("{:.2}", speed),
             "文件下载成功: {} 字节，耗时 {} ms，速度 {:.2} MB/s",
@@ -413,10 +1327,11 @@ impl StorageProvider for WebDavProvider {
         );
 
         Ok(DownloadResult {
-            bytes_downloaded: file_size,
+            bytes_downloaded: file_size - resumed_bytes,
             file_size,
             checksum: None,
             elapsed_time: elapsed,
+            resumed_bytes,
         })
     }
 
@@ -437,6 +1352,7 @@ impl StorageProvider for WebDavProvider {
                 error!(error = %e, "删除请求失败");
                 SyncError::Network(e)
             })?;
+        let response = http::check_throttled(response)?;
 
         let status = response.status();
         debug!(status = %status, "收到删除响应");
@@ -473,6 +1389,7 @@ impl StorageProvider for WebDavProvider {
                 error!(error = %e, "创建目录请求失败");
                 SyncError::Network(e)
             })?;
+        let response = http::check_throttled(response)?;
 
         let status = response.status();
         debug!(status = %status, "收到 MKCOL 响应");
@@ -497,42 +1414,35 @@ impl StorageProvider for WebDavProvider {
     #[instrument(skip(self), fields(path = %path))]
     async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
         debug!("查询文件或目录信息");
-        let url = self.get_full_url(path);
-
-        let response = self
-            .client
-            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
-            .header("Authorization", self.create_auth_header())
-            .header("Depth", "0")
-            .send()
-            .await
-            .map_err(|e| {
-                error!(error = %e, "PROPFIND 请求失败");
-                SyncError::Network(e)
-            })?;
+        let response = self.propfind(path, "0", PROPFIND_BODY).await?;
 
         let status = response.status();
         debug!(status = %status, "收到 stat 响应");
 
-        if !status.is_success() {
+        if status == StatusCode::NOT_FOUND {
             warn!("文件或目录不存在");
             return Err(SyncError::Provider(ProviderError::FileNotFound(
                 path.to_string(),
             )));
         }
+        if !status.is_success() {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "PROPFIND failed: {}",
+                status
+            ))));
+        }
 
-        let is_dir = path.ends_with('/');
-        debug!(is_dir = %is_dir, "查询成功");
+        let info = self
+            .parse_propfind_response(body_reader(response))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| SyncError::Provider(ProviderError::FileNotFound(path.to_string())))?;
+        debug!(is_dir = %info.is_dir, "查询成功");
 
         Ok(FileInfo {
             path: path.to_string(),
-            size: 0,
-            modified: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64,
-            hash: None,
-            is_dir,
+            ..info
         })
     }
 
@@ -553,7 +1463,180 @@ impl StorageProvider for WebDavProvider {
                 warn!(error = %e, "检查存在性时发生错误");
                 Err(e)
             }
-        }
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_hash: self.checksum,
+            can_set_mtime: self.can_set_mtime.load(Ordering::Relaxed),
+            supports_range_read: true,
+            supports_server_copy: true,
+            supports_server_move: true,
+            ..ProviderCapabilities::default()
+        }
+    }
+
+    /// 通过 PROPFIND 查询根目录的 RFC 4331 配额属性
+    async fn quota(&self) -> Result<StorageQuota, SyncError> {
+        let response = self.propfind("/", "0", QUOTA_PROPFIND_BODY).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(SyncError::Provider(ProviderError::ApiError(format!(
+                "Quota PROPFIND failed: {}",
+                status
+            ))));
+        }
+
+        let quota = parse_quota(&response.bytes().await.map_err(SyncError::Network)?);
+        if quota == StorageQuota::default() {
+            return Err(SyncError::Unsupported(
+                "Server does not report quota".to_string(),
+            ));
+        }
+        Ok(quota)
+    }
+
+    /// 通过 PROPPATCH 设置修改时间
+    ///
+    /// 服务器拒绝时本进程内不再尝试，`can_set_mtime` 随之变为 false。
+    #[instrument(skip(self), fields(path = %path, modified = %modified))]
+    async fn set_mtime(&self, path: &str, modified: i64) -> Result<(), SyncError> {
+        let Some(method) = self
+            .mtime_method
+            .filter(|_| self.can_set_mtime.load(Ordering::Relaxed))
+        else {
+            return Err(SyncError::Unsupported(format!(
+                "Setting modification time is disabled: {}",
+                path
+            )));
+        };
+
+        let prop = match method {
+            MtimeMethod::PropPatch => {
+                let date = chrono::DateTime::from_timestamp(modified, 0).ok_or_else(|| {
+                    SyncError::Validation(format!("Invalid modification time: {}", modified))
+                })?;
+                format!(
+                    "<d:getlastmodified>{}</d:getlastmodified>",
+                    date.format("%a, %d %b %Y %H:%M:%S GMT")
+                )
+            }
+            MtimeMethod::OwnCloud => format!("<d:lastmodified>{}</d:lastmodified>", modified),
+        };
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propertyupdate xmlns:d="DAV:"><d:set><d:prop>{}</d:prop></d:set></d:propertyupdate>"#,
+            prop
+        );
+
+        let response = self
+            .client
+            .request(
+                Method::from_bytes(b"PROPPATCH").unwrap(),
+                self.get_full_url(path),
+            )
+            .header("Authorization", self.create_auth_header())
+            .header("Content-Type", "application/xml")
+            .body(body)
+            .send()
+            .await
+            .map_err(SyncError::Network)?;
+        let response = http::check_throttled(response)?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(SyncError::Provider(ProviderError::FileNotFound(
+                path.to_string(),
+            )));
+        }
+        let accepted = status.is_success()
+            && proppatch_succeeded(&response.bytes().await.map_err(SyncError::Network)?);
+        if !accepted {
+            warn!(status = %status, method = ?method, "服务器拒绝设置修改时间，本次运行不再尝试");
+            self.can_set_mtime.store(false, Ordering::Relaxed);
+            return Err(SyncError::Unsupported(format!(
+                "Server rejected modification time update for {}",
+                path
+            )));
+        }
+        debug!("修改时间设置成功");
+        Ok(())
+    }
+
+    /// 使用 MOVE 方法在服务端移动或重命名
+    #[instrument(skip(self), fields(from = %from, to = %to))]
+    async fn move_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.transfer_on_server("MOVE", from, to).await
+    }
+
+    /// 使用 COPY 方法在服务端复制
+    #[instrument(skip(self), fields(from = %from, to = %to))]
+    async fn copy_path(&self, from: &str, to: &str) -> Result<(), SyncError> {
+        self.transfer_on_server("COPY", from, to).await
+    }
+
+    /// 流式上传，请求体直接来自读取器，不在内存中缓存整个文件
+    #[instrument(skip(self, reader), fields(remote_path = %remote_path, size = %size))]
+    async fn upload_stream(
+        &self,
+        reader: ByteStream,
+        size: u64,
+        remote_path: &str,
+    ) -> Result<UploadResult, SyncError> {
+        self.put(reader, size, remote_path, None).await
+    }
+
+    /// 流式下载，返回响应体的读取器
+    ///
+    /// 读取中断时以 `Range` 从已读位置重新请求，并用 `If-Range` 携带首次响应的 ETag，
+    /// 远端文件在此期间变化则读取失败，不会拼接出新旧混合的内容。
+    #[instrument(skip(self), fields(remote_path = %remote_path))]
+    async fn download_stream(&self, remote_path: &str) -> Result<ByteStream, SyncError> {
+        let url = self.get_full_url(remote_path);
+
+        debug!("发送 GET 请求");
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", self.create_auth_header())
+            .send()
+            .await
+            .map_err(|e| {
+                error!(error = %e, "下载请求失败");
+                SyncError::Network(e)
+            })?;
+        let response = http::check_throttled(response)?;
+
+        let status = response.status();
+        debug!(status = %status, "收到下载响应");
+
+        if status == StatusCode::NOT_FOUND {
+            warn!("文件不存在");
+            return Err(SyncError::Provider(ProviderError::FileNotFound(
+                remote_path.to_string(),
+            )));
+        }
+        if !status.is_success() {
+            warn!(status = %status, "下载失败");
+            return Err(SyncError::Provider(http::status_error(status, "Download")));
+        }
+
+        let body = ResumableBody::new(
+            self.client.clone(),
+            url,
+            self.create_auth_header(),
+            response,
+        );
+        let stream = futures::stream::unfold(Some(body), |body| async move {
+            let mut body = body?;
+            match body.next_chunk().await? {
+                Ok(bytes) => Some((Ok(bytes), Some(body))),
+                // 出错后结束流
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+        Ok(Box::new(StreamReader::new(Box::pin(stream))))
     }
 }
 
@@ -577,6 +1660,7 @@ mod tests {
             },
             rate_limit: None,
             retry_policy: crate::config::RetryPolicy::default(),
+            resource_limits: None,
         };
 
         let runtime = tokio::runtime::Runtime::new().unwrap();
@@ -617,6 +1701,7 @@ mod tests {
             },
             rate_limit: None,
             retry_policy: crate::config::RetryPolicy::default(),
+            resource_limits: None,
         };
 
         let runtime = tokio::runtime::Runtime::new().unwrap();
@@ -646,14 +1731,118 @@ mod tests {
         struct InMemoryFile {
             content: Vec<u8>,
             is_dir: bool,
+            modified: i64,
         }
 
+        /// 模拟服务器上文件的默认修改时间
+        const SERVER_MTIME: i64 = 1_700_000_000;
+
         type FileStore = Arc<RwLock<HashMap<String, InMemoryFile>>>;
 
+        /// 按顺序记录收到的请求（PROPFIND 记录 Depth 头，分块 PUT 记录路径）
+        type RequestLog = Arc<std::sync::Mutex<Vec<String>>>;
+
+        struct MockServer {
+            addr: SocketAddr,
+            store: FileStore,
+            depth_log: RequestLog,
+            chunk_log: RequestLog,
+            /// 该序号的分块 PUT 返回 500，模拟上传中断
+            fail_chunk: Arc<std::sync::Mutex<Option<String>>>,
+            /// 当前持有的写锁：路径 -> 锁令牌
+            locks: Arc<std::sync::Mutex<HashMap<String, String>>>,
+            /// 按顺序记录 LOCK/UNLOCK/PUT 请求
+            lock_log: RequestLog,
+        }
+
+        /// 模拟服务器报告的可用空间
+        const QUOTA_AVAILABLE: u64 = 1_000_000;
+
         async fn start_mock_server() -> (SocketAddr, FileStore) {
+            let server = start_mock_server_with(true, "lastmodified").await;
+            (server.addr, server.store)
+        }
+
+        /// 生成 PROPFIND 多状态响应，目录由文件路径推断
+        fn multistatus(files: &HashMap<String, InMemoryFile>, base: &str, depth: &str) -> String {
+            let base = base.trim_end_matches('/');
+            let mut entries: std::collections::BTreeMap<String, (bool, usize, i64)> =
+                std::collections::BTreeMap::new();
+            for (path, file) in files {
+                let mut parent = path.trim_end_matches('/');
+                while let Some((dir, _)) = parent.rsplit_once('/') {
+                    entries.insert(format!("{}/", dir), (true, 0, SERVER_MTIME));
+                    parent = dir;
+                }
+                if !file.is_dir {
+                    entries.insert(path.clone(), (false, file.content.len(), file.modified));
+                }
+            }
+
+            let mut xml = String::from(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">"#);
+            for (path, (is_dir, size, modified)) in entries {
+                let Some(rest) = path.strip_prefix(base) else {
+                    continue;
+                };
+                if !rest.is_empty() && !rest.starts_with('/') {
+                    continue;
+                }
+                let level = rest
+                    .trim_matches('/')
+                    .split('/')
+                    .filter(|c| !c.is_empty())
+                    .count();
+                let included = match depth {
+                    "0" => level == 0,
+                    "1" => level <= 1,
+                    _ => true,
+                };
+                if !included {
+                    continue;
+                }
+                let href: Vec<String> = path
+                    .split('/')
+                    .map(|c| urlencoding::encode(c).to_string())
+                    .collect();
+                let mut props = format!(
+                    "<d:getlastmodified>{}</d:getlastmodified>",
+                    chrono::DateTime::from_timestamp(modified, 0)
+                        .unwrap()
+                        .format("%a, %d %b %Y %H:%M:%S GMT")
+                );
+                if is_dir {
+                    props.push_str("<d:resourcetype><d:collection/></d:resourcetype>");
+                } else {
+                    props.push_str(&format!(
+                        "<d:resourcetype/><d:getcontentlength>{}</d:getcontentlength>\
+                         <d:getetag>\"{}-{}\"</d:getetag>",
+                        size, size, modified
+                    ));
+                }
+                xml.push_str(&format!(
+                    "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop>\
+                     <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+                    href.join("/"),
+                    props
+                ));
+            }
+            xml.push_str("</d:multistatus>");
+            xml
+        }
+
+        /// `mtime_prop` 为 PROPPATCH 时服务器接受的修改时间属性，其余属性返回 403
+        async fn start_mock_server_with(
+            allow_infinity: bool,
+            mtime_prop: &'static str,
+        ) -> MockServer {
             use warp::Filter;
 
             let store: FileStore = Arc::new(RwLock::new(HashMap::new()));
+            let depth_log: RequestLog = Arc::default();
+            let chunk_log: RequestLog = Arc::default();
+            let fail_chunk: Arc<std::sync::Mutex<Option<String>>> = Arc::default();
+            let locks: Arc<std::sync::Mutex<HashMap<String, String>>> = Arc::default();
+            let lock_log: RequestLog = Arc::default();
 
             // 初始化根目录
             {
@@ -663,6 +1852,7 @@ mod tests {
                     InMemoryFile {
                         content: vec![],
                         is_dir: true,
+                        modified: SERVER_MTIME,
                     },
                 );
             }
@@ -672,13 +1862,46 @@ mod tests {
             // PUT 处理器（上传）
             let put_route = warp::put()
                 .and(warp::path::full())
+                .and(warp::header::optional::<i64>("x-oc-mtime"))
+                .and(warp::header::optional::<String>("if"))
                 .and(warp::body::bytes())
                 .and_then({
                     let store = store_clone.clone();
-                    move |path: warp::path::FullPath, body: bytes::Bytes| {
+                    let chunk_log = chunk_log.clone();
+                    let fail_chunk = fail_chunk.clone();
+                    let locks = locks.clone();
+                    let lock_log = lock_log.clone();
+                    move |path: warp::path::FullPath,
+                          mtime: Option<i64>,
+                          if_header: Option<String>,
+                          body: bytes::Bytes| {
                         let store = store.clone();
+                        let chunk_log = chunk_log.clone();
+                        let fail_chunk = fail_chunk.clone();
+                        let locks = locks.clone();
+                        let lock_log = lock_log.clone();
                         async move {
                             let path_str = path.as_str().to_string();
+                            lock_log.lock().unwrap().push(format!("PUT {}", path_str));
+                            // 已加锁的资源只接受提交了对应令牌的写入
+                            if let Some(token) = locks.lock().unwrap().get(&path_str)
+                                && !if_header.is_some_and(|h| h.contains(token.as_str()))
+                            {
+                                return Ok(warp::reply::with_status(
+                                    String::new(),
+                                    warp::http::StatusCode::LOCKED,
+                                ));
+                            }
+                            if path_str.contains("/uploads/") {
+                                let index = path_str.rsplit('/').next().unwrap().to_string();
+                                if fail_chunk.lock().unwrap().as_ref() == Some(&index) {
+                                    return Ok(warp::reply::with_status(
+                                        String::new(),
+                                        warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+                                    ));
+                                }
+                                chunk_log.lock().unwrap().push(path_str.clone());
+                            }
                             let mut files = store.write().await;
 
                             files.insert(
@@ -686,6 +1909,7 @@ mod tests {
                                 InMemoryFile {
                                     content: body.to_vec(),
                                     is_dir: false,
+                                    modified: mtime.unwrap_or(SERVER_MTIME),
                                 },
                             );
 
@@ -723,13 +1947,270 @@ mod tests {
                 }
             });
 
-            let routes = put_route.or(get_route);
+            // PROPFIND 处理器（列举），可配置为拒绝 Depth: infinity
+            // 先匹配方法再读取请求体，请求体只能被一个路由读取
+            let propfind_route = warp::method()
+                .and_then(|method: warp::http::Method| async move {
+                    if method.as_str() == "PROPFIND" {
+                        Ok::<_, warp::Rejection>(())
+                    } else {
+                        Err(warp::reject::not_found())
+                    }
+                })
+                .untuple_one()
+                .and(warp::path::full())
+                .and(warp::header::optional::<String>("depth"))
+                .and(warp::body::bytes())
+                .and_then({
+                    let store = store_clone.clone();
+                    let depth_log = depth_log.clone();
+                    move |path: warp::path::FullPath, depth: Option<String>, body: bytes::Bytes| {
+                        let store = store.clone();
+                        let depth_log = depth_log.clone();
+                        async move {
+                            // 配额查询：已用空间为所有文件大小之和
+                            if String::from_utf8_lossy(&body).contains("quota-available-bytes") {
+                                let used: usize =
+                                    store.read().await.values().map(|f| f.content.len()).sum();
+                                return Ok(warp::reply::with_status(
+                                    format!(
+                                        "<d:multistatus xmlns:d=\"DAV:\"><d:response><d:href>/</d:href>\
+                                         <d:propstat><d:prop>\
+                                         <d:quota-available-bytes>{}</d:quota-available-bytes>\
+                                         <d:quota-used-bytes>{}</d:quota-used-bytes>\
+                                         </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>\
+                                         </d:response></d:multistatus>",
+                                        QUOTA_AVAILABLE, used
+                                    ),
+                                    warp::http::StatusCode::MULTI_STATUS,
+                                ));
+                            }
+                            let depth = depth.unwrap_or_else(|| "infinity".to_string());
+                            depth_log.lock().unwrap().push(depth.clone());
+                            if depth == "infinity" && !allow_infinity {
+                                return Ok(warp::reply::with_status(
+                                    r#"<d:error xmlns:d="DAV:"><d:propfind-finite-depth/></d:error>"#
+                                        .to_string(),
+                                    warp::http::StatusCode::FORBIDDEN,
+                                ));
+                            }
+                            let path = urlencoding::decode(path.as_str()).unwrap().to_string();
+                            let files = store.read().await;
+                            Ok::<_, warp::Rejection>(warp::reply::with_status(
+                                multistatus(&files, &path, &depth),
+                                warp::http::StatusCode::MULTI_STATUS,
+                            ))
+                        }
+                    }
+                });
+
+            // PROPPATCH 处理器（设置修改时间）
+            let proppatch_route = warp::method()
+                .and(warp::path::full())
+                .and(warp::body::bytes())
+                .and_then({
+                    let store = store_clone.clone();
+                    move |method: warp::http::Method,
+                          path: warp::path::FullPath,
+                          body: bytes::Bytes| {
+                        let store = store.clone();
+                        async move {
+                            if method.as_str() != "PROPPATCH" {
+                                return Err(warp::reject::not_found());
+                            }
+                            let body = String::from_utf8_lossy(&body).to_string();
+                            let value = |prop: &str| {
+                                let start = format!("<d:{}>", prop);
+                                let end = format!("</d:{}>", prop);
+                                let from = body.find(&start)? + start.len();
+                                Some(body[from..body.find(&end)?].to_string())
+                            };
+                            let modified = match mtime_prop {
+                                "lastmodified" => value("lastmodified").and_then(|v| v.parse().ok()),
+                                "getlastmodified" => value("getlastmodified").and_then(|v| {
+                                    chrono::DateTime::parse_from_rfc2822(&v)
+                                        .ok()
+                                        .map(|t| t.timestamp())
+                                }),
+                                _ => None,
+                            };
+                            let path = urlencoding::decode(path.as_str()).unwrap().to_string();
+                            let status = match (modified, store.write().await.get_mut(&path)) {
+                                (Some(modified), Some(file)) => {
+                                    file.modified = modified;
+                                    "HTTP/1.1 200 OK"
+                                }
+                                _ => "HTTP/1.1 403 Forbidden",
+                            };
+                            Ok(warp::reply::with_status(
+                                format!(
+                                    r#"<d:multistatus xmlns:d="DAV:"><d:response><d:href>{}</d:href>\
+                                       <d:propstat><d:prop/><d:status>{}</d:status></d:propstat>\
+                                       </d:response></d:multistatus>"#,
+                                    path, status
+                                ),
+                                warp::http::StatusCode::MULTI_STATUS,
+                            ))
+                        }
+                    }
+                });
+
+            // MKCOL 处理器（创建目录）
+            let mkcol_route = warp::method().and(warp::path::full()).and_then({
+                let store = store_clone.clone();
+                move |method: warp::http::Method, path: warp::path::FullPath| {
+                    let store = store.clone();
+                    async move {
+                        if method.as_str() != "MKCOL" {
+                            return Err(warp::reject::not_found());
+                        }
+                        store.write().await.insert(
+                            path.as_str().trim_end_matches('/').to_string(),
+                            InMemoryFile {
+                                content: vec![],
+                                is_dir: true,
+                                modified: SERVER_MTIME,
+                            },
+                        );
+                        Ok(warp::reply::with_status(
+                            String::new(),
+                            warp::http::StatusCode::CREATED,
+                        ))
+                    }
+                }
+            });
+
+            // MOVE 处理器，源为上传目录下的 `.file` 时按序号拼接分块
+            let move_route = warp::method()
+                .and(warp::path::full())
+                .and(warp::header::<String>("destination"))
+                .and(warp::header::optional::<i64>("x-oc-mtime"))
+                .and_then({
+                    let store = store_clone.clone();
+                    move |method: warp::http::Method,
+                          path: warp::path::FullPath,
+                          destination: String,
+                          mtime: Option<i64>| {
+                        let store = store.clone();
+                        async move {
+                            if method.as_str() != "MOVE" {
+                                return Err(warp::reject::not_found());
+                            }
+                            let target = Url::parse(&destination).unwrap().path().to_string();
+                            let mut files = store.write().await;
+                            let Some(dir) = path.as_str().strip_suffix("/.file") else {
+                                let Some(file) = files.remove(path.as_str()) else {
+                                    return Ok(warp::http::StatusCode::NOT_FOUND);
+                                };
+                                files.insert(target, file);
+                                return Ok(warp::http::StatusCode::CREATED);
+                            };
+                            if !files.contains_key(dir) {
+                                return Ok(warp::http::StatusCode::NOT_FOUND);
+                            }
+
+                            let prefix = format!("{}/", dir);
+                            let mut chunks: Vec<(u32, Vec<u8>)> = files
+                                .iter()
+                                .filter_map(|(p, f)| {
+                                    let index = p.strip_prefix(&prefix)?.parse().ok()?;
+                                    Some((index, f.content.clone()))
+                                })
+                                .collect();
+                            chunks.sort();
+                            files.retain(|p, _| p != dir && !p.starts_with(&prefix));
+                            files.insert(
+                                target,
+                                InMemoryFile {
+                                    content: chunks.into_iter().flat_map(|(_, c)| c).collect(),
+                                    is_dir: false,
+                                    modified: mtime.unwrap_or(SERVER_MTIME),
+                                },
+                            );
+                            Ok(warp::http::StatusCode::CREATED)
+                        }
+                    }
+                });
+
+            // LOCK/UNLOCK 处理器，同一路径同时只允许一个写锁
+            let lock_route = warp::method()
+                .and(warp::path::full())
+                .and(warp::header::optional::<String>("lock-token"))
+                .and_then({
+                    let locks = locks.clone();
+                    let lock_log = lock_log.clone();
+                    move |method: warp::http::Method,
+                          path: warp::path::FullPath,
+                          lock_token: Option<String>| {
+                        let locks = locks.clone();
+                        let lock_log = lock_log.clone();
+                        async move {
+                            let path = path.as_str().to_string();
+                            let mut locks = locks.lock().unwrap();
+                            match method.as_str() {
+                                "LOCK" => {
+                                    lock_log.lock().unwrap().push(format!("LOCK {}", path));
+                                    if locks.contains_key(&path) {
+                                        return Ok(warp::http::Response::builder()
+                                            .status(warp::http::StatusCode::LOCKED)
+                                            .body(String::new())
+                                            .unwrap());
+                                    }
+                                    let token = format!("urn:uuid:{}", uuid::Uuid::new_v4());
+                                    locks.insert(path, token.clone());
+                                    Ok(warp::http::Response::builder()
+                                        .status(warp::http::StatusCode::OK)
+                                        .header("Lock-Token", format!("<{}>", token))
+                                        .body(String::new())
+                                        .unwrap())
+                                }
+                                "UNLOCK" => {
+                                    lock_log.lock().unwrap().push(format!("UNLOCK {}", path));
+                                    let held = locks.get(&path).map(|t| format!("<{}>", t));
+                                    let status = if held.is_some() && held == lock_token {
+                                        locks.remove(&path);
+                                        warp::http::StatusCode::NO_CONTENT
+                                    } else {
+                                        warp::http::StatusCode::CONFLICT
+                                    };
+                                    Ok(warp::http::Response::builder()
+                                        .status(status)
+                                        .body(String::new())
+                                        .unwrap())
+                                }
+                                _ => Err(warp::reject::not_found()),
+                            }
+                        }
+                    }
+                });
+
+            let routes = put_route
+                .or(get_route)
+                .or(propfind_route)
+                .or(proppatch_route)
+                .or(mkcol_route)
+                .or(move_route)
+                .or(lock_route);
             let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
             let addr = listener.local_addr().unwrap();
+            drop(listener);
             let server_future = warp::serve(routes).run(addr);
             tokio::spawn(server_future);
 
-            (addr, store)
+            // 等待服务器就绪
+            while tokio::net::TcpStream::connect(addr).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            MockServer {
+                addr,
+                store,
+                depth_log,
+                chunk_log,
+                fail_chunk,
+                locks,
+                lock_log,
+            }
         }
 
         #[tokio::test]
@@ -749,6 +2230,7 @@ mod tests {
                 },
                 rate_limit: None,
                 retry_policy: crate::config::RetryPolicy::default(),
+                resource_limits: None,
             };
 
             let provider = WebDavProvider::new(&config).await.unwrap();
@@ -797,6 +2279,7 @@ mod tests {
                 },
                 rate_limit: None,
                 retry_policy: crate::config::RetryPolicy::default(),
+                resource_limits: None,
             };
 
             let provider = WebDavProvider::new(&config).await.unwrap();
@@ -814,5 +2297,742 @@ mod tests {
             // 清理
             tokio::fs::remove_file(&test_file).await.ok();
         }
+
+        #[tokio::test]
+        async fn test_stream_upload_download() {
+            let (addr, store) = start_mock_server().await;
+
+            let config = AccountConfig {
+                id: "test".to_string(),
+                provider: crate::config::ProviderType::WebDAV,
+                name: "test".to_string(),
+                credentials: {
+                    let mut creds = HashMap::new();
+                    creds.insert("url".to_string(), format!("http://{}", addr));
+                    creds.insert("username".to_string(), "test".to_string());
+                    creds.insert("password".to_string(), "test".to_string());
+                    creds
+                },
+                rate_limit: None,
+                retry_policy: crate::config::RetryPolicy::default(),
+                resource_limits: None,
+            };
+
+            let provider = WebDavProvider::new(&config).await.unwrap();
+
+            // 以流的方式上传 2MB 数据
+            let content: Vec<u8> = (0..2 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+            let reader: ByteStream = Box::new(std::io::Cursor::new(content.clone()));
+            let result = provider
+                .upload_stream(reader, content.len() as u64, "/stream.bin")
+                .await
+                .unwrap();
+            assert_eq!(result.bytes_uploaded, content.len() as u64);
+            assert_eq!(store.read().await["/stream.bin"].content, content);
+
+            // 以流的方式读回
+            let mut reader = provider.download_stream("/stream.bin").await.unwrap();
+            let mut downloaded = Vec::new();
+            tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut downloaded)
+                .await
+                .unwrap();
+            assert_eq!(downloaded, content);
+        }
+
+        fn mock_config(addr: SocketAddr) -> AccountConfig {
+            AccountConfig {
+                id: "test".to_string(),
+                provider: crate::config::ProviderType::WebDAV,
+                name: "test".to_string(),
+                credentials: {
+                    let mut creds = HashMap::new();
+                    creds.insert("url".to_string(), format!("http://{}", addr));
+                    creds.insert("username".to_string(), "test".to_string());
+                    creds.insert("password".to_string(), "test".to_string());
+                    creds
+                },
+                rate_limit: None,
+                retry_policy: crate::config::RetryPolicy::default(),
+                resource_limits: None,
+            }
+        }
+
+        async fn put_files(store: &FileStore, paths: &[&str]) {
+            let mut files = store.write().await;
+            for path in paths {
+                files.insert(
+                    path.to_string(),
+                    InMemoryFile {
+                        content: b"data".to_vec(),
+                        is_dir: false,
+                        modified: SERVER_MTIME,
+                    },
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn test_list_recursive_depth_infinity() {
+            let MockServer {
+                addr,
+                store,
+                depth_log,
+                ..
+            } = start_mock_server_with(true, "").await;
+            put_files(&store, &["/a.txt", "/dir/b c.txt", "/dir/sub/c.txt"]).await;
+            let provider = WebDavProvider::new(&mock_config(addr)).await.unwrap();
+
+            let mut paths: Vec<(String, bool)> = provider
+                .list_recursive("/")
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.path, f.is_dir))
+                .collect();
+            paths.sort();
+            assert_eq!(
+                paths,
+                vec![
+                    ("/a.txt".to_string(), false),
+                    ("/dir/".to_string(), true),
+                    ("/dir/b c.txt".to_string(), false),
+                    ("/dir/sub/".to_string(), true),
+                    ("/dir/sub/c.txt".to_string(), false),
+                ]
+            );
+            assert_eq!(provider.list("/").await.unwrap().len(), 2);
+            assert_eq!(*depth_log.lock().unwrap(), vec!["infinity", "1"]);
+        }
+
+        #[tokio::test]
+        async fn test_list_recursive_falls_back_when_rejected() {
+            let MockServer {
+                addr,
+                store,
+                depth_log,
+                ..
+            } = start_mock_server_with(false, "").await;
+            put_files(&store, &["/dir/b.txt"]).await;
+            let provider = WebDavProvider::new(&mock_config(addr)).await.unwrap();
+
+            assert!(matches!(
+                provider.list_recursive("/").await,
+                Err(SyncError::Unsupported(_))
+            ));
+            // 拒绝结果被记住，不再重复尝试
+            assert!(matches!(
+                provider.list_recursive("/").await,
+                Err(SyncError::Unsupported(_))
+            ));
+            assert_eq!(*depth_log.lock().unwrap(), vec!["infinity"]);
+            assert_eq!(provider.list("/dir").await.unwrap()[0].path, "/dir/b.txt");
+        }
+
+        #[tokio::test]
+        async fn test_parse_propfind_response_entities_and_absolute_href() {
+            let provider = WebDavProvider::new(&mock_config("127.0.0.1:8080".parse().unwrap()))
+                .await
+                .unwrap();
+            let xml = br#"<?xml version="1.0"?>
+                <D:multistatus xmlns:D="DAV:">
+                  <D:response><D:href>http://127.0.0.1:8080/docs/</D:href>
+                    <D:propstat><D:prop><D:resourcetype><D:collection></D:collection></D:resourcetype></D:prop></D:propstat>
+                  </D:response>
+                  <D:response><D:href>/docs/Tom%20&amp;%20Jerry.txt</D:href>
+                    <D:propstat><D:prop><D:getcontentlength>42</D:getcontentlength><D:resourcetype/></D:prop></D:propstat>
+                  </D:response>
+                </D:multistatus>"#;
+
+            let files = provider.parse_propfind_response(&xml[..]).await.unwrap();
+            let files = without_base(files, "/docs");
+            assert_eq!(files.len(), 1);
+            assert_eq!(files[0].path, "/docs/Tom & Jerry.txt");
+            assert_eq!(files[0].size, 42);
+            assert!(!files[0].is_dir);
+
+            assert!(
+                provider
+                    .parse_propfind_response(&b"<D:multistatus><D:response>"[..])
+                    .await
+                    .is_err()
+            );
+        }
+
+        #[tokio::test]
+        async fn test_parse_modified_etag_and_checksums() {
+            let xml = br#"<?xml version="1.0"?>
+                <d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+                  <d:response><d:href>/a.txt</d:href>
+                    <d:propstat><d:prop>
+                      <d:getlastmodified>Tue, 14 Nov 2023 22:13:20 GMT</d:getlastmodified>
+                      <d:getetag>W/"abc123"</d:getetag>
+                      <oc:checksums><oc:checksum>SHA1:AABBCC MD5:DDEEFF ADLER32:01</oc:checksum></oc:checksums>
+                    </d:prop></d:propstat>
+                  </d:response>
+                </d:multistatus>"#;
+
+            let mut config = mock_config("127.0.0.1:8080".parse().unwrap());
+            let provider = WebDavProvider::new(&config).await.unwrap();
+            let files = provider.parse_propfind_response(&xml[..]).await.unwrap();
+            assert_eq!(files[0].modified, 1_700_000_000);
+            assert_eq!(files[0].hash.as_deref(), Some("abc123"));
+            assert!(provider.capabilities().supports_hash.is_none());
+
+            config
+                .credentials
+                .insert("checksum".to_string(), "md5".to_string());
+            let provider = WebDavProvider::new(&config).await.unwrap();
+            let files = provider.parse_propfind_response(&xml[..]).await.unwrap();
+            assert_eq!(files[0].hash.as_deref(), Some("ddeeff"));
+            assert_eq!(
+                provider.capabilities().supports_hash,
+                Some(ChecksumType::Md5)
+            );
+
+            config
+                .credentials
+                .insert("checksum".to_string(), "crc32".to_string());
+            assert!(WebDavProvider::new(&config).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_set_mtime_via_proppatch() {
+            let addr = start_mock_server_with(true, "getlastmodified").await.addr;
+            let provider = WebDavProvider::new(&mock_config(addr)).await.unwrap();
+            assert_eq!(provider.mtime_method, Some(MtimeMethod::PropPatch));
+
+            let local = env::temp_dir().join(format!("webdav_mtime_{}", uuid::Uuid::new_v4()));
+            tokio::fs::write(&local, b"hello").await.unwrap();
+            provider.upload(&local, "/m.txt").await.unwrap();
+            assert_eq!(
+                provider.stat("/m.txt").await.unwrap().modified,
+                SERVER_MTIME
+            );
+
+            provider.set_mtime("/m.txt", 1_600_000_000).await.unwrap();
+            let info = provider.stat("/m.txt").await.unwrap();
+            assert_eq!(info.modified, 1_600_000_000);
+            assert_eq!(info.size, 5);
+            assert_eq!(info.hash.as_deref(), Some("5-1600000000"));
+            assert!(provider.capabilities().can_set_mtime);
+            tokio::fs::remove_file(&local).await.ok();
+        }
+
+        #[tokio::test]
+        async fn test_set_mtime_owncloud_and_rejection() {
+            // ownCloud 风格：上传时 X-OC-Mtime 生效，PROPPATCH 使用 lastmodified
+            let addr = start_mock_server_with(true, "lastmodified").await.addr;
+            let mut config = mock_config(addr);
+            config
+                .credentials
+                .insert("mtime".to_string(), "oc".to_string());
+            let provider = WebDavProvider::new(&config).await.unwrap();
+
+            let local = env::temp_dir().join(format!("webdav_oc_{}", uuid::Uuid::new_v4()));
+            tokio::fs::write(&local, b"hello").await.unwrap();
+            let local_mtime = std::fs::metadata(&local)
+                .unwrap()
+                .modified()
+                .unwrap()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64;
+            provider.upload(&local, "/oc.txt").await.unwrap();
+            assert_eq!(
+                provider.stat("/oc.txt").await.unwrap().modified,
+                local_mtime
+            );
+            provider.set_mtime("/oc.txt", 1_600_000_000).await.unwrap();
+            assert_eq!(
+                provider.stat("/oc.txt").await.unwrap().modified,
+                1_600_000_000
+            );
+
+            // 服务器不接受 getlastmodified 时放弃设置修改时间
+            let addr = start_mock_server_with(true, "lastmodified").await.addr;
+            let provider = WebDavProvider::new(&mock_config(addr)).await.unwrap();
+            provider.upload(&local, "/plain.txt").await.unwrap();
+            assert!(matches!(
+                provider.set_mtime("/plain.txt", 1_600_000_000).await,
+                Err(SyncError::Unsupported(_))
+            ));
+            assert!(!provider.capabilities().can_set_mtime);
+            assert!(matches!(
+                provider.stat("/missing.txt").await,
+                Err(SyncError::Provider(ProviderError::FileNotFound(_)))
+            ));
+            tokio::fs::remove_file(&local).await.ok();
+        }
+
+        #[test]
+        fn test_chunked_upload_endpoints() {
+            let nc = ChunkedUpload::from_base_url(
+                "https://cloud.example.com/nc/remote.php/dav/files/alice/Backup",
+                "alice@example.com",
+            )
+            .unwrap();
+            assert_eq!(
+                nc.uploads_url,
+                "https://cloud.example.com/nc/remote.php/dav/uploads/alice"
+            );
+            assert_eq!(
+                nc.files_url,
+                "https://cloud.example.com/nc/remote.php/dav/files/alice/Backup"
+            );
+
+            let legacy =
+                ChunkedUpload::from_base_url("https://oc.example.com/remote.php/webdav", "bob")
+                    .unwrap();
+            assert_eq!(
+                legacy.files_url,
+                "https://oc.example.com/remote.php/dav/files/bob"
+            );
+            assert!(ChunkedUpload::from_base_url("https://dav.example.com/dav", "bob").is_none());
+        }
+
+        #[tokio::test]
+        async fn test_chunked_upload_resumes_after_interruption() {
+            let server = start_mock_server_with(true, "lastmodified").await;
+            let mut config = mock_config(server.addr);
+            config.credentials.insert(
+                "url".to_string(),
+                format!("http://{}/remote.php/dav/files/alice", server.addr),
+            );
+            let resume_store = Arc::new(ChunkResumeStore::in_memory().unwrap());
+            let mut provider = WebDavProvider::new(&config)
+                .await
+                .unwrap()
+                .with_resume_store(resume_store.clone());
+            provider.chunk_size = 4;
+
+            let content = b"0123456789".to_vec();
+            *server.fail_chunk.lock().unwrap() = Some("3".to_string());
+            let stream =
+                |data: &Vec<u8>| -> ByteStream { Box::new(std::io::Cursor::new(data.clone())) };
+            assert!(
+                provider
+                    .upload_stream(stream(&content), content.len() as u64, "/big.bin")
+                    .await
+                    .is_err()
+            );
+            assert_eq!(server.chunk_log.lock().unwrap().len(), 2);
+
+            // 恢复后只上传剩余的分块
+            *server.fail_chunk.lock().unwrap() = None;
+            let result = provider
+                .upload_stream(stream(&content), content.len() as u64, "/big.bin")
+                .await
+                .unwrap();
+            assert_eq!(result.bytes_uploaded, 2);
+            assert_eq!(result.file_size, 10);
+            let chunk_log = server.chunk_log.lock().unwrap().clone();
+            assert_eq!(chunk_log.len(), 3);
+            assert!(chunk_log[2].ends_with("/3"));
+            assert_eq!(
+                chunk_log[0].rsplit_once('/').unwrap().0,
+                chunk_log[2].rsplit_once('/').unwrap().0
+            );
+
+            let files = server.store.read().await;
+            assert_eq!(
+                files["/remote.php/dav/files/alice/big.bin"].content,
+                content
+            );
+            assert!(!files.keys().any(|p| p.contains("/uploads/")));
+            drop(files);
+
+            // 上传完成后记录被清除，再次上传使用新的会话
+            provider
+                .upload_stream(stream(&content), content.len() as u64, "/big.bin")
+                .await
+                .unwrap();
+            assert_eq!(server.chunk_log.lock().unwrap().len(), 6);
+
+            // 不超过分块大小的文件仍然直接 PUT
+            provider
+                .upload_stream(stream(&b"abc".to_vec()), 3, "/small.bin")
+                .await
+                .unwrap();
+            assert_eq!(server.chunk_log.lock().unwrap().len(), 6);
+        }
+
+        #[tokio::test]
+        async fn test_quota_reports_used_and_available_bytes() {
+            let server = start_mock_server_with(true, "lastmodified").await;
+            put_files(&server.store, &["/a.txt", "/dir/b.txt"]).await;
+            let provider = WebDavProvider::new(&mock_config(server.addr))
+                .await
+                .unwrap();
+
+            let used: u64 = server
+                .store
+                .read()
+                .await
+                .values()
+                .map(|f| f.content.len() as u64)
+                .sum();
+            let quota = provider.quota().await.unwrap();
+            assert_eq!(quota.used_bytes, Some(used));
+            assert_eq!(quota.available_bytes, Some(QUOTA_AVAILABLE));
+            assert_eq!(quota.total_bytes(), Some(used + QUOTA_AVAILABLE));
+        }
+
+        #[test]
+        fn test_parse_quota_ignores_negative_values() {
+            let xml = br#"<d:multistatus xmlns:d="DAV:"><d:response><d:href>/</d:href>
+                <d:propstat><d:prop>
+                    <d:quota-available-bytes>-3</d:quota-available-bytes>
+                    <d:quota-used-bytes>2048</d:quota-used-bytes>
+                </d:prop></d:propstat></d:response></d:multistatus>"#;
+            let quota = parse_quota(xml);
+            assert_eq!(quota.used_bytes, Some(2048));
+            assert_eq!(quota.available_bytes, None);
+            assert_eq!(quota.total_bytes(), None);
+        }
+
+        #[tokio::test]
+        async fn test_upload_holds_lock_and_respects_foreign_locks() {
+            let server = start_mock_server_with(true, "lastmodified").await;
+            let mut config = mock_config(server.addr);
+            config
+                .credentials
+                .insert("lock".to_string(), "true".to_string());
+            let provider = WebDavProvider::new(&config).await.unwrap();
+
+            let data = b"locked write".to_vec();
+            provider
+                .upload_stream(Box::new(std::io::Cursor::new(data.clone())), 12, "/l.txt")
+                .await
+                .unwrap();
+            assert_eq!(
+                *server.lock_log.lock().unwrap(),
+                vec!["LOCK /l.txt", "PUT /l.txt", "UNLOCK /l.txt"]
+            );
+            assert!(server.locks.lock().unwrap().is_empty());
+            assert_eq!(server.store.read().await["/l.txt"].content, data);
+
+            // 其他客户端持有锁时不写入
+            server
+                .locks
+                .lock()
+                .unwrap()
+                .insert("/l.txt".to_string(), "urn:uuid:other".to_string());
+            let result = provider
+                .upload_stream(Box::new(std::io::Cursor::new(b"x".to_vec())), 1, "/l.txt")
+                .await;
+            assert!(matches!(result, Err(SyncError::Conflict(_))));
+            assert_eq!(server.store.read().await["/l.txt"].content, data);
+        }
+
+        type RangeHeaders = (Option<String>, Option<String>);
+
+        /// 可控制在何处断开连接的 HTTP 服务器，用于测试断点续传
+        struct RangeServer {
+            addr: SocketAddr,
+            /// 远端文件内容与 ETag
+            file: Arc<std::sync::Mutex<(Vec<u8>, String)>>,
+            /// 依次对每个请求生效：发送这么多字节后断开连接
+            cuts: Arc<std::sync::Mutex<std::collections::VecDeque<usize>>>,
+            /// 每个请求的 (Range, If-Range)
+            requests: Arc<std::sync::Mutex<Vec<RangeHeaders>>>,
+        }
+
+        async fn start_range_server(content: &[u8], etag: &str) -> RangeServer {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server = RangeServer {
+                addr: listener.local_addr().unwrap(),
+                file: Arc::new(std::sync::Mutex::new((content.to_vec(), etag.to_string()))),
+                cuts: Arc::default(),
+                requests: Arc::default(),
+            };
+            let (file, cuts, requests) = (
+                server.file.clone(),
+                server.cuts.clone(),
+                server.requests.clone(),
+            );
+            tokio::spawn(async move {
+                while let Ok((mut conn, _)) = listener.accept().await {
+                    let mut head = Vec::new();
+                    let mut byte = [0u8; 1];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        if conn.read(&mut byte).await.unwrap_or(0) == 0 {
+                            break;
+                        }
+                        head.push(byte[0]);
+                    }
+                    let head = String::from_utf8_lossy(&head).to_string();
+                    let header = |name: &str| {
+                        head.lines().find_map(|line| {
+                            let (key, value) = line.split_once(':')?;
+                            key.eq_ignore_ascii_case(name)
+                                .then(|| value.trim().to_string())
+                        })
+                    };
+                    // PROPFIND 列出根目录与 /file.bin，供 SyncEngine 扫描源端
+                    if head.starts_with("PROPFIND") {
+                        let length = header("content-length")
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or(0);
+                        let mut body = vec![0u8; length];
+                        let _ = conn.read_exact(&mut body).await;
+                        let (content, etag) = file.lock().unwrap().clone();
+                        let xml = format!(
+                            "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\">\
+                             <d:response><d:href>/</d:href><d:propstat><d:prop>\
+                             <d:resourcetype><d:collection/></d:resourcetype>\
+                             <d:getlastmodified>Thu, 01 Jan 2026 00:00:00 GMT</d:getlastmodified>\
+                             </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                             <d:response><d:href>/file.bin</d:href><d:propstat><d:prop>\
+                             <d:resourcetype/><d:getcontentlength>{}</d:getcontentlength>\
+                             <d:getetag>{}</d:getetag>\
+                             <d:getlastmodified>Thu, 01 Jan 2026 00:00:00 GMT</d:getlastmodified>\
+                             </d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                             </d:multistatus>",
+                            content.len(),
+                            etag
+                        );
+                        let response = format!(
+                            "HTTP/1.1 207 Multi-Status\r\nContent-Type: application/xml\r\n\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            xml.len(),
+                            xml
+                        );
+                        let _ = conn.write_all(response.as_bytes()).await;
+                        let _ = conn.shutdown().await;
+                        continue;
+                    }
+
+                    let (range, if_range) = (header("range"), header("if-range"));
+                    requests
+                        .lock()
+                        .unwrap()
+                        .push((range.clone(), if_range.clone()));
+
+                    let (content, etag) = file.lock().unwrap().clone();
+                    let start = range
+                        .filter(|_| if_range.as_deref() == Some(etag.as_str()))
+                        .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok());
+                    let (status, extra, body) = match start {
+                        Some(start) => (
+                            "206 Partial Content",
+                            format!(
+                                "Content-Range: bytes {}-{}/{}\r\n",
+                                start,
+                                content.len() - 1,
+                                content.len()
+                            ),
+                            content[start..].to_vec(),
+                        ),
+                        None => ("200 OK", String::new(), content),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: {}\r\n{}Connection: close\r\n\r\n",
+                        status,
+                        body.len(),
+                        etag,
+                        extra
+                    );
+                    let sent = cuts.lock().unwrap().pop_front().unwrap_or(body.len());
+                    let _ = conn.write_all(response.as_bytes()).await;
+                    let _ = conn.write_all(&body[..sent]).await;
+                    let _ = conn.shutdown().await;
+                }
+            });
+            server
+        }
+
+        #[tokio::test]
+        async fn test_download_resumes_with_range_and_if_range() {
+            let content: Vec<u8> = (0..100u8).collect();
+            let server = start_range_server(&content, "\"v1\"").await;
+            let provider = WebDavProvider::new(&mock_config(server.addr))
+                .await
+                .unwrap();
+            let dir = env::temp_dir().join(format!("webdav_range_{}", uuid::Uuid::new_v4()));
+            let local = dir.join("file.bin");
+
+            // 本次调用内断开一次，从断点继续
+            server.cuts.lock().unwrap().push_back(40);
+            let result = provider.download("/file.bin", &local).await.unwrap();
+            assert_eq!(tokio::fs::read(&local).await.unwrap(), content);
+            assert_eq!(result.resumed_bytes, 0);
+            assert_eq!(result.bytes_downloaded, 100);
+            assert_eq!(
+                server.requests.lock().unwrap()[1],
+                (Some("bytes=40-".to_string()), Some("\"v1\"".to_string()))
+            );
+            assert!(!sibling_path(&local, "part").exists());
+            assert!(!sibling_path(&local, "part.etag").exists());
+
+            // 重试次数用尽时保留 .part，下次调用继续
+            server.requests.lock().unwrap().clear();
+            server.cuts.lock().unwrap().extend([10, 10, 10, 10]);
+            assert!(provider.download("/file.bin", &local).await.is_err());
+            assert_eq!(
+                tokio::fs::read(sibling_path(&local, "part")).await.unwrap(),
+                content[..40]
+            );
+            let result = provider.download("/file.bin", &local).await.unwrap();
+            assert_eq!(tokio::fs::read(&local).await.unwrap(), content);
+            assert_eq!(result.resumed_bytes, 40);
+            assert_eq!(result.bytes_downloaded, 60);
+
+            // 远端文件在两次下载之间变化，If-Range 不匹配时重新下载完整内容
+            tokio::fs::write(sibling_path(&local, "part"), b"stale")
+                .await
+                .unwrap();
+            tokio::fs::write(sibling_path(&local, "part.etag"), "\"v0\"")
+                .await
+                .unwrap();
+            let result = provider.download("/file.bin", &local).await.unwrap();
+            assert_eq!(tokio::fs::read(&local).await.unwrap(), content);
+            assert_eq!(result.resumed_bytes, 0);
+
+            tokio::fs::remove_dir_all(&dir).await.ok();
+        }
+
+        #[tokio::test]
+        async fn test_download_stream_resumes_with_range_and_if_range() {
+            let content: Vec<u8> = (0..100u8).collect();
+            let server = start_range_server(&content, "\"v1\"").await;
+            let provider = WebDavProvider::new(&mock_config(server.addr))
+                .await
+                .unwrap();
+
+            // 读取中断后从已读位置继续
+            server.cuts.lock().unwrap().push_back(40);
+            let mut data = Vec::new();
+            provider
+                .download_stream("/file.bin")
+                .await
+                .unwrap()
+                .read_to_end(&mut data)
+                .await
+                .unwrap();
+            assert_eq!(data, content);
+            assert_eq!(
+                server.requests.lock().unwrap()[1],
+                (Some("bytes=40-".to_string()), Some("\"v1\"".to_string()))
+            );
+
+            // 远端文件在中断期间变化，不拼接新旧内容
+            server.cuts.lock().unwrap().push_back(40);
+            let mut reader = provider.download_stream("/file.bin").await.unwrap();
+            server.file.lock().unwrap().1 = "\"v2\"".to_string();
+            let mut data = Vec::new();
+            assert!(reader.read_to_end(&mut data).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_download_stream_maps_error_status() {
+            use warp::Filter;
+
+            let route = warp::path::param().map(|name: String| {
+                let status = match name.as_str() {
+                    "busy" => warp::http::StatusCode::TOO_MANY_REQUESTS,
+                    "broken" => warp::http::StatusCode::BAD_GATEWAY,
+                    "forbidden" => warp::http::StatusCode::FORBIDDEN,
+                    _ => warp::http::StatusCode::NOT_FOUND,
+                };
+                warp::reply::with_status(warp::reply(), status)
+            });
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            drop(listener);
+            tokio::spawn(warp::serve(route).run(addr));
+            while tokio::net::TcpStream::connect(addr).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let provider = WebDavProvider::new(&mock_config(addr)).await.unwrap();
+
+            let error = |path: &'static str| {
+                let provider = &provider;
+                async move { provider.download_stream(path).await.err().unwrap() }
+            };
+            let missing = error("/missing").await;
+            assert!(matches!(
+                missing,
+                SyncError::Provider(ProviderError::FileNotFound(_))
+            ));
+            assert!(!missing.is_retryable());
+            let busy = error("/busy").await;
+            assert!(matches!(
+                busy,
+                SyncError::Provider(ProviderError::RateLimited(..))
+            ));
+            let broken = error("/broken").await;
+            assert!(matches!(
+                broken,
+                SyncError::Provider(ProviderError::ServerError(_))
+            ));
+            assert!(broken.is_retryable());
+            let forbidden = error("/forbidden").await;
+            assert!(matches!(
+                forbidden,
+                SyncError::Provider(ProviderError::ApiError(_))
+            ));
+            assert!(!forbidden.is_retryable());
+        }
+
+        #[tokio::test]
+        async fn test_sync_engine_resumes_interrupted_webdav_stream() {
+            use crate::config::{DiffMode, SyncTask};
+            use crate::providers::MemoryProvider;
+            use crate::sync::engine::SyncEngine;
+
+            let content: Vec<u8> = (0..100u8).collect();
+            let server = start_range_server(&content, "\"v1\"").await;
+            let source = WebDavProvider::new(&mock_config(server.addr))
+                .await
+                .unwrap();
+
+            let mut engine = SyncEngine::new().await.unwrap();
+            engine.register_provider("src".to_string(), Box::new(source));
+            engine.register_provider("dst".to_string(), Box::new(MemoryProvider::new()));
+            let task = SyncTask {
+                id: "t_webdav_resume".to_string(),
+                name: "webdav resume".to_string(),
+                source_account: "src".to_string(),
+                source_path: "/".to_string(),
+                target_account: "dst".to_string(),
+                target_path: "/".to_string(),
+                schedule: None,
+                filters: vec![],
+                encryption: None,
+                compression: None,
+                diff_mode: DiffMode::Full,
+                preserve_metadata: false,
+                verify_integrity: false,
+                sync_policy: None,
+                resource_limits: None,
+                bandwidth_schedule: vec![],
+            };
+
+            // 同步途中源端连接断开一次，引擎的流式传输应从断点续传
+            server.cuts.lock().unwrap().push_back(40);
+            let report = engine.sync(&task).await.unwrap();
+            assert!(report.errors.is_empty(), "{:?}", report.errors);
+            assert_eq!(report.statistics.files_synced, 1);
+            assert!(
+                server
+                    .requests
+                    .lock()
+                    .unwrap()
+                    .contains(&(Some("bytes=40-".to_string()), Some("\"v1\"".to_string())))
+            );
+
+            let target = engine.get_provider("dst").unwrap();
+            let mut data = Vec::new();
+            target
+                .download_stream("/file.bin")
+                .await
+                .unwrap()
+                .read_to_end(&mut data)
+                .await
+                .unwrap();
+            assert_eq!(data, content);
+        }
     }
 }
@@ -28,10 +28,29 @@ impl SyncReport {
     }
 
     pub(crate) fn add_success(&mut self, diff_path: &String, diff_size: i64) {
-        let mut result = FileSyncResult::new(diff_path.clone(), FileOperation::Upload);
+        self.add_success_with_metadata(diff_path, diff_size, serde_json::Value::Null);
+    }
+
+    pub(crate) fn add_success_with_metadata(
+        &mut self,
+        diff_path: &str,
+        diff_size: i64,
+        metadata: serde_json::Value,
+    ) {
+        let mut result = FileSyncResult::new(diff_path.to_string(), FileOperation::Upload);
         result.status = FileSyncStatus::Success;
         result.size = diff_size.unsigned_abs();
         result.transferred_size = diff_size.unsigned_abs();
+        result.metadata = metadata;
+
+        self.statistics.add_file_result(&result);
+        self.files.push(result);
+    }
+
+    pub(crate) fn add_move(&mut self, diff_path: &str, old_path: &str) {
+        let mut result = FileSyncResult::new(diff_path.to_string(), FileOperation::Move);
+        result.status = FileSyncStatus::Success;
+        result.source_path = Some(old_path.to_string());
 
         self.statistics.add_file_result(&result);
         self.files.push(result);
@@ -1,10 +1,26 @@
-use crate::config::AccountConfig;
+use crate::config::{AccountConfig, ConfigManager};
+use crate::error::SyncError;
+use crate::providers::StorageQuota;
 use crate::services::provider_factory::create_provider;
 
 pub async fn verify_account_connection(
     account: &AccountConfig,
+    config: &ConfigManager,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let provider = create_provider(account).await?;
+    let provider = create_provider(account, config).await?;
     let _ = provider.list("/").await?;
     Ok(())
 }
+
+/// 查询账户的存储配额，提供商不支持时返回 `None`
+pub async fn query_account_quota(
+    account: &AccountConfig,
+    config: &ConfigManager,
+) -> Result<Option<StorageQuota>, Box<dyn std::error::Error>> {
+    let provider = create_provider(account, config).await?;
+    match provider.quota().await {
+        Ok(quota) => Ok(Some(quota)),
+        Err(SyncError::Unsupported(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
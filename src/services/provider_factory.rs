@@ -1,24 +1,142 @@
 use std::error::Error;
 
-use crate::config::{AccountConfig, ProviderType};
-use crate::providers::{AliYunDriveProvider, OneOneFiveProvider, StorageProvider, WebDavProvider};
+use std::sync::Arc;
 
+use crate::config::{AccountConfig, ConfigManager, ProviderType};
+use crate::error::SyncError;
+use crate::providers::fault::{FAULT_INJECTION_ENV, fault_injection_enabled};
+use crate::providers::{
+    AliYunDriveProvider, BandwidthLimitedProvider, CryptProvider, FaultConfig,
+    FaultInjectionProvider, LocalProvider, MemoryProvider, OneOneFiveProvider, RateLimitedProvider,
+    RetryingProvider, S3Provider, SftpProvider, SmbProvider, StorageProvider, WebDavProvider,
+};
+use crate::sync::resume::ChunkResumeStore;
+use tracing::warn;
+
+/// 按账户配置创建提供商，HTTP 提供商的客户端按 `config` 中的网络设置配置
+///
+/// 由内向外依次包装：设置了 `DISKSYNC_FAULT_INJECTION` 且凭证中含有 `fault_*` 项时的故障注入、
+/// 配置了带宽上限时的账户级限速、配置了 `rate_limit` 时的账户级限流（限速与限流均由同一账户的所有实例共享），
+/// 以及按 `retry_policy` 的自动重试，每次重试都重新经过限流。
+/// 加密账户（`Crypt`）包装 `remote` 凭据指定的账户，后者从 `config` 中查找并按自身配置创建。
 pub async fn create_provider(
     account: &AccountConfig,
+    config: &ConfigManager,
 ) -> Result<Box<dyn StorageProvider>, Box<dyn Error>> {
-    match account.provider {
+    let network = &config.network_settings();
+    let provider: Box<dyn StorageProvider> = match account.provider {
         ProviderType::AliYunDrive => {
-            let provider: AliYunDriveProvider = AliYunDriveProvider::new(account).await?;
-            Ok(Box::new(provider))
+            let provider: AliYunDriveProvider = AliYunDriveProvider::new(account)
+                .await?
+                .with_network_settings(network)?
+                .with_token_persister(Arc::new(persist_credentials));
+            Box::new(provider)
         }
         ProviderType::WebDAV => {
-            let provider: WebDavProvider = WebDavProvider::new(account).await?;
-            Ok(Box::new(provider))
+            let provider: WebDavProvider = WebDavProvider::new(account)
+                .await?
+                .with_network_settings(network)?
+                .with_resume_store(Arc::new(ChunkResumeStore::open_default()?));
+            Box::new(provider)
         }
         ProviderType::OneOneFive => {
-            let provider: OneOneFiveProvider = OneOneFiveProvider::new(account).await?;
-            Ok(Box::new(provider))
+            let provider: OneOneFiveProvider = OneOneFiveProvider::new(account)
+                .await?
+                .with_network_settings(network)?;
+            Box::new(provider)
+        }
+        ProviderType::Local => {
+            let provider: LocalProvider = LocalProvider::new(account).await?;
+            Box::new(provider)
+        }
+        ProviderType::S3 => {
+            let provider: S3Provider = S3Provider::new(account)
+                .await?
+                .with_network_settings(network)?;
+            Box::new(provider)
+        }
+        ProviderType::SFTP => {
+            let provider: SftpProvider = SftpProvider::new(account).await?;
+            Box::new(provider)
+        }
+        ProviderType::SMB => {
+            let provider: SmbProvider = SmbProvider::new(account).await?;
+            Box::new(provider)
+        }
+        ProviderType::Memory => Box::new(MemoryProvider::new()),
+        ProviderType::Crypt => {
+            // 被包装的账户自带限速、限流与重试，加密层本身不再重复包装
+            let remote_id = account
+                .credentials
+                .get("remote")
+                .ok_or("Missing credential for Crypt: remote")?;
+            let remote = config
+                .get_account(remote_id)
+                .ok_or_else(|| format!("Crypt remote account not found: {}", remote_id))?;
+            // 不允许加密层嵌套，也就排除了账户之间互相包装的循环
+            if matches!(remote.provider, ProviderType::Crypt) {
+                return Err(format!(
+                    "Crypt remote cannot be another Crypt account: {}",
+                    remote_id
+                )
+                .into());
+            }
+            let inner = Box::pin(create_provider(&remote, config)).await?;
+            return Ok(Box::new(CryptProvider::from_account(inner, account)?));
         }
-        _ => Err(format!("Unsupported provider type: {:?}", account.provider).into()),
-    }
+        _ => return Err(format!("Unsupported provider type: {:?}", account.provider).into()),
+    };
+
+    // 故障注入需要显式启用，误留在凭证中的 `fault_*` 项不会影响正常同步
+    let provider: Box<dyn StorageProvider> = if fault_injection_enabled() {
+        match FaultConfig::from_credentials(&account.credentials)? {
+            Some(faults) => Box::new(FaultInjectionProvider::new(provider, faults)),
+            None => provider,
+        }
+    } else {
+        if account.credentials.keys().any(|k| k.starts_with("fault_")) {
+            warn!(
+                account = %account.id,
+                "Ignoring fault_* credentials: {} is not set",
+                FAULT_INJECTION_ENV
+            );
+        }
+        provider
+    };
+    let provider: Box<dyn StorageProvider> = match account
+        .resource_limits
+        .as_ref()
+        .and_then(|limits| limits.max_network_bandwidth_bytes_per_sec)
+    {
+        Some(bytes_per_sec) => Box::new(BandwidthLimitedProvider::for_account(
+            provider,
+            &account.id,
+            bytes_per_sec,
+        )),
+        None => provider,
+    };
+    let provider: Box<dyn StorageProvider> = match &account.rate_limit {
+        Some(limits) => Box::new(RateLimitedProvider::for_account(
+            provider,
+            &account.id,
+            limits,
+        )),
+        None => provider,
+    };
+    Ok(Box::new(RetryingProvider::new(
+        provider,
+        &account.retry_policy,
+    )))
+}
+
+/// 将提供商刷新得到的凭据写回配置文件
+///
+/// 每次重新加载配置再合并，避免覆盖运行期间其他命令对配置的修改。
+fn persist_credentials(
+    account_id: &str,
+    updates: &std::collections::HashMap<String, String>,
+) -> Result<(), SyncError> {
+    let mut config_manager = ConfigManager::new()?;
+    config_manager.update_credentials(account_id, updates)?;
+    Ok(())
 }
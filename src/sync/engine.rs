@@ -1,8 +1,14 @@
 use crate::config::SyncPolicy;
-use crate::config::{DiffMode, SyncTask};
-use crate::encryption::EncryptionManager;
-use crate::error::{ProviderError, SyncError};
-use crate::providers::{FileInfo, StorageProvider};
+use crate::config::validator::ConfigValidatorImpl;
+use crate::config::{BandwidthRule, DiffMode, GlobalSettings, SyncTask, scheduled_bandwidth};
+use crate::core::rate_limit::BandwidthLimiter;
+use crate::core::resources::ResourceLimits;
+use crate::core::traits::ConfigValidator;
+use crate::error::{EncryptionError, ProviderError, SyncError};
+use crate::providers::{
+    CaseSensitivity, CompressProvider, CryptProvider, FileInfo, StorageProvider, bandwidth,
+    rate_limit,
+};
 use crate::report::{FileOperation, SyncReport};
 use crate::sync::diff::{DiffAction, DiffResult, FileDiff};
 use dashmap::DashMap;
@@ -10,29 +16,70 @@ use rusqlite::{Connection, params};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
-use tokio::time::{Duration, sleep};
+use tokio::time::Duration;
 use tracing::{debug, error, info, warn};
 
 pub struct SyncEngine {
     providers: HashMap<String, Box<dyn StorageProvider>>,
-    encryption_manager: EncryptionManager,
+    /// 已解锁的任务加密密钥：密钥 ID -> 主密钥
+    encryption_keys: HashMap<String, Vec<u8>>,
     diff_cache: DashMap<String, FileDiff>,
     resume_store: Arc<Mutex<Connection>>,
     /// 扫描缓存：key -> (列表快照, 上次扫描时间)
     scan_cache: DashMap<String, (Vec<FileInfo>, SystemTime)>,
+    /// 全局带宽限速器，该引擎执行的所有传输合计受限
+    bandwidth: ScheduledBandwidth,
 }
 
-impl SyncEngine {
-    pub async fn new() -> Result<Self, SyncError> {
-        let db_path = dirs::data_dir()
-            .ok_or(SyncError::Unknown(String::from(
-                "Failed to obtain data_dir",
-            )))?
-            .join("disksync");
+/// 同步期间重新检查带宽时间表的间隔
+const BANDWIDTH_SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 按时间表切换速率的带宽限速器，速率为 0 表示当前时段不限速
+#[derive(Clone)]
+struct ScheduledBandwidth {
+    limiter: Arc<BandwidthLimiter>,
+    schedule: Vec<BandwidthRule>,
+    default: Option<u64>,
+}
 
-        std::fs::create_dir_all(&db_path)?;
+impl ScheduledBandwidth {
+    fn new(schedule: Vec<BandwidthRule>, default: Option<u64>) -> Self {
+        let bytes_per_sec =
+            scheduled_bandwidth(&schedule, default, chrono::Local::now().naive_local());
+        Self {
+            limiter: Arc::new(BandwidthLimiter::new(bytes_per_sec.unwrap_or(0))),
+            schedule,
+            default,
+        }
+    }
+
+    /// 既没有上限也没有时间表时无需参与限速
+    fn is_configured(&self) -> bool {
+        self.default.is_some() || !self.schedule.is_empty()
+    }
+
+    /// 按 `at` 时刻适用的规则调整速率
+    fn refresh(&self, at: chrono::NaiveDateTime) {
+        let bytes_per_sec = scheduled_bandwidth(&self.schedule, self.default, at).unwrap_or(0);
+        if bytes_per_sec != self.limiter.bytes_per_sec() {
+            info!(bytes_per_sec, "Bandwidth limit switched by schedule");
+            self.limiter.set_bytes_per_sec(bytes_per_sec);
+        }
+    }
+}
+
+/// 同步结束（包括提前返回）时停止后台任务
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
 
-        let db_path = db_path.join("resume.db");
+impl SyncEngine {
+    pub async fn new() -> Result<Self, SyncError> {
+        let db_path = crate::sync::resume::default_db_path()?;
         let conn = Connection::open(&db_path)?;
 
         // 创建简历表
@@ -70,13 +117,78 @@ impl SyncEngine {
 
         Ok(Self {
             providers: HashMap::new(),
-            encryption_manager: EncryptionManager::new(),
+            encryption_keys: HashMap::new(),
             diff_cache: DashMap::new(),
             resume_store: Arc::new(Mutex::new(conn)),
             scan_cache: DashMap::new(),
+            bandwidth: ScheduledBandwidth::new(Vec::new(), None),
         })
     }
 
+    /// 应用全局资源限制，目前使用其中的带宽上限
+    pub fn set_resource_limits(&mut self, limits: &ResourceLimits) {
+        self.bandwidth = ScheduledBandwidth::new(
+            self.bandwidth.schedule.clone(),
+            limits.max_network_bandwidth_bytes_per_sec,
+        );
+    }
+
+    /// 应用全局带宽时间表，覆盖的时段内取代全局带宽上限
+    pub fn set_bandwidth_schedule(&mut self, schedule: Vec<BandwidthRule>) {
+        self.bandwidth = ScheduledBandwidth::new(schedule, self.bandwidth.default);
+    }
+
+    /// 校验并应用全局设置中的带宽上限与带宽时间表
+    pub fn apply_global_settings(&mut self, settings: &GlobalSettings) -> Result<(), SyncError> {
+        ConfigValidatorImpl::default().validate_global_settings(settings)?;
+        self.bandwidth = ScheduledBandwidth::new(
+            settings.bandwidth_schedule.clone(),
+            settings
+                .resource_limits
+                .as_ref()
+                .and_then(|limits| limits.max_network_bandwidth_bytes_per_sec),
+        );
+        Ok(())
+    }
+
+    /// 本次同步适用的带宽限速器：任务级与全局
+    fn bandwidth_limiters(&self, task: &SyncTask) -> Vec<ScheduledBandwidth> {
+        // 全局限速器跨多次同步复用，上次同步后可能已进入新的时段
+        self.bandwidth.refresh(chrono::Local::now().naive_local());
+        let task_bandwidth = ScheduledBandwidth::new(
+            task.bandwidth_schedule.clone(),
+            task.resource_limits
+                .as_ref()
+                .and_then(|limits| limits.max_network_bandwidth_bytes_per_sec),
+        );
+        [task_bandwidth, self.bandwidth.clone()]
+            .into_iter()
+            .filter(ScheduledBandwidth::is_configured)
+            .collect()
+    }
+
+    /// 存在时间表时在后台定期切换速率，长时间运行的同步也能按时段限速
+    fn watch_bandwidth_schedule(scheduled: &[ScheduledBandwidth]) -> Option<AbortOnDrop> {
+        let scheduled: Vec<_> = scheduled
+            .iter()
+            .filter(|bandwidth| !bandwidth.schedule.is_empty())
+            .cloned()
+            .collect();
+        if scheduled.is_empty() {
+            return None;
+        }
+        Some(AbortOnDrop(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BANDWIDTH_SCHEDULE_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let now = chrono::Local::now().naive_local();
+                for bandwidth in &scheduled {
+                    bandwidth.refresh(now);
+                }
+            }
+        })))
+    }
+
     /// 注册存储提供器到引擎
     pub fn register_provider(&mut self, account_id: String, provider: Box<dyn StorageProvider>) {
         self.providers.insert(account_id, provider);
@@ -86,6 +198,37 @@ impl SyncEngine {
         self.providers.get(account_id)
     }
 
+    /// 注册任务加密使用的主密钥，配置了 `encryption` 的任务按其 `key_id` 查找
+    pub fn register_encryption_key(&mut self, key_id: impl Into<String>, key: Vec<u8>) {
+        self.encryption_keys.insert(key_id.into(), key);
+    }
+
+    /// 任务的目标提供器，配置了加密时包装为 [`CryptProvider`]，配置了压缩时包装为 [`CompressProvider`]
+    fn target_provider(&self, task: &SyncTask) -> Result<Box<dyn StorageProvider + '_>, SyncError> {
+        let provider = self
+            .get_provider(&task.target_account)
+            .ok_or(SyncError::Provider(ProviderError::NotFound(
+                task.target_account.clone(),
+            )))?
+            .as_ref();
+        let provider: Box<dyn StorageProvider + '_> = match &task.encryption {
+            Some(config) => {
+                let key = self
+                    .encryption_keys
+                    .get(&config.key_id)
+                    .ok_or_else(|| EncryptionError::KeyNotFound(config.key_id.clone()))?;
+                // 只加密内容，目标上保持原有的目录结构与文件名
+                Box::new(CryptProvider::new(provider, key, false)?)
+            }
+            None => Box::new(provider),
+        };
+        // 压缩层在外，先压缩再加密，密文几乎无法再压缩
+        Ok(match &task.compression {
+            Some(config) => Box::new(CompressProvider::new(provider, config.clone())),
+            None => provider,
+        })
+    }
+
     pub async fn walk_directory(
         &self,
         provider: &dyn StorageProvider,
@@ -101,18 +244,6 @@ impl SyncEngine {
         Ok(map)
     }
 
-    fn create_temp_file(&self) -> Result<std::path::PathBuf, SyncError> {
-        let name = format!("sync_{}.tmp", uuid::Uuid::new_v4());
-        let path = std::env::temp_dir().join(name);
-        std::fs::File::create(&path)?;
-        Ok(path)
-    }
-
-    fn cleanup_temp_file(&self, path: &std::path::Path) -> Result<(), SyncError> {
-        std::fs::remove_file(path)?;
-        Ok(())
-    }
-
     pub async fn sync(&mut self, task: &SyncTask) -> Result<SyncReport, SyncError> {
         self.execute_sync(task, None::<fn(SyncProgress)>).await
     }
@@ -134,8 +265,12 @@ impl SyncEngine {
         F: Fn(SyncProgress) + Send + Sync + 'static,
     {
         info!(task_id = %task.id, "Starting sync task: {}", task.name);
+        let started_at = chrono::Utc::now();
         let mut report = SyncReport::new(&task.id);
 
+        // 目标提供商在整个同步过程中只创建一次，覆盖层可以沿用扫描阶段获得的目录列表
+        let target_provider = self.target_provider(task)?;
+
         // 计算文件差异（限定借用作用域）
         let diff = {
             let source_provider =
@@ -143,11 +278,8 @@ impl SyncEngine {
                     .ok_or(SyncError::Provider(ProviderError::NotFound(
                         task.source_account.clone(),
                     )))?;
-            let target_provider =
-                self.get_provider(&task.target_account)
-                    .ok_or(SyncError::Provider(ProviderError::NotFound(
-                        task.target_account.clone(),
-                    )))?;
+            // 目标提供商无法满足的任务配置在开始传输前报错
+            ConfigValidatorImpl::for_target(target_provider.capabilities()).validate_task(task)?;
             self.calculate_diff(
                 source_provider.as_ref(),
                 target_provider.as_ref(),
@@ -165,6 +297,12 @@ impl SyncEngine {
 
         let total_transfer_size = diff.total_transfer_size;
         let mut transferred_size = 0u64;
+        let limiters = self.bandwidth_limiters(task);
+        let _schedule_watcher = Self::watch_bandwidth_schedule(&limiters);
+        let bandwidth: Vec<_> = limiters
+            .iter()
+            .map(|scheduled| scheduled.limiter.clone())
+            .collect();
         let start_time = std::time::Instant::now();
 
         // 执行同步
@@ -177,11 +315,6 @@ impl SyncEngine {
                         && src_info.is_dir
                     {
                         debug!(path = %file_diff.path, "Creating directory (from Upload action)");
-                        let target_provider =
-                            self.get_provider(&task.target_account)
-                                .ok_or(SyncError::Provider(ProviderError::NotFound(
-                                    task.target_account.clone(),
-                                )))?;
                         let target_full_path = {
                             let base_path = std::path::Path::new(&task.target_path);
                             let rel_path = std::path::Path::new(&file_diff.path);
@@ -210,17 +343,11 @@ impl SyncEngine {
                         }
                     }
 
-                    // 重新获取 provider，避免与 &self 的可变借用冲突
                     let source_provider =
                         self.get_provider(&task.source_account)
                             .ok_or(SyncError::Provider(ProviderError::NotFound(
                                 task.source_account.clone(),
                             )))?;
-                    let target_provider =
-                        self.get_provider(&task.target_account)
-                            .ok_or(SyncError::Provider(ProviderError::NotFound(
-                                task.target_account.clone(),
-                            )))?;
 
                     let file_size = file_diff.transfer_size();
 
@@ -246,6 +373,7 @@ impl SyncEngine {
                             target_provider.as_ref(),
                             &file_diff,
                             task,
+                            &bandwidth,
                             &mut report,
                         )
                         .await
@@ -294,11 +422,6 @@ impl SyncEngine {
                 DiffAction::Delete => {
                     debug!(file = %file_diff.path, "Deleting target file");
                     // 删除目标文件
-                    let target_provider =
-                        self.get_provider(&task.target_account)
-                            .ok_or(SyncError::Provider(ProviderError::NotFound(
-                                task.target_account.clone(),
-                            )))?;
 
                     let target_full_path = {
                         let base_path = std::path::Path::new(&task.target_path);
@@ -326,11 +449,6 @@ impl SyncEngine {
                 }
                 DiffAction::CreateDir => {
                     debug!(path = %file_diff.path, "Creating directory");
-                    let target_provider =
-                        self.get_provider(&task.target_account)
-                            .ok_or(SyncError::Provider(ProviderError::NotFound(
-                                task.target_account.clone(),
-                            )))?;
                     let target_full_path = {
                         let base_path = std::path::Path::new(&task.target_path);
                         let rel_path = std::path::Path::new(&file_diff.path);
@@ -354,6 +472,75 @@ impl SyncEngine {
                         }
                     }
                 }
+                DiffAction::Move => {
+                    let old_path = file_diff
+                        .change_details
+                        .old_path
+                        .clone()
+                        .unwrap_or_default();
+                    debug!(from = %old_path, to = %file_diff.path, "Moving target file");
+                    let source_provider =
+                        self.get_provider(&task.source_account)
+                            .ok_or(SyncError::Provider(ProviderError::NotFound(
+                                task.source_account.clone(),
+                            )))?;
+
+                    let join_target = |rel: &str| {
+                        std::path::Path::new(&task.target_path)
+                            .join(rel)
+                            .to_string_lossy()
+                            .replace('\\', "/")
+                    };
+                    let from_full_path = join_target(&old_path);
+                    let to_full_path = join_target(&file_diff.path);
+
+                    let moved = if target_provider.capabilities().supports_server_move {
+                        target_provider
+                            .move_path(&from_full_path, &to_full_path)
+                            .await
+                    } else {
+                        match target_provider
+                            .copy_path(&from_full_path, &to_full_path)
+                            .await
+                        {
+                            Ok(_) => target_provider.delete(&from_full_path).await,
+                            Err(e) => Err(e),
+                        }
+                    };
+
+                    match moved {
+                        Ok(_) => {
+                            info!(from = %old_path, to = %file_diff.path, "Moved target file");
+                            report.add_move(&file_diff.path, &old_path);
+                        }
+                        Err(e) => {
+                            // 服务端移动失败时退回到重新上传 + 删除旧文件
+                            warn!(from = %old_path, to = %file_diff.path, error = %e, "Server-side move failed, falling back to upload");
+                            let fallback = match self
+                                .sync_file(
+                                    source_provider.as_ref(),
+                                    target_provider.as_ref(),
+                                    &file_diff,
+                                    task,
+                                    &bandwidth,
+                                    &mut report,
+                                )
+                                .await
+                            {
+                                Ok(_) => target_provider.delete(&from_full_path).await,
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = fallback {
+                                error!(file = %file_diff.path, error = %e, "Failed to move file");
+                                report.add_failure(
+                                    &file_diff.path,
+                                    FileOperation::Move,
+                                    e.to_string(),
+                                );
+                            }
+                        }
+                    }
+                }
                 DiffAction::Conflict => {
                     warn!(file = %file_diff.path, "Conflict detected");
                     report.add_conflict(&file_diff.path);
@@ -362,6 +549,27 @@ impl SyncEngine {
             }
         }
 
+        // 同步期间自适应限流的速率调整记入报告
+        let mut accounts = vec![task.source_account.as_str()];
+        if task.target_account != task.source_account {
+            accounts.push(task.target_account.as_str());
+        }
+        for account in accounts {
+            for change in rate_limit::rate_changes(account, started_at) {
+                report.warnings.push(format!(
+                    "{} 账户 {} 请求速率{}至 {:.2} 次/秒",
+                    change.time.format("%H:%M:%S"),
+                    account,
+                    if change.throttled {
+                        "因限流降低"
+                    } else {
+                        "恢复"
+                    },
+                    change.requests_per_second
+                ));
+            }
+        }
+
         let duration = start_time.elapsed().as_secs_f64();
         report.statistics.finalize(duration);
         report.duration_seconds = duration as i64;
@@ -438,11 +646,7 @@ impl SyncEngine {
                 .ok_or(SyncError::Provider(ProviderError::NotFound(
                     task.source_account.clone(),
                 )))?;
-        let target_provider =
-            self.get_provider(&task.target_account)
-                .ok_or(SyncError::Provider(ProviderError::NotFound(
-                    task.target_account.clone(),
-                )))?;
+        let target_provider = self.target_provider(task)?;
 
         let mut result = VerificationResult::new();
         let source_files = self
@@ -500,11 +704,7 @@ impl SyncEngine {
                 .ok_or(SyncError::Provider(ProviderError::NotFound(
                     task.source_account.clone(),
                 )))?;
-        let target_provider =
-            self.get_provider(&task.target_account)
-                .ok_or(SyncError::Provider(ProviderError::NotFound(
-                    task.target_account.clone(),
-                )))?;
+        let target_provider = self.target_provider(task)?;
 
         self.calculate_diff(
             source_provider.as_ref(),
@@ -591,39 +791,25 @@ impl SyncEngine {
 }
 
 impl SyncEngine {
-    async fn list_with_retry(
+    async fn recursive_list(
         &self,
         provider: &dyn StorageProvider,
-        path: &str,
+        root: &str,
     ) -> Result<Vec<FileInfo>, SyncError> {
-        let max_retries = 3;
-        let mut last_error = SyncError::Unknown("Initial".to_string());
-
-        for i in 0..=max_retries {
-            match provider.list(path).await {
-                Ok(list) => return Ok(list),
-                Err(e) => {
-                    last_error = e;
-                    if i < max_retries {
-                        sleep(Duration::from_millis(100 * (1 << i))).await;
-                    }
-                }
+        match provider.list_recursive(root).await {
+            Ok(entries) => return Ok(entries),
+            Err(SyncError::Unsupported(_)) => {}
+            Err(e) => {
+                warn!(root = root, error = %e, "Recursive listing failed, falling back to per-directory listing");
             }
         }
-        Err(last_error)
-    }
 
-    async fn recursive_list(
-        &self,
-        provider: &dyn StorageProvider,
-        root: &str,
-    ) -> Result<Vec<FileInfo>, SyncError> {
         let mut result = Vec::new();
         let mut stack = vec![root.to_string()];
 
         while let Some(dir) = stack.pop() {
-            // list_with_retry might fail for deep directories if we hit limits, but we have retry now.
-            let entries = self.list_with_retry(provider, &dir).await?;
+            // 重试由提供商的 RetryingProvider 按账户策略处理
+            let entries = provider.list(&dir).await?;
             for entry in entries {
                 if entry.is_dir {
                     // Ensure we don't get into infinite loop if provider returns "." or ".."
@@ -728,6 +914,9 @@ impl SyncEngine {
             meta.modified = info.modified;
             meta.is_dir = info.is_dir;
             meta.file_hash = info.hash.clone();
+            if let Some(mode) = info.permissions {
+                meta.permissions = mode;
+            }
             meta
         };
 
@@ -763,22 +952,81 @@ impl SyncEngine {
             all_paths.insert(p.clone());
         }
 
+        let source_caps = source.capabilities();
+        let target_caps = target.capabilities();
+        // 两端使用同一种校验和时才能直接比较哈希
+        let compare_hash = source_caps.supports_hash.is_some()
+            && source_caps.supports_hash == target_caps.supports_hash;
+
+        // 目标大小写不敏感时，源端仅大小写不同的路径会互相覆盖
+        let mut case_conflicts = std::collections::HashSet::new();
+        if target_caps.case_sensitivity == CaseSensitivity::Insensitive {
+            let mut seen: std::collections::HashMap<String, usize> =
+                std::collections::HashMap::new();
+            for p in src_map.keys() {
+                *seen.entry(p.to_lowercase()).or_default() += 1;
+            }
+            for p in src_map.keys() {
+                if seen.get(&p.to_lowercase()).copied().unwrap_or(0) > 1 {
+                    case_conflicts.insert(p.clone());
+                }
+            }
+        }
+
         let mut diff = DiffResult::new();
+        // 仅源存在 / 仅目标存在的文件，稍后尝试配对为移动操作
+        let mut pending_uploads = Vec::new();
+        let mut pending_deletes = Vec::new();
 
         for path in all_paths {
             let src_meta = src_map.get(&path);
             let dst_meta = dst_map.get(&path);
 
+            // 目标无法存放的文件直接跳过，避免每次同步都失败
+            if let Some(s) = src_meta
+                && !s.is_dir
+            {
+                let skip_tag = if target_caps.exceeds_max_file_size(s.size) {
+                    Some("skipped_too_large")
+                } else if target_caps.has_forbidden_chars(&path) {
+                    Some("skipped_forbidden_name")
+                } else if case_conflicts.contains(&path) {
+                    Some("skipped_case_conflict")
+                } else {
+                    None
+                };
+                if let Some(tag) = skip_tag {
+                    warn!(path = %path, reason = tag, "Target cannot store file, skipping");
+                    let mut d = FileDiff::new(
+                        path.clone(),
+                        DiffAction::Unchanged,
+                        Some(s.clone()),
+                        dst_meta.cloned(),
+                    );
+                    d.tags.push(tag.to_string());
+                    diff.add_file(d);
+                    continue;
+                }
+            }
+
             match (src_meta, dst_meta) {
                 (Some(s), Some(t)) => {
-                    // 两边都有，比较元数据
-                    // 这里可以加入更复杂的比较逻辑（如哈希）
-                    // 暂时只比较大小和修改时间
-                    let size_match = s.size == t.size;
-                    // 修改时间容差 2秒
-                    let time_match = (s.modified - t.modified).abs() <= 2;
-
-                    if size_match && time_match {
+                    // 两边都有，按双方能力选择比较方式
+                    let unchanged = if s.is_dir || t.is_dir {
+                        s.is_dir == t.is_dir
+                    } else if compare_hash
+                        && let (Some(sh), Some(th)) = (&s.file_hash, &t.file_hash)
+                    {
+                        s.size == t.size && sh.eq_ignore_ascii_case(th)
+                    } else if target_caps.can_set_mtime {
+                        // 修改时间容差 2秒
+                        s.size == t.size && (s.modified - t.modified).abs() <= 2
+                    } else {
+                        // 目标无法保留修改时间，只要目标不比源旧就认为相同
+                        s.size == t.size && s.modified <= t.modified + 2
+                    };
+
+                    if unchanged {
                         // 认为相同
                         diff.add_file(FileDiff::unchanged(path.clone(), s.clone(), t.clone()));
                     } else {
@@ -796,12 +1044,12 @@ impl SyncEngine {
                 }
                 (Some(s), None) => {
                     // 只有源有 -> Upload
-                    diff.add_file(FileDiff::upload(path.clone(), s.clone(), None));
+                    pending_uploads.push((path.clone(), s.clone()));
                 }
                 (None, Some(t)) => {
                     // 只有目标有 -> Delete (如果 delete_orphans) 否则 Unchanged (TargetOnly)
                     if delete_orphans {
-                        diff.add_file(FileDiff::delete(path.clone(), t.clone()));
+                        pending_deletes.push((path.clone(), t.clone()));
                     } else {
                         let mut d = FileDiff::unchanged(
                             path.clone(),
@@ -826,15 +1074,87 @@ impl SyncEngine {
             }
         }
 
+        // 目标支持服务端移动/复制时，把“删除 + 上传”配对为移动，避免重新上传
+        if target_caps.supports_server_move || target_caps.supports_server_copy {
+            for (from, to, s, t) in Self::pair_moves(&mut pending_uploads, &mut pending_deletes) {
+                debug!(from = %from, to = %to, "Detected move");
+                diff.add_file(FileDiff::move_file(from, to, s, t));
+            }
+        }
+        for (path, s) in pending_uploads {
+            diff.add_file(FileDiff::upload(path, s, None));
+        }
+        for (path, t) in pending_deletes {
+            diff.add_file(FileDiff::delete(path, t));
+        }
+
         Ok(diff)
     }
 
+    /// 将待上传与待删除的文件配对为移动操作，返回 (旧路径, 新路径, 源元数据, 目标元数据)
+    ///
+    /// 两端都有哈希时按哈希与大小匹配；否则要求文件名、大小一致且修改时间相差不超过 2 秒。
+    /// 只接受一对一的唯一匹配，存在歧义时保持原来的上传与删除。
+    fn pair_moves(
+        uploads: &mut Vec<(String, crate::sync::diff::FileMetadata)>,
+        deletes: &mut Vec<(String, crate::sync::diff::FileMetadata)>,
+    ) -> Vec<(
+        String,
+        String,
+        crate::sync::diff::FileMetadata,
+        crate::sync::diff::FileMetadata,
+    )> {
+        let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+        let is_same = |s: &crate::sync::diff::FileMetadata,
+                       s_path: &str,
+                       t: &crate::sync::diff::FileMetadata,
+                       t_path: &str| {
+            if s.is_dir || t.is_dir || s.size != t.size || s.size == 0 {
+                return false;
+            }
+            match (&s.file_hash, &t.file_hash) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => file_name(s_path) == file_name(t_path) && (s.modified - t.modified).abs() <= 2,
+            }
+        };
+
+        let mut pairs = Vec::new();
+        let mut i = 0;
+        while i < uploads.len() {
+            let (up_path, up_meta) = &uploads[i];
+            let candidates: Vec<usize> = deletes
+                .iter()
+                .enumerate()
+                .filter(|(_, (del_path, del_meta))| is_same(up_meta, up_path, del_meta, del_path))
+                .map(|(j, _)| j)
+                .collect();
+            let unique_source = candidates.len() == 1 && {
+                let (del_path, del_meta) = &deletes[candidates[0]];
+                uploads
+                    .iter()
+                    .filter(|(p, m)| is_same(m, p, del_meta, del_path))
+                    .count()
+                    == 1
+            };
+
+            if unique_source {
+                let (to, s) = uploads.remove(i);
+                let (from, t) = deletes.remove(candidates[0]);
+                pairs.push((from, to, s, t));
+            } else {
+                i += 1;
+            }
+        }
+        pairs
+    }
+
     async fn sync_file(
         &self,
         source: &dyn StorageProvider,
         target: &dyn StorageProvider,
         file_diff: &FileDiff,
         task: &SyncTask,
+        bandwidth: &[Arc<BandwidthLimiter>],
         report: &mut SyncReport,
     ) -> Result<(), SyncError> {
         // 构造完整路径辅助函数
@@ -869,35 +1189,49 @@ impl SyncEngine {
             }
         }
 
-        // 创建临时文件
-        let temp_path = self.create_temp_file()?;
-
-        // 下载文件
-        let _download_result = source.download(&source_full_path, &temp_path).await?;
-
-        // 加密（如果需要）
-        let (encrypted_data, _metadata) = if let Some(enc_config) = &task.encryption {
-            self.encryption_manager
-                .encrypt_file(&temp_path, enc_config)
-                .await?
-        } else {
-            (None, None)
+        // 直接在两个提供商之间流式传输，内存占用与文件大小无关
+        let size = match &file_diff.source_info {
+            Some(info) => info.size,
+            None => source.stat(&source_full_path).await?.size,
         };
+        let reader = bandwidth::limit_stream(
+            source.download_stream(&source_full_path).await?,
+            bandwidth.to_vec(),
+        );
+        let upload_result = target
+            .upload_stream(reader, size, &target_full_path)
+            .await?;
 
-        // 上传文件
-        let _upload_result = if let Some(encrypted) = encrypted_data {
-            // 上传加密文件
-            target.upload(&encrypted, &target_full_path).await?
-        } else {
-            // 上传原始文件
-            target.upload(&temp_path, &target_full_path).await?
-        };
+        // 目标支持时保留源文件的修改时间，便于下次按时间比较
+        if target.capabilities().can_set_mtime
+            && let Some(info) = &file_diff.source_info
+            && info.modified > 0
+            && let Err(e) = target.set_mtime(&target_full_path, info.modified).await
+        {
+            warn!(path = %target_full_path, error = %e, "Failed to preserve modification time");
+        }
 
-        // 记录成功
-        report.add_success(&file_diff.path, file_diff.size_diff);
+        // 按任务配置保留权限位，源端也需支持权限位才有可靠的值
+        if task.preserve_metadata
+            && source.capabilities().can_set_permissions
+            && target.capabilities().can_set_permissions
+            && let Some(info) = &file_diff.source_info
+            && let Err(e) = target
+                .set_permissions(&target_full_path, info.permissions)
+                .await
+        {
+            warn!(path = %target_full_path, error = %e, "Failed to preserve permissions");
+        }
 
-        // 清理临时文件
-        self.cleanup_temp_file(&temp_path)?;
+        // 记录成功，压缩上传时附带压缩后的大小
+        let metadata = match upload_result.compression {
+            Some(info) => serde_json::json!({
+                "compression": info.algorithm,
+                "compressed_size": info.compressed_size,
+            }),
+            None => serde_json::Value::Null,
+        };
+        report.add_success_with_metadata(&file_diff.path, file_diff.size_diff, metadata);
 
         Ok(())
     }
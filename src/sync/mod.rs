@@ -1,5 +1,6 @@
 pub mod diff;
 pub mod engine;
+pub mod resume;
 
 pub struct VerificationResult {
     pub total_files: i32,
@@ -0,0 +1,162 @@
+//! 分块上传的断点续传记录
+//!
+//! 与同步引擎共用 `resume.db`，提供商每上传完一个分块就写入一条记录，
+//! 中断后再次上传同一文件时可跳过服务器上已有的分块。
+
+use crate::error::SyncError;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 默认的断点续传数据库路径，所在目录不存在时创建
+pub fn default_db_path() -> Result<PathBuf, SyncError> {
+    let dir = dirs::data_dir()
+        .ok_or(SyncError::Unknown(String::from(
+            "Failed to obtain data_dir",
+        )))?
+        .join("disksync");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join("resume.db"))
+}
+
+/// 一次未完成的分块上传
+#[derive(Debug, Clone)]
+pub struct UploadSession {
+    /// 服务器上的上传会话标识
+    pub upload_id: String,
+    pub chunk_size: u64,
+    /// 已上传的分块序号及其 SHA-256
+    pub chunks: HashMap<u32, String>,
+}
+
+/// 分块上传进度存储
+pub struct ChunkResumeStore {
+    conn: Mutex<Connection>,
+}
+
+impl ChunkResumeStore {
+    pub fn open(path: &Path) -> Result<Self, SyncError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// 打开默认位置的 `resume.db`
+    pub fn open_default() -> Result<Self, SyncError> {
+        Self::open(&default_db_path()?)
+    }
+
+    /// 仅存在于内存中的存储，进程退出后丢失
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self, SyncError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, SyncError> {
+        // 同步引擎持有同一数据库的另一个连接
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS upload_sessions (
+                upload_key TEXT PRIMARY KEY,
+                upload_id TEXT NOT NULL,
+                chunk_size INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS upload_chunks (
+                upload_key TEXT NOT NULL,
+                chunk_index INTEGER NOT NULL,
+                checksum TEXT NOT NULL,
+                PRIMARY KEY (upload_key, chunk_index)
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 查询未完成的上传
+    pub fn session(&self, upload_key: &str) -> Result<Option<UploadSession>, SyncError> {
+        let conn = self.conn.lock().unwrap();
+        let session = conn
+            .query_row(
+                "SELECT upload_id, chunk_size FROM upload_sessions WHERE upload_key = ?1",
+                params![upload_key],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()?;
+        let Some((upload_id, chunk_size)) = session else {
+            return Ok(None);
+        };
+
+        let mut stmt =
+            conn.prepare("SELECT chunk_index, checksum FROM upload_chunks WHERE upload_key = ?1")?;
+        let chunks = stmt
+            .query_map(params![upload_key], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<u32, String>, _>>()?;
+
+        Ok(Some(UploadSession {
+            upload_id,
+            chunk_size: chunk_size as u64,
+            chunks,
+        }))
+    }
+
+    /// 开始新的上传，覆盖同一文件之前的记录
+    pub fn start_session(
+        &self,
+        upload_key: &str,
+        upload_id: &str,
+        chunk_size: u64,
+    ) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM upload_chunks WHERE upload_key = ?1",
+            params![upload_key],
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO upload_sessions (upload_key, upload_id, chunk_size, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                upload_key,
+                upload_id,
+                chunk_size as i64,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 记录一个已上传的分块
+    pub fn record_chunk(
+        &self,
+        upload_key: &str,
+        chunk_index: u32,
+        checksum: &str,
+    ) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO upload_chunks (upload_key, chunk_index, checksum)
+             VALUES (?1, ?2, ?3)",
+            params![upload_key, chunk_index, checksum],
+        )?;
+        Ok(())
+    }
+
+    /// 上传完成或放弃后删除记录
+    pub fn finish_session(&self, upload_key: &str) -> Result<(), SyncError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM upload_chunks WHERE upload_key = ?1",
+            params![upload_key],
+        )?;
+        conn.execute(
+            "DELETE FROM upload_sessions WHERE upload_key = ?1",
+            params![upload_key],
+        )?;
+        Ok(())
+    }
+}
@@ -23,3 +23,6 @@ pub fn generate_recovery_code(key: &[u8]) -> String {
     // 取前8个单词
     words[..8].join("-")
 }
+
+/// 由主密码派生密钥加密密钥时的 PBKDF2 迭代次数
+pub const KEY_DERIVATION_ITERATIONS: u32 = 100_000;
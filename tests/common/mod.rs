@@ -1,16 +1,10 @@
-use async_trait::async_trait;
 use bytes::Bytes;
-use cloud_disk_sync::error::{ProviderError, SyncError};
-use cloud_disk_sync::providers::{DownloadResult, FileInfo, StorageProvider, UploadResult};
-use rand::{Rng, rng};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::Once;
-use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::time::sleep;
 use warp::Filter;
 use warp::http::Method;
 
@@ -26,117 +20,6 @@ pub fn init_logging() {
     });
 }
 
-#[derive(Clone, Debug)]
-pub struct FaultConfig {
-    pub latency_ms: u64,
-    pub latency_jitter_ms: u64,
-    pub error_rate: f64,    // 0.0 to 1.0
-    pub error_type: String, // "timeout", "auth", "server"
-}
-
-impl Default for FaultConfig {
-    fn default() -> Self {
-        Self {
-            latency_ms: 0,
-            latency_jitter_ms: 0,
-            error_rate: 0.0,
-            error_type: "server".to_string(),
-        }
-    }
-}
-
-pub struct FaultInjectionProvider {
-    inner: Box<dyn StorageProvider>,
-    config: FaultConfig,
-}
-
-impl FaultInjectionProvider {
-    pub fn new(inner: Box<dyn StorageProvider>, config: FaultConfig) -> Self {
-        Self { inner, config }
-    }
-
-    async fn maybe_inject_fault(&self) -> Result<(), SyncError> {
-        // 1. 注入延迟
-        if self.config.latency_ms > 0 {
-            let jitter = if self.config.latency_jitter_ms > 0 {
-                rand::rng().random_range(0..=self.config.latency_jitter_ms)
-            } else {
-                0
-            };
-            sleep(Duration::from_millis(self.config.latency_ms + jitter)).await;
-        }
-
-        // 2. 注入错误
-        if self.config.error_rate > 0.0 {
-            let r: f64 = rand::random();
-            if r < self.config.error_rate {
-                return Err(match self.config.error_type.as_str() {
-                    "timeout" => {
-                        SyncError::Provider(ProviderError::Timeout("Simulated timeout".into()))
-                    }
-                    "auth" => SyncError::Provider(ProviderError::AuthFailed(
-                        "Simulated auth failure".into(),
-                    )),
-                    _ => SyncError::Provider(ProviderError::ApiError(
-                        "Simulated server error".into(),
-                    )),
-                });
-            }
-        }
-        Ok(())
-    }
-}
-
-#[async_trait]
-impl StorageProvider for FaultInjectionProvider {
-    async fn verify(&self) -> Result<(), SyncError> {
-        Ok(())
-    }
-
-    async fn list(&self, path: &str) -> Result<Vec<FileInfo>, SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.list(path).await
-    }
-
-    async fn upload(
-        &self,
-        local_path: &Path,
-        remote_path: &str,
-    ) -> Result<UploadResult, SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.upload(local_path, remote_path).await
-    }
-
-    async fn download(
-        &self,
-        remote_path: &str,
-        local_path: &Path,
-    ) -> Result<DownloadResult, SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.download(remote_path, local_path).await
-    }
-
-    async fn delete(&self, path: &str) -> Result<(), SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.delete(path).await
-    }
-
-    async fn mkdir(&self, path: &str) -> Result<(), SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.mkdir(path).await
-    }
-
-    async fn stat(&self, path: &str) -> Result<FileInfo, SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.stat(path).await
-    }
-
-    async fn exists(&self, path: &str) -> Result<bool, SyncError> {
-        self.maybe_inject_fault().await?;
-        self.inner.exists(path).await
-    }
-}
-
 // 辅助函数：生成测试文件
 pub async fn generate_test_files(dir: &Path, count: usize, size_bytes: usize) -> Vec<String> {
     let mut files = Vec::new();
@@ -308,11 +191,13 @@ pub async fn start_mock_server_with_seed(seed: Vec<(&str, &str, bool)>) -> (Sock
     let propfind_route = warp::method()
         .and(warp::path::full())
         .and(warp::header::optional("Depth"))
+        .and(warp::header::optional::<String>("Destination"))
         .and(warp::body::bytes())
         .and_then(
             move |method: Method,
                   path: warp::path::FullPath,
                   _depth: Option<String>,
+                  destination: Option<String>,
                   _body: Bytes| {
                 let store = store_prop.clone();
                 async move {
@@ -346,6 +231,33 @@ pub async fn start_mock_server_with_seed(seed: Vec<(&str, &str, bool)>) -> (Sock
                         ));
                     }
 
+                    if method == Method::from_bytes(b"MOVE").unwrap()
+                        || method == Method::from_bytes(b"COPY").unwrap()
+                    {
+                        // Destination 为完整 URL，取出其中的路径部分
+                        let dest = destination.unwrap_or_default();
+                        let dest_path = dest
+                            .splitn(4, '/')
+                            .nth(3)
+                            .map(|p| format!("/{}", p.trim_end_matches('/')))
+                            .unwrap_or_default();
+                        let mut files = store.write().await;
+                        let Some(entry) = files.get(&path_str).cloned() else {
+                            return Ok(warp::reply::with_status(
+                                String::new(),
+                                warp::http::StatusCode::NOT_FOUND,
+                            ));
+                        };
+                        if method == Method::from_bytes(b"MOVE").unwrap() {
+                            files.remove(&path_str);
+                        }
+                        files.insert(dest_path, entry);
+                        return Ok(warp::reply::with_status(
+                            String::new(),
+                            warp::http::StatusCode::CREATED,
+                        ));
+                    }
+
                     if method != Method::from_bytes(b"PROPFIND").unwrap() {
                         return Err(warp::reject::not_found());
                     }
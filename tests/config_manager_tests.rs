@@ -1,5 +1,8 @@
+use chrono::{NaiveDate, NaiveTime, Weekday};
 use cloud_disk_sync::config::{
-    AccountConfig, ConfigManager, DiffMode, ProviderType, RetryPolicy, SyncPolicy, SyncTask,
+    AccountConfig, BandwidthRule, ConfigFile, ConfigManager, DiffMode, OverlapPolicy, PluginConfig,
+    PluginSource, ProviderType, RetryPolicy, Schedule, ScheduleConfig, SyncPolicy, SyncTask,
+    scheduled_bandwidth,
 };
 use std::collections::HashMap;
 use std::fs;
@@ -35,6 +38,7 @@ fn test_config_manager_crud() {
         credentials: creds.clone(),
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     mgr.add_account(acc.clone()).unwrap();
 
@@ -49,6 +53,7 @@ fn test_config_manager_crud() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -57,6 +62,8 @@ fn test_config_manager_crud() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
     mgr.add_task(task.clone()).unwrap();
 
@@ -101,3 +108,197 @@ fn test_config_save_logging() {
     assert!(config_path.exists());
     let _ = fs::remove_file(config_path);
 }
+
+/// 凭据更新测试：刷新后的令牌合并写回并能重新加载
+#[test]
+fn test_config_manager_update_credentials() {
+    let config_path = get_test_config_path();
+    let mut mgr = ConfigManager::new_with_path(config_path.clone()).unwrap();
+
+    let mut creds = HashMap::new();
+    creds.insert("refresh_token".into(), "old-refresh".into());
+    creds.insert("client_id".into(), "cid".into());
+    mgr.add_account(AccountConfig {
+        id: "ali".into(),
+        provider: ProviderType::AliYunDrive,
+        name: "ali".into(),
+        credentials: creds,
+        rate_limit: None,
+        retry_policy: RetryPolicy::default(),
+        resource_limits: None,
+    })
+    .unwrap();
+
+    let mut updates = HashMap::new();
+    updates.insert("token".to_string(), "new-access".to_string());
+    updates.insert("refresh_token".to_string(), "new-refresh".to_string());
+    mgr.update_credentials("ali", &updates).unwrap();
+    assert!(mgr.update_credentials("missing", &updates).is_err());
+
+    let reloaded = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    let account = reloaded.get_account("ali").unwrap();
+    assert_eq!(account.credentials["token"], "new-access");
+    assert_eq!(account.credentials["refresh_token"], "new-refresh");
+    assert_eq!(account.credentials["client_id"], "cid");
+
+    let _ = fs::remove_file(config_path);
+}
+
+/// 保存往返测试：刷新令牌后写回配置，不会丢失插件、计划与安全设置等其他节
+#[test]
+fn test_config_manager_save_preserves_other_sections() {
+    let config_path = get_test_config_path();
+    let mut config = ConfigFile::new();
+    config.plugins.push(PluginConfig {
+        name: "notify".into(),
+        enabled: true,
+        version: "1.0.0".into(),
+        source: PluginSource::Builtin,
+        config: HashMap::new(),
+        hooks: vec![],
+    });
+    config.schedules.push(ScheduleConfig {
+        id: "nightly".into(),
+        name: "nightly".into(),
+        schedule: Schedule::Interval { seconds: 86400 },
+        task_ids: vec!["t1".into()],
+        enabled: true,
+        max_runtime: None,
+        overlap_policy: OverlapPolicy::Skip,
+        notifications: vec![],
+    });
+    fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+    let mut mgr = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    mgr.add_account(AccountConfig {
+        id: "ali".into(),
+        provider: ProviderType::AliYunDrive,
+        name: "ali".into(),
+        credentials: HashMap::from([("refresh_token".to_string(), "old".to_string())]),
+        rate_limit: None,
+        retry_policy: RetryPolicy::default(),
+        resource_limits: None,
+    })
+    .unwrap();
+    let updates = HashMap::from([("refresh_token".to_string(), "new".to_string())]);
+    mgr.update_credentials("ali", &updates).unwrap();
+
+    let saved: ConfigFile =
+        serde_yaml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+    assert_eq!(saved.plugins.len(), 1);
+    assert_eq!(saved.plugins[0].name, "notify");
+    assert_eq!(saved.schedules.len(), 1);
+    assert_eq!(saved.schedules[0].task_ids, vec!["t1".to_string()]);
+    assert!(saved.security_settings.is_some());
+
+    let _ = fs::remove_file(config_path);
+}
+
+/// 全局设置测试：只写出带宽上限的资源限制可以加载，保存后保持不变
+#[test]
+fn test_config_manager_preserves_global_resource_limits() {
+    let config_path = get_test_config_path();
+    let mut config = serde_yaml::to_value(ConfigFile::new()).unwrap();
+    config["global_settings"]["resource_limits"] =
+        serde_yaml::from_str("max_network_bandwidth_bytes_per_sec: 1048576").unwrap();
+    fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+    let mgr = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    let limits = mgr.global_settings().resource_limits.clone().unwrap();
+    assert_eq!(limits.max_network_bandwidth_bytes_per_sec, Some(1_048_576));
+    assert_eq!(limits.max_memory_bytes, None);
+    limits.validate().unwrap();
+
+    mgr.save().unwrap();
+    let reloaded = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    assert_eq!(
+        reloaded
+            .global_settings()
+            .resource_limits
+            .as_ref()
+            .and_then(|l| l.max_network_bandwidth_bytes_per_sec),
+        Some(1_048_576)
+    );
+
+    let _ = fs::remove_file(config_path);
+}
+
+/// 带宽时间表测试：工作时间限速、夜间不限速的时间表可以加载，并按时刻选出速率
+#[test]
+fn test_config_manager_bandwidth_schedule() {
+    let config_path = get_test_config_path();
+    let mut config = serde_yaml::to_value(ConfigFile::new()).unwrap();
+    config["global_settings"]["bandwidth_schedule"] = serde_yaml::from_str(
+        r#"
+- weekdays: [Mon, Tue, Wed, Thu, Fri]
+  start: "09:00:00"
+  end: "18:00:00"
+  bytes_per_sec: 1048576
+- start: "23:00:00"
+  end: "06:00:00"
+  bytes_per_sec: null
+"#,
+    )
+    .unwrap();
+    fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+    let mgr = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    let schedule = mgr.global_settings().bandwidth_schedule.clone();
+    assert_eq!(schedule.len(), 2);
+    assert_eq!(schedule[0].weekdays.len(), 5);
+    assert!(schedule[1].weekdays.is_empty());
+
+    // 2026-10-16 是星期五
+    let friday = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let saturday = friday.succ_opt().unwrap();
+    let at = |day: NaiveDate, h: u32, m: u32| day.and_hms_opt(h, m, 0).unwrap();
+    let default = Some(512 * 1024);
+
+    assert_eq!(
+        scheduled_bandwidth(&schedule, default, at(friday, 10, 0)),
+        Some(1_048_576)
+    );
+    // 结束时间不含
+    assert_eq!(
+        scheduled_bandwidth(&schedule, default, at(friday, 18, 0)),
+        default
+    );
+    assert_eq!(
+        scheduled_bandwidth(&schedule, default, at(saturday, 10, 0)),
+        default
+    );
+    // 跨午夜的规则在次日凌晨仍然生效
+    assert_eq!(
+        scheduled_bandwidth(&schedule, default, at(saturday, 2, 30)),
+        None
+    );
+    assert_eq!(
+        scheduled_bandwidth(&schedule, default, at(friday, 23, 30)),
+        None
+    );
+
+    mgr.save().unwrap();
+    let reloaded = ConfigManager::new_with_path(config_path.clone()).unwrap();
+    assert_eq!(reloaded.global_settings().bandwidth_schedule, schedule);
+
+    let _ = fs::remove_file(config_path);
+}
+
+/// 带宽规则测试：限定星期的跨午夜规则按开始时间所在的那一天判断
+#[test]
+fn test_bandwidth_rule_overnight_weekdays() {
+    let rule = BandwidthRule {
+        weekdays: vec![Weekday::Fri],
+        start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+        bytes_per_sec: Some(1024),
+    };
+    let friday = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let thursday = friday.pred_opt().unwrap();
+    let saturday = friday.succ_opt().unwrap();
+
+    assert!(rule.matches(friday.and_hms_opt(23, 0, 0).unwrap()));
+    assert!(rule.matches(saturday.and_hms_opt(1, 0, 0).unwrap()));
+    assert!(!rule.matches(friday.and_hms_opt(1, 0, 0).unwrap()));
+    assert!(!rule.matches(thursday.and_hms_opt(23, 0, 0).unwrap()));
+}
@@ -1,10 +1,15 @@
-use cloud_disk_sync::config::{AccountConfig, DiffMode, RetryPolicy, SyncPolicy, SyncTask};
-use cloud_disk_sync::providers::StorageProvider;
+use cloud_disk_sync::config::{
+    AccountConfig, CompressionAlgorithm, CompressionConfig, DiffMode, EncryptionConfig,
+    RetryPolicy, SyncPolicy, SyncTask,
+};
+use cloud_disk_sync::encryption::types::{EncryptionAlgorithm, IvMode};
 use cloud_disk_sync::providers::WebDavProvider;
+use cloud_disk_sync::providers::{CryptProvider, MemoryProvider, StorageProvider};
 use cloud_disk_sync::sync::engine::SyncEngine;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::time::Duration;
+use tokio::io::AsyncReadExt;
 use tracing::info;
 
 mod common;
@@ -55,6 +60,7 @@ async fn test_consistency_deep_nesting() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: true, // 开启校验
@@ -63,6 +69,8 @@ async fn test_consistency_deep_nesting() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let report = engine.sync(&task).await.unwrap();
@@ -171,6 +179,7 @@ async fn test_consistency_conflict_skip() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: false,
@@ -179,6 +188,8 @@ async fn test_consistency_conflict_skip() {
             overwrite_existing: false, // 关键：不覆盖
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let report = engine.sync(&task).await.unwrap();
@@ -247,6 +258,7 @@ async fn test_consistency_conflict_overwrite() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: false,
@@ -255,6 +267,8 @@ async fn test_consistency_conflict_overwrite() {
             overwrite_existing: true, // 关键：覆盖
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let report = engine.sync(&task).await.unwrap();
@@ -279,6 +293,359 @@ async fn test_consistency_conflict_overwrite() {
     assert_eq!(content, "source content modified");
 }
 
+#[tokio::test]
+async fn test_consistency_memory_providers_preserve_metadata() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/a.txt", "alpha", 1_600_000_000)
+        .unwrap();
+    src_provider
+        .insert_file("/file_root/sub/b.txt", "beta", 1_600_000_100)
+        .unwrap();
+    src_provider
+        .set_permissions("/file_root/sub/b.txt", 0o600)
+        .await
+        .unwrap();
+
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src".to_string(), Box::new(src_provider));
+    engine.register_provider("dst".to_string(), Box::new(dst_provider));
+
+    let task = SyncTask {
+        id: "t_memory".to_string(),
+        name: "memory sync".to_string(),
+        source_account: "src".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: true,
+        verify_integrity: true,
+        sync_policy: Some(SyncPolicy {
+            delete_orphans: true,
+            overwrite_existing: true,
+            scan_cooldown_secs: 0,
+        }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+
+    let src = engine.get_provider("src").unwrap();
+    let dst = engine.get_provider("dst").unwrap();
+    for (from, to) in [
+        ("/file_root/a.txt", "/backup/a.txt"),
+        ("/file_root/sub/b.txt", "/backup/sub/b.txt"),
+    ] {
+        let expected = src.stat(from).await.unwrap();
+        let actual = dst.stat(to).await.unwrap();
+        assert_eq!(actual.size, expected.size);
+        assert_eq!(actual.hash, expected.hash);
+        assert_eq!(actual.modified, expected.modified);
+        assert_eq!(actual.permissions, expected.permissions);
+    }
+
+    // 两端哈希与修改时间一致，再次同步不应传输任何文件
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 0);
+}
+
+#[tokio::test]
+async fn test_consistency_crypt_target_round_trip() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/a.txt", "alpha", 1_600_000_000)
+        .unwrap();
+    src_provider
+        .insert_file("/file_root/sub/b.txt", vec![7u8; 200 * 1024], 1_600_000_100)
+        .unwrap();
+
+    let dst_provider = CryptProvider::new(MemoryProvider::new(), &[9u8; 32], true).unwrap();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_crypt".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_crypt".to_string(), Box::new(dst_provider));
+
+    let task = SyncTask {
+        id: "t_crypt".to_string(),
+        name: "crypt sync".to_string(),
+        source_account: "src_crypt".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_crypt".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: true,
+        verify_integrity: false,
+        sync_policy: Some(SyncPolicy {
+            delete_orphans: true,
+            overwrite_existing: true,
+            scan_cooldown_secs: 0,
+        }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+
+    // 加密端按明文路径与大小呈现，内容解密后与源一致
+    let src = engine.get_provider("src_crypt").unwrap();
+    let dst = engine.get_provider("dst_crypt").unwrap();
+    for (from, to) in [
+        ("/file_root/a.txt", "/backup/a.txt"),
+        ("/file_root/sub/b.txt", "/backup/sub/b.txt"),
+    ] {
+        let expected = src.stat(from).await.unwrap();
+        let actual = dst.stat(to).await.unwrap();
+        assert_eq!(actual.size, expected.size);
+        assert_eq!(actual.modified, expected.modified);
+
+        let mut expected_content = Vec::new();
+        let mut actual_content = Vec::new();
+        src.download_stream(from)
+            .await
+            .unwrap()
+            .read_to_end(&mut expected_content)
+            .await
+            .unwrap();
+        dst.download_stream(to)
+            .await
+            .unwrap()
+            .read_to_end(&mut actual_content)
+            .await
+            .unwrap();
+        assert_eq!(actual_content, expected_content);
+    }
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 0);
+}
+
+/// 任务级加密经由 CryptProvider 写入目标，未解锁密钥时拒绝同步
+#[tokio::test]
+async fn test_consistency_task_encryption_round_trip() {
+    common::init_logging();
+    let content = vec![5u8; 100 * 1024];
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/data.bin", content.clone(), 1_600_000_000)
+        .unwrap();
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_enc".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_enc".to_string(), Box::new(dst_provider));
+
+    let task = SyncTask {
+        id: "t_task_encryption".to_string(),
+        name: "task encryption".to_string(),
+        source_account: "src_enc".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_enc".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: Some(EncryptionConfig {
+            algorithm: EncryptionAlgorithm::Aes256Gcm,
+            key_id: "backup".to_string(),
+            iv_mode: IvMode::Random,
+        }),
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    assert!(engine.sync(&task).await.is_err());
+
+    let key = [3u8; 32];
+    engine.register_encryption_key("backup", key.to_vec());
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+
+    // 目标端保存密文，文件名不变，以同一密钥解密后与源一致
+    let dst = engine.get_provider("dst_enc").unwrap();
+    let mut stored = Vec::new();
+    dst.download_stream("/backup/data.bin")
+        .await
+        .unwrap()
+        .read_to_end(&mut stored)
+        .await
+        .unwrap();
+    assert!(stored.starts_with(b"CDSCRYPT"));
+    assert_ne!(stored.len(), content.len());
+
+    let mut decrypted = Vec::new();
+    CryptProvider::new(dst, &key, false)
+        .unwrap()
+        .download_stream("/backup/data.bin")
+        .await
+        .unwrap()
+        .read_to_end(&mut decrypted)
+        .await
+        .unwrap();
+    assert_eq!(decrypted, content);
+
+    // 比较按明文大小进行，再次同步无需传输
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 0);
+}
+
+/// 同时启用压缩与加密时先压缩再加密，目标端密文远小于原文
+#[tokio::test]
+async fn test_consistency_compress_then_encrypt() {
+    common::init_logging();
+    let log = b"2026-10-17 INFO request served\n".repeat(2000);
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/app.log", log.clone(), 1_600_000_000)
+        .unwrap();
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_both".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_both".to_string(), Box::new(dst_provider));
+    engine.register_encryption_key("backup", vec![4u8; 32]);
+
+    let task = SyncTask {
+        id: "t_compress_encrypt".to_string(),
+        name: "compress then encrypt".to_string(),
+        source_account: "src_both".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_both".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: Some(EncryptionConfig {
+            algorithm: EncryptionAlgorithm::Aes256Gcm,
+            key_id: "backup".to_string(),
+            iv_mode: IvMode::Random,
+        }),
+        compression: Some(CompressionConfig {
+            algorithm: CompressionAlgorithm::Zstd,
+            level: None,
+        }),
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    let result = report.files.iter().find(|f| f.path == "app.log").unwrap();
+    assert_eq!(result.metadata["compression"], "Zstd");
+
+    let dst = engine.get_provider("dst_both").unwrap();
+    let stored = dst.list("/backup").await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert!(stored[0].path.starts_with("/backup/app.log.csz-"));
+    assert!(stored[0].size < log.len() as u64 / 10);
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 0);
+}
+
+#[tokio::test]
+async fn test_consistency_compressed_target_round_trip() {
+    common::init_logging();
+    let log = b"2026-10-17 INFO request served\n".repeat(2000);
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/app.log", log.clone(), 1_600_000_000)
+        .unwrap();
+    src_provider
+        .insert_file(
+            "/file_root/photo.jpg",
+            vec![0xFFu8, 0xD8, 0xFF, 0xE0],
+            1_600_000_100,
+        )
+        .unwrap();
+
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_zstd".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_zstd".to_string(), Box::new(dst_provider));
+
+    let task = SyncTask {
+        id: "t_zstd".to_string(),
+        name: "compressed sync".to_string(),
+        source_account: "src_zstd".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_zstd".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: Some(CompressionConfig {
+            algorithm: CompressionAlgorithm::Zstd,
+            level: None,
+        }),
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+
+    // 报告记录压缩后的大小，已压缩的图片原样上传
+    let log_result = report.files.iter().find(|f| f.path == "app.log").unwrap();
+    let compressed_size = log_result.metadata["compressed_size"].as_u64().unwrap();
+    assert!(compressed_size < log.len() as u64 / 10);
+    let photo_result = report.files.iter().find(|f| f.path == "photo.jpg").unwrap();
+    assert!(photo_result.metadata.is_null());
+
+    // 目标端保存的是压缩文件，图片保持原名
+    let dst = engine.get_provider("dst_zstd").unwrap();
+    let stored: Vec<String> = dst
+        .list("/backup")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    assert!(stored.contains(&"/backup/photo.jpg".to_string()));
+    assert!(stored.iter().any(|p| p.starts_with("/backup/app.log.csz-")));
+    assert!(!stored.contains(&"/backup/app.log".to_string()));
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 0);
+}
+
 // Helpers
 fn create_test_config(id: &str, addr: SocketAddr) -> AccountConfig {
     AccountConfig {
@@ -294,6 +661,7 @@ fn create_test_config(id: &str, addr: SocketAddr) -> AccountConfig {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     }
 }
 
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
 use cloud_disk_sync::config::{DiffMode, SyncPolicy, SyncTask};
 use cloud_disk_sync::error::SyncError;
-use cloud_disk_sync::providers::{DownloadResult, FileInfo, StorageProvider, UploadResult};
-use cloud_disk_sync::sync::diff::DiffAction;
+use cloud_disk_sync::providers::{
+    DownloadResult, FileInfo, ProviderCapabilities, StorageProvider, UploadResult,
+};
+use cloud_disk_sync::sync::diff::{ChecksumType, DiffAction};
 use cloud_disk_sync::sync::engine::SyncEngine;
 use std::collections::HashMap;
 use std::path::Path;
@@ -12,6 +14,7 @@ use std::sync::{Arc, Mutex};
 #[derive(Clone)]
 struct MockProvider {
     files: Arc<Mutex<HashMap<String, FileInfo>>>,
+    capabilities: ProviderCapabilities,
 }
 
 impl MockProvider {
@@ -22,8 +25,14 @@ impl MockProvider {
         }
         Self {
             files: Arc::new(Mutex::new(map)),
+            capabilities: ProviderCapabilities::default(),
         }
     }
+
+    fn with_capabilities(mut self, capabilities: ProviderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
 }
 
 #[async_trait]
@@ -32,6 +41,10 @@ impl StorageProvider for MockProvider {
         Ok(())
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.capabilities.clone()
+    }
+
     async fn list(&self, _path: &str) -> Result<Vec<FileInfo>, SyncError> {
         let files = self.files.lock().unwrap();
         Ok(files.values().cloned().collect())
@@ -47,6 +60,7 @@ impl StorageProvider for MockProvider {
             file_size: 0,
             checksum: None,
             elapsed_time: std::time::Duration::from_secs(0),
+            compression: None,
         })
     }
 
@@ -60,6 +74,7 @@ impl StorageProvider for MockProvider {
             file_size: 0,
             checksum: None,
             elapsed_time: std::time::Duration::from_secs(0),
+            resumed_bytes: 0,
         })
     }
 
@@ -91,17 +106,33 @@ fn create_file_info(path: &str, size: u64, modified: i64) -> FileInfo {
         modified,
         is_dir: false,
         hash: None,
+        permissions: None,
     }
 }
 
 async fn setup_engine(
     src_files: Vec<FileInfo>,
     dst_files: Vec<FileInfo>,
+) -> (SyncEngine, SyncTask) {
+    setup_engine_with_capabilities(
+        src_files,
+        dst_files,
+        ProviderCapabilities::default(),
+        ProviderCapabilities::default(),
+    )
+    .await
+}
+
+async fn setup_engine_with_capabilities(
+    src_files: Vec<FileInfo>,
+    dst_files: Vec<FileInfo>,
+    src_caps: ProviderCapabilities,
+    dst_caps: ProviderCapabilities,
 ) -> (SyncEngine, SyncTask) {
     let mut engine = SyncEngine::new().await.unwrap();
 
-    let src_provider = MockProvider::new(src_files);
-    let dst_provider = MockProvider::new(dst_files);
+    let src_provider = MockProvider::new(src_files).with_capabilities(src_caps);
+    let dst_provider = MockProvider::new(dst_files).with_capabilities(dst_caps);
 
     engine.register_provider("src".to_string(), Box::new(src_provider));
     engine.register_provider("dst".to_string(), Box::new(dst_provider));
@@ -116,6 +147,7 @@ async fn setup_engine(
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: true,
         verify_integrity: false,
@@ -124,6 +156,8 @@ async fn setup_engine(
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     (engine, task)
@@ -244,3 +278,108 @@ async fn test_diff_skip_overwrite() {
     assert!(matches!(file.action, DiffAction::Unchanged));
     assert!(file.tags.contains(&"skipped_overwrite".to_string()));
 }
+
+fn create_hashed_file_info(path: &str, size: u64, modified: i64, hash: &str) -> FileInfo {
+    FileInfo {
+        hash: Some(hash.to_string()),
+        ..create_file_info(path, size, modified)
+    }
+}
+
+#[tokio::test]
+async fn test_diff_hash_comparison_ignores_mtime() {
+    let caps = ProviderCapabilities {
+        supports_hash: Some(ChecksumType::Sha1),
+        ..ProviderCapabilities::default()
+    };
+    let src_files = vec![
+        create_hashed_file_info("/same.txt", 100, 5000, "abc"),
+        create_hashed_file_info("/changed.txt", 100, 1000, "abc"),
+    ];
+    let dst_files = vec![
+        create_hashed_file_info("/same.txt", 100, 1000, "ABC"),
+        create_hashed_file_info("/changed.txt", 100, 1000, "def"),
+    ];
+
+    let (engine, task) =
+        setup_engine_with_capabilities(src_files, dst_files, caps.clone(), caps).await;
+    let diff = engine.calculate_diff_for_dry_run(&task).await.unwrap();
+
+    let same = diff.files.iter().find(|f| f.path == "same.txt").unwrap();
+    assert!(matches!(same.action, DiffAction::Unchanged));
+    let changed = diff.files.iter().find(|f| f.path == "changed.txt").unwrap();
+    assert!(matches!(changed.action, DiffAction::Update));
+}
+
+#[tokio::test]
+async fn test_diff_target_without_mtime_support() {
+    // 目标无法设置修改时间时，上传后的目标文件总是比源更新
+    let src_files = vec![create_file_info("/g.txt", 100, 1000)];
+    let dst_files = vec![create_file_info("/g.txt", 100, 3000)];
+
+    let (engine, task) = setup_engine(src_files.clone(), dst_files.clone()).await;
+    let diff = engine.calculate_diff_for_dry_run(&task).await.unwrap();
+    assert!(matches!(diff.files[0].action, DiffAction::Unchanged));
+
+    let caps = ProviderCapabilities {
+        can_set_mtime: true,
+        ..ProviderCapabilities::default()
+    };
+    let (engine, task) =
+        setup_engine_with_capabilities(src_files, dst_files, ProviderCapabilities::default(), caps)
+            .await;
+    let diff = engine.calculate_diff_for_dry_run(&task).await.unwrap();
+    assert!(matches!(diff.files[0].action, DiffAction::Update));
+}
+
+#[tokio::test]
+async fn test_diff_skips_files_target_cannot_store() {
+    let caps = ProviderCapabilities {
+        max_file_size: Some(1000),
+        forbidden_chars: vec![':'],
+        ..ProviderCapabilities::default()
+    };
+    let src_files = vec![
+        create_file_info("/big.bin", 2000, 1000),
+        create_file_info("/a:b.txt", 10, 1000),
+        create_file_info("/ok.txt", 10, 1000),
+    ];
+
+    let (engine, task) =
+        setup_engine_with_capabilities(src_files, vec![], ProviderCapabilities::default(), caps)
+            .await;
+    let diff = engine.calculate_diff_for_dry_run(&task).await.unwrap();
+
+    let big = diff.files.iter().find(|f| f.path == "big.bin").unwrap();
+    assert!(matches!(big.action, DiffAction::Unchanged));
+    assert!(big.tags.contains(&"skipped_too_large".to_string()));
+    let bad = diff.files.iter().find(|f| f.path == "a:b.txt").unwrap();
+    assert!(bad.tags.contains(&"skipped_forbidden_name".to_string()));
+    let ok = diff.files.iter().find(|f| f.path == "ok.txt").unwrap();
+    assert!(matches!(ok.action, DiffAction::Upload));
+}
+
+#[tokio::test]
+async fn test_sync_rejects_task_target_cannot_satisfy() {
+    // 目标路径含有目标不允许的字符，同步在传输前报错
+    let src_files = vec![create_file_info("/a.txt", 100, 1000)];
+    let caps = ProviderCapabilities {
+        forbidden_chars: vec![':'],
+        ..ProviderCapabilities::default()
+    };
+    let (mut engine, mut task) = setup_engine_with_capabilities(
+        src_files.clone(),
+        vec![],
+        ProviderCapabilities::default(),
+        caps,
+    )
+    .await;
+    task.target_path = "/backup:2026".to_string();
+    let err = engine.sync(&task).await.unwrap_err();
+    assert!(matches!(err, SyncError::Config(_)), "{:?}", err);
+
+    // 目标无法设置修改时间时只跳过 mtime，开启 preserve_metadata 的旧任务照常同步
+    let (mut engine, task) = setup_engine(src_files, vec![]).await;
+    assert!(task.preserve_metadata);
+    assert!(engine.sync(&task).await.is_ok());
+}
@@ -1,21 +1,30 @@
 //! 115网盘手动测试模块
 //!
-//! 该模块包含115网盘提供者的手动测试用例，用于验证基本功能。
-//! 这些测试需要有效的115网盘会话凭证，通过环境变量 `ONEONEFIVE_SESSION` 提供。
+//! 该模块包含115网盘提供者的测试用例，用于验证基本功能。
+//! 每个测试场景都会在本地模拟的115接口上运行；带 `#[ignore]` 的版本则针对真实的115网盘，
+//! 需要通过环境变量 `ONEONEFIVE_SESSION` 提供有效的会话凭证。
 //!
 //! 使用方法：
 //! 1. 设置环境变量：`set ONEONEFIVE_SESSION=your_session_cookie`
 //! 2. 运行测试：`cargo test --test manual_oneonefive_test -- --ignored`
 
+use bytes::Bytes;
 use cloud_disk_sync::config::{AccountConfig, ProviderType, RetryPolicy};
 use cloud_disk_sync::error::SyncError;
 use cloud_disk_sync::providers::{OneOneFiveProvider, StorageProvider};
+use sha1::{Digest, Sha1};
 use std::collections::HashMap;
 use std::env;
-use std::path::Path;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::fs;
-use tracing::{debug, error, info, warn};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use warp::Filter;
+
+/// 真实网盘上测试使用的目录，测试结束后会被删除
+const TEST_DIR: &str = "/cloud_disk_sync_manual_test";
 
 /// 初始化日志配置
 fn init_logging() {
@@ -35,9 +44,12 @@ fn get_oneonefive_session() -> Result<String, SyncError> {
 }
 
 /// 创建115网盘提供者配置
-fn create_oneonefive_config(session: &str) -> AccountConfig {
+fn create_oneonefive_config(session: &str, api_base_url: Option<&str>) -> AccountConfig {
     let mut credentials = HashMap::new();
     credentials.insert("cookie".to_string(), session.to_string());
+    if let Some(url) = api_base_url {
+        credentials.insert("api_base_url".to_string(), url.to_string());
+    }
 
     AccountConfig {
         id: "manual_oneonefive_test".to_string(),
@@ -51,130 +63,40 @@ fn create_oneonefive_config(session: &str) -> AccountConfig {
             backoff_factor: 2.0,
             max_delay_ms: 0,
         },
+        resource_limits: None,
     }
 }
 
-/// 测试115网盘连接和验证
-#[tokio::test]
-#[ignore]
-async fn test_oneonefive_connection_and_verification() {
-    init_logging();
-
-    info!("🚀 开始115网盘连接和验证测试");
-
-    // 获取会话凭证
+/// 使用真实会话创建提供者，未设置凭证时返回 None
+async fn real_provider() -> Option<OneOneFiveProvider> {
     let session = match get_oneonefive_session() {
-        Ok(session) => {
-            info!("✅ 成功获取115网盘会话凭证");
-            session
-        }
+        Ok(session) => session,
         Err(e) => {
             error!("❌ 获取会话凭证失败: {}", e);
-            return;
+            return None;
         }
     };
-
-    // 创建配置
-    let config = create_oneonefive_config(&session);
-
-    info!("正在初始化115网盘提供者...");
-
-    // 初始化提供者
-    let provider_result = OneOneFiveProvider::new(&config).await;
-    let provider = match provider_result {
-        Ok(provider) => {
-            info!("✅ 115网盘提供者初始化成功");
-            provider
-        }
+    match OneOneFiveProvider::new(&create_oneonefive_config(&session, None)).await {
+        Ok(provider) => Some(provider),
         Err(e) => {
             error!("❌ 115网盘提供者初始化失败: {}", e);
-            return;
-        }
-    };
-
-    // 验证连接
-    info!("正在验证115网盘连接...");
-    match provider.verify().await {
-        Ok(()) => info!("✅ 115网盘连接验证成功"),
-        Err(e) => {
-            error!("❌ 115网盘连接验证失败: {}", e);
-            return;
+            None
         }
     }
-
-    info!("🎉 115网盘连接和验证测试完成");
 }
 
-/// 测试文件列表获取功能
-#[tokio::test]
-#[ignore]
-async fn test_oneonefive_list_files() {
-    init_logging();
-
-    info!("📁 开始115网盘文件列表获取测试");
-
-    // 获取会话凭证
-    let session = match get_oneonefive_session() {
-        Ok(session) => session,
-        Err(e) => {
-            error!("❌ 获取会话凭证失败: {}", e);
-            return;
-        }
-    };
-
-    // 创建配置和提供者
-    let config = create_oneonefive_config(&session);
-    let provider = match OneOneFiveProvider::new(&config).await {
-        Ok(provider) => provider,
-        Err(e) => {
-            error!("❌ 115网盘提供者初始化失败: {}", e);
-            return;
-        }
-    };
-
-    // 获取根目录文件列表
-    info!("正在获取根目录文件列表...");
-    match provider.list("/").await {
-        Ok(files) => {
-            info!("✅ 成功获取文件列表，共 {} 个文件/目录", files.len());
-
-            if files.is_empty() {
-                warn!("⚠️  根目录为空，这可能是正常的");
-            } else {
-                info!("\n📋 文件列表详情:");
-                info!(
-                    "{:<10} {:<20} {:<12} {}",
-                    "类型", "大小", "修改时间", "名称"
-                );
-                info!("{}", "-".repeat(60));
-
-                for file in files.iter().take(10) {
-                    // 只显示前10个
-                    let file_type = if file.is_dir { "DIR" } else { "FILE" };
-                    let size_str = if file.is_dir {
-                        "-".to_string()
-                    } else {
-                        format_size(file.size)
-                    };
-
-                    info!(
-                        "{:<10} {:<20} {:<12} {}",
-                        file_type, size_str, file.modified, file.path
-                    );
-                }
-
-                if files.len() > 10 {
-                    info!("... 还有 {} 个文件未显示", files.len() - 10);
-                }
-            }
-        }
-        Err(e) => {
-            error!("❌ 获取文件列表失败: {}", e);
-            return;
-        }
-    }
+/// 创建连接到模拟服务器的提供者
+async fn mock_provider() -> (OneOneFiveProvider, MockState) {
+    let (addr, state) = start_mock_oneonefive_server().await;
+    let config = create_oneonefive_config("UID=mock; CID=mock", Some(&format!("http://{}", addr)));
+    let provider = OneOneFiveProvider::new(&config).await.unwrap();
+    (provider, state)
+}
 
-    info!("🎉 文件列表获取测试完成");
+async fn create_temp_file(content: &[u8]) -> PathBuf {
+    let path = env::temp_dir().join(format!("oneonefive_test_{}.txt", uuid::Uuid::new_v4()));
+    fs::write(&path, content).await.unwrap();
+    path
 }
 
 /// 格式化文件大小
@@ -194,187 +116,200 @@ fn format_size(size: u64) -> String {
     }
 }
 
-/// 测试文件存在性检查
-#[tokio::test]
-#[ignore]
-async fn test_oneonefive_exists_check() {
-    init_logging();
-
-    info!("🔍 开始115网盘文件存在性检查测试");
-
-    let session = match get_oneonefive_session() {
-        Ok(session) => session,
-        Err(e) => {
-            error!("❌ 获取会话凭证失败: {}", e);
-            return;
-        }
-    };
+// ---------------------------------------------------------------------------
+// 测试场景
+// ---------------------------------------------------------------------------
 
-    let config = create_oneonefive_config(&session);
-    let provider = match OneOneFiveProvider::new(&config).await {
-        Ok(provider) => provider,
-        Err(e) => {
-            error!("❌ 115网盘提供者初始化失败: {}", e);
-            return;
-        }
-    };
+/// 场景：连接验证
+async fn scenario_connection(provider: &OneOneFiveProvider) -> Result<(), SyncError> {
+    info!("正在验证115网盘连接...");
+    provider.verify().await?;
+    info!("✅ 115网盘连接验证成功");
+    Ok(())
+}
 
-    // 先获取根目录文件列表
-    info!("正在获取根目录文件列表用于测试...");
-    let files = match provider.list("/").await {
-        Ok(files) => files,
-        Err(e) => {
-            error!("❌ 获取文件列表失败: {}", e);
-            return;
-        }
-    };
+/// 场景：获取根目录文件列表
+async fn scenario_list_files(provider: &OneOneFiveProvider) -> Result<(), SyncError> {
+    info!("正在获取根目录文件列表...");
+    let files = provider.list("/").await?;
+    info!("✅ 成功获取文件列表，共 {} 个文件/目录", files.len());
 
     if files.is_empty() {
-        warn!("⚠️  根目录为空，跳过存在性检查测试");
-        return;
+        warn!("⚠️  根目录为空，这可能是正常的");
     }
+    for file in files.iter().take(10) {
+        let file_type = if file.is_dir { "DIR" } else { "FILE" };
+        let size_str = if file.is_dir {
+            "-".to_string()
+        } else {
+            format_size(file.size)
+        };
+        info!(
+            "{:<10} {:<20} {:<12} {}",
+            file_type, size_str, file.modified, file.path
+        );
+    }
+    Ok(())
+}
 
-    // 测试第一个文件的存在性
-    let test_file = &files[0];
-    info!("正在检查文件 '{}' 是否存在...", test_file.path);
-
-    match provider.exists(&test_file.path).await {
-        Ok(exists) => {
-            if exists {
-                info!("✅ 文件 '{}' 存在", test_file.path);
-            } else {
-                warn!("⚠️  文件 '{}' 不存在", test_file.path);
-            }
-        }
-        Err(e) => {
-            error!("❌ 检查文件存在性失败: {}", e);
-        }
+/// 场景：文件存在性检查
+async fn scenario_exists_check(provider: &OneOneFiveProvider) -> Result<(), SyncError> {
+    let files = provider.list("/").await?;
+    if let Some(test_file) = files.first() {
+        info!("正在检查文件 '{}' 是否存在...", test_file.path);
+        assert!(provider.exists(&test_file.path).await?);
+    } else {
+        warn!("⚠️  根目录为空，跳过已有文件的存在性检查");
     }
 
-    // 测试一个不存在的文件
     let non_existent_file = "this_file_should_not_exist_12345.txt";
     info!("正在检查不存在的文件 '{}'...", non_existent_file);
+    assert!(!provider.exists(non_existent_file).await?);
+    Ok(())
+}
 
-    match provider.exists(non_existent_file).await {
-        Ok(exists) => {
-            if !exists {
-                info!("✅ 不存在的文件正确返回 false");
-            } else {
-                warn!("⚠️  不存在的文件错误返回 true");
-            }
-        }
-        Err(e) => {
-            error!("❌ 检查不存在文件时出错: {}", e);
-        }
-    }
-
-    info!("🎉 文件存在性检查测试完成");
+/// 场景：创建目录、上传、秒传、下载、删除
+async fn scenario_upload_download(provider: &OneOneFiveProvider) -> Result<(), SyncError> {
+    let test_content = b"Hello, 115 Cloud Disk! This is a test file for manual testing.";
+    let dir = format!("{}/nested", TEST_DIR);
+    let remote_path = format!("{}/test_upload_file.txt", dir);
+
+    provider.mkdir(&dir).await?;
+    assert!(provider.stat(&dir).await?.is_dir);
+
+    let local = create_temp_file(test_content).await;
+    let result = provider.upload(&local, &remote_path).await?;
+    info!("✅ 文件上传成功: {:?}", result);
+    assert_eq!(result.file_size, test_content.len() as u64);
+
+    let info = provider.stat(&remote_path).await?;
+    assert!(!info.is_dir);
+    assert_eq!(info.size, test_content.len() as u64);
+    assert_eq!(info.hash, result.checksum);
+
+    // 相同内容再次上传应命中秒传
+    let copy_path = format!("{}/test_upload_copy.txt", dir);
+    let fast = provider.upload(&local, &copy_path).await?;
+    assert_eq!(fast.bytes_uploaded, 0);
+    assert!(provider.exists(&copy_path).await?);
+
+    let downloaded = local.with_extension("download");
+    provider.download(&remote_path, &downloaded).await?;
+    assert_eq!(fs::read(&downloaded).await.unwrap(), test_content);
+
+    provider.delete(TEST_DIR).await?;
+    assert!(!provider.exists(&remote_path).await?);
+
+    let _ = fs::remove_file(&local).await;
+    let _ = fs::remove_file(&downloaded).await;
+    Ok(())
 }
 
-/// 测试上传功能（需要实现上传API）
+// ---------------------------------------------------------------------------
+// 基于模拟服务器的测试
+// ---------------------------------------------------------------------------
+
 #[tokio::test]
-#[ignore]
-async fn test_oneonefive_upload() {
+async fn test_mock_connection_and_verification() {
     init_logging();
+    let (provider, _) = mock_provider().await;
+    scenario_connection(&provider).await.unwrap();
+}
 
-    info!("⬆️  开始115网盘上传功能测试");
+#[tokio::test]
+async fn test_mock_list_files() {
+    init_logging();
+    let (provider, state) = mock_provider().await;
+    state.seed_file("0", "readme.txt", b"hello").await;
+    scenario_list_files(&provider).await.unwrap();
+
+    let files = provider.list("/").await.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "/readme.txt");
+    assert_eq!(files[0].size, 5);
+}
 
-    let session = match get_oneonefive_session() {
-        Ok(session) => session,
-        Err(e) => {
-            error!("❌ 获取会话凭证失败: {}", e);
-            return;
-        }
-    };
+#[tokio::test]
+async fn test_mock_exists_check() {
+    init_logging();
+    let (provider, state) = mock_provider().await;
+    state.seed_file("0", "readme.txt", b"hello").await;
+    scenario_exists_check(&provider).await.unwrap();
+}
 
-    let config = create_oneonefive_config(&session);
-    let provider = match OneOneFiveProvider::new(&config).await {
-        Ok(provider) => provider,
-        Err(e) => {
-            error!("❌ 115网盘提供者初始化失败: {}", e);
-            return;
-        }
-    };
+#[tokio::test]
+async fn test_mock_upload_download() {
+    init_logging();
+    let (provider, state) = mock_provider().await;
+    scenario_upload_download(&provider).await.unwrap();
+    // 第二次上传为秒传，只有第一次需要上传数据
+    assert_eq!(*state.oss_uploads.read().await, 1);
+}
 
-    // 创建测试文件
-    let test_content = "Hello, 115 Cloud Disk! This is a test file for manual testing.";
-    let test_file_path = "test_upload_file.txt";
-
-    // 先检查文件是否已存在
-    info!("检查测试文件是否已存在...");
-    match provider.exists(test_file_path).await {
-        Ok(exists) => {
-            if exists {
-                warn!("⚠️  测试文件已存在，跳过上传测试");
-                return;
-            }
-        }
-        Err(e) => {
-            error!("❌ 检查文件存在性失败: {}", e);
-            return;
-        }
+#[tokio::test]
+async fn test_mock_list_paginates() {
+    init_logging();
+    let (provider, state) = mock_provider().await;
+    for i in 0..1005 {
+        state.seed_file("0", &format!("f{}.txt", i), b"x").await;
     }
+    assert_eq!(provider.list("/").await.unwrap().len(), 1005);
+}
 
-    info!("📝 上传功能尚未实现，需要先实现115网盘的上传API");
-    info!("💡 提示: 115网盘上传通常需要以下步骤:");
-    info!("   1. 预上传检查 (fast upload)");
-    info!("   2. 获取上传token和服务器地址");
-    info!("   3. 分块上传文件数据");
-    info!("   4. 完成上传确认");
-
-    // 这里可以添加上传测试代码，当上传功能实现后
-    /*
-    let temp_file = create_temp_file(test_content).await?;
-    match provider.upload(&temp_file, test_file_path).await {
-        Ok(result) => {
-            info!("✅ 文件上传成功: {:?}", result);
-
-            // 验证文件确实存在
-            match provider.exists(test_file_path).await {
-                Ok(exists) => {
-                    if exists {
-                        info!("✅ 上传验证成功，文件确实存在");
-                    } else {
-                        warn!("⚠️  上传验证失败，文件不存在");
-                    }
-                }
-                Err(e) => error!("❌ 上传验证失败: {}", e),
-            }
-        }
-        Err(e) => error!("❌ 文件上传失败: {}", e),
-    }
-    */
+// ---------------------------------------------------------------------------
+// 针对真实115网盘的手动测试
+// ---------------------------------------------------------------------------
 
-    info!("🔧 上传功能测试完成（功能待实现）");
+/// 测试115网盘连接和验证
+#[tokio::test]
+#[ignore]
+async fn test_oneonefive_connection_and_verification() {
+    init_logging();
+    info!("🚀 开始115网盘连接和验证测试");
+    let Some(provider) = real_provider().await else {
+        return;
+    };
+    scenario_connection(&provider).await.unwrap();
+    info!("🎉 115网盘连接和验证测试完成");
 }
 
-/// 测试删除功能（谨慎使用）
+/// 测试文件列表获取功能
 #[tokio::test]
 #[ignore]
-async fn test_oneonefive_delete_with_caution() {
+async fn test_oneonefive_list_files() {
     init_logging();
+    info!("📁 开始115网盘文件列表获取测试");
+    let Some(provider) = real_provider().await else {
+        return;
+    };
+    scenario_list_files(&provider).await.unwrap();
+    info!("🎉 文件列表获取测试完成");
+}
 
-    warn!("⚠️  开始115网盘删除功能测试 - 此操作会实际删除文件，请谨慎使用！");
-
-    // 这个测试默认跳过，需要手动取消注释并谨慎使用
-    info!("🔒 删除测试默认跳过，如需测试请手动取消注释");
-    return;
-
-    /*
-    // 以下是删除测试的示例代码
-    let session = get_oneonefive_session()?;
-    let config = create_oneonefive_config(&session);
-    let provider = OneOneFiveProvider::new(&config).await?;
-
-    // 先创建一个测试文件
-    let test_file_path = "test_delete_file.txt";
+/// 测试文件存在性检查
+#[tokio::test]
+#[ignore]
+async fn test_oneonefive_exists_check() {
+    init_logging();
+    info!("🔍 开始115网盘文件存在性检查测试");
+    let Some(provider) = real_provider().await else {
+        return;
+    };
+    scenario_exists_check(&provider).await.unwrap();
+    info!("🎉 文件存在性检查测试完成");
+}
 
-    // 删除测试文件
-    match provider.delete(test_file_path).await {
-        Ok(()) => info!("✅ 文件删除成功"),
-        Err(e) => error!("❌ 文件删除失败: {}", e),
-    }
-    */
+/// 测试上传、秒传、下载与删除（会在网盘中创建并删除测试目录）
+#[tokio::test]
+#[ignore]
+async fn test_oneonefive_upload() {
+    init_logging();
+    info!("⬆️  开始115网盘上传功能测试");
+    let Some(provider) = real_provider().await else {
+        return;
+    };
+    scenario_upload_download(&provider).await.unwrap();
+    info!("🎉 上传功能测试完成");
 }
 
 /// 主测试函数 - 运行所有测试
@@ -382,14 +317,14 @@ async fn test_oneonefive_delete_with_caution() {
 #[ignore]
 async fn test_oneonefive_comprehensive() {
     init_logging();
-
     info!("🎯 开始115网盘综合测试");
-
-    // 运行各个子测试
-    test_oneonefive_connection_and_verification();
-    test_oneonefive_list_files();
-    test_oneonefive_exists_check();
-
+    let Some(provider) = real_provider().await else {
+        return;
+    };
+    scenario_connection(&provider).await.unwrap();
+    scenario_list_files(&provider).await.unwrap();
+    scenario_exists_check(&provider).await.unwrap();
+    scenario_upload_download(&provider).await.unwrap();
     info!("🎉 115网盘综合测试完成");
 }
 
@@ -411,6 +346,300 @@ fn show_test_instructions() {
     println!("   - test_oneonefive_connection_and_verification");
     println!("   - test_oneonefive_list_files");
     println!("   - test_oneonefive_exists_check");
+    println!("   - test_oneonefive_upload");
     println!("   - test_oneonefive_comprehensive (运行所有测试)");
     println!("========================================\n");
 }
+
+// ---------------------------------------------------------------------------
+// 115 接口模拟服务器
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+struct MockEntry {
+    parent: String,
+    name: String,
+    is_dir: bool,
+    content: Vec<u8>,
+    sha1: String,
+    pick_code: String,
+}
+
+#[derive(Clone, Default)]
+struct MockState {
+    entries: Arc<RwLock<HashMap<String, MockEntry>>>,
+    /// 等待对象存储上传的文件：object -> (父目录cid, 文件名)
+    pending: Arc<RwLock<HashMap<String, (String, String)>>>,
+    oss_uploads: Arc<RwLock<usize>>,
+}
+
+impl MockState {
+    async fn insert(&self, parent: &str, name: &str, is_dir: bool, content: &[u8]) -> String {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        self.entries.write().await.insert(
+            id.clone(),
+            MockEntry {
+                parent: parent.to_string(),
+                name: name.to_string(),
+                is_dir,
+                content: content.to_vec(),
+                sha1: hex::encode_upper(Sha1::digest(content)),
+                pick_code: format!("pc{}", id),
+            },
+        );
+        id
+    }
+
+    async fn seed_file(&self, parent: &str, name: &str, content: &[u8]) -> String {
+        self.insert(parent, name, false, content).await
+    }
+
+    async fn remove_recursive(&self, id: &str) -> bool {
+        let mut entries = self.entries.write().await;
+        if entries.remove(id).is_none() {
+            return false;
+        }
+        let mut stack = vec![id.to_string()];
+        while let Some(parent) = stack.pop() {
+            let children: Vec<String> = entries
+                .iter()
+                .filter(|(_, e)| e.parent == parent)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for child in children {
+                entries.remove(&child);
+                stack.push(child);
+            }
+        }
+        true
+    }
+}
+
+fn ok(data: serde_json::Value) -> warp::reply::Json {
+    warp::reply::json(&serde_json::json!({ "state": true, "data": data }))
+}
+
+fn fail(msg: &str) -> warp::reply::Json {
+    warp::reply::json(&serde_json::json!({ "state": false, "error": msg }))
+}
+
+async fn start_mock_oneonefive_server() -> (SocketAddr, MockState) {
+    let state = MockState::default();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let with_state = {
+        let state = state.clone();
+        warp::any().map(move || state.clone())
+    };
+
+    let files = warp::get()
+        .and(warp::path!("open" / "ufile" / "files"))
+        .and(warp::query::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .then(|q: HashMap<String, String>, state: MockState| async move {
+            let cid = q.get("cid").cloned().unwrap_or_else(|| "0".into());
+            let offset: usize = q.get("offset").and_then(|v| v.parse().ok()).unwrap_or(0);
+            let limit: usize = q.get("limit").and_then(|v| v.parse().ok()).unwrap_or(20);
+            let entries = state.entries.read().await;
+            let mut children: Vec<(&String, &MockEntry)> =
+                entries.iter().filter(|(_, e)| e.parent == cid).collect();
+            children.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+            let page: Vec<serde_json::Value> = children
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|(id, e)| {
+                    if e.is_dir {
+                        serde_json::json!({ "cid": id, "pid": e.parent, "n": e.name, "t": "1700000000" })
+                    } else {
+                        serde_json::json!({
+                            "fid": id, "cid": e.parent, "n": e.name,
+                            "s": e.content.len(), "t": "1700000000",
+                            "sha": e.sha1, "pc": e.pick_code,
+                        })
+                    }
+                })
+                .collect();
+            ok(serde_json::json!({ "data": page }))
+        });
+
+    let folder_add = warp::post()
+        .and(warp::path!("open" / "folder" / "add"))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .then(
+            |form: HashMap<String, String>, state: MockState| async move {
+                let pid = form.get("pid").cloned().unwrap_or_default();
+                let name = form.get("file_name").cloned().unwrap_or_default();
+                let exists = state
+                    .entries
+                    .read()
+                    .await
+                    .values()
+                    .any(|e| e.parent == pid && e.name == name);
+                if exists {
+                    return fail("该目录名称已存在");
+                }
+                let id = state.insert(&pid, &name, true, &[]).await;
+                ok(serde_json::json!({ "file_id": id, "file_name": name }))
+            },
+        );
+
+    let delete = warp::post()
+        .and(warp::path!("open" / "ufile" / "delete"))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .then(
+            |form: HashMap<String, String>, state: MockState| async move {
+                let fid = form.get("fid").cloned().unwrap_or_default();
+                if state.remove_recursive(&fid).await {
+                    warp::reply::json(&serde_json::json!({ "state": true }))
+                } else {
+                    fail("文件不存在")
+                }
+            },
+        );
+
+    let downurl = warp::post()
+        .and(warp::path!("open" / "ufile" / "downurl"))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .then(
+            move |form: HashMap<String, String>, state: MockState| async move {
+                let pick_code = form.get("pick_code").cloned().unwrap_or_default();
+                let entries = state.entries.read().await;
+                match entries.iter().find(|(_, e)| e.pick_code == pick_code) {
+                    Some((id, e)) => ok(serde_json::json!({
+                        id.clone(): {
+                            "file_name": e.name,
+                            "url": { "url": format!("http://{}/download/{}", addr, pick_code) }
+                        }
+                    })),
+                    None => fail("文件不存在"),
+                }
+            },
+        );
+
+    let download = warp::get()
+        .and(warp::path!("download" / String))
+        .and(with_state.clone())
+        .then(|pick_code: String, state: MockState| async move {
+            let entries = state.entries.read().await;
+            let content = entries
+                .values()
+                .find(|e| e.pick_code == pick_code)
+                .map(|e| e.content.clone())
+                .unwrap_or_default();
+            warp::reply::with_status(content, warp::http::StatusCode::OK)
+        });
+
+    let upload_init = warp::post()
+        .and(warp::path!("open" / "upload" / "init"))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .then(|form: HashMap<String, String>, state: MockState| async move {
+            let name = form.get("file_name").cloned().unwrap_or_default();
+            let sha1 = form.get("fileid").cloned().unwrap_or_default();
+            let parent = form
+                .get("target")
+                .and_then(|t| t.strip_prefix("U_1_"))
+                .unwrap_or("0")
+                .to_string();
+
+            let known = state
+                .entries
+                .read()
+                .await
+                .values()
+                .find(|e| !e.is_dir && e.sha1 == sha1)
+                .map(|e| e.content.clone());
+            let Some(content) = known else {
+                let object = format!("mock/{}", uuid::Uuid::new_v4().simple());
+                state
+                    .pending
+                    .write()
+                    .await
+                    .insert(object.clone(), (parent, name));
+                return ok(serde_json::json!({
+                    "status": 1,
+                    "bucket": "fhnfile",
+                    "object": object,
+                    "callback": { "callback": "{\"callbackUrl\":\"mock\"}", "callback_var": "{}" },
+                }));
+            };
+
+            // 秒传前要求校验文件前若干字节的SHA1
+            let end = content.len().min(10).saturating_sub(1);
+            let expected = hex::encode_upper(Sha1::digest(&content[..=end]));
+            match form.get("sign_val") {
+                None => ok(serde_json::json!({
+                    "status": 7,
+                    "sign_key": "mock_sign_key",
+                    "sign_check": format!("0-{}", end),
+                })),
+                Some(val) if *val == expected => {
+                    state.insert(&parent, &name, false, &content).await;
+                    ok(serde_json::json!({ "status": 2 }))
+                }
+                Some(_) => fail("sign check failed"),
+            }
+        });
+
+    let upload_token = warp::get()
+        .and(warp::path!("open" / "upload" / "get_token"))
+        .map(move || {
+            ok(serde_json::json!({
+                "endpoint": format!("http://{}", addr),
+                "AccessKeyId": "mock_ak",
+                "AccessKeySecret": "mock_sk",
+                "SecurityToken": "mock_token",
+            }))
+        });
+
+    let oss_put = warp::put()
+        .and(warp::path!("fhnfile" / "mock" / String))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::bytes())
+        .and(with_state.clone())
+        .then(
+            |object: String, auth: String, body: Bytes, state: MockState| async move {
+                if !auth.starts_with("OSS mock_ak:") {
+                    return fail("bad signature");
+                }
+                let pending = state
+                    .pending
+                    .write()
+                    .await
+                    .remove(&format!("mock/{}", object));
+                let Some((parent, name)) = pending else {
+                    return fail("unknown object");
+                };
+                state.insert(&parent, &name, false, &body).await;
+                *state.oss_uploads.write().await += 1;
+                warp::reply::json(&serde_json::json!({ "state": true }))
+            },
+        );
+
+    let routes = files
+        .or(folder_add)
+        .or(delete)
+        .or(downurl)
+        .or(download)
+        .or(upload_init)
+        .or(upload_token)
+        .or(oss_put);
+    tokio::spawn(warp::serve(routes).run(addr));
+
+    // 等待服务器就绪
+    let start = std::time::Instant::now();
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        if start.elapsed() > std::time::Duration::from_secs(5) {
+            panic!("Mock server failed to start on {} within 5 seconds", addr);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+
+    (addr, state)
+}
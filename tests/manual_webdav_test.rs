@@ -26,6 +26,7 @@ async fn test_manual_webdav_connection() {
         credentials,
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     println!("正在连接 WebDAV 服务器: {}", config.credentials["url"]);
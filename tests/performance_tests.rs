@@ -1,6 +1,9 @@
-use cloud_disk_sync::config::{AccountConfig, DiffMode, RetryPolicy, SyncTask};
+use cloud_disk_sync::config::{
+    AccountConfig, BandwidthRule, DiffMode, GlobalSettings, RetryPolicy, SyncTask,
+};
+use cloud_disk_sync::core::resources::ResourceLimits;
 use cloud_disk_sync::providers::StorageProvider; // Added import
-use cloud_disk_sync::providers::WebDavProvider;
+use cloud_disk_sync::providers::{MemoryProvider, WebDavProvider};
 use cloud_disk_sync::sync::engine::SyncEngine;
 use std::collections::HashMap;
 use std::time::Instant;
@@ -33,6 +36,7 @@ async fn test_sync_throughput_small_files() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "dst_perf".to_string(),
@@ -47,6 +51,7 @@ async fn test_sync_throughput_small_files() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     let src_provider = WebDavProvider::new(&src_cfg).await.unwrap();
@@ -97,10 +102,13 @@ async fn test_sync_throughput_small_files() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: false,
         sync_policy: None, // Added field
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let start = Instant::now();
@@ -120,3 +128,131 @@ async fn test_sync_throughput_small_files() {
     // 清理
     tokio::fs::remove_dir_all(&temp_dir).await.ok();
 }
+
+#[tokio::test]
+async fn test_sync_respects_bandwidth_limits() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    for i in 0..4 {
+        src_provider
+            .insert_file(&format!("/file_root/{}.bin", i), vec![0u8; 10 * 1024], 0)
+            .unwrap();
+    }
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_bw".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_bw".to_string(), Box::new(dst_provider));
+    // 全局 1MB/s 不构成限制，任务级 40KB/s 使 40KB 数据至少传输约 1 秒
+    engine.set_resource_limits(&ResourceLimits {
+        max_network_bandwidth_bytes_per_sec: Some(1024 * 1024),
+        ..Default::default()
+    });
+
+    let task = SyncTask {
+        id: "t_bandwidth".to_string(),
+        name: "bandwidth test".to_string(),
+        source_account: "src_bw".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_bw".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: Some(ResourceLimits {
+            max_network_bandwidth_bytes_per_sec: Some(40 * 1024),
+            ..Default::default()
+        }),
+        bandwidth_schedule: vec![],
+    };
+
+    let start = Instant::now();
+    let report = engine.sync(&task).await.unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(report.statistics.files_synced, 4);
+    println!("Synced 40KB at 40KB/s in {:?}", elapsed);
+    assert!(elapsed.as_secs_f64() >= 0.9);
+}
+
+#[tokio::test]
+async fn test_sync_respects_bandwidth_schedule() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    for i in 0..4 {
+        src_provider
+            .insert_file(&format!("/file_root/{}.bin", i), vec![0u8; 10 * 1024], 0)
+            .unwrap();
+    }
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_sched".to_string(), Box::new(src_provider));
+    engine.register_provider("dst_sched".to_string(), Box::new(dst_provider));
+    // 全局时间表全天不限速，任务级时间表全天 40KB/s，取代任务级 1MB/s 上限
+    let all_day = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    engine.set_bandwidth_schedule(vec![BandwidthRule {
+        weekdays: vec![],
+        start: all_day,
+        end: all_day,
+        bytes_per_sec: None,
+    }]);
+
+    let task = SyncTask {
+        id: "t_bandwidth_schedule".to_string(),
+        name: "bandwidth schedule test".to_string(),
+        source_account: "src_sched".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_sched".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: Some(ResourceLimits {
+            max_network_bandwidth_bytes_per_sec: Some(1024 * 1024),
+            ..Default::default()
+        }),
+        bandwidth_schedule: vec![BandwidthRule {
+            weekdays: vec![],
+            start: all_day,
+            end: all_day,
+            bytes_per_sec: Some(40 * 1024),
+        }],
+    };
+
+    let start = Instant::now();
+    let report = engine.sync(&task).await.unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(report.statistics.files_synced, 4);
+    println!("Synced 40KB on a 40KB/s schedule in {:?}", elapsed);
+    assert!(elapsed.as_secs_f64() >= 0.9);
+}
+
+/// 全局带宽时间表中速率为 0 的规则会让传输永远停滞，应用全局设置时直接拒绝
+#[tokio::test]
+async fn test_global_bandwidth_schedule_rejects_zero_rate() {
+    let mut engine = SyncEngine::new().await.unwrap();
+    let all_day = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    let mut settings = GlobalSettings::default();
+    settings.bandwidth_schedule = vec![BandwidthRule {
+        weekdays: vec![],
+        start: all_day,
+        end: all_day,
+        bytes_per_sec: Some(0),
+    }];
+    assert!(engine.apply_global_settings(&settings).is_err());
+
+    settings.bandwidth_schedule[0].bytes_per_sec = None;
+    engine.apply_global_settings(&settings).unwrap();
+}
@@ -1,12 +1,15 @@
-use cloud_disk_sync::config::{AccountConfig, DiffMode, RetryPolicy, SyncTask};
-use cloud_disk_sync::providers::StorageProvider; // Added import
-use cloud_disk_sync::providers::WebDavProvider;
+use cloud_disk_sync::config::{AccountConfig, DiffMode, RateLimitConfig, RetryPolicy, SyncTask};
+use cloud_disk_sync::providers::fault::{FaultKind, FaultOp, Latency};
+use cloud_disk_sync::providers::{
+    FaultConfig, FaultInjectionProvider, MemoryProvider, RateLimitedProvider, RetryingProvider,
+    StorageProvider, WebDavProvider,
+};
 use cloud_disk_sync::sync::engine::SyncEngine;
 use std::collections::HashMap;
 use std::time::Duration;
 
 mod common;
-use common::{FaultConfig, FaultInjectionProvider, start_mock_server_with_seed};
+use common::start_mock_server_with_seed;
 
 #[tokio::test]
 async fn test_sync_with_latency() {
@@ -30,6 +33,7 @@ async fn test_sync_with_latency() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "dst_latency".to_string(),
@@ -44,6 +48,7 @@ async fn test_sync_with_latency() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     // 3. 创建 Provider 并注入延迟
@@ -54,8 +59,10 @@ async fn test_sync_with_latency() {
     let fault_src = FaultInjectionProvider::new(
         Box::new(src_provider),
         FaultConfig {
-            latency_ms: 50,
-            latency_jitter_ms: 10,
+            latency: Latency::Uniform {
+                min: Duration::from_millis(50),
+                max: Duration::from_millis(60),
+            },
             error_rate: 0.0,
             ..Default::default()
         },
@@ -65,8 +72,10 @@ async fn test_sync_with_latency() {
     let fault_dst = FaultInjectionProvider::new(
         Box::new(dst_provider),
         FaultConfig {
-            latency_ms: 50,
-            latency_jitter_ms: 10,
+            latency: Latency::Uniform {
+                min: Duration::from_millis(50),
+                max: Duration::from_millis(60),
+            },
             error_rate: 0.0,
             ..Default::default()
         },
@@ -99,10 +108,13 @@ async fn test_sync_with_latency() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: false,
         sync_policy: None, // Added field
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let report = engine.sync(&task).await.unwrap();
@@ -140,6 +152,7 @@ async fn test_sync_with_random_errors() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "dst_err".to_string(),
@@ -154,16 +167,26 @@ async fn test_sync_with_random_errors() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
-    // 3. 注入高错误率 (20%)
+    // 3. 注入高错误率 (20%) 的连接错误，固定种子使结果可复现；重试由 RetryingProvider 负责
     let src_provider = WebDavProvider::new(&src_cfg).await.unwrap();
-    let fault_src = FaultInjectionProvider::new(
-        Box::new(src_provider),
-        FaultConfig {
-            error_rate: 0.2,
-            error_type: "server".to_string(),
-            ..Default::default()
+    let fault_src = RetryingProvider::new(
+        FaultInjectionProvider::new(
+            Box::new(src_provider),
+            FaultConfig {
+                error_rate: 0.2,
+                error_kind: FaultKind::Connection,
+                seed: Some(7),
+                ..Default::default()
+            },
+        ),
+        &RetryPolicy {
+            max_retries: 5,
+            initial_delay_ms: 10,
+            max_delay_ms: 50,
+            backoff_factor: 2.0,
         },
     );
 
@@ -184,18 +207,191 @@ async fn test_sync_with_random_errors() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Full,
         preserve_metadata: false,
         verify_integrity: false,
         sync_policy: None, // Added field
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
-    // 此时可能因为没有自动重试而失败，或者报告中有错误
+    // 服务端错误在提供商层重试，同步完整成功
     let report = engine.sync(&task).await.unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 1);
+}
+
+#[tokio::test]
+async fn test_sync_reports_adaptive_rate_changes() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    for i in 0..8 {
+        src_provider
+            .insert_file(&format!("/file_root/{}.txt", i), "content", 0)
+            .unwrap();
+    }
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
 
-    println!(
-        "Sync report with faults: success={}, errors={}",
-        report.statistics.files_synced, // Fixed field access
-        report.errors.len()
+    // 目标端一半的上传被限流，限流器降速，重试后全部成功
+    let account = format!("dst_throttled_{}", uuid::Uuid::new_v4());
+    let throttled = RateLimitedProvider::for_account(
+        FaultInjectionProvider::new(
+            dst_provider,
+            FaultConfig {
+                op_error_rates: HashMap::from([(FaultOp::Upload, 0.5)]),
+                error_kind: FaultKind::RateLimited,
+                seed: Some(7),
+                ..Default::default()
+            },
+        ),
+        &account,
+        &RateLimitConfig {
+            requests_per_minute: 6_000,
+            max_concurrent: 4,
+            chunk_size: 0,
+        },
+    );
+    let policy = RetryPolicy {
+        max_retries: 10,
+        initial_delay_ms: 10,
+        max_delay_ms: 50,
+        backoff_factor: 2.0,
+    };
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_throttled".to_string(), Box::new(src_provider));
+    engine.register_provider(
+        account.clone(),
+        Box::new(RetryingProvider::new(throttled, &policy)),
     );
+
+    let task = SyncTask {
+        id: "t_throttled".to_string(),
+        name: "throttled sync".to_string(),
+        source_account: "src_throttled".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: account.clone(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = engine.sync(&task).await.unwrap();
+    assert!(
+        report
+            .warnings
+            .iter()
+            .any(|w| w.contains(&account) && w.contains("因限流降低")),
+        "Warnings: {:?}",
+        report.warnings
+    );
+}
+
+#[tokio::test]
+async fn test_sync_does_not_retry_listing_outside_provider() {
+    common::init_logging();
+    let src_provider = MemoryProvider::new();
+    src_provider
+        .insert_file("/file_root/a.txt", "content", 0)
+        .unwrap();
+    // 引擎不再自行重试列目录，认证失败只出现在递归列出与逐级列出各一次
+    let faulty: &'static FaultInjectionProvider<MemoryProvider> =
+        Box::leak(Box::new(FaultInjectionProvider::new(
+            src_provider,
+            FaultConfig {
+                op_error_rates: HashMap::from([(FaultOp::List, 1.0)]),
+                error_kind: FaultKind::Auth,
+                ..Default::default()
+            },
+        )));
+    let dst_provider = MemoryProvider::new();
+    dst_provider.mkdir("/backup").await.unwrap();
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider("src_auth".to_string(), Box::new(faulty));
+    engine.register_provider("dst_auth".to_string(), Box::new(dst_provider));
+
+    let task = SyncTask {
+        id: "t_auth".to_string(),
+        name: "auth failure".to_string(),
+        source_account: "src_auth".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_auth".to_string(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    assert!(engine.sync(&task).await.is_err());
+    assert_eq!(faulty.injected_faults(), 2);
+}
+
+#[tokio::test]
+async fn test_sync_within_one_account_with_single_permit() {
+    common::init_logging();
+    let memory = MemoryProvider::new();
+    for i in 0..3 {
+        memory
+            .insert_file(&format!("/file_root/{}.txt", i), "content", 0)
+            .unwrap();
+    }
+    memory.mkdir("/backup").await.unwrap();
+    // 源与目标为同一账户，读取源文件流的同时要向同一账户上传
+    let account = format!("single_permit_{}", uuid::Uuid::new_v4());
+    let limited = RateLimitedProvider::for_account(
+        memory,
+        &account,
+        &RateLimitConfig {
+            requests_per_minute: 60_000,
+            max_concurrent: 1,
+            chunk_size: 0,
+        },
+    );
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider(account.clone(), Box::new(limited));
+
+    let task = SyncTask {
+        id: "t_single_permit".to_string(),
+        name: "same account copy".to_string(),
+        source_account: account.clone(),
+        source_path: "/file_root".to_string(),
+        target_account: account.clone(),
+        target_path: "/backup".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: None,
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let report = tokio::time::timeout(Duration::from_secs(10), engine.sync(&task))
+        .await
+        .expect("sync deadlocked on the account's only permit")
+        .unwrap();
+    assert!(report.errors.is_empty(), "Errors: {:?}", report.errors);
+    assert_eq!(report.statistics.files_synced, 3);
 }
@@ -47,6 +47,7 @@ async fn test_webdav_sync_basic() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     let webdav2 = AccountConfig {
@@ -62,6 +63,7 @@ async fn test_webdav_sync_basic() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     // 创建 Provider
@@ -94,6 +96,7 @@ async fn test_webdav_sync_basic() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -102,6 +105,8 @@ async fn test_webdav_sync_basic() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     let _report = engine.sync(&task).await.unwrap();
@@ -164,6 +169,7 @@ async fn test_webdav_sync_directory_operations() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     config_manager.add_account(source_account).unwrap();
 
@@ -181,6 +187,7 @@ async fn test_webdav_sync_directory_operations() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     config_manager.add_account(target_account).unwrap();
 
@@ -197,9 +204,12 @@ async fn test_webdav_sync_directory_operations() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -291,6 +301,7 @@ async fn test_webdav_sync_policy_no_delete_orphans() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "p_no_del_dst".to_string(),
@@ -305,6 +316,7 @@ async fn test_webdav_sync_policy_no_delete_orphans() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     let mut engine = SyncEngine::new().await.unwrap();
@@ -327,6 +339,7 @@ async fn test_webdav_sync_policy_no_delete_orphans() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -335,6 +348,8 @@ async fn test_webdav_sync_policy_no_delete_orphans() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     engine.sync(&task).await.unwrap();
@@ -371,6 +386,7 @@ async fn test_webdav_sync_policy_no_overwrite() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "p_no_ov_dst".to_string(),
@@ -385,6 +401,7 @@ async fn test_webdav_sync_policy_no_overwrite() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     let mut engine = SyncEngine::new().await.unwrap();
@@ -407,6 +424,7 @@ async fn test_webdav_sync_policy_no_overwrite() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -415,6 +433,8 @@ async fn test_webdav_sync_policy_no_overwrite() {
             overwrite_existing: false,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
 
     engine.sync(&task).await.unwrap();
@@ -448,6 +468,7 @@ async fn test_webdav_diff_scan_cooldown() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
     let dst_cfg = AccountConfig {
         id: "p_scan_dst".to_string(),
@@ -462,6 +483,7 @@ async fn test_webdav_diff_scan_cooldown() {
         },
         rate_limit: None,
         retry_policy: RetryPolicy::default(),
+        resource_limits: None,
     };
 
     let mut engine = SyncEngine::new().await.unwrap();
@@ -485,6 +507,7 @@ async fn test_webdav_diff_scan_cooldown() {
         schedule: None,
         filters: vec![],
         encryption: None,
+        compression: None,
         diff_mode: DiffMode::Smart,
         preserve_metadata: false,
         verify_integrity: false,
@@ -493,6 +516,8 @@ async fn test_webdav_diff_scan_cooldown() {
             overwrite_existing: true,
             scan_cooldown_secs: 100,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
     };
     engine.sync(&task1).await.unwrap();
 
@@ -539,6 +564,8 @@ async fn test_webdav_diff_scan_cooldown() {
             overwrite_existing: true,
             scan_cooldown_secs: 0,
         }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
         ..task1
     };
     engine2.sync(&task3).await.unwrap();
@@ -548,3 +575,97 @@ async fn test_webdav_diff_scan_cooldown() {
         "关闭限频后应同步 a2.txt"
     );
 }
+
+/// 移动检测：目标端已有同名同大小文件位于旧目录，应使用 MOVE 而不是重新上传
+#[tokio::test]
+async fn test_webdav_sync_server_side_move() {
+    common::init_logging();
+    let (addr1, _s1) = start_mock_server_with_seed(vec![
+        ("/file_root/new_dir", "", true),
+        ("/file_root/new_dir/report.txt", "report body", false),
+    ])
+    .await;
+    let (addr2, store2) = start_mock_server_with_seed(vec![
+        ("/file_root/old_dir", "", true),
+        ("/file_root/old_dir/report.txt", "report body", false),
+    ])
+    .await;
+
+    let account = |id: &str, addr: SocketAddr| AccountConfig {
+        id: id.to_string(),
+        provider: ProviderType::WebDAV,
+        name: id.to_string(),
+        credentials: {
+            let mut c = HashMap::new();
+            c.insert("url".to_string(), format!("http://{}", addr));
+            c.insert("username".to_string(), "u".to_string());
+            c.insert("password".to_string(), "p".to_string());
+            c
+        },
+        rate_limit: None,
+        retry_policy: RetryPolicy::default(),
+        resource_limits: None,
+    };
+
+    let mut engine = SyncEngine::new().await.unwrap();
+    engine.register_provider(
+        "src_mv".to_string(),
+        Box::new(
+            WebDavProvider::new(&account("src_mv", addr1))
+                .await
+                .unwrap(),
+        ),
+    );
+    engine.register_provider(
+        "dst_mv".to_string(),
+        Box::new(
+            WebDavProvider::new(&account("dst_mv", addr2))
+                .await
+                .unwrap(),
+        ),
+    );
+
+    let task = SyncTask {
+        id: "t_move".to_string(),
+        name: "server side move".to_string(),
+        source_account: "src_mv".to_string(),
+        source_path: "/file_root".to_string(),
+        target_account: "dst_mv".to_string(),
+        target_path: "/file_root".to_string(),
+        schedule: None,
+        filters: vec![],
+        encryption: None,
+        compression: None,
+        diff_mode: DiffMode::Full,
+        preserve_metadata: false,
+        verify_integrity: false,
+        sync_policy: Some(SyncPolicy {
+            delete_orphans: true,
+            overwrite_existing: true,
+            scan_cooldown_secs: 0,
+        }),
+        resource_limits: None,
+        bandwidth_schedule: vec![],
+    };
+
+    let diff = engine.calculate_diff_for_dry_run(&task).await.unwrap();
+    let moved = diff
+        .files
+        .iter()
+        .find(|f| f.action == DiffAction::Move)
+        .expect("应检测到移动操作");
+    assert_eq!(moved.path, "new_dir/report.txt");
+    assert_eq!(
+        moved.change_details.old_path.as_deref(),
+        Some("old_dir/report.txt")
+    );
+
+    engine.sync(&task).await.unwrap();
+
+    let files = store2.read().await;
+    assert_eq!(
+        files["/file_root/new_dir/report.txt"].content,
+        b"report body".to_vec()
+    );
+    assert!(!files.contains_key("/file_root/old_dir/report.txt"));
+}